@@ -0,0 +1,41 @@
+use candid::{export_service, Principal};
+use ic_cdk::caller;
+use ic_cdk_macros::{init, query, update};
+
+use errors::response_error::error::ResponseError;
+use event_records::archive::{AppendEventRecordsResult, ArchivedEventRecord, GetArchivedEventRecordsResult};
+use event_records::event_query::EventRecordsQuery;
+
+pub mod memory;
+pub mod service;
+
+/// `owner` is the canister whose event records are archived
+#[init]
+fn init(owner: Principal) {
+    service::set_owner(owner);
+}
+
+#[update]
+fn append_event_records(records: Vec<ArchivedEventRecord>) -> AppendEventRecordsResult {
+    let result = service::append_event_records(caller(), records)
+        .map_err(ResponseError::from_internal_error);
+
+    AppendEventRecordsResult(result)
+}
+
+#[query]
+fn get_event_records(query: EventRecordsQuery) -> GetArchivedEventRecordsResult {
+    GetArchivedEventRecordsResult(Ok(service::get_event_records(&query)))
+}
+
+#[query]
+fn get_owner() -> Principal {
+    service::get_owner()
+}
+
+export_service!();
+
+#[ic_cdk_macros::query(name = "export_candid")]
+fn export_candid() -> String {
+    __export_service()
+}
@@ -0,0 +1,20 @@
+use std::cell::RefCell;
+use ic_stable_structures::DefaultMemoryImpl;
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+
+pub type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+pub const EVENT_RECORDS_MEMORY_ID: MemoryId = MemoryId::new(0);
+pub const EVENT_RECORDS_LOOKUP_MEMORY_ID: MemoryId = MemoryId::new(1);
+pub const NEXT_EVENT_RECORD_ID_MEMORY_ID: MemoryId = MemoryId::new(2);
+/// Canister whose event records are archived, the only one allowed to append them
+pub const OWNER_MEMORY_ID: MemoryId = MemoryId::new(3);
+
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+}
+
+pub fn get_memory(id: MemoryId) -> Memory {
+    MEMORY_MANAGER.with(|memory_manager| memory_manager.borrow().get(id))
+}
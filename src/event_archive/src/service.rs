@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use candid::Principal;
+use ic_stable_structures::StableCell;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use event_records::archive::ArchivedEventRecord;
+use event_records::event_query::{EventRecordsPage, EventRecordsQuery};
+use event_records::event_store::EventStore;
+
+use crate::memory::{
+    get_memory,
+    Memory,
+    EVENT_RECORDS_MEMORY_ID,
+    EVENT_RECORDS_LOOKUP_MEMORY_ID,
+    NEXT_EVENT_RECORD_ID_MEMORY_ID,
+    OWNER_MEMORY_ID,
+};
+
+thread_local! {
+    static EVENT_RECORDS: RefCell<EventStore<ArchivedEventRecord, Memory>> = RefCell::new(
+        EventStore::init(
+            get_memory(EVENT_RECORDS_MEMORY_ID),
+            get_memory(EVENT_RECORDS_LOOKUP_MEMORY_ID),
+            get_memory(NEXT_EVENT_RECORD_ID_MEMORY_ID),
+        )
+    );
+
+    static OWNER: RefCell<StableCell<Principal, Memory>> = RefCell::new(
+        StableCell::init(get_memory(OWNER_MEMORY_ID), Principal::anonymous())
+            .expect("failed to initialize the archive owner")
+    );
+}
+
+pub fn set_owner(owner: Principal) {
+    OWNER.with(|cell| cell.borrow_mut().set(owner).expect("failed to save the archive owner"));
+}
+
+pub fn get_owner() -> Principal {
+    OWNER.with(|cell| *cell.borrow().get())
+}
+
+pub fn append_event_records(caller: Principal, records: Vec<ArchivedEventRecord>) -> Result<(), InternalError> {
+    let owner = get_owner();
+    if caller != owner {
+        return Err(InternalError::access_denied(
+            build_error_code(5100, 5, 1), // 5100 05 01
+            "service::append_event_records".to_string(),
+            "Caller is not the archive owner".to_string(),
+            Some(HashMap::from([
+                ("caller".to_string(), caller.to_text()),
+                ("owner".to_string(), owner.to_text()),
+            ])),
+        ));
+    }
+
+    let next_id = EVENT_RECORDS.with(|store| {
+        let store = store.borrow();
+        (!store.is_empty() || store.next_id() > 0).then(|| store.next_id())
+    });
+    validate_record_ids(next_id, &records)?;
+
+    EVENT_RECORDS.with(|store| {
+        let mut store = store.borrow_mut();
+        for record in records {
+            store.insert(record);
+        }
+    });
+
+    Ok(())
+}
+
+pub fn get_event_records(query: &EventRecordsQuery) -> EventRecordsPage<ArchivedEventRecord> {
+    EVENT_RECORDS.with(|store| store.borrow().query(query))
+}
+
+/// Records must continue the archived ones without gaps, `next_id` is none for an empty archive
+fn validate_record_ids(next_id: Option<u64>, records: &[ArchivedEventRecord]) -> Result<(), InternalError> {
+    let first_id = match records.first() {
+        Some(record) => record.id,
+        None => return Ok(()),
+    };
+
+    let expected_ids = next_id.unwrap_or(first_id)..;
+    let is_contiguous = records.iter()
+        .zip(expected_ids)
+        .all(|(record, expected_id)| record.id == expected_id);
+
+    if !is_contiguous {
+        return Err(InternalError::validation(
+            build_error_code(5100, 2, 1), // 5100 02 01
+            "service::validate_record_ids".to_string(),
+            "Event records must follow the archived ones without gaps".to_string(),
+            Some(HashMap::from([
+                ("next_id".to_string(), format!("{:?}", next_id)),
+                ("first_id".to_string(), first_id.to_string()),
+                ("records_count".to_string(), records.len().to_string()),
+            ])),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use event_records::archive::ArchivedEvent;
+    use event_records::generic_event_record::GenericEventRecord;
+
+    fn record(id: u64) -> ArchivedEventRecord {
+        GenericEventRecord {
+            id,
+            timestamp: id,
+            event: ArchivedEvent {
+                event_type: "Test".to_string(),
+                strategy_id: None,
+                pool_ids: vec![],
+            },
+            correlation_id: id.to_string(),
+            user: None,
+            prev_hash: None,
+            payload: vec![],
+        }
+    }
+
+    mod validate_record_ids {
+        use super::*;
+
+        #[test]
+        fn accepts_any_first_id_for_empty_archive() {
+            assert!(validate_record_ids(None, &[record(40), record(41)]).is_ok());
+        }
+
+        #[test]
+        fn accepts_records_following_archived_ones() {
+            assert!(validate_record_ids(Some(40), &[record(40), record(41)]).is_ok());
+        }
+
+        #[test]
+        fn rejects_gaps() {
+            let error = validate_record_ids(Some(40), &[record(41)]).unwrap_err();
+            assert_eq!(error.code, build_error_code(5100, 2, 1));
+
+            assert!(validate_record_ids(None, &[record(40), record(42)]).is_err());
+        }
+    }
+}
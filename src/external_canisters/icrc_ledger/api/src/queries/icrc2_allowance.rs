@@ -0,0 +1,4 @@
+pub use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
+
+pub type Args = AllowanceArgs;
+pub type Response = Allowance;
@@ -8,7 +8,8 @@ pub struct Args {
     pub pay_amount: Nat,
     // pub pay_tx_id: Option<Nat>,
     pub receive_token: String,
-    // pub receive_amount: Option<Nat>,
+    /// Minimum amount to receive, the swap fails if the output is lower
+    pub receive_amount: Option<Nat>,
     // pub receive_address: Option<String>,
     pub max_slippage: Option<f64>,
     // pub referred_by: Option<String>,
@@ -0,0 +1,29 @@
+#![allow(non_snake_case)]
+
+pub mod queries;
+pub mod updates;
+
+use candid::{CandidType, Deserialize, Int, Nat, Principal};
+use serde::Serialize;
+use types::ResultLowercase;
+pub use updates::*;
+pub use queries::*;
+
+/// Result of the Sonic updates, `ok` holds the transaction index
+pub type TxReceipt = ResultLowercase<Nat, String>;
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct PairInfoExt {
+    pub id: String,
+    pub token0: String,
+    pub token1: String,
+    pub creator: Principal,
+    pub reserve0: Nat,
+    pub reserve1: Nat,
+    pub price0CumulativeLast: Nat,
+    pub price1CumulativeLast: Nat,
+    pub kLast: Nat,
+    pub blockTimestampLast: Int,
+    pub totalSupply: Nat,
+    pub lptoken: String,
+}
@@ -0,0 +1,5 @@
+use candid::{Nat, Principal};
+
+/// Balance of the token (ledger canister id as text) deposited by the user
+pub type Args = (String, Principal);
+pub type Response = (Nat,);
@@ -0,0 +1,3 @@
+pub use crate::PairInfoExt;
+
+pub type Response = Vec<PairInfoExt>;
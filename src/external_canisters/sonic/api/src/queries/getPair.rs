@@ -0,0 +1,6 @@
+use candid::Principal;
+
+pub use crate::PairInfoExt;
+
+pub type Args = (Principal, Principal);
+pub type Response = (Option<PairInfoExt>,);
@@ -0,0 +1,5 @@
+use candid::{Nat, Principal};
+
+pub type Args = (Principal,);
+/// LP token balances of the user keyed by the pair id
+pub type Response = (Vec<(String, Nat)>,);
@@ -0,0 +1,4 @@
+pub mod getPair;
+pub mod getAllPairs;
+pub mod getUserLPBalances;
+pub mod balanceOf;
@@ -0,0 +1,7 @@
+use candid::{Int, Nat, Principal};
+
+use crate::TxReceipt;
+
+/// Token0, token1, desired amounts, minimum amounts and deadline in nanoseconds
+pub type Args = (Principal, Principal, Nat, Nat, Nat, Nat, Int);
+pub type Response = (TxReceipt,);
@@ -0,0 +1,6 @@
+use candid::{Nat, Principal};
+
+use crate::TxReceipt;
+
+pub type Args = (Principal, Nat);
+pub type Response = (TxReceipt,);
@@ -0,0 +1,5 @@
+pub mod deposit;
+pub mod withdraw;
+pub mod swapExactTokensForTokens;
+pub mod addLiquidity;
+pub mod removeLiquidity;
@@ -0,0 +1,7 @@
+use candid::{Int, Nat, Principal};
+
+use crate::TxReceipt;
+
+/// Token0, token1, LP amount, minimum amounts, recipient and deadline in nanoseconds
+pub type Args = (Principal, Principal, Nat, Nat, Nat, Principal, Int);
+pub type Response = (TxReceipt,);
@@ -0,0 +1,7 @@
+use candid::{Int, Nat, Principal};
+
+use crate::TxReceipt;
+
+/// Amount in, minimum amount out, token path, recipient and deadline in nanoseconds
+pub type Args = (Nat, Nat, Vec<String>, Principal, Int);
+pub type Response = (TxReceipt,);
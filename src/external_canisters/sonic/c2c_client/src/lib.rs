@@ -0,0 +1,17 @@
+#![allow(non_snake_case)]
+
+use canister_client::{generate_candid_c2c_call_no_args, generate_candid_c2c_call_tuple_args};
+pub use sonic_canister::*;
+
+// Queries
+generate_candid_c2c_call_tuple_args!(getPair);
+generate_candid_c2c_call_no_args!(getAllPairs);
+generate_candid_c2c_call_tuple_args!(getUserLPBalances);
+generate_candid_c2c_call_tuple_args!(balanceOf);
+
+// Updates
+generate_candid_c2c_call_tuple_args!(deposit);
+generate_candid_c2c_call_tuple_args!(withdraw);
+generate_candid_c2c_call_tuple_args!(swapExactTokensForTokens);
+generate_candid_c2c_call_tuple_args!(addLiquidity);
+generate_candid_c2c_call_tuple_args!(removeLiquidity);
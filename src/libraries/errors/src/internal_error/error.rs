@@ -110,6 +110,21 @@ impl InternalError {
             extra
         )
     }
+
+    pub fn access_denied(
+        code: u32,
+        context: String,
+        message: String,
+        extra: Option<HashMap<String, String>>
+    ) -> Self {
+        Self::new(
+            code,
+            InternalErrorKind::AccessDenied,
+            context,
+            message,
+            extra
+        )
+    }
 }
 
 
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use errors::response_error::error::ResponseError;
+
+use crate::event_query::EventRecordsPage;
+use crate::event_store::{IndexedEvent, StoredEventRecord};
+use crate::generic_event_record::GenericEventRecord;
+
+/// Archive canister holding the event records with ids in `start_id..end_id`
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct EventArchive {
+    pub canister_id: Principal,
+    pub start_id: u64,
+    pub end_id: u64,
+}
+
+impl EventArchive {
+    pub fn len(&self) -> u64 {
+        self.end_id - self.start_id
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end_id == self.start_id
+    }
+}
+
+/// Canister serving the event records with ids in `start_id..end_id`,
+/// either an archive or the canister which recorded them
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct EventRecordsLocation {
+    pub canister_id: Principal,
+    pub start_id: u64,
+    pub end_id: u64,
+}
+
+/// When event records are moved to archives and how many at once
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct EventRetentionPolicy {
+    /// Records kept by the canister, older ones are archived
+    pub max_local_records: u64,
+    /// Records sent to an archive in one call
+    pub archive_batch_size: u64,
+    /// Records an archive holds before the next one is used
+    pub archive_capacity: u64,
+}
+
+impl Default for EventRetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_local_records: 100_000,
+            archive_batch_size: 1_000,
+            archive_capacity: 5_000_000,
+        }
+    }
+}
+
+impl EventRetentionPolicy {
+    pub fn validate(&self) -> Result<(), InternalError> {
+        if self.max_local_records == 0 || self.archive_batch_size == 0 || self.archive_capacity == 0 {
+            return Err(InternalError::validation(
+                build_error_code(5000, 2, 1), // 5000 02 01
+                "EventRetentionPolicy::validate".to_string(),
+                "Retention limits must be greater than 0".to_string(),
+                Some(HashMap::from([
+                    ("max_local_records".to_string(), self.max_local_records.to_string()),
+                    ("archive_batch_size".to_string(), self.archive_batch_size.to_string()),
+                    ("archive_capacity".to_string(), self.archive_capacity.to_string()),
+                ])),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Archives of a canister's event records, the last one takes the next archived records
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default)]
+pub struct EventArchiveState {
+    pub archives: Vec<EventArchive>,
+    pub retention_policy: EventRetentionPolicy,
+    /// Installed on the archive spawned when the last one is full, empty until uploaded
+    #[serde(with = "serde_bytes")]
+    pub archive_wasm: Vec<u8>,
+    /// Archive created for the next records whose wasm is not installed yet
+    pub pending_archive: Option<Principal>,
+}
+
+impl EventArchiveState {
+    /// The last archive, unless it is full
+    pub fn writable_archive(&self) -> Option<&EventArchive> {
+        self.archives.last()
+            .filter(|archive| archive.len() < self.retention_policy.archive_capacity)
+    }
+
+    /// Makes `canister_id` the archive of the records from `start_id` on,
+    /// the id of the oldest record not archived yet
+    pub fn add_archive(&mut self, canister_id: Principal, start_id: u64) -> Result<EventArchive, InternalError> {
+        if self.archives.iter().any(|archive| archive.canister_id == canister_id) {
+            return Err(InternalError::business_logic(
+                build_error_code(5000, 3, 1), // 5000 03 01
+                "EventArchiveState::add_archive".to_string(),
+                "Canister is already an event archive".to_string(),
+                Some(HashMap::from([
+                    ("canister_id".to_string(), canister_id.to_text()),
+                ])),
+            ));
+        }
+
+        let archive = EventArchive {
+            canister_id,
+            start_id,
+            end_id: start_id,
+        };
+        self.archives.push(archive.clone());
+
+        Ok(archive)
+    }
+
+    /// Records that the archive holds the records up to `end_id`
+    pub fn extend_archive(&mut self, canister_id: Principal, end_id: u64) {
+        if let Some(archive) = self.archives.iter_mut().rev().find(|archive| archive.canister_id == canister_id) {
+            archive.end_id = end_id;
+        }
+    }
+}
+
+/// Canisters serving the ids of `range`, in id order.
+/// `local_ids` are the ids of the records kept by `local_canister_id`.
+pub fn locate_event_records(
+    archives: &[EventArchive],
+    local_canister_id: Principal,
+    local_ids: Range<u64>,
+    range: Range<u64>,
+) -> Vec<EventRecordsLocation> {
+    archives.iter()
+        .map(|archive| (archive.canister_id, archive.start_id..archive.end_id))
+        .chain(std::iter::once((local_canister_id, local_ids)))
+        .filter_map(|(canister_id, ids)| {
+            let start_id = ids.start.max(range.start);
+            let end_id = ids.end.min(range.end);
+
+            (start_id < end_id).then_some(EventRecordsLocation { canister_id, start_id, end_id })
+        })
+        .collect()
+}
+
+/// Event kept by archives: the fields records are looked up by, the encoded event
+/// stays in the record payload, so that archives serve records of any canister
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ArchivedEvent {
+    pub event_type: String,
+    pub strategy_id: Option<String>,
+    pub pool_ids: Vec<String>,
+}
+
+impl ArchivedEvent {
+    pub fn from_event<TEvent: IndexedEvent>(event: &TEvent) -> Self {
+        Self {
+            event_type: event.type_str().to_string(),
+            strategy_id: event.strategy_id().map(|strategy_id| strategy_id.to_string()),
+            pool_ids: event.pool_ids().into_iter().map(|pool_id| pool_id.to_string()).collect(),
+        }
+    }
+}
+
+impl IndexedEvent for ArchivedEvent {
+    fn type_str(&self) -> &str {
+        &self.event_type
+    }
+
+    fn strategy_id(&self) -> Option<&str> {
+        self.strategy_id.as_deref()
+    }
+
+    fn pool_ids(&self) -> Vec<&str> {
+        self.pool_ids.iter().map(|pool_id| pool_id.as_str()).collect()
+    }
+}
+
+pub type ArchivedEventRecord = GenericEventRecord<ArchivedEvent>;
+
+/// Keeps the hash of the record, as the archived record keeps the payload
+pub fn to_archived_event_record<TRecord: StoredEventRecord>(record: &TRecord) -> ArchivedEventRecord {
+    let record = record.record();
+
+    GenericEventRecord {
+        id: record.id,
+        timestamp: record.timestamp,
+        event: ArchivedEvent::from_event(&record.event),
+        correlation_id: record.correlation_id.clone(),
+        user: record.user,
+        prev_hash: record.prev_hash.clone(),
+        payload: record.payload.clone(),
+    }
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct AppendEventRecordsResult(pub Result<(), ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetArchivedEventRecordsResult(pub Result<EventRecordsPage<ArchivedEventRecord>, ResponseError>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canister(text: &str) -> Principal {
+        Principal::from_text(text).unwrap()
+    }
+
+    fn archive(canister_id: Principal, start_id: u64, end_id: u64) -> EventArchive {
+        EventArchive { canister_id, start_id, end_id }
+    }
+
+    fn location(canister_id: Principal, start_id: u64, end_id: u64) -> EventRecordsLocation {
+        EventRecordsLocation { canister_id, start_id, end_id }
+    }
+
+    mod locate_event_records {
+        use super::*;
+
+        #[test]
+        fn splits_range_across_archives_and_local_records() {
+            let first = canister("ryjl3-tyaaa-aaaaa-aaaba-cai");
+            let second = canister("mxzaz-hqaaa-aaaar-qaada-cai");
+            let local = canister("aaaaa-aa");
+            let archives = vec![archive(first, 0, 100), archive(second, 100, 150)];
+
+            assert_eq!(
+                locate_event_records(&archives, local, 150..180, 90..160),
+                vec![location(first, 90, 100), location(second, 100, 150), location(local, 150, 160)],
+            );
+        }
+
+        #[test]
+        fn skips_canisters_outside_of_range() {
+            let first = canister("ryjl3-tyaaa-aaaaa-aaaba-cai");
+            let local = canister("aaaaa-aa");
+            let archives = vec![archive(first, 0, 100)];
+
+            assert_eq!(locate_event_records(&archives, local, 100..120, 105..200), vec![location(local, 105, 120)]);
+            assert!(locate_event_records(&archives, local, 100..120, 120..200).is_empty());
+        }
+    }
+
+    mod writable_archive {
+        use super::*;
+
+        #[test]
+        fn returns_last_archive_until_it_is_full() {
+            let mut state = EventArchiveState {
+                retention_policy: EventRetentionPolicy { archive_capacity: 10, ..Default::default() },
+                ..Default::default()
+            };
+            let archive_id = canister("ryjl3-tyaaa-aaaaa-aaaba-cai");
+
+            state.add_archive(archive_id, 5).unwrap();
+            state.extend_archive(archive_id, 14);
+            assert_eq!(state.writable_archive(), Some(&archive(archive_id, 5, 14)));
+
+            state.extend_archive(archive_id, 15);
+            assert_eq!(state.writable_archive(), None);
+        }
+
+        #[test]
+        fn rejects_archive_added_twice() {
+            let mut state = EventArchiveState::default();
+            let archive_id = canister("ryjl3-tyaaa-aaaaa-aaaba-cai");
+
+            state.add_archive(archive_id, 0).unwrap();
+
+            assert_eq!(state.add_archive(archive_id, 0).unwrap_err().code, build_error_code(5000, 3, 1));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use candid::{Encode, Principal};
+use ic_cdk::api::management_canister::main::{
+    create_canister,
+    install_code,
+    CanisterInstallMode,
+    CanisterSettings,
+    CreateCanisterArgument,
+    InstallCodeArgument,
+};
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+
+use crate::archive::{AppendEventRecordsResult, ArchivedEventRecord, GetArchivedEventRecordsResult};
+use crate::event_query::{EventRecordsPage, EventRecordsQuery};
+
+/// Cycles a spawned archive starts with
+pub const ARCHIVE_CANISTER_CYCLES: u128 = 2_000_000_000_000;
+
+pub async fn append_event_records(
+    archive_id: Principal,
+    records: Vec<ArchivedEventRecord>,
+) -> Result<(), InternalError> {
+    let records_count = records.len();
+
+    let (result,): (AppendEventRecordsResult,) = ic_cdk::call(archive_id, "append_event_records", (records,))
+        .await
+        .map_err(|error| InternalError::external_service(
+            build_error_code(5001, 4, 1), // 5001 04 01
+            "archive_client::append_event_records".to_string(),
+            format!("IC error calling 'append_event_records': {error:?}"),
+            Some(HashMap::from([
+                ("archive_id".to_string(), archive_id.to_text()),
+                ("records_count".to_string(), records_count.to_string()),
+            ])),
+        ))?;
+
+    result.0.map_err(|error| InternalError::external_service(
+        build_error_code(5001, 4, 2), // 5001 04 02
+        "archive_client::append_event_records".to_string(),
+        format!("Archive rejected event records: {}", error.message),
+        Some(HashMap::from([
+            ("archive_id".to_string(), archive_id.to_text()),
+            ("archive_error_code".to_string(), error.code.to_string()),
+        ])),
+    ))
+}
+
+/// Fetches a page of records from an archive, callable from updates and composite queries
+pub async fn get_archived_event_records(
+    archive_id: Principal,
+    query: EventRecordsQuery,
+) -> Result<EventRecordsPage<ArchivedEventRecord>, InternalError> {
+    let (result,): (GetArchivedEventRecordsResult,) = ic_cdk::call(archive_id, "get_event_records", (query,))
+        .await
+        .map_err(|error| InternalError::external_service(
+            build_error_code(5001, 4, 5), // 5001 04 05
+            "archive_client::get_archived_event_records".to_string(),
+            format!("IC error calling 'get_event_records': {error:?}"),
+            Some(HashMap::from([
+                ("archive_id".to_string(), archive_id.to_text()),
+            ])),
+        ))?;
+
+    result.0.map_err(|error| InternalError::external_service(
+        build_error_code(5001, 4, 6), // 5001 04 06
+        "archive_client::get_archived_event_records".to_string(),
+        format!("Archive rejected event records query: {}", error.message),
+        Some(HashMap::from([
+            ("archive_id".to_string(), archive_id.to_text()),
+            ("archive_error_code".to_string(), error.code.to_string()),
+        ])),
+    ))
+}
+
+/// Creates an empty archive canister controlled by this canister
+pub async fn create_event_archive() -> Result<Principal, InternalError> {
+    let settings = CanisterSettings {
+        controllers: Some(vec![ic_cdk::id()]),
+        ..Default::default()
+    };
+
+    let (canister,) = create_canister(CreateCanisterArgument { settings: Some(settings) }, ARCHIVE_CANISTER_CYCLES)
+        .await
+        .map_err(|error| InternalError::external_service(
+            build_error_code(5001, 4, 3), // 5001 04 03
+            "archive_client::create_event_archive".to_string(),
+            format!("IC error calling 'create_canister': {error:?}"),
+            None,
+        ))?;
+
+    Ok(canister.canister_id)
+}
+
+/// Installs, reinstalls or upgrades the archive with `wasm`,
+/// this canister is passed as the archive owner
+pub async fn install_event_archive(
+    archive_id: Principal,
+    wasm: Vec<u8>,
+    mode: CanisterInstallMode,
+) -> Result<(), InternalError> {
+    install_code(InstallCodeArgument {
+        mode,
+        canister_id: archive_id,
+        wasm_module: wasm,
+        arg: Encode!(&ic_cdk::id()).unwrap(),
+    })
+        .await
+        .map_err(|error| InternalError::external_service(
+            build_error_code(5001, 4, 4), // 5001 04 04
+            "archive_client::install_event_archive".to_string(),
+            format!("IC error calling 'install_code': {error:?}"),
+            Some(HashMap::from([
+                ("archive_id".to_string(), archive_id.to_text()),
+                ("mode".to_string(), format!("{mode:?}")),
+            ])),
+        ))
+}
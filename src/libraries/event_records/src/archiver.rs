@@ -0,0 +1,166 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::thread::LocalKey;
+use candid::CandidType;
+use ic_cdk::api::management_canister::main::CanisterInstallMode;
+use ic_stable_structures::Memory;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+
+use crate::archive::{to_archived_event_record, EventArchive, EventArchiveState};
+use crate::archive_client;
+use crate::event_store::{EventStore, StoredEventRecord};
+
+thread_local! {
+    static IS_ARCHIVING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Releases the archiving flag, also when the call traps after an await
+struct ArchivingGuard;
+
+impl ArchivingGuard {
+    fn acquire() -> Option<Self> {
+        let is_archiving = IS_ARCHIVING.with(|flag| flag.replace(true));
+        (!is_archiving).then_some(Self)
+    }
+}
+
+impl Drop for ArchivingGuard {
+    fn drop(&mut self) {
+        IS_ARCHIVING.with(|flag| flag.set(false));
+    }
+}
+
+/// Moves the records beyond the retention policy limit to the writable archive, one batch per call.
+/// When the last archive is full, a new one is spawned from the uploaded archive wasm.
+/// Returns the number of archived records.
+pub async fn archive_event_records<TRecord, M>(
+    store: &'static LocalKey<RefCell<EventStore<TRecord, M>>>,
+    state: &'static LocalKey<RefCell<EventArchiveState>>,
+) -> Result<u64, InternalError>
+where
+    TRecord: StoredEventRecord,
+    TRecord::Event: CandidType,
+    M: Memory,
+{
+    let _guard = match ArchivingGuard::acquire() {
+        Some(guard) => guard,
+        None => return Ok(0),
+    };
+
+    let (policy, writable_archive) = state.with(|state| {
+        let state = state.borrow();
+        (state.retention_policy.clone(), state.writable_archive().cloned())
+    });
+
+    let excess = store.with(|store| store.borrow().len()).saturating_sub(policy.max_local_records);
+    if excess == 0 {
+        return Ok(0);
+    }
+
+    let archive = match writable_archive {
+        Some(archive) => archive,
+        None => spawn_archive(store, state).await?,
+    };
+
+    let count = excess
+        .min(policy.archive_batch_size)
+        .min(policy.archive_capacity.saturating_sub(archive.len()));
+    let records = store.with(|store| store.borrow().oldest(count));
+
+    let end_id = match records.last() {
+        Some(record) => record.record().id + 1,
+        None => return Ok(0),
+    };
+
+    archive_client::append_event_records(
+        archive.canister_id,
+        records.iter().map(to_archived_event_record).collect(),
+    ).await?;
+
+    store.with(|store| store.borrow_mut().remove_before(end_id));
+    state.with(|state| state.borrow_mut().extend_archive(archive.canister_id, end_id));
+
+    Ok(records.len() as u64)
+}
+
+async fn spawn_archive<TRecord, M>(
+    store: &'static LocalKey<RefCell<EventStore<TRecord, M>>>,
+    state: &'static LocalKey<RefCell<EventArchiveState>>,
+) -> Result<EventArchive, InternalError>
+where
+    TRecord: StoredEventRecord,
+    M: Memory,
+{
+    let wasm = state.with(|state| state.borrow().archive_wasm.clone());
+    if wasm.is_empty() {
+        return Err(InternalError::business_logic(
+            build_error_code(5002, 3, 1), // 5002 03 01
+            "archiver::spawn_archive".to_string(),
+            "No archive can take more records and no archive wasm is uploaded".to_string(),
+            Some(HashMap::from([
+                ("archives_count".to_string(), state.with(|state| state.borrow().archives.len()).to_string()),
+            ])),
+        ));
+    }
+
+    // The created canister is kept until its wasm is installed,
+    // so a failed install is retried on it instead of creating another canister
+    let pending_archive = state.with(|state| state.borrow().pending_archive);
+    let (canister_id, mode) = match pending_archive {
+        Some(canister_id) => (canister_id, CanisterInstallMode::Reinstall),
+        None => {
+            let canister_id = archive_client::create_event_archive().await?;
+            state.with(|state| state.borrow_mut().pending_archive = Some(canister_id));
+            (canister_id, CanisterInstallMode::Install)
+        }
+    };
+
+    archive_client::install_event_archive(canister_id, wasm, mode).await?;
+    state.with(|state| state.borrow_mut().pending_archive = None);
+
+    // Records are only removed by archiving, so the oldest record is the first one not archived yet
+    let start_id = store.with(|store| store.borrow().id_range().start);
+    state.with(|state| state.borrow_mut().add_archive(canister_id, start_id))
+}
+
+/// Upgrades every archive to the uploaded archive wasm. Archives keep their records in stable memory.
+/// Stops at the first archive which fails to upgrade, the archives before it stay upgraded.
+/// Returns the number of upgraded archives.
+pub async fn upgrade_archives(state: &'static LocalKey<RefCell<EventArchiveState>>) -> Result<u64, InternalError> {
+    // Records are not appended to an archive while it is upgraded
+    let _guard = ArchivingGuard::acquire().ok_or_else(|| InternalError::business_logic(
+        build_error_code(5002, 3, 2), // 5002 03 02
+        "archiver::upgrade_archives".to_string(),
+        "Event records are being archived".to_string(),
+        None,
+    ))?;
+
+    let (archives, wasm) = state.with(|state| {
+        let state = state.borrow();
+        (state.archives.clone(), state.archive_wasm.clone())
+    });
+
+    if wasm.is_empty() {
+        return Err(InternalError::business_logic(
+            build_error_code(5002, 3, 3), // 5002 03 03
+            "archiver::upgrade_archives".to_string(),
+            "No archive wasm is uploaded".to_string(),
+            None,
+        ));
+    }
+
+    let mut upgraded = 0;
+
+    for archive in archives {
+        archive_client::install_event_archive(
+            archive.canister_id,
+            wasm.clone(),
+            CanisterInstallMode::Upgrade(None),
+        ).await?;
+        upgraded += 1;
+    }
+
+    Ok(upgraded)
+}
@@ -0,0 +1,241 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::thread::LocalKey;
+use std::time::Duration;
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use ic_stable_structures::Memory;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+
+use crate::event_query::MAX_EVENT_RECORDS_PAGE_SIZE;
+use crate::event_store::{EventStore, IndexedEvent, StoredEventRecord};
+use crate::generic_event_record::GenericEventRecord;
+
+/// SHA-256 hash of an event record, see `hash_event_record`
+pub type EventHash = Vec<u8>;
+
+thread_local! {
+    /// Id and hash of the record published as the canister's certified data
+    static CERTIFIED_TIP: RefCell<Option<(u64, EventHash)>> = const { RefCell::new(None) };
+    /// Set while a certification of the chain tip is scheduled
+    static CERTIFICATION_SCHEDULED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Records following `start_id` up to the certified tip with the hash of the tip and the certificate
+/// the canister has published it with, so that the records can be checked against the tip
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct CertifiedEventRecords<TRecord> {
+    pub records: Vec<TRecord>,
+    pub tip_id: Option<u64>,
+    pub tip_hash: Option<EventHash>,
+    /// Certificate of the subnet for the canister's certified data, the tip hash.
+    /// Only set when called as a query.
+    pub certificate: Option<Vec<u8>>,
+}
+
+/// Hashes the record fields and the event payload, the hash of the previous record included,
+/// so that no record can be changed without changing the hashes of all the following ones.
+/// The payload holds the event as encoded when it was appended, so the hashes do not change
+/// when the event type is extended later. Archived records keep the payload.
+pub fn hash_event_record<TEvent: IndexedEvent>(record: &GenericEventRecord<TEvent>) -> EventHash {
+    let mut hasher = Sha256::new();
+
+    match &record.prev_hash {
+        Some(prev_hash) => {
+            hasher.update([1]);
+            hash_bytes(&mut hasher, prev_hash);
+        }
+        None => hasher.update([0]),
+    }
+    hasher.update(record.id.to_be_bytes());
+    hasher.update(record.timestamp.to_be_bytes());
+    hash_bytes(&mut hasher, record.correlation_id.as_bytes());
+    match &record.user {
+        Some(user) => {
+            hasher.update([1]);
+            hash_bytes(&mut hasher, user.as_slice());
+        }
+        None => hasher.update([0]),
+    }
+    hash_bytes(&mut hasher, &record.payload);
+
+    hasher.finalize().to_vec()
+}
+
+/// Length prefixed, so that the boundaries of variable length fields are part of the hash
+fn hash_bytes(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_be_bytes());
+    hasher.update(bytes);
+}
+
+/// Checks that the records follow each other and link to the hash of their predecessor.
+/// Returns the hash of the last record, to be compared with the certified tip.
+pub fn verify_event_chain<TEvent: IndexedEvent>(
+    records: &[GenericEventRecord<TEvent>],
+) -> Result<Option<EventHash>, InternalError> {
+    let mut previous: Option<(u64, EventHash)> = None;
+
+    for record in records {
+        if let Some((previous_id, previous_hash)) = &previous {
+            if record.id != previous_id + 1 || record.prev_hash.as_ref() != Some(previous_hash) {
+                return Err(InternalError::business_logic(
+                    build_error_code(5003, 3, 1), // 5003 03 01
+                    "event_chain::verify_event_chain".to_string(),
+                    "Event record does not link to the previous record".to_string(),
+                    Some(HashMap::from([
+                        ("id".to_string(), record.id.to_string()),
+                        ("previous_id".to_string(), previous_id.to_string()),
+                    ])),
+                ));
+            }
+        }
+
+        previous = Some((record.id, hash_event_record(record)));
+    }
+
+    Ok(previous.map(|(_, hash)| hash))
+}
+
+/// Publishes the hash of the latest record as the canister's certified data
+pub fn certify_event_chain_tip<TRecord, M>(store: &EventStore<TRecord, M>)
+where
+    TRecord: StoredEventRecord,
+    M: Memory,
+{
+    let tip = store.last().map(|record| (record.record().id, hash_event_record(record.record())));
+
+    ic_cdk::api::set_certified_data(tip.as_ref().map(|(_, hash)| hash.as_slice()).unwrap_or_default());
+    CERTIFIED_TIP.with(|cell| *cell.borrow_mut() = tip);
+}
+
+/// Certifies the chain tip in a timer once the current message is done, so that the records
+/// appended by an update call are certified together instead of one by one
+pub fn schedule_event_chain_certification<TRecord, M>(store: &'static LocalKey<RefCell<EventStore<TRecord, M>>>)
+where
+    TRecord: StoredEventRecord + 'static,
+    M: Memory + 'static,
+{
+    if CERTIFICATION_SCHEDULED.with(|scheduled| scheduled.replace(true)) {
+        return;
+    }
+
+    ic_cdk_timers::set_timer(Duration::ZERO, move || {
+        CERTIFICATION_SCHEDULED.with(|scheduled| scheduled.set(false));
+        store.with(|store| certify_event_chain_tip(&store.borrow()));
+    });
+}
+
+/// Records from `start_id` on up to the certified tip, at most a page of them.
+/// Records appended after the last certification are left out until they are certified.
+pub fn get_certified_event_records<TRecord, M>(
+    store: &EventStore<TRecord, M>,
+    start_id: u64,
+    limit: u64,
+) -> CertifiedEventRecords<TRecord>
+where
+    TRecord: StoredEventRecord,
+    M: Memory,
+{
+    let tip = CERTIFIED_TIP.with(|cell| cell.borrow().clone());
+    let count = tip.as_ref()
+        .map(|(tip_id, _)| certified_count(start_id, limit, *tip_id))
+        .unwrap_or(0);
+
+    CertifiedEventRecords {
+        records: store.range(start_id, count),
+        tip_id: tip.as_ref().map(|(tip_id, _)| *tip_id),
+        tip_hash: tip.map(|(_, tip_hash)| tip_hash),
+        certificate: ic_cdk::api::data_certificate(),
+    }
+}
+
+/// Number of records from `start_id` to return, at most a page and none after the certified tip
+fn certified_count(start_id: u64, limit: u64, tip_id: u64) -> u64 {
+    limit
+        .min(MAX_EVENT_RECORDS_PAGE_SIZE)
+        .min((tip_id + 1).saturating_sub(start_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Encode;
+
+    #[derive(CandidType, Deserialize, Clone, Debug)]
+    struct TestEvent(String);
+
+    impl IndexedEvent for TestEvent {
+        fn type_str(&self) -> &str {
+            "Test"
+        }
+    }
+
+    fn chain(count: u64) -> Vec<GenericEventRecord<TestEvent>> {
+        let mut records: Vec<GenericEventRecord<TestEvent>> = Vec::new();
+
+        for id in 0..count {
+            let prev_hash = records.last().map(hash_event_record);
+            let event = TestEvent(format!("event {}", id));
+            records.push(GenericEventRecord {
+                id,
+                timestamp: 1000 + id,
+                payload: Encode!(&event).unwrap(),
+                event,
+                correlation_id: id.to_string(),
+                user: None,
+                prev_hash,
+            });
+        }
+
+        records
+    }
+
+    mod verify_event_chain {
+        use super::*;
+
+        #[test]
+        fn returns_hash_of_last_record() {
+            let records = chain(3);
+
+            assert_eq!(verify_event_chain(&records).unwrap(), Some(hash_event_record(&records[2])));
+            assert_eq!(verify_event_chain::<TestEvent>(&[]).unwrap(), None);
+        }
+
+        #[test]
+        fn rejects_rewritten_record() {
+            let mut records = chain(3);
+            records[1].payload = Encode!(&TestEvent("rewritten".to_string())).unwrap();
+
+            let error = verify_event_chain(&records).unwrap_err();
+
+            assert_eq!(error.code, build_error_code(5003, 3, 1));
+        }
+
+        #[test]
+        fn rejects_removed_record() {
+            let mut records = chain(3);
+            records.remove(1);
+
+            assert!(verify_event_chain(&records).is_err());
+        }
+    }
+
+    mod certified_count {
+        use super::*;
+
+        #[test]
+        fn stops_at_certified_tip() {
+            assert_eq!(certified_count(5, 10, 7), 3);
+            assert_eq!(certified_count(8, 10, 7), 0);
+        }
+
+        #[test]
+        fn caps_at_page_size() {
+            assert_eq!(certified_count(0, 10, 100), 10);
+            assert_eq!(certified_count(0, u64::MAX, u64::MAX - 1), MAX_EVENT_RECORDS_PAGE_SIZE);
+        }
+    }
+}
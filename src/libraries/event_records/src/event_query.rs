@@ -0,0 +1,41 @@
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
+
+pub const MAX_EVENT_RECORDS_PAGE_SIZE: u64 = 100;
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Criteria an event record must meet, all of them when several are set.
+/// The time range is inclusive, in nanoseconds.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default)]
+pub struct EventRecordsFilter {
+    pub user: Option<Principal>,
+    pub strategy_id: Option<String>,
+    pub pool_id: Option<String>,
+    pub correlation_id: Option<String>,
+    pub event_type: Option<String>,
+    pub from_timestamp: Option<u64>,
+    pub to_timestamp: Option<u64>,
+}
+
+/// Records are returned in the order they were recorded.
+/// `cursor` is the `next_cursor` of the previous page, none for the first page.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct EventRecordsQuery {
+    pub filter: EventRecordsFilter,
+    pub cursor: Option<u64>,
+    pub limit: u64,
+    pub sort_order: SortOrder,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct EventRecordsPage<TRecord> {
+    pub items: Vec<TRecord>,
+    /// Number of records matching the filter, across all pages
+    pub total: u64,
+    pub next_cursor: Option<u64>,
+}
@@ -0,0 +1,535 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use candid::{CandidType, Decode, Deserialize, Encode};
+use serde::de::DeserializeOwned;
+use ic_stable_structures::{Memory, StableBTreeMap, StableCell};
+use ic_stable_structures::storable::{Bound, Storable};
+
+use crate::event_query::{
+    EventRecordsFilter,
+    EventRecordsPage,
+    EventRecordsQuery,
+    SortOrder,
+    MAX_EVENT_RECORDS_PAGE_SIZE,
+};
+use crate::event_chain::{hash_event_record, EventHash};
+use crate::generic_event_record::GenericEventRecord;
+
+/// Correlation ids, pool ids and event types are well below the key bound
+const MAX_LOOKUP_KEY_SIZE: u32 = 512;
+
+/// Fields of an event its records are looked up by, besides the user and the correlation id
+pub trait IndexedEvent {
+    fn type_str(&self) -> &str;
+
+    fn strategy_id(&self) -> Option<&str> {
+        None
+    }
+
+    fn pool_ids(&self) -> Vec<&str> {
+        Vec::new()
+    }
+}
+
+/// Record kept in an `EventStore`, canisters wrap `GenericEventRecord` in their own record type
+pub trait StoredEventRecord: Storable + Clone {
+    type Event: IndexedEvent + CandidType;
+
+    fn record(&self) -> &GenericEventRecord<Self::Event>;
+
+    fn record_mut(&mut self) -> &mut GenericEventRecord<Self::Event>;
+}
+
+impl<TEvent> Storable for GenericEventRecord<TEvent>
+where
+    TEvent: CandidType + DeserializeOwned,
+{
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl<TEvent> StoredEventRecord for GenericEventRecord<TEvent>
+where
+    TEvent: IndexedEvent + CandidType + DeserializeOwned + Clone,
+{
+    type Event = TEvent;
+
+    fn record(&self) -> &GenericEventRecord<TEvent> {
+        self
+    }
+
+    fn record_mut(&mut self) -> &mut GenericEventRecord<TEvent> {
+        self
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum LookupField {
+    User,
+    Strategy,
+    Pool,
+    Correlation,
+    EventType,
+}
+
+/// Ids of the records with a field value, in the order they were recorded
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct LookupKey {
+    field: LookupField,
+    value: String,
+    id: u64,
+}
+
+impl Storable for LookupKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded { max_size: MAX_LOOKUP_KEY_SIZE, is_fixed_size: false };
+}
+
+/// Event records by id, with lookups by user, strategy, pool, correlation id and event type.
+/// Ids are contiguous: records are added after the last one and only the oldest are removed.
+pub struct EventStore<TRecord, M>
+where
+    TRecord: StoredEventRecord,
+    M: Memory,
+{
+    records: StableBTreeMap<u64, TRecord, M>,
+    lookup: StableBTreeMap<LookupKey, (), M>,
+    next_id: StableCell<u64, M>,
+}
+
+impl<TRecord, M> EventStore<TRecord, M>
+where
+    TRecord: StoredEventRecord,
+    M: Memory,
+{
+    pub fn init(records_memory: M, lookup_memory: M, next_id_memory: M) -> Self {
+        Self {
+            records: StableBTreeMap::init(records_memory),
+            lookup: StableBTreeMap::init(lookup_memory),
+            next_id: StableCell::init(next_id_memory, 0).expect("failed to initialize the next event record id"),
+        }
+    }
+
+    /// Id of the next record, ids are not reused once the records are removed
+    pub fn next_id(&self) -> u64 {
+        *self.next_id.get()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Ids of the records kept in the store
+    pub fn id_range(&self) -> Range<u64> {
+        let end = self.next_id();
+        let start = self.records.first_key_value()
+            .map(|(id, _)| id)
+            .unwrap_or(end);
+
+        start..end
+    }
+
+    pub fn get(&self, id: u64) -> Option<TRecord> {
+        self.records.get(&id)
+    }
+
+    pub fn last(&self) -> Option<TRecord> {
+        self.records.last_key_value().map(|(_, record)| record)
+    }
+
+    /// Hash of the latest record, which the next appended record links to.
+    /// Archiving keeps at least one record, so the chain continues after the archived ones.
+    pub fn tip_hash(&self) -> Option<EventHash> {
+        self.last().map(|record| hash_event_record(record.record()))
+    }
+
+    /// Records from `start_id` on, at most `count` of them
+    pub fn range(&self, start_id: u64, count: u64) -> Vec<TRecord> {
+        self.records.range(start_id..)
+            .take(count as usize)
+            .map(|(_, record)| record)
+            .collect()
+    }
+
+    /// Adds a record recorded by this canister, linked to the hash of the latest record.
+    /// The event is encoded once here, the record hash is computed from these bytes from then on.
+    pub fn append(&mut self, mut record: TRecord) -> TRecord {
+        let prev_hash = self.tip_hash();
+        let event_record = record.record_mut();
+        event_record.payload = Encode!(&event_record.event).unwrap();
+        event_record.prev_hash = prev_hash;
+        self.insert(record.clone());
+
+        record
+    }
+
+    /// Adds a record as it is, archives use it to keep the hashes of the records they receive
+    pub fn insert(&mut self, record: TRecord) {
+        let id = record.record().id;
+
+        for (field, value) in get_record_lookups(record.record()) {
+            self.lookup.insert(LookupKey { field, value, id }, ());
+        }
+        self.records.insert(id, record);
+
+        if id >= self.next_id() {
+            self.next_id.set(id + 1).expect("failed to save the next event record id");
+        }
+    }
+
+    /// The oldest records, at most `count` of them
+    pub fn oldest(&self, count: u64) -> Vec<TRecord> {
+        self.records.iter()
+            .take(count as usize)
+            .map(|(_, record)| record)
+            .collect()
+    }
+
+    /// Removes the records with ids below `end_id` and their lookups
+    pub fn remove_before(&mut self, end_id: u64) {
+        while let Some((id, record)) = self.records.first_key_value() {
+            if id >= end_id {
+                break;
+            }
+
+            for (field, value) in get_record_lookups(record.record()) {
+                self.lookup.remove(&LookupKey { field, value, id });
+            }
+            self.records.remove(&id);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear_new();
+        self.lookup.clear_new();
+        self.next_id.set(0).expect("failed to save the next event record id");
+    }
+
+    pub fn query(&self, query: &EventRecordsQuery) -> EventRecordsPage<TRecord> {
+        let ids = self.get_time_range_ids(&query.filter);
+        let mut lookups = get_filter_lookups(&query.filter);
+
+        let (total, (page_ids, next_cursor)) = if lookups.is_empty() {
+            let total = ids.end.saturating_sub(ids.start);
+            (total, get_page(after_cursor(ids, query), query))
+        } else {
+            // The first lookup is scanned, the records it yields are checked against the others
+            let (field, value) = lookups.remove(0);
+
+            let start = LookupKey { field, value: value.clone(), id: ids.start };
+            let end = LookupKey { field, value, id: ids.end };
+
+            let matching = self.lookup.range(start..end)
+                .map(|(key, _)| key.id)
+                .filter(|id| {
+                    lookups.iter().all(|(field, value)| self.lookup.contains_key(&LookupKey {
+                        field: *field,
+                        value: value.clone(),
+                        id: *id,
+                    }))
+                })
+                .collect::<Vec<_>>();
+
+            let page_range = after_cursor(ids, query);
+            let total = matching.len() as u64;
+            (total, get_page(matching.into_iter().filter(|id| page_range.contains(id)), query))
+        };
+
+        let items = page_ids.into_iter()
+            .filter_map(|id| self.records.get(&id))
+            .collect();
+
+        EventRecordsPage {
+            items,
+            total,
+            next_cursor,
+        }
+    }
+
+    /// Records are added in time order, so a time range is a range of ids
+    fn get_time_range_ids(&self, filter: &EventRecordsFilter) -> Range<u64> {
+        let ids = self.id_range();
+
+        let start = filter.from_timestamp
+            .map(|timestamp| self.get_first_id_from(&ids, timestamp))
+            .unwrap_or(ids.start);
+        let end = filter.to_timestamp
+            .map(|timestamp| self.get_first_id_from(&ids, timestamp.saturating_add(1)))
+            .unwrap_or(ids.end);
+
+        start..end.max(start)
+    }
+
+    fn get_first_id_from(&self, ids: &Range<u64>, timestamp: u64) -> u64 {
+        let mut low = ids.start;
+        let mut high = ids.end;
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.records.get(&middle).is_some_and(|record| record.record().timestamp < timestamp) {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        low
+    }
+}
+
+fn get_record_lookups<TEvent: IndexedEvent>(record: &GenericEventRecord<TEvent>) -> Vec<(LookupField, String)> {
+    let mut lookups = vec![
+        (LookupField::Correlation, record.correlation_id.clone()),
+        (LookupField::EventType, record.event.type_str().to_string()),
+    ];
+
+    if let Some(user) = record.user {
+        lookups.push((LookupField::User, user.to_text()));
+    }
+
+    if let Some(strategy_id) = record.event.strategy_id() {
+        lookups.push((LookupField::Strategy, strategy_id.to_string()));
+    }
+
+    for pool_id in record.event.pool_ids() {
+        lookups.push((LookupField::Pool, pool_id.to_string()));
+    }
+
+    lookups
+}
+
+fn get_filter_lookups(filter: &EventRecordsFilter) -> Vec<(LookupField, String)> {
+    vec![
+        (LookupField::Correlation, filter.correlation_id.clone()),
+        (LookupField::User, filter.user.map(|user| user.to_text())),
+        (LookupField::Strategy, filter.strategy_id.clone()),
+        (LookupField::Pool, filter.pool_id.clone()),
+        (LookupField::EventType, filter.event_type.clone()),
+    ]
+        .into_iter()
+        .filter_map(|(field, value)| value.map(|value| (field, value)))
+        .collect()
+}
+
+/// Ids following the cursor in the requested order
+fn after_cursor(ids: Range<u64>, query: &EventRecordsQuery) -> Range<u64> {
+    match (query.cursor, &query.sort_order) {
+        (None, _) => ids,
+        (Some(cursor), SortOrder::Asc) => cursor.saturating_add(1).max(ids.start)..ids.end,
+        (Some(cursor), SortOrder::Desc) => ids.start..cursor.min(ids.end),
+    }
+}
+
+/// Takes a page of ids and the cursor of the next page, if there is one
+fn get_page(ids: impl DoubleEndedIterator<Item = u64>, query: &EventRecordsQuery) -> (Vec<u64>, Option<u64>) {
+    let limit = query.limit.min(MAX_EVENT_RECORDS_PAGE_SIZE) as usize;
+
+    let mut page: Vec<u64> = match query.sort_order {
+        SortOrder::Asc => ids.take(limit + 1).collect(),
+        SortOrder::Desc => ids.rev().take(limit + 1).collect(),
+    };
+
+    if page.len() > limit {
+        page.truncate(limit);
+        let next_cursor = page.last().copied();
+        (page, next_cursor)
+    } else {
+        (page, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_stable_structures::VectorMemory;
+
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+    enum TestEvent {
+        Deposit { strategy_id: String, pool_id: String },
+        Swap { pool_id: String },
+    }
+
+    impl IndexedEvent for TestEvent {
+        fn type_str(&self) -> &str {
+            match self {
+                Self::Deposit { .. } => "Deposit",
+                Self::Swap { .. } => "Swap",
+            }
+        }
+
+        fn strategy_id(&self) -> Option<&str> {
+            match self {
+                Self::Deposit { strategy_id, .. } => Some(strategy_id),
+                Self::Swap { .. } => None,
+            }
+        }
+
+        fn pool_ids(&self) -> Vec<&str> {
+            match self {
+                Self::Deposit { pool_id, .. } | Self::Swap { pool_id } => vec![pool_id],
+            }
+        }
+    }
+
+    type TestStore = EventStore<GenericEventRecord<TestEvent>, VectorMemory>;
+
+    fn store() -> TestStore {
+        EventStore::init(VectorMemory::default(), VectorMemory::default(), VectorMemory::default())
+    }
+
+    fn add_record(store: &mut TestStore, event: TestEvent, timestamp: u64) {
+        store.append(GenericEventRecord {
+            id: store.next_id(),
+            timestamp,
+            event,
+            correlation_id: timestamp.to_string(),
+            user: None,
+            prev_hash: None,
+            payload: Vec::new(),
+        });
+    }
+
+    fn deposit(pool_id: &str) -> TestEvent {
+        TestEvent::Deposit { strategy_id: "strategy".to_string(), pool_id: pool_id.to_string() }
+    }
+
+    fn swap(pool_id: &str) -> TestEvent {
+        TestEvent::Swap { pool_id: pool_id.to_string() }
+    }
+
+    fn query(filter: EventRecordsFilter) -> EventRecordsQuery {
+        EventRecordsQuery {
+            filter,
+            cursor: None,
+            limit: 10,
+            sort_order: SortOrder::Asc,
+        }
+    }
+
+    fn ids(page: &EventRecordsPage<GenericEventRecord<TestEvent>>) -> Vec<u64> {
+        page.items.iter().map(|record| record.id).collect()
+    }
+
+    mod append {
+        use super::*;
+
+        #[test]
+        fn links_records_to_hash_of_previous_record() {
+            let mut store = store();
+            add_record(&mut store, deposit("pool"), 1);
+            add_record(&mut store, deposit("pool"), 2);
+
+            let first = store.get(0).unwrap();
+            let second = store.get(1).unwrap();
+
+            assert_eq!(first.prev_hash, None);
+            assert_eq!(second.prev_hash, Some(hash_event_record(&first)));
+            assert_eq!(store.tip_hash(), Some(hash_event_record(&second)));
+        }
+
+        #[test]
+        fn keeps_chain_after_oldest_records_are_removed() {
+            let mut store = store();
+            for timestamp in 1..=3 {
+                add_record(&mut store, deposit("pool"), timestamp);
+            }
+
+            store.remove_before(2);
+            add_record(&mut store, deposit("pool"), 4);
+
+            assert_eq!(store.get(3).unwrap().prev_hash, Some(hash_event_record(&store.get(2).unwrap())));
+        }
+    }
+
+    mod remove_before {
+        use super::*;
+
+        #[test]
+        fn removes_oldest_records_and_keeps_ids_increasing() {
+            let mut store = store();
+            for timestamp in 1..=5 {
+                add_record(&mut store, deposit("pool"), timestamp);
+            }
+
+            store.remove_before(3);
+            add_record(&mut store, deposit("pool"), 6);
+
+            assert_eq!(store.len(), 3);
+            assert_eq!(store.id_range(), 3..6);
+            assert_eq!(ids(&store.query(&query(EventRecordsFilter::default()))), vec![3, 4, 5]);
+        }
+
+        #[test]
+        fn removes_lookups_of_removed_records() {
+            let mut store = store();
+            add_record(&mut store, swap("pool1"), 1);
+            add_record(&mut store, swap("pool2"), 2);
+            add_record(&mut store, swap("pool1"), 3);
+
+            store.remove_before(1);
+
+            let by_pool = EventRecordsFilter { pool_id: Some("pool1".to_string()), ..Default::default() };
+            let page = store.query(&query(by_pool));
+
+            assert_eq!(ids(&page), vec![2]);
+            assert_eq!(page.total, 1);
+        }
+    }
+
+    mod query {
+        use super::*;
+
+        #[test]
+        fn finds_time_range_after_oldest_records_are_removed() {
+            let mut store = store();
+            for timestamp in [10, 20, 30, 40, 50] {
+                add_record(&mut store, deposit("pool"), timestamp);
+            }
+            store.remove_before(2);
+
+            let filter = EventRecordsFilter {
+                from_timestamp: Some(10),
+                to_timestamp: Some(40),
+                ..Default::default()
+            };
+            let page = store.query(&query(filter));
+
+            assert_eq!(ids(&page), vec![2, 3]);
+            assert_eq!(page.total, 2);
+        }
+
+        #[test]
+        fn combines_strategy_and_pool_lookups() {
+            let mut store = store();
+            add_record(&mut store, deposit("pool1"), 1);
+            add_record(&mut store, swap("pool1"), 2);
+            add_record(&mut store, deposit("pool2"), 3);
+
+            let filter = EventRecordsFilter {
+                strategy_id: Some("strategy".to_string()),
+                pool_id: Some("pool2".to_string()),
+                ..Default::default()
+            };
+
+            assert_eq!(ids(&store.query(&query(filter))), vec![2]);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use errors::internal_error::error::InternalError;
+
+/// Correlation and span ids could not be seeded with randomness, they stay seeded with the time
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct ContextIdsSeedingFailed {
+    pub error: InternalError,
+}
@@ -1 +1,2 @@
 pub mod pool_events;
+pub mod context_events;
@@ -48,3 +48,10 @@ pub struct WithdrawLiquidityFromPoolFailed {
     pub shares: Nat,
     pub error: InternalError,
 }
+
+// Pool stats requests
+/// Pools requested by another canister which are not registered, no stats are returned for them
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct PoolsNotFound {
+    pub pool_ids: Vec<String>,
+}
@@ -1,5 +1,6 @@
-use candid::{CandidType, Deserialize, Principal};
+use candid::{CandidType, Decode, Deserialize, Principal};
 use serde::Serialize;
+use serde::de::DeserializeOwned;
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct GenericEventRecord<TEvent> {
@@ -8,4 +9,16 @@ pub struct GenericEventRecord<TEvent> {
     pub event: TEvent,
     pub correlation_id: String,
     pub user: Option<Principal>,
+    /// Hash of the previous record, none for the first record of the chain
+    pub prev_hash: Option<Vec<u8>>,
+    /// Event as encoded when the record was appended, the record hash covers these bytes
+    #[serde(with = "serde_bytes")]
+    pub payload: Vec<u8>,
+}
+
+impl<TEvent> GenericEventRecord<TEvent> {
+    /// Decodes the event from the bytes it was recorded with
+    pub fn decode_payload<TDecoded: CandidType + DeserializeOwned>(&self) -> Result<TDecoded, candid::Error> {
+        Decode!(&self.payload, TDecoded)
+    }
 }
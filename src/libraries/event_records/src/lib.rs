@@ -1,2 +1,8 @@
 pub mod generic_event_record;
 pub mod events;
+pub mod event_query;
+pub mod event_store;
+pub mod event_chain;
+pub mod archive;
+pub mod archive_client;
+pub mod archiver;
@@ -1,13 +1,17 @@
 use candid::{Principal, Nat};
+use ic_cdk::api::time;
 use ic_cdk::id;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 
 use ::types::CanisterId;
 use errors::internal_error::error::InternalError;
 use errors::internal_error::error::build_error_code;
+use icrc_ledger_canister::icrc2_allowance::{Allowance, AllowanceArgs};
 use icrc_ledger_canister::icrc2_approve::ApproveArgs;
 use icrc_ledger_canister::updates::icrc2_transfer_from::Args as Icrc2TransferFromArgs;
 use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
 
 pub async fn icrc1_decimals(ledger_canister_id: CanisterId) -> Result<u8, InternalError> {
     icrc_ledger_canister_c2c_client::icrc1_decimals(ledger_canister_id)
@@ -24,20 +28,105 @@ pub async fn icrc1_decimals(ledger_canister_id: CanisterId) -> Result<u8, Intern
         })
 }
 
-pub async fn icrc2_approve(spender: Principal, ledger_canister_id: CanisterId, amount: Nat) -> Result<Nat, InternalError> {
+/// Approvals expire after this time, long enough for the operation to spend them
+pub const APPROVAL_EXPIRATION: u64 = 300; // 5 minutes
+
+thread_local! {
+    /// Spender and ledger pairs approved by this canister which are not revoked yet
+    static GRANTED_APPROVALS: RefCell<HashSet<(Principal, CanisterId)>> = RefCell::new(HashSet::new());
+}
+
+pub async fn icrc2_allowance(spender: Principal, ledger_canister_id: CanisterId) -> Result<Allowance, InternalError> {
+    let args = AllowanceArgs {
+        account: Account { owner: id(), subaccount: None },
+        spender: spender.into(),
+    };
+
+    icrc_ledger_canister_c2c_client::icrc2_allowance(ledger_canister_id, &args)
+        .await
+        .map_err(|error| {
+            InternalError::external_service(
+                build_error_code(1100, 4, 8), // 1100 04 08
+                "icrc_ledger_client::icrc2_allowance".to_string(),
+                format!("IC error calling 'icrc_ledger_canister_c2c_client::icrc2_allowance': {error:?}"),
+                Some(HashMap::from([
+                    ("spender".to_string(), spender.to_text()),
+                    ("ledger_canister_id".to_string(), ledger_canister_id.to_text()),
+                ]))
+            )
+        })
+}
+
+/// Adds `amount` plus the transfer fee of the ledger to the allowance of the spender,
+/// so the allowances of concurrent operations with the same spender stay in place.
+/// The approval expires after `APPROVAL_EXPIRATION` and fails if the allowance
+/// changed since it was read.
+pub async fn icrc2_approve(
+    spender: Principal,
+    ledger_canister_id: CanisterId,
+    amount: Nat,
+    fee: Nat,
+) -> Result<Nat, InternalError> {
+    let current_allowance = icrc2_allowance(spender, ledger_canister_id).await?;
+    let expires_at = time() + APPROVAL_EXPIRATION * 1_000_000_000;
+
+    let block_index = approve(
+        spender,
+        ledger_canister_id,
+        current_allowance.allowance.clone() + amount + fee,
+        current_allowance.allowance,
+        Some(expires_at),
+    ).await?;
+
+    GRANTED_APPROVALS.with(|approvals| approvals.borrow_mut().insert((spender, ledger_canister_id)));
+
+    Ok(block_index)
+}
+
+/// Sets the allowance of the spender to zero if anything is left of it
+pub async fn icrc2_revoke(spender: Principal, ledger_canister_id: CanisterId) -> Result<(), InternalError> {
+    let current_allowance = icrc2_allowance(spender, ledger_canister_id).await?;
+
+    if current_allowance.allowance > 0u8 {
+        approve(
+            spender,
+            ledger_canister_id,
+            Nat::from(0u8),
+            current_allowance.allowance,
+            None,
+        ).await?;
+    }
+
+    GRANTED_APPROVALS.with(|approvals| approvals.borrow_mut().remove(&(spender, ledger_canister_id)));
+
+    Ok(())
+}
+
+/// Spender and ledger pairs which may still hold an allowance granted by this canister
+pub fn granted_approvals() -> Vec<(Principal, CanisterId)> {
+    GRANTED_APPROVALS.with(|approvals| approvals.borrow().iter().copied().collect())
+}
+
+async fn approve(
+    spender: Principal,
+    ledger_canister_id: CanisterId,
+    amount: Nat,
+    expected_allowance: Nat,
+    expires_at: Option<u64>,
+) -> Result<Nat, InternalError> {
     let args = ApproveArgs {
         from_subaccount: None,
-            spender: spender.into(),
-            amount: Nat::from(99999999999999 as u128), //TODO: amount + fee
-            expected_allowance: None,
-            expires_at: None,
-            fee: None,
-            memo: None,
-            created_at_time: None,
+        spender: spender.into(),
+        amount: amount.clone(),
+        expected_allowance: Some(expected_allowance),
+        expires_at,
+        fee: None,
+        memo: None,
+        created_at_time: None,
     };
 
     icrc_ledger_canister_c2c_client::icrc2_approve(
-        ledger_canister_id.clone(),
+        ledger_canister_id,
         &args,
     ).await
         .map_err(|error| {
@@ -126,3 +215,18 @@ pub async fn icrc1_fee(ledger_canister_id: CanisterId) -> Result<Nat, InternalEr
             )
         })
 }
+
+pub async fn icrc1_metadata(ledger_canister_id: CanisterId) -> Result<Vec<(String, MetadataValue)>, InternalError> {
+    icrc_ledger_canister_c2c_client::icrc1_metadata(ledger_canister_id)
+        .await
+        .map_err(|error| {
+            InternalError::external_service(
+                build_error_code(1100, 4, 7), // 1100 04 07
+                "icrc_ledger_client::icrc1_metadata".to_string(),
+                format!("IC error calling 'icrc_ledger_canister_c2c_client::icrc1_metadata': {error:?}"),
+                Some(HashMap::from([
+                    ("ledger_canister_id".to_string(), ledger_canister_id.to_text()),
+                ]))
+            )
+        })
+}
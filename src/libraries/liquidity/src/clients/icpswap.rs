@@ -5,7 +5,7 @@ use num_traits::ToPrimitive;
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use utils::util::{nat_to_u64, int_to_nat};
+use utils::util::{nat_to_u64, nat_to_u128, nat_to_f64, int_to_nat};
 use types::CanisterId;
 use providers::providers_factory::ProviderImpls;
 use providers::icpswap::ICPSwapProvider;
@@ -20,58 +20,94 @@ use icpswap_swap_factory_canister::ICPSwapPool;
 use icpswap_swap_calculator_canister::getTokenAmountByLiquidity::GetTokenAmountByLiquidityResponse;
 use icpswap_node_index_canister::getAllTokens::TokenData;
 use icpswap_tvl_storage_canister::getPoolChartTvl::PoolChartTvl;
-use swap::token_swaps::icpswap::SLIPPAGE_TOLERANCE;
+use swap::swap_limits;
+use types::swap_tokens::SwapLimits;
 use errors::internal_error::error::{InternalError, build_error_code};
-use utils::constants::CKUSDT_TOKEN_CANISTER_ID;
 use icrc_ledger_client;
+use token_registry::ledger_fees::{self, APPROVED_TRANSFER_FEE_COUNT};
 use types::liquidity::{
     AddLiquidityResponse,
     WithdrawLiquidityResponse,
     TokensFee,
     GetPositionByIdResponse,
     GetPoolDataResponse,
+    QuoteAddLiquidityResponse,
+    QuoteWithdrawLiquidityResponse,
+    LiquidityFees,
 };
+use types::exchange_id::ExchangeId;
 
 use crate::liquidity_client::LiquidityClient;
+use crate::liquidity_adapters::LiquidityAdapter;
 
 // Use full range of prices for liquidity in the pool
 const TICK_LOWER: i32 = -887220;
 const TICK_UPPER: i32 = 887220;
 
+/// Liquidity client of a resolved ICPSwap pool, it can only be built once the pool of the tokens is known
 pub struct ICPSwapLiquidityClient {
-    provider_impls: ProviderImpls,
-    canister_id: Option<CanisterId>,
+    icpswap_provider: Arc<dyn ICPSwapProvider + Send + Sync>,
+    canister_id: CanisterId,
     token0: CanisterId, // token0 may be token1 in the pool and vice versa
     token1: CanisterId, // token1 may be token0 in the pool and vice versa
-    pool: Option<ICPSwapPool>,
+    pool: ICPSwapPool,
+    swap_limits: SwapLimits,
 }
 
 impl ICPSwapLiquidityClient {
-    pub fn new(
+    /// Looks up the ICPSwap pool of the tokens and builds the client for it
+    pub async fn resolve(
         provider_impls: ProviderImpls,
         token0: CanisterId,
         token1: CanisterId,
-    ) -> ICPSwapLiquidityClient {
-        ICPSwapLiquidityClient {
-            provider_impls,
-            canister_id: None,
+    ) -> Result<ICPSwapLiquidityClient, InternalError> {
+        let pool = provider_impls.get::<dyn ICPSwapProvider + Send + Sync>(ExchangeId::ICPSwap)?
+            .get_pool(token0, token1).await?;
+
+        Self::from_pool(provider_impls, token0, token1, pool)
+    }
+
+    /// Builds the client for a known pool, fails if the pool does not hold the tokens
+    pub fn from_pool(
+        provider_impls: ProviderImpls,
+        token0: CanisterId,
+        token1: CanisterId,
+        pool: ICPSwapPool,
+    ) -> Result<ICPSwapLiquidityClient, InternalError> {
+        let client = ICPSwapLiquidityClient {
+            icpswap_provider: provider_impls.get::<dyn ICPSwapProvider + Send + Sync>(ExchangeId::ICPSwap)?,
+            canister_id: pool.canisterId,
             token0,
             token1,
-            pool: None,
-        }
-    }
+            pool,
+            swap_limits: SwapLimits::default(),
+        };
 
-    pub async fn with_pool(mut self) -> Result<Self, InternalError> {
-        let pool = self.get_pool(self.token0.clone(), self.token1.clone()).await?;
+        client.is_zero_for_one_swap_direction()?;
 
-        self.pool = Some(pool.clone());
-        self.canister_id = Some(pool.canisterId);
+        Ok(client)
+    }
 
-        Ok(self)
+    pub fn with_swap_limits(mut self, swap_limits: SwapLimits) -> Self {
+        self.swap_limits = swap_limits;
+        self
+    }
+
+    /// Token amount of the position, which the calculator must not return negative
+    fn position_token_amount(amount: Int, position_id: u64) -> Result<Nat, InternalError> {
+        int_to_nat(amount.clone()).ok_or_else(|| InternalError::business_logic(
+            build_error_code(2102, 3, 11), // 2102 03 11
+            "ICPSwapLiquidityClient::position_token_amount".to_string(),
+            "Negative token amount of ICPSwap position".to_string(),
+            Some(HashMap::from([
+                ("position_id".to_string(), position_id.to_string()),
+                ("amount".to_string(), amount.to_string()),
+            ])),
+        ))
     }
 
     fn icpswap_provider(&self) -> &Arc<dyn ICPSwapProvider + Send + Sync> {
-        &self.provider_impls.icpswap
+        &self.icpswap_provider
     }
 
     fn extract_token_decimals(&self, meta: &Vec<(String, TokenMetadataValue)>) -> Option<u128> {
@@ -93,7 +129,7 @@ impl ICPSwapLiquidityClient {
         let token_in_str = self.token0.to_text();
         let token_out_str = self.token1.to_text();
 
-        let pool = self.pool.as_ref().unwrap();
+        let pool = &self.pool;
 
         match (pool.token0.address.as_str(), pool.token1.address.as_str()) {
             (t0, t1) if t0 == token_in_str && t1 == token_out_str => Ok(TokensFee {
@@ -122,7 +158,7 @@ impl ICPSwapLiquidityClient {
         let token_in_str = self.token0.to_text();
         let token_out_str = self.token1.to_text();
 
-        let pool = self.pool.as_ref().unwrap();
+        let pool = &self.pool;
 
         match (pool.token0.address.as_str(), pool.token1.address.as_str()) {
             (t0, t1) if t0 == token_in_str && t1 == token_out_str => Ok(true),
@@ -141,14 +177,8 @@ impl ICPSwapLiquidityClient {
         }
     }
 
-    async fn get_pool(&self, token0: CanisterId, token1: CanisterId) -> Result<ICPSwapPool, InternalError> {
-        let pool =  self.icpswap_provider().get_pool(token0, token1).await?;
-
-        Ok(pool)
-    }
-
     async fn get_token_meta(&self) -> Result<TokenMeta, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let token_meta = self.icpswap_provider().get_token_meta(canister_id.clone()).await?;
 
@@ -156,7 +186,7 @@ impl ICPSwapLiquidityClient {
     }
 
     async fn deposit_from(&self, token: CanisterId, amount: Nat, token_fee: Nat) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let deposited_amount = self.icpswap_provider().deposit_from(
             canister_id.clone(),
@@ -169,7 +199,7 @@ impl ICPSwapLiquidityClient {
     }
 
     async fn metadata(&self) -> Result<Metadata, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let metadata = self.icpswap_provider().metadata(canister_id.clone()).await?;
 
@@ -197,7 +227,7 @@ impl ICPSwapLiquidityClient {
         zero_for_one: bool,
         amount_out_minimum: Nat
     ) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let amount_out = self.icpswap_provider().quote(
             canister_id.clone(),
@@ -219,7 +249,7 @@ impl ICPSwapLiquidityClient {
         tick_lower: i32,
         tick_upper: i32
     ) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let minted_amount = self.icpswap_provider().mint(
             canister_id.clone(),
@@ -241,7 +271,7 @@ impl ICPSwapLiquidityClient {
         zero_for_one: bool,
         amount_out_minimum: Nat
     ) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let amount_out_nat = self.icpswap_provider().swap(
             canister_id.clone(),
@@ -259,7 +289,7 @@ impl ICPSwapLiquidityClient {
         amount0_desired: String,
         amount1_desired: String
     ) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let amount_out_nat = self.icpswap_provider().increase_liquidity(
             canister_id.clone(),
@@ -276,7 +306,7 @@ impl ICPSwapLiquidityClient {
         position_id: Nat,
         liquidity: String
     ) -> Result<DecreaseLiquidityResponse, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let amount_out_nat = self.icpswap_provider().decrease_liquidity(
             canister_id.clone(),
@@ -293,7 +323,7 @@ impl ICPSwapLiquidityClient {
         amount: Nat,
         token_fee: Nat
     ) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let amount_out_nat = self.icpswap_provider().withdraw(
             canister_id.clone(),
@@ -309,7 +339,7 @@ impl ICPSwapLiquidityClient {
         &self,
         position_id: Nat
     ) -> Result<ClaimResponse, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let claim_response = self.icpswap_provider().claim(
             canister_id.clone(),
@@ -320,7 +350,7 @@ impl ICPSwapLiquidityClient {
     }
 
     async fn get_user_position_ids_by_principal(&self) -> Result<Vec<Nat>, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
         let principal = ic_cdk::api::id();
 
         let position_ids = self.icpswap_provider().get_user_position_ids_by_principal(
@@ -332,7 +362,7 @@ impl ICPSwapLiquidityClient {
     }
 
     async fn get_user_positions_by_principal(&self) -> Result<Vec<UserPositionWithId>, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
         let principal = ic_cdk::api::id();
 
         let user_positions = self.icpswap_provider().get_user_positions_by_principal(
@@ -344,7 +374,7 @@ impl ICPSwapLiquidityClient {
     }
 
     async fn get_user_position(&self, position_id: Nat) -> Result<UserPosition, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         let user_position = self.icpswap_provider().get_user_position(
             canister_id.clone(),
@@ -381,11 +411,65 @@ impl ICPSwapLiquidityClient {
     async fn get_tvl_storage_canister(&self) -> Result<String, InternalError> {
         let tvl_storage_canister_response = self.icpswap_provider().get_tvl_storage_canister().await?;
 
-        Ok(tvl_storage_canister_response[0].clone())
+        tvl_storage_canister_response.first()
+            .cloned()
+            .ok_or_else(|| InternalError::not_found(
+                build_error_code(2102, 1, 12), // 2102 01 12
+                "ICPSwapLiquidityClient::get_tvl_storage_canister".to_string(),
+                "No TVL storage canister for ICPSwap".to_string(),
+                None,
+            ))
+    }
+
+    // Token0 and token1 in the pool are determined by the token0 and token1 in the metadata
+    fn order_amounts_for_position(&self, metadata: &Metadata, amount0: Nat, amount1: Nat) -> Option<(String, String)> {
+        match (
+            self.token0.to_text() == metadata.token0.address,
+            self.token1.to_text() == metadata.token1.address,
+            self.token0.to_text() == metadata.token1.address,
+            self.token1.to_text() == metadata.token0.address,
+        ) {
+            // Token0 is token0 in the pool and token1 is token1 in the pool
+            (true, true, _, _) => Some((amount0.to_string(), amount1.to_string())),
+            // Token1 is token0 in the pool and token0 is token1 in the pool
+            (_, _, true, true) => Some((amount1.to_string(), amount0.to_string())),
+            _ => None,
+        }
+    }
+
+    // In case of no position exists, mint new position
+    // In case of position exists, increase liquidity
+    async fn mint_or_increase_liquidity(
+        &self,
+        metadata: &Metadata,
+        user_position_ids: &[Nat],
+        amount0_for_position: String,
+        amount1_for_position: String,
+    ) -> Result<Nat, InternalError> {
+        match user_position_ids {
+            [] => {
+                self.mint(
+                    metadata.token0.address.clone(),
+                    metadata.token1.address.clone(),
+                    amount0_for_position,
+                    amount1_for_position,
+                    Nat::from(metadata.fee.clone()),
+                    TICK_LOWER,
+                    TICK_UPPER,
+                ).await
+            }
+            [position_id, ..] => {
+                self.increase_liquidity(
+                    position_id.clone(),
+                    amount0_for_position,
+                    amount1_for_position,
+                ).await
+            }
+        }
     }
 
     async fn get_pool_chart_tvl(&self, tvl_storage_canister_id: Principal) -> Result<Vec<PoolChartTvl>, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
         let offset = Nat::from(0u128);
         let limit = Nat::from(0u128);
 
@@ -403,7 +487,7 @@ impl ICPSwapLiquidityClient {
 #[async_trait]
 impl LiquidityClient for ICPSwapLiquidityClient {
     fn canister_id(&self) -> CanisterId {
-        self.canister_id.as_ref().unwrap().clone()
+        self.canister_id
     }
 
     async fn add_liquidity_to_pool(&self, amount: Nat) -> Result<AddLiquidityResponse, InternalError> {
@@ -411,9 +495,9 @@ impl LiquidityClient for ICPSwapLiquidityClient {
         // 1. Get user position ids
         // 2. Get token fees
         // 3. Get metadata
-        // 4. Approve before deposit
-        // 5. Deposit
-        // 6. Quote
+        // 4. Quote and check the price impact
+        // 5. Approve before deposit
+        // 6. Deposit
         // 7. Swap half of the token0 amount for the pool
         // 8. Mint new position or increase liquidity
 
@@ -422,33 +506,24 @@ impl LiquidityClient for ICPSwapLiquidityClient {
         // 1. Get user position ids
         let user_position_ids = self.get_user_position_ids_by_principal().await?;
 
-        // 2. Get token fees
-        let token0_fee = icrc_ledger_client::icrc1_fee(self.token0.clone()).await?;
+        // 2. Get token fees, the approval and the deposit are paid out of the amount
+        let token0_deduction = ledger_fees::deduct_ledger_fees(
+            self.token0,
+            &amount,
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
+        let token0_fee = token0_deduction.fee.clone();
 
         // 3. Get metadata
         let metadata = self.metadata().await?;
-        
-        // 4. Approve before deposit
-        icrc_ledger_client::icrc2_approve(
-            self.canister_id(),
-            self.token0.clone(),
-            amount.clone()
-        ).await?;
-
-        // 5. Deposit
-        let amount0_deposited = self.deposit_from(
-            self.token0.clone(),
-            amount.clone(),
-            token0_fee.clone()
-        ).await?;
 
         // Divided by 2 to swap half of the token0 amount to token1 for the pool
-        let amount0_for_swap = amount0_deposited.clone().div(2u32);
-        let amount0_for_pool = amount0_deposited.clone() - amount0_for_swap.clone();
+        let amount0_for_swap = token0_deduction.amount.clone().div(2u32);
         let amount1_out_minimum = Nat::from(0u128);
         let is_zero_for_one_swap_direction = self.is_zero_for_one_swap_direction()?;
 
-        // 6. Quote
+        // 4. Quote
+        // Checked before the deposit, so that a rejected add leaves no tokens in the pool
         // ICPSWAP provider is more convenient for swap for adding liquidity to ICPSwap pool
         let quote_amount = self.quote(
             amount0_for_swap.clone(),
@@ -456,8 +531,44 @@ impl LiquidityClient for ICPSwapLiquidityClient {
             amount1_out_minimum.clone()
         ).await?;
 
+        let reference_amount_in = swap_limits::reference_amount(nat_to_u128(&amount0_for_swap));
+        let reference_amount_out = self.quote(
+            Nat::from(reference_amount_in),
+            is_zero_for_one_swap_direction,
+            amount1_out_minimum.clone()
+        ).await?;
+
+        swap_limits::check_price_impact(
+            &self.swap_limits,
+            swap_limits::price_impact_bps(
+                reference_amount_in,
+                nat_to_u128(&reference_amount_out),
+                nat_to_u128(&amount0_for_swap),
+                nat_to_u128(&quote_amount),
+            ),
+            nat_to_u128(&amount0_for_swap),
+            nat_to_u128(&quote_amount),
+        )?;
+
         // Considering slippage tolerance
-        let amount1_min_after_swap = quote_amount.clone().div(1000u128) * (1000u128 - SLIPPAGE_TOLERANCE);
+        let amount1_min_after_swap = Nat::from(self.swap_limits.min_amount_out(nat_to_u128(&quote_amount)));
+
+        // 5. Approve before deposit
+        icrc_ledger_client::icrc2_approve(
+            self.canister_id(),
+            self.token0.clone(),
+            token0_deduction.amount.clone(),
+            token0_fee.clone()
+        ).await?;
+
+        // 6. Deposit
+        let amount0_deposited = self.deposit_from(
+            self.token0.clone(),
+            token0_deduction.amount.clone(),
+            token0_fee.clone()
+        ).await?;
+
+        let amount0_for_pool = amount0_deposited.clone() - amount0_for_swap.clone();
 
         // 7. Swap half of the token0 amount for the pool
         // ICPSWAP provider is more convenient for swap for adding liquidity to ICPSwap pool
@@ -469,60 +580,128 @@ impl LiquidityClient for ICPSwapLiquidityClient {
 
         // Token0 and token1 in the pool are determined by the token0 and token1 in the metadata
         // So we need to determine the tokens amount order in the pool for minting new position or increasing liquidity
-        let (amount0_for_position, amount1_for_position) = match (
-            self.token0.to_text() == metadata.token0.address,
-            self.token1.to_text() == metadata.token1.address,
-            self.token0.to_text() == metadata.token1.address,
-            self.token1.to_text() == metadata.token0.address,
-        ) {
-            // Token0 is token0 in the pool and token1 is token1 in the pool
-            (true, true, _, _) => (amount0_for_pool.to_string(), amount1_swapped_for_pool.to_string()),
-            // Token1 is token0 in the pool and token0 is token1 in the pool
-            (_, _, true, true) => (amount1_swapped_for_pool.to_string(), amount0_for_pool.to_string()),
-            _ => {
-                return Err(InternalError::business_logic(
-                    build_error_code(2102, 3, 3), // 2102 03 03
-                    error_context.clone(),
-                    "Token order does not match pool metadata".to_string(),
-                    Some(HashMap::from([
-                        ("token0".to_string(), self.token0.to_text()),
-                        ("token1".to_string(), self.token1.to_text()),
-                        ("metadata_token0".to_string(), metadata.token0.address),
-                        ("metadata_token1".to_string(), metadata.token1.address),
-                    ])),
-                ));
-            }
-        };
+        let (amount0_for_position, amount1_for_position) = self.order_amounts_for_position(
+            &metadata,
+            amount0_for_pool.clone(),
+            amount1_swapped_for_pool.clone(),
+        ).ok_or_else(|| {
+            InternalError::business_logic(
+                build_error_code(2102, 3, 3), // 2102 03 03
+                error_context.clone(),
+                "Token order does not match pool metadata".to_string(),
+                Some(HashMap::from([
+                    ("token0".to_string(), self.token0.to_text()),
+                    ("token1".to_string(), self.token1.to_text()),
+                    ("metadata_token0".to_string(), metadata.token0.address.clone()),
+                    ("metadata_token1".to_string(), metadata.token1.address.clone()),
+                ])),
+            )
+        })?;
 
-        // In case of no position exists, mint new position
-        // In case of position exists, increase liquidity
-        let position_id = match user_position_ids.as_slice() {
-            [] => {
-                // 8. Mint new position if no position exists
-                self.mint(
-                    metadata.token0.address.clone(),
-                    metadata.token1.address.clone(),
-                    amount0_for_position.to_string(),
-                    amount1_for_position.to_string(),
-                    Nat::from(metadata.fee.clone()),
-                    TICK_LOWER,
-                    TICK_UPPER,
-                ).await?
-            }
-            [position_id, ..] => {
-                // 8. Increase liquidity if position already exists
-                self.increase_liquidity(
-                    position_id.clone(),
-                    amount0_for_position.to_string(),
-                    amount1_for_position.to_string(),
-                ).await?
-            }
-        };
+        // 8. Mint new position or increase liquidity
+        let position_id = self.mint_or_increase_liquidity(
+            &metadata,
+            &user_position_ids,
+            amount0_for_position,
+            amount1_for_position,
+        ).await?;
 
         Ok(AddLiquidityResponse {
             token_0_amount: Nat::from(amount0_for_pool),
             token_1_amount: Nat::from(amount1_swapped_for_pool),
             position_id: nat_to_u64(&position_id),
+            ledger_fees: LiquidityFees {
+                token_0_fee: token0_deduction.fees_paid,
+                token_1_fee: Nat::from(0u8),
+            },
+        })
+    }
+
+    async fn add_liquidity_to_pool_with_amounts(
+        &self,
+        amount0: Nat,
+        amount1: Nat
+    ) -> Result<AddLiquidityResponse, InternalError> {
+        // Flow:
+        // 1. Get user position ids
+        // 2. Get token fees
+        // 3. Get metadata
+        // 4. Approve and deposit token0 and token1
+        // 5. Mint new position or increase liquidity
+
+        let error_context = "ICPSwapLiquidityClient::add_liquidity_to_pool_with_amounts".to_string();
+
+        // 1. Get user position ids
+        let user_position_ids = self.get_user_position_ids_by_principal().await?;
+
+        // 2. Get token fees, the approvals and the deposits are paid out of the amounts
+        let token0_deduction = ledger_fees::deduct_ledger_fees(self.token0, &amount0, APPROVED_TRANSFER_FEE_COUNT).await?;
+        let token1_deduction = ledger_fees::deduct_ledger_fees(self.token1, &amount1, APPROVED_TRANSFER_FEE_COUNT).await?;
+
+        // 3. Get metadata
+        let metadata = self.metadata().await?;
+
+        // 4. Approve and deposit token0 and token1
+        icrc_ledger_client::icrc2_approve(
+            self.canister_id(),
+            self.token0.clone(),
+            token0_deduction.amount.clone(),
+            token0_deduction.fee.clone()
+        ).await?;
+
+        let amount0_deposited = self.deposit_from(
+            self.token0.clone(),
+            token0_deduction.amount.clone(),
+            token0_deduction.fee.clone()
+        ).await?;
+
+        icrc_ledger_client::icrc2_approve(
+            self.canister_id(),
+            self.token1.clone(),
+            token1_deduction.amount.clone(),
+            token1_deduction.fee.clone()
+        ).await?;
+
+        let amount1_deposited = self.deposit_from(
+            self.token1.clone(),
+            token1_deduction.amount.clone(),
+            token1_deduction.fee.clone()
+        ).await?;
+
+        let (amount0_for_position, amount1_for_position) = self.order_amounts_for_position(
+            &metadata,
+            amount0_deposited.clone(),
+            amount1_deposited.clone(),
+        ).ok_or_else(|| {
+            InternalError::business_logic(
+                build_error_code(2102, 3, 8), // 2102 03 08
+                error_context.clone(),
+                "Token order does not match pool metadata".to_string(),
+                Some(HashMap::from([
+                    ("token0".to_string(), self.token0.to_text()),
+                    ("token1".to_string(), self.token1.to_text()),
+                    ("metadata_token0".to_string(), metadata.token0.address.clone()),
+                    ("metadata_token1".to_string(), metadata.token1.address.clone()),
+                ])),
+            )
+        })?;
+
+        // 5. Mint new position or increase liquidity
+        let position_id = self.mint_or_increase_liquidity(
+            &metadata,
+            &user_position_ids,
+            amount0_for_position,
+            amount1_for_position,
+        ).await?;
+
+        Ok(AddLiquidityResponse {
+            token_0_amount: amount0_deposited,
+            token_1_amount: amount1_deposited,
+            position_id: nat_to_u64(&position_id),
+            ledger_fees: LiquidityFees {
+                token_0_fee: token0_deduction.fees_paid,
+                token_1_fee: token1_deduction.fees_paid,
+            },
         })
     }
 
@@ -606,6 +785,126 @@ impl LiquidityClient for ICPSwapLiquidityClient {
         Ok(WithdrawLiquidityResponse {
             token_0_amount: amount0_to_withdraw,
             token_1_amount: amount1_to_withdraw,
+            ledger_fees: LiquidityFees::default(),
+        })
+    }
+
+    async fn quote_add_liquidity(&self, amount: Nat) -> Result<QuoteAddLiquidityResponse, InternalError> {
+        // Flow:
+        // 1. Get token fees
+        // 2. Quote half of the token0 amount to token1
+
+        // 1. Get token fees, the approval and the deposit are paid out of the amount
+        let token0_deduction = ledger_fees::deduct_ledger_fees(
+            self.token0,
+            &amount,
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
+
+        // Divided by 2 to swap half of the deposited token0 amount to token1 for the pool.
+        // The swap is made inside the pool, so token1 is not moved with the ledger.
+        let amount0_for_swap = token0_deduction.amount.clone().div(2u32);
+        let amount0_for_pool = token0_deduction.amount.clone() - amount0_for_swap.clone();
+        let is_zero_for_one_swap_direction = self.is_zero_for_one_swap_direction()?;
+
+        // 2. Quote, the pool fee is taken out of the quoted amount
+        let quote_amount = self.quote(
+            amount0_for_swap.clone(),
+            is_zero_for_one_swap_direction,
+            Nat::from(0u128)
+        ).await?;
+
+        Ok(QuoteAddLiquidityResponse {
+            token_0_for_swap: amount0_for_swap,
+            token_0_for_pool: amount0_for_pool,
+            token_1_for_pool: quote_amount,
+            swap_provider: ExchangeId::ICPSwap,
+            fees: LiquidityFees {
+                token_0_fee: token0_deduction.fees_paid,
+                token_1_fee: Nat::from(0u128),
+            },
+        })
+    }
+
+    async fn quote_withdraw_liquidity(
+        &self,
+        total_shares: Nat,
+        shares: Nat
+    ) -> Result<QuoteWithdrawLiquidityResponse, InternalError> {
+        // Flow:
+        // 1. Get user position ids
+        // 2. Get user position
+        // 3. Calculate how much liquidity would be withdrawn
+        // 4. Get token amounts by liquidity
+        // 5. Determine which token is token0 and which is token1 in the pool
+
+        let error_context = "ICPSwapLiquidityClient::quote_withdraw_liquidity".to_string();
+
+        // 1. Get user position ids
+        let user_position_ids = self.get_user_position_ids_by_principal().await?;
+
+        if user_position_ids.is_empty() {
+            return Err(InternalError::business_logic(
+                build_error_code(2102, 3, 6), // 2102 03 06
+                error_context.clone(),
+                "No position ids found for user".to_string(),
+                None,
+            ));
+        }
+
+        let position_id = user_position_ids[0].clone();
+
+        let metadata = self.metadata().await?;
+
+        // 2. Get user position
+        let user_position = self.get_user_position(position_id.clone()).await?;
+
+        // 3. Calculate how much liquidity would be withdrawn
+        let liquidity_to_withdraw = user_position.liquidity
+            .clone()
+            .mul(shares.clone())
+            .div(total_shares.clone());
+
+        // 4. Get token amounts by liquidity
+        let token_amounts = self.get_token_amount_by_liquidity(
+            metadata.sqrtPriceX96.clone(),
+            user_position.tickLower.clone(),
+            user_position.tickUpper.clone(),
+            liquidity_to_withdraw
+        ).await?;
+
+        let amount0 = Self::position_token_amount(token_amounts.amount0, nat_to_u64(&position_id))?;
+        let amount1 = Self::position_token_amount(token_amounts.amount1, nat_to_u64(&position_id))?;
+
+        // 5. Determine which token is token0 and which is token1 in the pool
+        let (amount0_to_withdraw, amount1_to_withdraw) = match (
+            self.token0.to_text() == metadata.token0.address,
+            self.token1.to_text() == metadata.token1.address,
+            self.token0.to_text() == metadata.token1.address,
+            self.token1.to_text() == metadata.token0.address,
+        ) {
+            (true, true, _, _) => (amount0, amount1),
+            (_, _, true, true) => (amount1, amount0),
+            _ => {
+                return Err(InternalError::business_logic(
+                    build_error_code(2102, 3, 7), // 2102 03 07
+                    error_context.clone(),
+                    "Token order does not match pool metadata".to_string(),
+                    Some(HashMap::from([
+                        ("token0".to_string(), self.token0.to_text()),
+                        ("token1".to_string(), self.token1.to_text()),
+                        ("metadata_token0".to_string(), metadata.token0.address),
+                        ("metadata_token1".to_string(), metadata.token1.address),
+                    ])),
+                ));
+            }
+        };
+
+        // The withdrawn amounts stay in the pool balance of the canister, no ledger fee is paid
+        Ok(QuoteWithdrawLiquidityResponse {
+            token_0_amount: amount0_to_withdraw,
+            token_1_amount: amount1_to_withdraw,
+            fees: LiquidityFees::default(),
         })
     }
 
@@ -632,8 +931,8 @@ impl LiquidityClient for ICPSwapLiquidityClient {
             liquidity.clone()
         ).await?;
 
-        let token0_amount = int_to_nat(token_amounts.amount0).unwrap() + token0_owed;
-        let token1_amount = int_to_nat(token_amounts.amount1).unwrap() + token1_owed;
+        let token0_amount = Self::position_token_amount(token_amounts.amount0, position_id)? + token0_owed;
+        let token1_amount = Self::position_token_amount(token_amounts.amount1, position_id)? + token1_owed;
 
         // 4. Get all tokens
         let all_tokens = self.get_all_tokens().await?;
@@ -659,9 +958,9 @@ impl LiquidityClient for ICPSwapLiquidityClient {
             }
         }
 
-        let token0_decimals = icrc_ledger_client::icrc1_decimals(self.token0.clone()).await?;
-        let token1_decimals = icrc_ledger_client::icrc1_decimals(self.token1.clone()).await?;
-        let usdt_decimals = icrc_ledger_client::icrc1_decimals(*CKUSDT_TOKEN_CANISTER_ID).await?;
+        let token0_decimals = token_registry::registry::decimals(self.token0.clone()).await?;
+        let token1_decimals = token_registry::registry::decimals(self.token1.clone()).await?;
+        let usdt_decimals = token_registry::registry::usd_token().await?.decimals;
 
         let token0_usd_amount = Nat::from(
             (nat_to_u64(&token0_amount) as f64
@@ -691,12 +990,217 @@ impl LiquidityClient for ICPSwapLiquidityClient {
     async fn get_pool_data(&self) -> Result<GetPoolDataResponse, InternalError> {
         let tvl_storage_canister_id = self.get_tvl_storage_canister().await?;
 
-        let pool_chart_tvl_response = self.get_pool_chart_tvl(
-            Principal::from_text(tvl_storage_canister_id).unwrap()
-        ).await?;
+        let tvl_storage_canister = Principal::from_text(&tvl_storage_canister_id)
+            .map_err(|error| InternalError::external_service(
+                build_error_code(2102, 4, 9), // 2102 04 09
+                "ICPSwapLiquidityClient::get_pool_data".to_string(),
+                format!("Invalid TVL storage canister id: {error:?}"),
+                Some(HashMap::from([
+                    ("tvl_storage_canister_id".to_string(), tvl_storage_canister_id.clone()),
+                ])),
+            ))?;
 
-        let tvl = Nat::from(pool_chart_tvl_response.last().unwrap().tvlUSD as u128);
+        let pool_chart_tvl_response = self.get_pool_chart_tvl(tvl_storage_canister).await?;
+
+        let latest_tvl = pool_chart_tvl_response.last()
+            .ok_or_else(|| InternalError::not_found(
+                build_error_code(2102, 1, 10), // 2102 01 10
+                "ICPSwapLiquidityClient::get_pool_data".to_string(),
+                "No TVL data for ICPSwap pool".to_string(),
+                Some(HashMap::from([
+                    ("pool_canister_id".to_string(), self.canister_id.to_text()),
+                ])),
+            ))?;
+
+        let tvl = Nat::from(latest_tvl.tvlUSD as u128);
 
         Ok(GetPoolDataResponse { tvl })
     }
+
+    async fn get_pool_ratio(&self) -> Result<f64, InternalError> {
+        let metadata = self.metadata().await?;
+
+        // Full range position takes tokens in proportion to the pool price:
+        // price = (sqrtPriceX96 / 2^96)^2 of pool token1 per pool token0
+        let sqrt_price = nat_to_f64(&metadata.sqrtPriceX96) / 2f64.powi(96);
+        let price = sqrt_price * sqrt_price;
+
+        match self.is_zero_for_one_swap_direction()? {
+            true => Ok(price),
+            false => Ok(1.0 / price),
+        }
+    }
+}
+
+pub struct ICPSwapLiquidityAdapter;
+
+#[async_trait]
+impl LiquidityAdapter for ICPSwapLiquidityAdapter {
+    fn exchange_id(&self) -> ExchangeId {
+        ExchangeId::ICPSwap
+    }
+
+    /// Resolves the ICPSwap pool of the tokens, the client can not be built without it
+    async fn liquidity_client(
+        &self,
+        provider_impls: ProviderImpls,
+        token0: CanisterId,
+        token1: CanisterId,
+        swap_limits: SwapLimits,
+    ) -> Result<Box<dyn LiquidityClient>, InternalError> {
+        Ok(Box::new(
+            ICPSwapLiquidityClient::resolve(
+                provider_impls,
+                token0,
+                token1
+            ).await?.with_swap_limits(swap_limits)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::token;
+    use futures::executor::block_on;
+    use icpswap_swap_factory_canister::ICPSwapToken;
+    use providers::mock::icpswap::MockICPSwapProvider;
+    use providers::providers_factory;
+    use utils::environment::Environment;
+
+    fn pool(token0: CanisterId, token1: CanisterId) -> ICPSwapPool {
+        ICPSwapPool {
+            fee: Nat::from(3_000u64),
+            key: format!("{}_{}_3000", token0.to_text(), token1.to_text()),
+            tickSpacing: 60,
+            token0: ICPSwapToken { address: token0.to_text(), standard: "ICRC2".to_string() },
+            token1: ICPSwapToken { address: token1.to_text(), standard: "ICRC2".to_string() },
+            canisterId: token(9),
+        }
+    }
+
+    fn provider_impls(icpswap: MockICPSwapProvider) -> ProviderImpls {
+        providers_factory::get_provider_impls(Environment::Test)
+            .with_provider::<dyn ICPSwapProvider + Send + Sync>(ExchangeId::ICPSwap, Arc::new(icpswap))
+    }
+
+    mod resolve {
+        use super::*;
+
+        #[test]
+        fn returns_error_when_pool_is_missing() {
+            let result = block_on(ICPSwapLiquidityClient::resolve(
+                provider_impls(MockICPSwapProvider::new()),
+                token(1),
+                token(2),
+            ));
+
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn resolves_pool_of_tokens() {
+            let mut icpswap = MockICPSwapProvider::new();
+            icpswap.mock_get_pool(token(1), token(2), Ok(pool(token(1), token(2))));
+
+            let client = block_on(ICPSwapLiquidityClient::resolve(provider_impls(icpswap), token(1), token(2))).unwrap();
+
+            assert_eq!(client.canister_id(), token(9));
+        }
+    }
+
+    mod from_pool {
+        use super::*;
+
+        #[test]
+        fn rejects_pool_of_other_tokens() {
+            let result = ICPSwapLiquidityClient::from_pool(
+                provider_impls(MockICPSwapProvider::new()),
+                token(1),
+                token(2),
+                pool(token(3), token(2)),
+            );
+
+            assert!(result.is_err());
+        }
+    }
+
+    mod get_pool_data {
+        use super::*;
+
+        #[test]
+        fn returns_error_without_tvl_storage_canister() {
+            let mut icpswap = MockICPSwapProvider::new();
+            icpswap.mock_get_tvl_storage_canister(Ok(vec![]));
+
+            let client = ICPSwapLiquidityClient::from_pool(
+                provider_impls(icpswap),
+                token(1),
+                token(2),
+                pool(token(1), token(2)),
+            ).unwrap();
+
+            assert!(block_on(client.get_pool_data()).is_err());
+        }
+
+        #[test]
+        fn returns_error_without_tvl_data() {
+            let tvl_storage_canister = token(8);
+
+            let mut icpswap = MockICPSwapProvider::new();
+            icpswap.mock_get_tvl_storage_canister(Ok(vec![tvl_storage_canister.to_text()]));
+            icpswap.mock_get_pool_chart_tvl(
+                tvl_storage_canister,
+                token(9).to_text(),
+                Nat::from(0u128),
+                Nat::from(0u128),
+                Ok(vec![]),
+            );
+
+            let client = ICPSwapLiquidityClient::from_pool(
+                provider_impls(icpswap),
+                token(1),
+                token(2),
+                pool(token(1), token(2)),
+            ).unwrap();
+
+            let error = block_on(client.get_pool_data()).err().unwrap();
+
+            assert_eq!(error.code, build_error_code(2102, 1, 10));
+        }
+    }
+
+    mod position_token_amount {
+        use super::*;
+
+        #[test]
+        fn returns_amount_of_position() {
+            let amount = ICPSwapLiquidityClient::position_token_amount(Int::from(1_000), 7).unwrap();
+
+            assert_eq!(amount, Nat::from(1_000u64));
+        }
+
+        #[test]
+        fn returns_error_for_negative_amount() {
+            let error = ICPSwapLiquidityClient::position_token_amount(Int::from(-1), 7).unwrap_err();
+
+            assert_eq!(error.code, build_error_code(2102, 3, 11));
+        }
+    }
+
+    mod icpswap_liquidity_adapter {
+        use super::*;
+
+        #[test]
+        fn returns_error_when_pool_is_missing() {
+            let result = block_on(ICPSwapLiquidityAdapter.liquidity_client(
+                provider_impls(MockICPSwapProvider::new()),
+                token(1),
+                token(2),
+                SwapLimits::default(),
+            ));
+
+            assert!(result.is_err());
+        }
+    }
 }
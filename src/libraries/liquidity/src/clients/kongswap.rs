@@ -7,25 +7,39 @@ use std::sync::Arc;
 use types::CanisterId;
 use providers::providers_factory::ProviderImpls;
 use providers::kongswap::KongSwapProvider;
-use providers::icpswap::ICPSwapProvider;
 use kongswap_canister::user_balances::UserBalancesReply;
+use kongswap_canister::queries::add_liquidity_amounts::AddLiquidityAmountsReply;
 use utils::util::nat_to_f64;
 use swap::swap_service;
-use types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse, GetPositionByIdResponse, GetPoolDataResponse};
+use types::exchange_id::ExchangeId;
+use types::swap_tokens::SwapLimits;
+use types::liquidity::{
+    AddLiquidityResponse,
+    WithdrawLiquidityResponse,
+    GetPositionByIdResponse,
+    GetPoolDataResponse,
+    QuoteAddLiquidityResponse,
+    QuoteWithdrawLiquidityResponse,
+    LiquidityFees,
+};
 use errors::internal_error::error::InternalError;
 use errors::internal_error::error::build_error_code;
-use icrc_ledger_client;
-use utils::constants::CKUSDT_TOKEN_CANISTER_ID;
+use utils::constants::KONGSWAP_CANISTER_ID;
+use token_registry::ledger_fees::{self, APPROVED_TRANSFER_FEE_COUNT};
 
 use crate::liquidity_client::LiquidityClient;
-use crate::liquidity_calculator::LiquidityCalculator;
+use crate::liquidity_adapters::LiquidityAdapter;
+use crate::liquidity_calculator::{LiquidityCalculator, CalculatePoolLiquidityAmountsResponse};
+use crate::liquidity_quotes;
 
 pub struct KongSwapLiquidityClient {
     provider_impls: ProviderImpls,
+    kongswap_provider: Arc<dyn KongSwapProvider + Send + Sync>,
     canister_id: CanisterId,
     // TODO: change to Pool
     token0: CanisterId,
     token1: CanisterId,
+    swap_limits: SwapLimits,
 }
 
 impl KongSwapLiquidityClient {
@@ -34,13 +48,20 @@ impl KongSwapLiquidityClient {
         canister_id: CanisterId,
         token0: CanisterId,
         token1: CanisterId,
-    ) -> KongSwapLiquidityClient {
-        KongSwapLiquidityClient {
+    ) -> Result<KongSwapLiquidityClient, InternalError> {
+        Ok(KongSwapLiquidityClient {
+            kongswap_provider: provider_impls.get::<dyn KongSwapProvider + Send + Sync>(ExchangeId::KongSwap)?,
             provider_impls,
             canister_id,
             token0,
             token1,
-        }
+            swap_limits: SwapLimits::default(),
+        })
+    }
+
+    pub fn with_swap_limits(mut self, swap_limits: SwapLimits) -> Self {
+        self.swap_limits = swap_limits;
+        self
     }
 
     fn token_kongswap_format(&self, token: CanisterId) -> String {
@@ -48,29 +69,21 @@ impl KongSwapLiquidityClient {
     }
 
     fn kongswap_provider(&self) -> &Arc<dyn KongSwapProvider + Send + Sync> {
-        &self.provider_impls.kongswap
-    }
-
-    fn icpswap_provider(&self) -> &Arc<dyn ICPSwapProvider + Send + Sync> {
-        &self.provider_impls.icpswap
-    }
-}
-
-#[async_trait]
-impl LiquidityClient for KongSwapLiquidityClient {
-    fn canister_id(&self) -> CanisterId {
-        self.canister_id
+        &self.kongswap_provider
     }
 
-    async fn add_liquidity_to_pool(&self, amount: Nat) -> Result<AddLiquidityResponse, InternalError> {
+    async fn calculate_add_liquidity_amounts(
+        &self,
+        amount: Nat
+    ) -> Result<(AddLiquidityAmountsReply, CalculatePoolLiquidityAmountsResponse, ExchangeId), InternalError> {
         let add_liq_amounts_reply = self.kongswap_provider().add_liquidity_amounts(
             self.token_kongswap_format(self.token0.clone()),
             amount.clone(),
             self.token_kongswap_format(self.token1.clone()),
         ).await?;
 
-        let amount_0_for_pool = add_liq_amounts_reply.amount_0;
-        let amount_1_for_pool = add_liq_amounts_reply.amount_1;
+        let amount_0_for_pool = add_liq_amounts_reply.amount_0.clone();
+        let amount_1_for_pool = add_liq_amounts_reply.amount_1.clone();
 
         // Get quote for token swap
         let quote_result = swap_service::quote_swap_icrc2_optimal(
@@ -95,6 +108,51 @@ impl LiquidityClient for KongSwapLiquidityClient {
             swap_price.clone(),
         );
 
+        Ok((add_liq_amounts_reply, calculator_response, swap_provider))
+    }
+
+    async fn get_lp_balance(&self) -> Result<Option<f64>, InternalError> {
+        let canister_id = ic_cdk::id();
+
+        // Fetch LP positions in pool
+        let user_balances_response = self.kongswap_provider().user_balances(
+            canister_id.to_string()
+        ).await?;
+
+        // Get user balance in pool
+        let balance = user_balances_response
+            .into_iter()
+            .filter_map(|reply| match reply {
+                UserBalancesReply::LP(lp) => Some(lp),
+                _ => None,
+            })
+            .find(|balance|
+                (balance.address_0 == self.token0.to_text() && balance.address_1 == self.token1.to_text()) ||
+                (balance.address_0 == self.token1.to_text() && balance.address_1 == self.token0.to_text())
+            )
+            .map(|balance_reply| balance_reply.balance);
+
+        Ok(balance)
+    }
+
+    fn lp_tokens_to_withdraw(&self, balance: f64, total_shares: &Nat, shares: &Nat) -> Nat {
+        let lp_tokens_to_withdraw: f64 = balance.mul(nat_to_f64(shares)).div(nat_to_f64(total_shares)).mul(100000000.0);
+
+        Nat::from(lp_tokens_to_withdraw.round() as u128)
+    }
+}
+
+#[async_trait]
+impl LiquidityClient for KongSwapLiquidityClient {
+    fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    async fn add_liquidity_to_pool(&self, amount: Nat) -> Result<AddLiquidityResponse, InternalError> {
+        let (_, calculator_response, swap_provider) = self.calculate_add_liquidity_amounts(
+            amount.clone()
+        ).await?;
+
         let token_0_for_swap_amount = calculator_response.token_0_for_swap;
         let token_0_for_pool_amount = calculator_response.token_0_for_pool;
 
@@ -105,49 +163,72 @@ impl LiquidityClient for KongSwapLiquidityClient {
             self.token1.clone(),
             Nat::from(token_0_for_swap_amount as u128),
             swap_provider,
+            self.swap_limits,
+            None,
         ).await?;
 
-        let token_1_for_pool_amount = swap_response.amount_out;
+        // The approvals and transfers to the pool are paid out of the amounts for the pool
+        let token_0_deduction = ledger_fees::deduct_ledger_fees(
+            self.token0,
+            &Nat::from(token_0_for_pool_amount as u128),
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
+
+        let token_1_deduction = ledger_fees::deduct_ledger_fees(
+            self.token1,
+            &Nat::from(swap_response.amount_out),
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
 
         // Add token0 and token1 liquidity to pool
         let response = self.kongswap_provider().add_liquidity(
             self.token_kongswap_format(self.token0.clone()),
-            Nat::from(token_0_for_pool_amount as u128),
+            token_0_deduction.amount.clone(),
             self.token_kongswap_format(self.token1.clone()),
-            Nat::from(token_1_for_pool_amount as u128),
+            token_1_deduction.amount.clone(),
             self.token0,
             self.token1,
         ).await?;
 
-        // panic!("response: {:?}", response);
-
         Ok(AddLiquidityResponse {
-            token_0_amount: Nat::from(token_0_for_pool_amount as u128),
-            token_1_amount: Nat::from(token_1_for_pool_amount as u128),
+            token_0_amount: token_0_deduction.amount,
+            token_1_amount: token_1_deduction.amount,
             position_id: response.request_id,
+            ledger_fees: LiquidityFees {
+                token_0_fee: token_0_deduction.fees_paid + Nat::from(swap_response.ledger_fee),
+                token_1_fee: token_1_deduction.fees_paid,
+            },
         })
     }
 
-    async fn withdraw_liquidity_from_pool(&self, total_shares: Nat, shares: Nat) -> Result<WithdrawLiquidityResponse, InternalError> {
-        let canister_id = ic_cdk::id();
+    async fn add_liquidity_to_pool_with_amounts(&self, amount0: Nat, amount1: Nat) -> Result<AddLiquidityResponse, InternalError> {
+        let token_0_deduction = ledger_fees::deduct_ledger_fees(self.token0, &amount0, APPROVED_TRANSFER_FEE_COUNT).await?;
+        let token_1_deduction = ledger_fees::deduct_ledger_fees(self.token1, &amount1, APPROVED_TRANSFER_FEE_COUNT).await?;
 
-        // Fetch LP positions in pool
-        let user_balances_response = self.kongswap_provider().user_balances(
-            canister_id.to_string()
+        // Add token0 and token1 liquidity to pool as is, without swapping
+        let response = self.kongswap_provider().add_liquidity(
+            self.token_kongswap_format(self.token0.clone()),
+            token_0_deduction.amount.clone(),
+            self.token_kongswap_format(self.token1.clone()),
+            token_1_deduction.amount.clone(),
+            self.token0,
+            self.token1,
         ).await?;
 
+        Ok(AddLiquidityResponse {
+            token_0_amount: token_0_deduction.amount,
+            token_1_amount: token_1_deduction.amount,
+            position_id: response.request_id,
+            ledger_fees: LiquidityFees {
+                token_0_fee: token_0_deduction.fees_paid,
+                token_1_fee: token_1_deduction.fees_paid,
+            },
+        })
+    }
+
+    async fn withdraw_liquidity_from_pool(&self, total_shares: Nat, shares: Nat) -> Result<WithdrawLiquidityResponse, InternalError> {
         // Get user balance in pool
-        let balance = user_balances_response
-            .into_iter()
-            .filter_map(|reply| match reply {
-                UserBalancesReply::LP(lp) => Some(lp),
-                _ => None,
-            })
-            .find(|balance|
-                (balance.address_0 == self.token0.to_text() && balance.address_1 == self.token1.to_text()) ||
-                (balance.address_0 == self.token1.to_text() && balance.address_1 == self.token0.to_text())
-            )
-            .map(|balance_reply| balance_reply.balance)
+        let balance = self.get_lp_balance().await?
             .ok_or_else(|| {
                 InternalError::business_logic(
                     build_error_code(2101, 3, 1), // 2101 03 01
@@ -163,21 +244,114 @@ impl LiquidityClient for KongSwapLiquidityClient {
             })?;
 
         // Calculate how much LP tokens to withdraw
-        let lp_tokens_to_withdraw: f64 = balance.mul(nat_to_f64(&shares)).div(nat_to_f64(&total_shares)).mul(100000000.0);
+        let lp_tokens_to_withdraw = self.lp_tokens_to_withdraw(balance, &total_shares, &shares);
 
         // Remove liquidity from pool
         let remove_liquidity_response = self.kongswap_provider().remove_liquidity(
             self.token_kongswap_format(self.token0.clone()),
             self.token_kongswap_format(self.token1.clone()),
-            Nat::from(lp_tokens_to_withdraw.round() as u128),
+            lp_tokens_to_withdraw,
         ).await?;
 
+        // KongSwap pays out the amounts with ledger transfers, the fees are taken from the amounts
+        let token_0_fee = token_registry::registry::fee(self.token0).await?;
+        let token_1_fee = token_registry::registry::fee(self.token1).await?;
+
+        let token_0_received = ledger_fees::received_after_fee(&remove_liquidity_response.amount_0, &token_0_fee);
+        let token_1_received = ledger_fees::received_after_fee(&remove_liquidity_response.amount_1, &token_1_fee);
+
         Ok(WithdrawLiquidityResponse {
-            token_0_amount: remove_liquidity_response.amount_0,
-            token_1_amount: remove_liquidity_response.amount_1,
+            token_0_amount: token_0_received.amount,
+            token_1_amount: token_1_received.amount,
+            ledger_fees: LiquidityFees {
+                token_0_fee: token_0_received.fees_paid,
+                token_1_fee: token_1_received.fees_paid,
+            },
         })
     }
 
+    async fn quote_add_liquidity(&self, amount: Nat) -> Result<QuoteAddLiquidityResponse, InternalError> {
+        let (_, calculator_response, swap_provider) = self.calculate_add_liquidity_amounts(
+            amount.clone()
+        ).await?;
+
+        let token_0_for_swap_amount = Nat::from(calculator_response.token_0_for_swap as u128);
+
+        // The swap is made with the part of the amount left after its approval and transfer
+        let swap_deduction = ledger_fees::deduct_ledger_fees(
+            self.token0,
+            &token_0_for_swap_amount,
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
+
+        // Get quote for the swap part of the deposit with the same provider used for the execution
+        let swap_quote = swap_service::quote_swap_icrc2(
+            self.provider_impls.clone(),
+            self.token0.clone(),
+            self.token1.clone(),
+            swap_deduction.amount.clone(),
+            swap_provider,
+        ).await?;
+
+        let token_0_deduction = ledger_fees::deduct_ledger_fees(
+            self.token0,
+            &Nat::from(calculator_response.token_0_for_pool as u128),
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
+
+        let token_1_deduction = ledger_fees::deduct_ledger_fees(
+            self.token1,
+            &Nat::from(swap_quote.amount_out),
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
+
+        Ok(liquidity_quotes::swap_and_deposit_quote(
+            token_0_for_swap_amount,
+            &swap_deduction,
+            &token_0_deduction,
+            &token_1_deduction,
+            swap_provider,
+        ))
+    }
+
+    async fn quote_withdraw_liquidity(&self, total_shares: Nat, shares: Nat) -> Result<QuoteWithdrawLiquidityResponse, InternalError> {
+        // Get user balance in pool
+        let balance = self.get_lp_balance().await?
+            .ok_or_else(|| {
+                InternalError::business_logic(
+                    build_error_code(2101, 3, 4), // 2101 03 04
+                    "KongSwapLiquidityClient::quote_withdraw_liquidity".to_string(),
+                    "No user LP balance".to_string(),
+                    Some(HashMap::from([
+                        ("token0".to_string(), self.token0.to_text()),
+                        ("token1".to_string(), self.token1.to_text()),
+                        ("total_shares".to_string(), total_shares.to_string()),
+                        ("shares".to_string(), shares.to_string()),
+                    ]))
+                )
+            })?;
+
+        // Calculate how much LP tokens would be withdrawn
+        let lp_tokens_to_withdraw = self.lp_tokens_to_withdraw(balance, &total_shares, &shares);
+
+        let remove_liquidity_amounts_response = self.kongswap_provider().remove_liquidity_amounts(
+            self.token_kongswap_format(self.token0.clone()),
+            self.token_kongswap_format(self.token1.clone()),
+            lp_tokens_to_withdraw,
+        ).await?;
+
+        // KongSwap pays out the amounts with ledger transfers, the fees are taken from the amounts
+        let token_0_fee = token_registry::registry::fee(self.token0).await?;
+        let token_1_fee = token_registry::registry::fee(self.token1).await?;
+
+        Ok(liquidity_quotes::transfer_withdrawal_quote(
+            &remove_liquidity_amounts_response.amount_0,
+            &remove_liquidity_amounts_response.amount_1,
+            &token_0_fee,
+            &token_1_fee,
+        ))
+    }
+
     async fn get_position_by_id(&self, position_id: u64) -> Result<GetPositionByIdResponse, InternalError> {
         let canister_id = ic_cdk::id();
 
@@ -207,9 +381,9 @@ impl LiquidityClient for KongSwapLiquidityClient {
                 ]))
             ))?;
 
-        let token0_decimals = icrc_ledger_client::icrc1_decimals(self.token0.clone()).await?;
-        let token1_decimals = icrc_ledger_client::icrc1_decimals(self.token1.clone()).await?;
-        let usdt_decimals = icrc_ledger_client::icrc1_decimals(*CKUSDT_TOKEN_CANISTER_ID).await?;
+        let token0_decimals = token_registry::registry::decimals(self.token0.clone()).await?;
+        let token1_decimals = token_registry::registry::decimals(self.token1.clone()).await?;
+        let usdt_decimals = token_registry::registry::usd_token().await?.decimals;
 
         let token0_position_balance = Nat::from(
             (user_balance.amount_0 * 10f64.powi(token0_decimals as i32)).round() as u128
@@ -256,9 +430,10 @@ impl LiquidityClient for KongSwapLiquidityClient {
         let token0_balance = pool_data.balance_0.clone() + pool_data.lp_fee_0.clone();
         let token1_balance = pool_data.balance_1.clone() + pool_data.lp_fee_1.clone();
 
-        let decimals_token0 = icrc_ledger_client::icrc1_decimals(self.token0.clone()).await?;
-        let decimals_token1 = icrc_ledger_client::icrc1_decimals(self.token1.clone()).await?;
-        let decimals_usdt = icrc_ledger_client::icrc1_decimals(*CKUSDT_TOKEN_CANISTER_ID).await?;
+        let decimals_token0 = token_registry::registry::decimals(self.token0.clone()).await?;
+        let decimals_token1 = token_registry::registry::decimals(self.token1.clone()).await?;
+        let usd_token = token_registry::registry::usd_token().await?;
+        let decimals_usdt = usd_token.decimals;
 
         let token0_base_unit = Nat::from(10u32.pow(decimals_token0 as u32)); // 10^decimals_token0
         let token1_base_unit = Nat::from(10u32.pow(decimals_token1 as u32)); // 10^decimals_token1
@@ -273,14 +448,14 @@ impl LiquidityClient for KongSwapLiquidityClient {
         let swap_amount0_reply = self.kongswap_provider().swap_amounts(
             self.token0.clone(),
             token0_base_unit_multiplied.clone(),
-            *CKUSDT_TOKEN_CANISTER_ID
+            usd_token.canister_id
         ).await?;
 
         // Get quote for token1 swap to USDT
         let swap_amount1_reply = self.kongswap_provider().swap_amounts(
             self.token1,
             token1_base_unit_multiplied.clone(),
-            *CKUSDT_TOKEN_CANISTER_ID
+            usd_token.canister_id
         ).await?;
 
         let token0_usdt_price = swap_amount0_reply.receive_amount.div(multiplier.clone());
@@ -302,4 +477,62 @@ impl LiquidityClient for KongSwapLiquidityClient {
             tvl: tvl,
         })
     }
-}
\ No newline at end of file
+
+    async fn get_pool_ratio(&self) -> Result<f64, InternalError> {
+        let pools = self.kongswap_provider().pools().await?;
+
+        let pool_data = pools
+            .iter()
+            .find(|pool|
+                (pool.address_0 == self.token0.to_text() && pool.address_1 == self.token1.to_text()) ||
+                (pool.address_0 == self.token1.to_text() && pool.address_1 == self.token0.to_text())
+            )
+            .ok_or_else(|| InternalError::business_logic(
+                build_error_code(2101, 3, 5), // 2101 03 05
+                "KongSwapLiquidityClient::get_pool_ratio".to_string(),
+                "No pool data".to_string(),
+                Some(HashMap::from([
+                    ("token0".to_string(), self.token0.to_text()),
+                    ("token1".to_string(), self.token1.to_text()),
+                ]))
+            ))?;
+
+        let pool_balance_0 = pool_data.balance_0.clone() + pool_data.lp_fee_0.clone();
+        let pool_balance_1 = pool_data.balance_1.clone() + pool_data.lp_fee_1.clone();
+
+        // Liquidity is added in proportion to the pool balances
+        let (balance_0, balance_1) = if pool_data.address_0 == self.token0.to_text() {
+            (pool_balance_0, pool_balance_1)
+        } else {
+            (pool_balance_1, pool_balance_0)
+        };
+
+        Ok(nat_to_f64(&balance_1) / nat_to_f64(&balance_0))
+    }
+}
+
+pub struct KongSwapLiquidityAdapter;
+
+#[async_trait]
+impl LiquidityAdapter for KongSwapLiquidityAdapter {
+    fn exchange_id(&self) -> ExchangeId {
+        ExchangeId::KongSwap
+    }
+
+    async fn liquidity_client(
+        &self,
+        provider_impls: ProviderImpls,
+        token0: CanisterId,
+        token1: CanisterId,
+        swap_limits: SwapLimits,
+    ) -> Result<Box<dyn LiquidityClient>, InternalError> {
+        Ok(Box::new(
+            KongSwapLiquidityClient::new(
+                provider_impls,
+                *KONGSWAP_CANISTER_ID,
+                token0,
+                token1
+            )?.with_swap_limits(swap_limits)
+        ))
+    }
+}
@@ -1,2 +1,3 @@
 pub mod icpswap;
 pub mod kongswap;
+pub mod sonic;
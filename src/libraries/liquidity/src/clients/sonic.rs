@@ -0,0 +1,461 @@
+use async_trait::async_trait;
+use candid::Nat;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use types::CanisterId;
+use providers::providers_factory::ProviderImpls;
+use providers::sonic::SonicProvider;
+use sonic_canister::PairInfoExt;
+use swap::swap_service;
+use swap::token_swaps::sonic::{credited_amount, pair_reserves};
+use types::exchange_id::ExchangeId;
+use types::swap_tokens::SwapLimits;
+use types::liquidity::{
+    AddLiquidityResponse,
+    WithdrawLiquidityResponse,
+    GetPositionByIdResponse,
+    GetPoolDataResponse,
+    QuoteAddLiquidityResponse,
+    QuoteWithdrawLiquidityResponse,
+    LiquidityFees,
+};
+use utils::util::{nat_to_f64, nat_to_u64, nat_to_u128};
+use utils::constants::SONIC_SWAP_CANISTER_ID;
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use token_registry::ledger_fees::{self, FeeDeduction, APPROVED_TRANSFER_FEE_COUNT};
+
+use crate::liquidity_client::LiquidityClient;
+use crate::liquidity_quotes;
+use crate::liquidity_adapters::LiquidityAdapter;
+use crate::liquidity_calculator::{LiquidityCalculator, CalculatePoolLiquidityAmountsResponse};
+
+/// Part of the pair reserves owned by the LP amount
+pub fn lp_share_amounts(lp_amount: &Nat, total_supply: &Nat, reserve0: &Nat, reserve1: &Nat) -> (Nat, Nat) {
+    if *total_supply == 0u8 {
+        return (Nat::from(0u8), Nat::from(0u8));
+    }
+
+    (
+        reserve0.clone() * lp_amount.clone() / total_supply.clone(),
+        reserve1.clone() * lp_amount.clone() / total_supply.clone(),
+    )
+}
+
+pub struct SonicLiquidityClient {
+    provider_impls: ProviderImpls,
+    sonic_provider: Arc<dyn SonicProvider + Send + Sync>,
+    canister_id: CanisterId,
+    token0: CanisterId,
+    token1: CanisterId,
+    swap_limits: SwapLimits,
+}
+
+impl SonicLiquidityClient {
+    pub fn new(
+        provider_impls: ProviderImpls,
+        canister_id: CanisterId,
+        token0: CanisterId,
+        token1: CanisterId,
+    ) -> Result<SonicLiquidityClient, InternalError> {
+        Ok(SonicLiquidityClient {
+            sonic_provider: provider_impls.get::<dyn SonicProvider + Send + Sync>(ExchangeId::Sonic)?,
+            provider_impls,
+            canister_id,
+            token0,
+            token1,
+            swap_limits: SwapLimits::default(),
+        })
+    }
+
+    pub fn with_swap_limits(mut self, swap_limits: SwapLimits) -> Self {
+        self.swap_limits = swap_limits;
+        self
+    }
+
+    fn sonic_provider(&self) -> &Arc<dyn SonicProvider + Send + Sync> {
+        &self.sonic_provider
+    }
+
+    /// Pair of the tokens with its reserves ordered as (token0, token1) of the client
+    async fn get_pair(&self) -> Result<(PairInfoExt, Nat, Nat), InternalError> {
+        self.sonic_provider().get_pair(self.token0, self.token1).await?
+            .and_then(|pair| {
+                pair_reserves(&pair, self.token0, self.token1)
+                    .map(|(reserve0, reserve1)| (pair, reserve0, reserve1))
+            })
+            .ok_or_else(|| InternalError::business_logic(
+                build_error_code(2103, 3, 1), // 2103 03 01
+                "SonicLiquidityClient::get_pair".to_string(),
+                "No Sonic pair for the tokens".to_string(),
+                Some(HashMap::from([
+                    ("token0".to_string(), self.token0.to_text()),
+                    ("token1".to_string(), self.token1.to_text()),
+                ])),
+            ))
+    }
+
+    async fn get_lp_balance(&self, pair: &PairInfoExt) -> Result<Nat, InternalError> {
+        let lp_balances = self.sonic_provider().get_user_lp_balances(ic_cdk::id()).await?;
+
+        lp_balances
+            .into_iter()
+            .find(|(pair_id, balance)| *pair_id == pair.id && *balance > 0u8)
+            .map(|(_, balance)| balance)
+            .ok_or_else(|| InternalError::business_logic(
+                build_error_code(2103, 3, 2), // 2103 03 02
+                "SonicLiquidityClient::get_lp_balance".to_string(),
+                "No user LP balance".to_string(),
+                Some(HashMap::from([
+                    ("token0".to_string(), self.token0.to_text()),
+                    ("token1".to_string(), self.token1.to_text()),
+                    ("pair_id".to_string(), pair.id.clone()),
+                ])),
+            ))
+    }
+
+    fn lp_tokens_to_withdraw(&self, balance: &Nat, total_shares: &Nat, shares: &Nat) -> Nat {
+        balance.clone() * shares.clone() / total_shares.clone()
+    }
+
+    async fn calculate_add_liquidity_amounts(
+        &self,
+        amount: Nat,
+    ) -> Result<(CalculatePoolLiquidityAmountsResponse, ExchangeId), InternalError> {
+        let (_, reserve0, reserve1) = self.get_pair().await?;
+
+        // Get quote for token swap
+        let quote_result = swap_service::quote_swap_icrc2_optimal(
+            self.provider_impls.clone(),
+            self.token0,
+            self.token1,
+            amount.clone(),
+        ).await?;
+
+        // Sonic adds liquidity in proportion to the pair reserves
+        let pool_ratio = nat_to_f64(&reserve1) / nat_to_f64(&reserve0);
+        let swap_price = (quote_result.amount_out as f64) / nat_to_f64(&amount);
+
+        let calculator_response = LiquidityCalculator::calculate_token_amounts_for_deposit(
+            nat_to_f64(&amount),
+            pool_ratio,
+            swap_price,
+        );
+
+        Ok((calculator_response, quote_result.provider))
+    }
+
+    /// Approves and deposits the amount to the Sonic balance of the canister.
+    /// The approval and the transfer made by Sonic are paid out of the amount.
+    async fn deposit(&self, token: CanisterId, amount: &Nat) -> Result<FeeDeduction, InternalError> {
+        let deduction = ledger_fees::deduct_ledger_fees(token, amount, APPROVED_TRANSFER_FEE_COUNT).await?;
+
+        icrc_ledger_client::icrc2_approve(
+            self.canister_id,
+            token,
+            deduction.amount.clone(),
+            deduction.fee.clone(),
+        ).await?;
+
+        self.sonic_provider().deposit(token, deduction.amount.clone()).await?;
+
+        Ok(deduction)
+    }
+
+    async fn sonic_balance(&self, token: CanisterId) -> Result<Nat, InternalError> {
+        self.sonic_provider().balance_of(token, ic_cdk::id()).await
+    }
+
+    /// Withdraws the amount from the Sonic balance of the canister.
+    /// Sonic pays out the withdrawal with a ledger transfer, the fee is taken from the amount.
+    /// An amount which does not cover the fee stays in the Sonic balance.
+    async fn withdraw(&self, token: CanisterId, amount: Nat) -> Result<FeeDeduction, InternalError> {
+        let fee = token_registry::registry::fee(token).await?;
+        let received = ledger_fees::received_after_fee(&amount, &fee);
+
+        if received.amount > 0u8 {
+            self.sonic_provider().withdraw(token, amount).await?;
+        }
+
+        Ok(received)
+    }
+
+    /// Value of the amount in base units of the USD token, quoted by the best route
+    async fn usd_amount(&self, token: CanisterId, amount: Nat) -> Result<Nat, InternalError> {
+        let usd_token = token_registry::registry::usd_token().await?.canister_id;
+
+        if token == usd_token || amount == 0u8 {
+            return Ok(amount);
+        }
+
+        let quote = swap_service::quote_swap_icrc2_optimal(
+            self.provider_impls.clone(),
+            token,
+            usd_token,
+            amount,
+        ).await?;
+
+        Ok(Nat::from(quote.amount_out))
+    }
+}
+
+#[async_trait]
+impl LiquidityClient for SonicLiquidityClient {
+    fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    async fn add_liquidity_to_pool(&self, amount: Nat) -> Result<AddLiquidityResponse, InternalError> {
+        let (calculator_response, swap_provider) = self.calculate_add_liquidity_amounts(amount.clone()).await?;
+
+        // Swap token0 for token1 with the best exchange provider
+        let swap_response = swap_service::swap_icrc2(
+            self.provider_impls.clone(),
+            self.token0,
+            self.token1,
+            Nat::from(calculator_response.token_0_for_swap as u128),
+            swap_provider,
+            self.swap_limits,
+            None,
+        ).await?;
+
+        let mut response = self.add_liquidity_to_pool_with_amounts(
+            Nat::from(calculator_response.token_0_for_pool as u128),
+            Nat::from(swap_response.amount_out),
+        ).await?;
+
+        response.ledger_fees.token_0_fee += Nat::from(swap_response.ledger_fee);
+
+        Ok(response)
+    }
+
+    async fn add_liquidity_to_pool_with_amounts(&self, amount0: Nat, amount1: Nat) -> Result<AddLiquidityResponse, InternalError> {
+        let token_0_deposit = self.deposit(self.token0, &amount0).await?;
+        let token_1_deposit = self.deposit(self.token1, &amount1).await?;
+
+        let token_0_balance = self.sonic_balance(self.token0).await?;
+        let token_1_balance = self.sonic_balance(self.token1).await?;
+
+        let tx_index = self.sonic_provider().add_liquidity(
+            self.token0,
+            self.token1,
+            token_0_deposit.amount.clone(),
+            token_1_deposit.amount.clone(),
+            Nat::from(self.swap_limits.min_amount_out(nat_to_u128(&token_0_deposit.amount))),
+            Nat::from(self.swap_limits.min_amount_out(nat_to_u128(&token_1_deposit.amount))),
+        ).await?;
+
+        // Sonic takes the amounts it adds in the ratio of the reserves from the Sonic balance,
+        // the rest of the deposits stays there
+        let token_0_added = credited_amount(&self.sonic_balance(self.token0).await?, &token_0_balance)
+            .min(token_0_deposit.amount.clone());
+        let token_1_added = credited_amount(&self.sonic_balance(self.token1).await?, &token_1_balance)
+            .min(token_1_deposit.amount.clone());
+
+        let token_0_withdrawal = self.withdraw(self.token0, token_0_deposit.amount.clone() - token_0_added.clone()).await?;
+        let token_1_withdrawal = self.withdraw(self.token1, token_1_deposit.amount.clone() - token_1_added.clone()).await?;
+
+        Ok(AddLiquidityResponse {
+            token_0_amount: token_0_added,
+            token_1_amount: token_1_added,
+            position_id: nat_to_u64(&tx_index),
+            ledger_fees: LiquidityFees {
+                token_0_fee: token_0_deposit.fees_paid + token_0_withdrawal.fees_paid,
+                token_1_fee: token_1_deposit.fees_paid + token_1_withdrawal.fees_paid,
+            },
+        })
+    }
+
+    async fn withdraw_liquidity_from_pool(&self, total_shares: Nat, shares: Nat) -> Result<WithdrawLiquidityResponse, InternalError> {
+        let (pair, reserve0, reserve1) = self.get_pair().await?;
+        let balance = self.get_lp_balance(&pair).await?;
+
+        // Calculate how much LP tokens to withdraw
+        let lp_tokens_to_withdraw = self.lp_tokens_to_withdraw(&balance, &total_shares, &shares);
+        let (amount0, amount1) = lp_share_amounts(&lp_tokens_to_withdraw, &pair.totalSupply, &reserve0, &reserve1);
+
+        let token_0_balance = self.sonic_balance(self.token0).await?;
+        let token_1_balance = self.sonic_balance(self.token1).await?;
+
+        // The pair rejects the removal if the amounts are below the slippage tolerance
+        self.sonic_provider().remove_liquidity(
+            self.token0,
+            self.token1,
+            lp_tokens_to_withdraw,
+            Nat::from(self.swap_limits.min_amount_out(nat_to_u128(&amount0))),
+            Nat::from(self.swap_limits.min_amount_out(nat_to_u128(&amount1))),
+            ic_cdk::id(),
+        ).await?;
+
+        // The removed amounts are credited to the Sonic balance of the canister
+        let token_0_removed = credited_amount(&token_0_balance, &self.sonic_balance(self.token0).await?);
+        let token_1_removed = credited_amount(&token_1_balance, &self.sonic_balance(self.token1).await?);
+
+        let token_0_withdrawal = self.withdraw(self.token0, token_0_removed).await?;
+        let token_1_withdrawal = self.withdraw(self.token1, token_1_removed).await?;
+
+        Ok(WithdrawLiquidityResponse {
+            token_0_amount: token_0_withdrawal.amount,
+            token_1_amount: token_1_withdrawal.amount,
+            ledger_fees: LiquidityFees {
+                token_0_fee: token_0_withdrawal.fees_paid,
+                token_1_fee: token_1_withdrawal.fees_paid,
+            },
+        })
+    }
+
+    async fn quote_add_liquidity(&self, amount: Nat) -> Result<QuoteAddLiquidityResponse, InternalError> {
+        let (calculator_response, swap_provider) = self.calculate_add_liquidity_amounts(amount).await?;
+
+        let token_0_for_swap_amount = Nat::from(calculator_response.token_0_for_swap as u128);
+
+        // The swap is made with the part of the amount left after its approval and transfer
+        let swap_deduction = ledger_fees::deduct_ledger_fees(
+            self.token0,
+            &token_0_for_swap_amount,
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
+
+        // Get quote for the swap part of the deposit with the same provider used for the execution
+        let swap_quote = swap_service::quote_swap_icrc2(
+            self.provider_impls.clone(),
+            self.token0,
+            self.token1,
+            swap_deduction.amount.clone(),
+            swap_provider,
+        ).await?;
+
+        // Sonic takes no fee for adding liquidity, the deposits to the Sonic balance pay the ledger fees
+        let token_0_deduction = ledger_fees::deduct_ledger_fees(
+            self.token0,
+            &Nat::from(calculator_response.token_0_for_pool as u128),
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
+
+        let token_1_deduction = ledger_fees::deduct_ledger_fees(
+            self.token1,
+            &Nat::from(swap_quote.amount_out),
+            APPROVED_TRANSFER_FEE_COUNT,
+        ).await?;
+
+        Ok(liquidity_quotes::swap_and_deposit_quote(
+            token_0_for_swap_amount,
+            &swap_deduction,
+            &token_0_deduction,
+            &token_1_deduction,
+            swap_provider,
+        ))
+    }
+
+    async fn quote_withdraw_liquidity(&self, total_shares: Nat, shares: Nat) -> Result<QuoteWithdrawLiquidityResponse, InternalError> {
+        let (pair, reserve0, reserve1) = self.get_pair().await?;
+        let balance = self.get_lp_balance(&pair).await?;
+
+        let lp_tokens_to_withdraw = self.lp_tokens_to_withdraw(&balance, &total_shares, &shares);
+        let (amount0, amount1) = lp_share_amounts(&lp_tokens_to_withdraw, &pair.totalSupply, &reserve0, &reserve1);
+
+        // The LP fees are accrued in the reserves, Sonic takes no fee for removing liquidity.
+        // The removed amounts are paid out with withdrawals from the Sonic balance.
+        let token_0_fee = token_registry::registry::fee(self.token0).await?;
+        let token_1_fee = token_registry::registry::fee(self.token1).await?;
+
+        Ok(liquidity_quotes::transfer_withdrawal_quote(&amount0, &amount1, &token_0_fee, &token_1_fee))
+    }
+
+    async fn get_position_by_id(&self, position_id: u64) -> Result<GetPositionByIdResponse, InternalError> {
+        let (pair, reserve0, reserve1) = self.get_pair().await?;
+        let balance = self.get_lp_balance(&pair).await?;
+
+        let (amount0, amount1) = lp_share_amounts(&balance, &pair.totalSupply, &reserve0, &reserve1);
+
+        Ok(GetPositionByIdResponse {
+            position_id,
+            usd_amount_0: self.usd_amount(self.token0, amount0.clone()).await?,
+            usd_amount_1: self.usd_amount(self.token1, amount1.clone()).await?,
+            token_0_amount: amount0,
+            token_1_amount: amount1,
+        })
+    }
+
+    async fn get_pool_data(&self) -> Result<GetPoolDataResponse, InternalError> {
+        let (_, reserve0, reserve1) = self.get_pair().await?;
+
+        let usdt_decimals = token_registry::registry::usd_token().await?.decimals;
+        let usdt_base_unit = Nat::from(10u128.pow(usdt_decimals as u32));
+
+        let usd_reserve0 = self.usd_amount(self.token0, reserve0).await?;
+        let usd_reserve1 = self.usd_amount(self.token1, reserve1).await?;
+
+        Ok(GetPoolDataResponse {
+            tvl: (usd_reserve0 + usd_reserve1) / usdt_base_unit,
+        })
+    }
+
+    async fn get_pool_ratio(&self) -> Result<f64, InternalError> {
+        let (_, reserve0, reserve1) = self.get_pair().await?;
+
+        Ok(nat_to_f64(&reserve1) / nat_to_f64(&reserve0))
+    }
+}
+
+pub struct SonicLiquidityAdapter;
+
+#[async_trait]
+impl LiquidityAdapter for SonicLiquidityAdapter {
+    fn exchange_id(&self) -> ExchangeId {
+        ExchangeId::Sonic
+    }
+
+    async fn liquidity_client(
+        &self,
+        provider_impls: ProviderImpls,
+        token0: CanisterId,
+        token1: CanisterId,
+        swap_limits: SwapLimits,
+    ) -> Result<Box<dyn LiquidityClient>, InternalError> {
+        Ok(Box::new(
+            SonicLiquidityClient::new(
+                provider_impls,
+                *SONIC_SWAP_CANISTER_ID,
+                token0,
+                token1
+            )?.with_swap_limits(swap_limits)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod lp_share_amounts {
+        use super::*;
+
+        #[test]
+        fn splits_reserves_by_lp_share() {
+            let (amount0, amount1) = lp_share_amounts(
+                &Nat::from(250u64),
+                &Nat::from(1_000u64),
+                &Nat::from(4_000u64),
+                &Nat::from(8_000u64),
+            );
+
+            assert_eq!(amount0, Nat::from(1_000u64));
+            assert_eq!(amount1, Nat::from(2_000u64));
+        }
+
+        #[test]
+        fn is_zero_without_supply() {
+            let (amount0, amount1) = lp_share_amounts(
+                &Nat::from(250u64),
+                &Nat::from(0u8),
+                &Nat::from(4_000u64),
+                &Nat::from(8_000u64),
+            );
+
+            assert_eq!(amount0, Nat::from(0u8));
+            assert_eq!(amount1, Nat::from(0u8));
+        }
+    }
+}
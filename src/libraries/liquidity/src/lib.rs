@@ -1,4 +1,6 @@
 pub mod clients;
 pub mod liquidity_client;
 pub mod liquidity_calculator;
+pub mod liquidity_quotes;
 pub mod liquidity_router;
+pub mod liquidity_adapters;
@@ -0,0 +1,80 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use providers::providers_factory::ProviderImpls;
+use types::CanisterId;
+use types::exchange_id::ExchangeId;
+use types::swap_tokens::SwapLimits;
+
+use crate::clients::icpswap::ICPSwapLiquidityAdapter;
+use crate::clients::kongswap::KongSwapLiquidityAdapter;
+use crate::clients::sonic::SonicLiquidityAdapter;
+use crate::liquidity_client::LiquidityClient;
+
+/// Liquidity side of an exchange integration, registered in `liquidity_adapters` under its exchange id
+#[async_trait]
+pub trait LiquidityAdapter: Send + Sync {
+    fn exchange_id(&self) -> ExchangeId;
+
+    /// Liquidity client of the exchange for the pool of the tokens,
+    /// with the providers taken from `provider_impls`
+    async fn liquidity_client(
+        &self,
+        provider_impls: ProviderImpls,
+        token0: CanisterId,
+        token1: CanisterId,
+        swap_limits: SwapLimits,
+    ) -> Result<Box<dyn LiquidityClient>, InternalError>;
+}
+
+/// Adapters of all exchanges liquidity can be provided to
+pub fn liquidity_adapters() -> Vec<Box<dyn LiquidityAdapter>> {
+    vec![
+        Box::new(KongSwapLiquidityAdapter),
+        Box::new(ICPSwapLiquidityAdapter),
+        Box::new(SonicLiquidityAdapter),
+    ]
+}
+
+pub fn get_liquidity_adapter(exchange_id: ExchangeId) -> Result<Box<dyn LiquidityAdapter>, InternalError> {
+    liquidity_adapters()
+        .into_iter()
+        .find(|adapter| adapter.exchange_id() == exchange_id)
+        .ok_or_else(|| InternalError::validation(
+            build_error_code(2100, 2, 1), // 2100 02 01
+            "liquidity_adapters::get_liquidity_adapter".to_string(),
+            "Exchange is not supported for liquidity".to_string(),
+            Some(HashMap::from([
+                ("exchange_id".to_string(), exchange_id.to_string()),
+            ])),
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod liquidity_adapters {
+        use super::*;
+        use swap::swap_adapters::swap_adapters;
+
+        #[test]
+        fn registers_the_exchanges_with_swap_adapters() {
+            let mut liquidity_exchange_ids: Vec<String> = liquidity_adapters()
+                .iter()
+                .map(|adapter| adapter.exchange_id().to_string())
+                .collect();
+            let mut swap_exchange_ids: Vec<String> = swap_adapters()
+                .iter()
+                .map(|adapter| adapter.exchange_id().to_string())
+                .collect();
+
+            liquidity_exchange_ids.sort();
+            swap_exchange_ids.sort();
+
+            assert_eq!(liquidity_exchange_ids, swap_exchange_ids);
+        }
+    }
+}
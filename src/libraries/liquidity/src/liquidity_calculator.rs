@@ -10,18 +10,31 @@ pub struct CalculatePoolLiquidityAmountsResponse {
     pub token_1_for_pool: f64,
 }
 
+pub struct CalculateMigrationSwapResponse {
+    pub token_0_for_swap: f64,
+    pub token_1_for_swap: f64,
+    // Swap volumes are denominated in token0
+    pub naive_swap_volume: f64,
+    pub migration_swap_volume: f64,
+    pub saved_swap_volume: f64,
+}
+
 
 // TODO: move methods to separate services
 impl LiquidityCalculator {
+    /// Balance per share, 1 before the first deposit
+    pub fn calculate_share_price(total_balance: Nat, total_shares: Nat) -> Nat {
+        if total_shares == Nat::from(0u64) {
+            Nat::from(1u64)
+        } else {
+            total_balance / total_shares
+        }
+    }
+
     pub fn calculate_shares_for_deposit(amount: Nat, total_balance: Nat, total_shares: Nat) -> Nat {
         let zero = Nat::from(0u64);
-        let one = Nat::from(1u64);
 
-        let share_price = if total_shares == zero {
-            one.clone()
-        } else {
-            total_balance.clone() / total_shares.clone()
-        };
+        let share_price = Self::calculate_share_price(total_balance.clone(), total_shares.clone());
 
         if total_balance == zero || total_shares == zero {
             amount
@@ -60,6 +73,44 @@ impl LiquidityCalculator {
             token_1_for_pool: final_token_1_for_pool.round(),
         }
     }
+
+    /// Calculates the swap needed to move withdrawn token0 and token1 amounts
+    /// into a pool with `pool_ratio` (token1 per token0), swapping only the delta
+    /// instead of converting everything to token0 and re-depositing.
+    pub fn calculate_migration_swap(
+        amount_0: f64,
+        amount_1: f64,
+        pool_ratio: f64,
+        swap_price: f64,
+    ) -> CalculateMigrationSwapResponse {
+        let required_token_1 = amount_0.mul(pool_ratio);
+
+        // Swap the excess side so that the resulting amounts match the pool ratio
+        let (token_0_for_swap, token_1_for_swap) = if amount_1 < required_token_1 {
+            ((required_token_1 - amount_1) / (pool_ratio + swap_price), 0f64)
+        } else {
+            (0f64, swap_price * (amount_1 - required_token_1) / (swap_price + pool_ratio))
+        };
+
+        // Naive flow swaps all token1 to token0 and then swaps part of token0 back on deposit
+        let token_0_from_token_1 = amount_1 / swap_price;
+        let naive_swap_volume = token_0_from_token_1 + Self::calculate_token_amounts_for_deposit(
+            amount_0 + token_0_from_token_1,
+            pool_ratio,
+            swap_price,
+        ).token_0_for_swap;
+
+        let migration_swap_volume = token_0_for_swap + token_1_for_swap / swap_price;
+        let saved_swap_volume = (naive_swap_volume - migration_swap_volume).max(0f64);
+
+        CalculateMigrationSwapResponse {
+            token_0_for_swap: token_0_for_swap.round(),
+            token_1_for_swap: token_1_for_swap.round(),
+            naive_swap_volume: naive_swap_volume.round(),
+            migration_swap_volume: migration_swap_volume.round(),
+            saved_swap_volume: saved_swap_volume.round(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -155,4 +206,41 @@ mod tests {
             assert!(total_token_0 <= amount);
         }
     }
+
+    mod calculate_migration_swap {
+        use super::super::*;
+
+        #[test]
+        fn test_with_amounts_already_in_pool_ratio() {
+            let result = LiquidityCalculator::calculate_migration_swap(1000f64, 1000f64, 1f64, 1f64);
+
+            assert_eq!(result.token_0_for_swap, 0f64);
+            assert_eq!(result.token_1_for_swap, 0f64);
+            assert_eq!(result.naive_swap_volume, 2000f64);
+            assert_eq!(result.migration_swap_volume, 0f64);
+            assert_eq!(result.saved_swap_volume, 2000f64);
+        }
+
+        #[test]
+        fn test_with_only_token_0() {
+            let result = LiquidityCalculator::calculate_migration_swap(1000f64, 0f64, 1f64, 1f64);
+
+            assert_eq!(result.token_0_for_swap, 500f64);
+            assert_eq!(result.token_1_for_swap, 0f64);
+            assert_eq!(result.naive_swap_volume, 500f64);
+            assert_eq!(result.migration_swap_volume, 500f64);
+            assert_eq!(result.saved_swap_volume, 0f64);
+        }
+
+        #[test]
+        fn test_with_excess_token_1() {
+            let result = LiquidityCalculator::calculate_migration_swap(0f64, 1000f64, 2f64, 2f64);
+
+            assert_eq!(result.token_0_for_swap, 0f64);
+            assert_eq!(result.token_1_for_swap, 500f64);
+            assert_eq!(result.naive_swap_volume, 750f64);
+            assert_eq!(result.migration_swap_volume, 250f64);
+            assert_eq!(result.saved_swap_volume, 500f64);
+        }
+    }
 }
@@ -2,14 +2,25 @@ use async_trait::async_trait;
 use types::CanisterId;
 use candid::Nat;
 
-use types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse, GetPositionByIdResponse, GetPoolDataResponse};
+use types::liquidity::{
+    AddLiquidityResponse,
+    WithdrawLiquidityResponse,
+    GetPositionByIdResponse,
+    GetPoolDataResponse,
+    QuoteAddLiquidityResponse,
+    QuoteWithdrawLiquidityResponse,
+};
 use errors::internal_error::error::InternalError;
 
 #[async_trait]
 pub trait LiquidityClient: Send + Sync + 'static {
     fn canister_id(&self) -> CanisterId;
     async fn add_liquidity_to_pool(&self, amount: Nat) -> Result<AddLiquidityResponse, InternalError>;
+    async fn add_liquidity_to_pool_with_amounts(&self, amount0: Nat, amount1: Nat) -> Result<AddLiquidityResponse, InternalError>;
     async fn withdraw_liquidity_from_pool(&self, total_shares: Nat, shares: Nat) -> Result<WithdrawLiquidityResponse, InternalError>;
+    async fn quote_add_liquidity(&self, amount: Nat) -> Result<QuoteAddLiquidityResponse, InternalError>;
+    async fn quote_withdraw_liquidity(&self, total_shares: Nat, shares: Nat) -> Result<QuoteWithdrawLiquidityResponse, InternalError>;
     async fn get_position_by_id(&self, position_id: u64) -> Result<GetPositionByIdResponse, InternalError>;
     async fn get_pool_data(&self) -> Result<GetPoolDataResponse, InternalError>;
+    async fn get_pool_ratio(&self) -> Result<f64, InternalError>;
 }
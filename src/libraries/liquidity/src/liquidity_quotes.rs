@@ -0,0 +1,115 @@
+use candid::Nat;
+
+use types::exchange_id::ExchangeId;
+use types::liquidity::{QuoteAddLiquidityResponse, QuoteWithdrawLiquidityResponse, LiquidityFees};
+use token_registry::ledger_fees::{self, FeeDeduction};
+
+// Quotes report the ledger fees the quoted operation pays and the amounts left after them,
+// the same way the responses of the executed operations report them in `ledger_fees`.
+// Pool fees are not reported, they are already taken out of the quoted swap and pool amounts.
+
+/// Quote of a deposit which swaps part of token0 for token1 and then moves both pool amounts
+/// to the exchange with approvals. The approvals and transfers are paid out of the moved amounts.
+pub fn swap_and_deposit_quote(
+    token_0_for_swap: Nat,
+    swap: &FeeDeduction,
+    token_0_for_pool: &FeeDeduction,
+    token_1_for_pool: &FeeDeduction,
+    swap_provider: ExchangeId,
+) -> QuoteAddLiquidityResponse {
+    QuoteAddLiquidityResponse {
+        token_0_for_swap,
+        token_0_for_pool: token_0_for_pool.amount.clone(),
+        token_1_for_pool: token_1_for_pool.amount.clone(),
+        swap_provider,
+        fees: LiquidityFees {
+            token_0_fee: swap.fees_paid.clone() + token_0_for_pool.fees_paid.clone(),
+            token_1_fee: token_1_for_pool.fees_paid.clone(),
+        },
+    }
+}
+
+/// Quote of a withdrawal which the exchange pays out with a ledger transfer of each token,
+/// the fees taken from the transferred amounts
+pub fn transfer_withdrawal_quote(
+    amount_0: &Nat,
+    amount_1: &Nat,
+    token_0_fee: &Nat,
+    token_1_fee: &Nat,
+) -> QuoteWithdrawLiquidityResponse {
+    let token_0_received = ledger_fees::received_after_fee(amount_0, token_0_fee);
+    let token_1_received = ledger_fees::received_after_fee(amount_1, token_1_fee);
+
+    QuoteWithdrawLiquidityResponse {
+        token_0_amount: token_0_received.amount,
+        token_1_amount: token_1_received.amount,
+        fees: LiquidityFees {
+            token_0_fee: token_0_received.fees_paid,
+            token_1_fee: token_1_received.fees_paid,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use token_registry::ledger_fees::{deduct_fees, APPROVED_TRANSFER_FEE_COUNT};
+
+    mod swap_and_deposit_quote {
+        use super::*;
+
+        #[test]
+        fn reports_fees_of_swap_and_pool_transfers() {
+            let fee = Nat::from(10u64);
+            let swap = deduct_fees(&Nat::from(500u64), &fee, APPROVED_TRANSFER_FEE_COUNT).unwrap();
+            let token_0_for_pool = deduct_fees(&Nat::from(500u64), &fee, APPROVED_TRANSFER_FEE_COUNT).unwrap();
+            let token_1_for_pool = deduct_fees(&Nat::from(960u64), &fee, APPROVED_TRANSFER_FEE_COUNT).unwrap();
+
+            let quote = swap_and_deposit_quote(
+                Nat::from(500u64),
+                &swap,
+                &token_0_for_pool,
+                &token_1_for_pool,
+                ExchangeId::KongSwap,
+            );
+
+            assert_eq!(quote.token_0_for_pool, Nat::from(470u64));
+            assert_eq!(quote.token_1_for_pool, Nat::from(930u64));
+            assert_eq!(quote.fees.token_0_fee, Nat::from(60u64));
+            assert_eq!(quote.fees.token_1_fee, Nat::from(30u64));
+            assert_eq!(quote.token_0_for_swap.clone() + quote.token_0_for_pool.clone() + token_0_for_pool.fees_paid, Nat::from(1_000u64));
+        }
+    }
+
+    mod transfer_withdrawal_quote {
+        use super::*;
+
+        #[test]
+        fn takes_transfer_fees_from_amounts() {
+            let quote = transfer_withdrawal_quote(
+                &Nat::from(1_000u64),
+                &Nat::from(2_000u64),
+                &Nat::from(10u64),
+                &Nat::from(20u64),
+            );
+
+            assert_eq!(quote.token_0_amount, Nat::from(990u64));
+            assert_eq!(quote.token_1_amount, Nat::from(1_980u64));
+            assert_eq!(quote.fees.token_0_fee, Nat::from(10u64));
+            assert_eq!(quote.fees.token_1_fee, Nat::from(20u64));
+        }
+
+        #[test]
+        fn quotes_nothing_for_amount_below_fee() {
+            let quote = transfer_withdrawal_quote(
+                &Nat::from(5u64),
+                &Nat::from(2_000u64),
+                &Nat::from(10u64),
+                &Nat::from(20u64),
+            );
+
+            assert_eq!(quote.token_0_amount, Nat::from(0u8));
+            assert_eq!(quote.fees.token_0_fee, Nat::from(0u8));
+        }
+    }
+}
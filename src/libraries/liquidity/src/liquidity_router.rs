@@ -1,10 +1,10 @@
 use types::exchange_id::ExchangeId;
 use types::CanisterId;
-use utils::constants::KONGSWAP_CANISTER_ID;
+use types::swap_tokens::SwapLimits;
 use providers::providers_factory::ProviderImpls;
+use errors::internal_error::error::InternalError;
 
-use crate::clients::kongswap::KongSwapLiquidityClient;
-use crate::clients::icpswap::ICPSwapLiquidityClient;
+use crate::liquidity_adapters::get_liquidity_adapter;
 use crate::liquidity_client::LiquidityClient;
 
 pub async fn get_liquidity_client(
@@ -12,23 +12,19 @@ pub async fn get_liquidity_client(
     token0: CanisterId,
     token1: CanisterId,
     provider: ExchangeId,
-) -> Box<dyn LiquidityClient + 'static> {
-    match provider {
-        ExchangeId::KongSwap => Box::new(
-            KongSwapLiquidityClient::new(
-                provider_impls.clone(),
-                *KONGSWAP_CANISTER_ID,
-                token0.clone(), 
-                token1.clone()
-            )
-        ),
-        ExchangeId::ICPSwap => Box::new(
-            ICPSwapLiquidityClient::new(
-                provider_impls,
-                token0.clone(), 
-                token1.clone()
-            ).with_pool().await.unwrap() // TODO: handle error
-        ),
-        _ => panic!("Unsupported provider"),
-    }
+) -> Result<Box<dyn LiquidityClient + 'static>, InternalError> {
+    get_liquidity_client_with_swap_limits(provider_impls, token0, token1, provider, SwapLimits::default()).await
+}
+
+/// Liquidity client which checks its internal swaps against the given limits
+pub async fn get_liquidity_client_with_swap_limits(
+    provider_impls: ProviderImpls,
+    token0: CanisterId,
+    token1: CanisterId,
+    provider: ExchangeId,
+    swap_limits: SwapLimits,
+) -> Result<Box<dyn LiquidityClient + 'static>, InternalError> {
+    get_liquidity_adapter(provider)?
+        .liquidity_client(provider_impls, token0, token1, swap_limits)
+        .await
 }
@@ -22,7 +22,7 @@ use utils::constants::KONGSWAP_CANISTER_ID;
 pub trait KongSwapProvider: Send + Sync + 'static {
     async fn pools(&self) -> Result<Vec<PoolReply>, InternalError>;
     async fn swap_amounts(&self, token_in: CanisterId, amount: Nat, token_out: CanisterId) -> Result<SwapAmountsReply, InternalError>;
-    async fn swap(&self, token_in: CanisterId, amount: Nat, token_out: CanisterId, max_slippage: Option<f64>) -> Result<SwapReply, InternalError>;
+    async fn swap(&self, token_in: CanisterId, amount: Nat, token_out: CanisterId, receive_amount: Option<Nat>, max_slippage: Option<f64>) -> Result<SwapReply, InternalError>;
     async fn add_liquidity_amounts(&self, token_0: String, amount: Nat, token_1: String) -> Result<AddLiquidityAmountsReply, InternalError>;
     async fn add_liquidity(&self, token_0: String, amount_0: Nat, token_1: String, amount_1: Nat, ledger0: Principal, ledger1: Principal) -> Result<AddLiquidityReply, InternalError>;
     async fn user_balances(&self, principal_id: String) -> Result<Vec<UserBalancesReply>, InternalError>;
@@ -106,12 +106,14 @@ impl KongSwapProvider for DefaultKongSwapProvider {
         token_in: CanisterId,
         amount: Nat,
         token_out: CanisterId,
+        receive_amount: Option<Nat>,
         max_slippage: Option<f64>
     ) -> Result<SwapReply, InternalError> {
         let args = SwapArgs {
             pay_amount: amount.into(),
             pay_token: Self::token_kongswap_format(&token_in.clone()),
             receive_token: Self::token_kongswap_format(&token_out.clone()),
+            receive_amount,
             max_slippage,
         };
 
@@ -128,6 +130,7 @@ impl KongSwapProvider for DefaultKongSwapProvider {
                         ("pay_amount".to_string(), args.pay_amount.to_string()),
                         ("pay_token".to_string(), args.pay_token.to_string()),
                         ("receive_token".to_string(), args.receive_token.to_string()),
+                        ("receive_amount".to_string(), args.receive_amount.clone().unwrap_or_default().to_string()),
                         ("max_slippage".to_string(), args.max_slippage.unwrap_or(0.0).to_string()),
                     ])),
                 )
@@ -141,6 +144,7 @@ impl KongSwapProvider for DefaultKongSwapProvider {
                         ("pay_amount".to_string(), args.pay_amount.to_string()),
                         ("pay_token".to_string(), args.pay_token.to_string()),
                         ("receive_token".to_string(), args.receive_token.to_string()),
+                        ("receive_amount".to_string(), args.receive_amount.clone().unwrap_or_default().to_string()),
                         ("max_slippage".to_string(), args.max_slippage.unwrap_or(0.0).to_string()),
                     ])),
                 )
@@ -198,13 +202,15 @@ impl KongSwapProvider for DefaultKongSwapProvider {
         icrc_ledger_client::icrc2_approve(
             KONGSWAP_CANISTER_ID.clone().into(),
             ledger0,
-            amount_0.clone()
+            amount_0.clone(),
+            token_registry::registry::fee(ledger0).await?
         ).await?;
 
         icrc_ledger_client::icrc2_approve(
             KONGSWAP_CANISTER_ID.clone().into(),
             ledger1,
-            amount_1.clone()
+            amount_1.clone(),
+            token_registry::registry::fee(ledger1).await?
         ).await?;
 
         let args = AddLiquidityArgs {
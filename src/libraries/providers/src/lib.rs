@@ -1,7 +1,9 @@
 pub mod icpswap;
 pub mod kongswap;
+pub mod sonic;
 pub mod providers_factory;
 pub mod mock {
     pub mod icpswap;
     pub mod kongswap;
+    pub mod sonic;
 }
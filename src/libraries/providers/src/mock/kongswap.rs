@@ -25,11 +25,15 @@ fn slippage_to_string(slippage: Option<f64>) -> String {
     slippage.map_or("none".to_string(), |v| format!("{:.8}", v))
 }
 
+fn receive_amount_to_string(receive_amount: &Option<Nat>) -> String {
+    receive_amount.as_ref().map_or("none".to_string(), |v| v.to_string())
+}
+
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct MockKongSwapProvider {
     pub pools_response: Result<Vec<PoolReply>, InternalError>,
     pub swap_amounts_responses: HashMap<(String, String, String), Result<SwapAmountsReply, InternalError>>,
-    pub swap_responses: HashMap<(String, String, String, String), Result<SwapReply, InternalError>>,
+    pub swap_responses: HashMap<(String, String, String, String, String), Result<SwapReply, InternalError>>,
     pub add_liquidity_amounts_responses: HashMap<(String, String, String), Result<AddLiquidityAmountsReply, InternalError>>,
     pub add_liquidity_responses: HashMap<(String, String, String, String, String, String), Result<AddLiquidityReply, InternalError>>,
     pub user_balances_responses: HashMap<String, Result<Vec<UserBalancesReply>, InternalError>>,
@@ -84,6 +88,7 @@ impl MockKongSwapProvider {
         token_in: CanisterId,
         amount: Nat,
         token_out: CanisterId,
+        receive_amount: Option<Nat>,
         max_slippage: Option<f64>,
         response: Result<SwapReply, InternalError>,
     ) {
@@ -92,6 +97,7 @@ impl MockKongSwapProvider {
                 token_in.to_text(),
                 amount.to_string(),
                 token_out.to_text(),
+                receive_amount_to_string(&receive_amount),
                 slippage_to_string(max_slippage)
             ),
             response
@@ -196,12 +202,14 @@ impl KongSwapProvider for MockKongSwapProvider {
         token_in: CanisterId,
         amount: Nat,
         token_out: CanisterId,
+        receive_amount: Option<Nat>,
         max_slippage: Option<f64>,
     ) -> Result<SwapReply, InternalError> {
         let key = (
             token_in.to_text(),
             amount.to_string(),
             token_out.to_text(),
+            receive_amount_to_string(&receive_amount),
             slippage_to_string(max_slippage)
         );
 
@@ -216,6 +224,7 @@ impl KongSwapProvider for MockKongSwapProvider {
                         ("token_in".to_string(), token_in.to_text()),
                         ("amount".to_string(), amount.to_string()),
                         ("token_out".to_string(), token_out.to_text()),
+                        ("receive_amount".to_string(), receive_amount_to_string(&receive_amount)),
                         ("max_slippage".to_string(), slippage_to_string(max_slippage)),
                     ]))
                 )),
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use candid::{Nat, Principal};
+use types::CanisterId;
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use candid::CandidType;
+
+use sonic_canister::PairInfoExt;
+use errors::internal_error::error::{InternalError, build_error_code};
+
+use crate::sonic::SonicProvider;
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct MockSonicProvider {
+    pub get_pair_responses: HashMap<(String, String), Result<Option<PairInfoExt>, InternalError>>,
+    pub get_all_pairs_response: Result<Vec<PairInfoExt>, InternalError>,
+    pub get_user_lp_balances_responses: HashMap<String, Result<Vec<(String, Nat)>, InternalError>>,
+    pub balance_of_responses: HashMap<(String, String), Result<Nat, InternalError>>,
+    pub deposit_responses: HashMap<(String, String), Result<Nat, InternalError>>,
+    pub withdraw_responses: HashMap<(String, String), Result<Nat, InternalError>>,
+    pub swap_responses: HashMap<(String, String, String, String, String), Result<Nat, InternalError>>,
+    pub add_liquidity_responses: HashMap<(String, String, String, String, String, String), Result<Nat, InternalError>>,
+    pub remove_liquidity_responses: HashMap<(String, String, String, String, String, String), Result<Nat, InternalError>>,
+}
+
+impl Default for MockSonicProvider {
+    fn default() -> Self {
+        Self {
+            get_pair_responses: HashMap::new(),
+            get_all_pairs_response: Err(InternalError::not_found(
+                build_error_code(0000, 0, 0),
+                "mock_error".to_string(),
+                "Mock response not set for get_all_pairs".to_string(),
+                None
+            )),
+            get_user_lp_balances_responses: HashMap::new(),
+            balance_of_responses: HashMap::new(),
+            deposit_responses: HashMap::new(),
+            withdraw_responses: HashMap::new(),
+            swap_responses: HashMap::new(),
+            add_liquidity_responses: HashMap::new(),
+            remove_liquidity_responses: HashMap::new(),
+        }
+    }
+}
+
+impl MockSonicProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mock_get_pair(
+        &mut self,
+        token0: CanisterId,
+        token1: CanisterId,
+        response: Result<Option<PairInfoExt>, InternalError>,
+    ) {
+        self.get_pair_responses.insert((token0.to_text(), token1.to_text()), response);
+    }
+
+    pub fn mock_get_all_pairs(&mut self, response: Result<Vec<PairInfoExt>, InternalError>) {
+        self.get_all_pairs_response = response;
+    }
+
+    pub fn mock_get_user_lp_balances(
+        &mut self,
+        user: Principal,
+        response: Result<Vec<(String, Nat)>, InternalError>,
+    ) {
+        self.get_user_lp_balances_responses.insert(user.to_text(), response);
+    }
+
+    pub fn mock_balance_of(
+        &mut self,
+        token: CanisterId,
+        user: Principal,
+        response: Result<Nat, InternalError>,
+    ) {
+        self.balance_of_responses.insert((token.to_text(), user.to_text()), response);
+    }
+
+    pub fn mock_deposit(&mut self, token: CanisterId, amount: Nat, response: Result<Nat, InternalError>) {
+        self.deposit_responses.insert((token.to_text(), amount.to_string()), response);
+    }
+
+    pub fn mock_withdraw(&mut self, token: CanisterId, amount: Nat, response: Result<Nat, InternalError>) {
+        self.withdraw_responses.insert((token.to_text(), amount.to_string()), response);
+    }
+
+    pub fn mock_swap_exact_tokens_for_tokens(
+        &mut self,
+        amount_in: Nat,
+        amount_out_min: Nat,
+        token_in: CanisterId,
+        token_out: CanisterId,
+        to: Principal,
+        response: Result<Nat, InternalError>,
+    ) {
+        self.swap_responses.insert(
+            (amount_in.to_string(), amount_out_min.to_string(), token_in.to_text(), token_out.to_text(), to.to_text()),
+            response
+        );
+    }
+
+    pub fn mock_add_liquidity(
+        &mut self,
+        token0: CanisterId,
+        token1: CanisterId,
+        amount0_desired: Nat,
+        amount1_desired: Nat,
+        amount0_min: Nat,
+        amount1_min: Nat,
+        response: Result<Nat, InternalError>,
+    ) {
+        self.add_liquidity_responses.insert(
+            (
+                token0.to_text(),
+                token1.to_text(),
+                amount0_desired.to_string(),
+                amount1_desired.to_string(),
+                amount0_min.to_string(),
+                amount1_min.to_string(),
+            ),
+            response
+        );
+    }
+
+    pub fn mock_remove_liquidity(
+        &mut self,
+        token0: CanisterId,
+        token1: CanisterId,
+        lp_amount: Nat,
+        amount0_min: Nat,
+        amount1_min: Nat,
+        to: Principal,
+        response: Result<Nat, InternalError>,
+    ) {
+        self.remove_liquidity_responses.insert(
+            (
+                token0.to_text(),
+                token1.to_text(),
+                lp_amount.to_string(),
+                amount0_min.to_string(),
+                amount1_min.to_string(),
+                to.to_text(),
+            ),
+            response
+        );
+    }
+}
+
+#[async_trait]
+impl SonicProvider for MockSonicProvider {
+    async fn get_pair(&self, token0: CanisterId, token1: CanisterId) -> Result<Option<PairInfoExt>, InternalError> {
+        self.get_pair_responses
+            .get(&(token0.to_text(), token1.to_text()))
+            .map_or_else(
+                || Err(InternalError::not_found(
+                    build_error_code(2303, 01, 01), // 2303 01 01
+                    "MockSonicProvider::get_pair".to_string(),
+                    "Mock response not set for get_pair".to_string(),
+                    Some(HashMap::from([
+                        ("token0".to_string(), token0.to_text()),
+                        ("token1".to_string(), token1.to_text()),
+                    ]))
+                )),
+                |r| r.to_owned()
+            )
+    }
+
+    async fn get_all_pairs(&self) -> Result<Vec<PairInfoExt>, InternalError> {
+        self.get_all_pairs_response.clone()
+    }
+
+    async fn get_user_lp_balances(&self, user: Principal) -> Result<Vec<(String, Nat)>, InternalError> {
+        self.get_user_lp_balances_responses
+            .get(&user.to_text())
+            .map_or_else(
+                || Err(InternalError::not_found(
+                    build_error_code(2303, 01, 02), // 2303 01 02
+                    "MockSonicProvider::get_user_lp_balances".to_string(),
+                    "Mock response not set for get_user_lp_balances".to_string(),
+                    Some(HashMap::from([
+                        ("user".to_string(), user.to_text()),
+                    ]))
+                )),
+                |r| r.to_owned()
+            )
+    }
+
+    async fn balance_of(&self, token: CanisterId, user: Principal) -> Result<Nat, InternalError> {
+        self.balance_of_responses
+            .get(&(token.to_text(), user.to_text()))
+            .map_or_else(
+                || Err(InternalError::not_found(
+                    build_error_code(2303, 01, 03), // 2303 01 03
+                    "MockSonicProvider::balance_of".to_string(),
+                    "Mock response not set for balance_of".to_string(),
+                    Some(HashMap::from([
+                        ("token".to_string(), token.to_text()),
+                        ("user".to_string(), user.to_text()),
+                    ]))
+                )),
+                |r| r.to_owned()
+            )
+    }
+
+    async fn deposit(&self, token: CanisterId, amount: Nat) -> Result<Nat, InternalError> {
+        self.deposit_responses
+            .get(&(token.to_text(), amount.to_string()))
+            .map_or_else(
+                || Err(InternalError::not_found(
+                    build_error_code(2303, 01, 04), // 2303 01 04
+                    "MockSonicProvider::deposit".to_string(),
+                    "Mock response not set for deposit".to_string(),
+                    Some(HashMap::from([
+                        ("token".to_string(), token.to_text()),
+                        ("amount".to_string(), amount.to_string()),
+                    ]))
+                )),
+                |r| r.to_owned()
+            )
+    }
+
+    async fn withdraw(&self, token: CanisterId, amount: Nat) -> Result<Nat, InternalError> {
+        self.withdraw_responses
+            .get(&(token.to_text(), amount.to_string()))
+            .map_or_else(
+                || Err(InternalError::not_found(
+                    build_error_code(2303, 01, 05), // 2303 01 05
+                    "MockSonicProvider::withdraw".to_string(),
+                    "Mock response not set for withdraw".to_string(),
+                    Some(HashMap::from([
+                        ("token".to_string(), token.to_text()),
+                        ("amount".to_string(), amount.to_string()),
+                    ]))
+                )),
+                |r| r.to_owned()
+            )
+    }
+
+    async fn swap_exact_tokens_for_tokens(
+        &self,
+        amount_in: Nat,
+        amount_out_min: Nat,
+        token_in: CanisterId,
+        token_out: CanisterId,
+        to: Principal,
+    ) -> Result<Nat, InternalError> {
+        let key = (
+            amount_in.to_string(),
+            amount_out_min.to_string(),
+            token_in.to_text(),
+            token_out.to_text(),
+            to.to_text(),
+        );
+
+        self.swap_responses
+            .get(&key)
+            .map_or_else(
+                || Err(InternalError::not_found(
+                    build_error_code(2303, 01, 06), // 2303 01 06
+                    "MockSonicProvider::swap_exact_tokens_for_tokens".to_string(),
+                    "Mock response not set for swap_exact_tokens_for_tokens".to_string(),
+                    Some(HashMap::from([
+                        ("amount_in".to_string(), amount_in.to_string()),
+                        ("amount_out_min".to_string(), amount_out_min.to_string()),
+                        ("token_in".to_string(), token_in.to_text()),
+                        ("token_out".to_string(), token_out.to_text()),
+                        ("to".to_string(), to.to_text()),
+                    ]))
+                )),
+                |r| r.to_owned()
+            )
+    }
+
+    async fn add_liquidity(
+        &self,
+        token0: CanisterId,
+        token1: CanisterId,
+        amount0_desired: Nat,
+        amount1_desired: Nat,
+        amount0_min: Nat,
+        amount1_min: Nat,
+    ) -> Result<Nat, InternalError> {
+        let key = (
+            token0.to_text(),
+            token1.to_text(),
+            amount0_desired.to_string(),
+            amount1_desired.to_string(),
+            amount0_min.to_string(),
+            amount1_min.to_string(),
+        );
+
+        self.add_liquidity_responses
+            .get(&key)
+            .map_or_else(
+                || Err(InternalError::not_found(
+                    build_error_code(2303, 01, 07), // 2303 01 07
+                    "MockSonicProvider::add_liquidity".to_string(),
+                    "Mock response not set for add_liquidity".to_string(),
+                    Some(HashMap::from([
+                        ("token0".to_string(), token0.to_text()),
+                        ("token1".to_string(), token1.to_text()),
+                        ("amount0_desired".to_string(), amount0_desired.to_string()),
+                        ("amount1_desired".to_string(), amount1_desired.to_string()),
+                        ("amount0_min".to_string(), amount0_min.to_string()),
+                        ("amount1_min".to_string(), amount1_min.to_string()),
+                    ]))
+                )),
+                |r| r.to_owned()
+            )
+    }
+
+    async fn remove_liquidity(
+        &self,
+        token0: CanisterId,
+        token1: CanisterId,
+        lp_amount: Nat,
+        amount0_min: Nat,
+        amount1_min: Nat,
+        to: Principal,
+    ) -> Result<Nat, InternalError> {
+        let key = (
+            token0.to_text(),
+            token1.to_text(),
+            lp_amount.to_string(),
+            amount0_min.to_string(),
+            amount1_min.to_string(),
+            to.to_text(),
+        );
+
+        self.remove_liquidity_responses
+            .get(&key)
+            .map_or_else(
+                || Err(InternalError::not_found(
+                    build_error_code(2303, 01, 08), // 2303 01 08
+                    "MockSonicProvider::remove_liquidity".to_string(),
+                    "Mock response not set for remove_liquidity".to_string(),
+                    Some(HashMap::from([
+                        ("token0".to_string(), token0.to_text()),
+                        ("token1".to_string(), token1.to_text()),
+                        ("lp_amount".to_string(), lp_amount.to_string()),
+                        ("amount0_min".to_string(), amount0_min.to_string()),
+                        ("amount1_min".to_string(), amount1_min.to_string()),
+                        ("to".to_string(), to.to_text()),
+                    ]))
+                )),
+                |r| r.to_owned()
+            )
+    }
+}
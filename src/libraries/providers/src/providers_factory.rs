@@ -1,25 +1,58 @@
+use std::any::Any;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use types::exchange_id::ExchangeId;
 use utils::environment::Environment;
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
 
 use crate::icpswap::{ICPSwapProvider, DefaultICPSwapProvider};
 use crate::kongswap::{KongSwapProvider, DefaultKongSwapProvider};
+use crate::sonic::{SonicProvider, DefaultSonicProvider};
 use crate::mock::icpswap::MockICPSwapProvider;
 use crate::mock::kongswap::MockKongSwapProvider;
+use crate::mock::sonic::MockSonicProvider;
 
-#[derive(Clone)]
+/// Providers of the exchanges, registered under their exchange ids.
+/// Each exchange registers the provider trait object its clients look up with `get`.
+#[derive(Clone, Default)]
 pub struct ProviderImpls {
-    pub kongswap: Arc<dyn KongSwapProvider + Send + Sync>,
-    pub icpswap: Arc<dyn ICPSwapProvider + Send + Sync>,
+    providers: HashMap<ExchangeId, Arc<dyn Any + Send + Sync>>,
 }
 
-pub fn get_provider_impls(environment: Environment) -> ProviderImpls {
-    ProviderImpls {
-        kongswap: get_kongswap_provider_impl(environment),
-        icpswap: get_icpswap_provider_impl(environment),
+impl ProviderImpls {
+    /// Registers the provider of the exchange, replacing the provider registered before
+    pub fn with_provider<P: ?Sized + Send + Sync + 'static>(mut self, exchange_id: ExchangeId, provider: Arc<P>) -> Self {
+        self.providers.insert(exchange_id, Arc::new(provider));
+        self
+    }
+
+    /// Provider registered under the exchange, an error if there is none of the requested type
+    pub fn get<P: ?Sized + Send + Sync + 'static>(&self, exchange_id: ExchangeId) -> Result<Arc<P>, InternalError> {
+        self.providers
+            .get(&exchange_id)
+            .and_then(|provider| provider.downcast_ref::<Arc<P>>())
+            .cloned()
+            .ok_or_else(|| InternalError::not_found(
+                build_error_code(1000, 1, 1), // 1000 01 01
+                "ProviderImpls::get".to_string(),
+                "No provider registered for the exchange".to_string(),
+                Some(HashMap::from([
+                    ("exchange_id".to_string(), exchange_id.to_string()),
+                ])),
+            ))
     }
 }
 
+/// Providers of all supported exchanges, mocked in the environments which use mock providers
+pub fn get_provider_impls(environment: Environment) -> ProviderImpls {
+    ProviderImpls::default()
+        .with_provider(ExchangeId::KongSwap, get_kongswap_provider_impl(environment))
+        .with_provider(ExchangeId::ICPSwap, get_icpswap_provider_impl(environment))
+        .with_provider(ExchangeId::Sonic, get_sonic_provider_impl(environment))
+}
+
 fn get_kongswap_provider_impl(env: Environment) -> Arc<dyn KongSwapProvider + Send + Sync> {
     if env.should_use_mock_providers() {
         Arc::new(MockKongSwapProvider::new())
@@ -35,3 +68,44 @@ fn get_icpswap_provider_impl(env: Environment) -> Arc<dyn ICPSwapProvider + Send
         Arc::new(DefaultICPSwapProvider)
     }
 }
+
+fn get_sonic_provider_impl(env: Environment) -> Arc<dyn SonicProvider + Send + Sync> {
+    if env.should_use_mock_providers() {
+        Arc::new(MockSonicProvider::new())
+    } else {
+        Arc::new(DefaultSonicProvider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod get {
+        use super::*;
+
+        #[test]
+        fn returns_registered_provider() {
+            let provider_impls = get_provider_impls(Environment::Test);
+
+            assert!(provider_impls.get::<dyn KongSwapProvider + Send + Sync>(ExchangeId::KongSwap).is_ok());
+            assert!(provider_impls.get::<dyn ICPSwapProvider + Send + Sync>(ExchangeId::ICPSwap).is_ok());
+            assert!(provider_impls.get::<dyn SonicProvider + Send + Sync>(ExchangeId::Sonic).is_ok());
+        }
+
+        #[test]
+        fn returns_error_for_missing_provider() {
+            let provider_impls = ProviderImpls::default()
+                .with_provider::<dyn SonicProvider + Send + Sync>(ExchangeId::Sonic, Arc::new(MockSonicProvider::new()));
+
+            assert!(provider_impls.get::<dyn KongSwapProvider + Send + Sync>(ExchangeId::KongSwap).is_err());
+        }
+
+        #[test]
+        fn returns_error_for_provider_of_other_type() {
+            let provider_impls = get_provider_impls(Environment::Test);
+
+            assert!(provider_impls.get::<dyn KongSwapProvider + Send + Sync>(ExchangeId::Sonic).is_err());
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use candid::{Int, Nat, Principal};
+use types::CanisterId;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use candid::CandidType;
+
+use sonic_canister::PairInfoExt;
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use utils::constants::SONIC_SWAP_CANISTER_ID;
+
+/// Sonic rejects updates received after the deadline
+const DEADLINE: u64 = 300_000_000_000; // 5 minutes in nanoseconds
+
+#[async_trait::async_trait]
+pub trait SonicProvider: Send + Sync + 'static {
+    async fn get_pair(&self, token0: CanisterId, token1: CanisterId) -> Result<Option<PairInfoExt>, InternalError>;
+    async fn get_all_pairs(&self) -> Result<Vec<PairInfoExt>, InternalError>;
+    async fn get_user_lp_balances(&self, user: Principal) -> Result<Vec<(String, Nat)>, InternalError>;
+    async fn balance_of(&self, token: CanisterId, user: Principal) -> Result<Nat, InternalError>;
+    async fn deposit(&self, token: CanisterId, amount: Nat) -> Result<Nat, InternalError>;
+    async fn withdraw(&self, token: CanisterId, amount: Nat) -> Result<Nat, InternalError>;
+    async fn swap_exact_tokens_for_tokens(&self, amount_in: Nat, amount_out_min: Nat, token_in: CanisterId, token_out: CanisterId, to: Principal) -> Result<Nat, InternalError>;
+    async fn add_liquidity(&self, token0: CanisterId, token1: CanisterId, amount0_desired: Nat, amount1_desired: Nat, amount0_min: Nat, amount1_min: Nat) -> Result<Nat, InternalError>;
+    async fn remove_liquidity(&self, token0: CanisterId, token1: CanisterId, lp_amount: Nat, amount0_min: Nat, amount1_min: Nat, to: Principal) -> Result<Nat, InternalError>;
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct DefaultSonicProvider;
+
+impl DefaultSonicProvider {
+    fn deadline() -> Int {
+        Int::from(ic_cdk::api::time() + DEADLINE)
+    }
+}
+
+#[async_trait::async_trait]
+impl SonicProvider for DefaultSonicProvider {
+    async fn get_pair(&self, token0: CanisterId, token1: CanisterId) -> Result<Option<PairInfoExt>, InternalError> {
+        let (result,) = sonic_canister_c2c_client::getPair(
+            *SONIC_SWAP_CANISTER_ID,
+            (token0, token1)
+        ).await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(1003, 4, 1), // 1003 04 01
+                    "SonicProvider::get_pair".to_string(),
+                    format!("IC error calling 'sonic_canister_c2c_client::getPair': {error:?}"),
+                    Some(HashMap::from([
+                        ("token0".to_string(), token0.to_text()),
+                        ("token1".to_string(), token1.to_text()),
+                    ]))
+                )
+            })?;
+
+        Ok(result)
+    }
+
+    async fn get_all_pairs(&self) -> Result<Vec<PairInfoExt>, InternalError> {
+        sonic_canister_c2c_client::getAllPairs(*SONIC_SWAP_CANISTER_ID).await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(1003, 4, 2), // 1003 04 02
+                    "SonicProvider::get_all_pairs".to_string(),
+                    format!("IC error calling 'sonic_canister_c2c_client::getAllPairs': {error:?}"),
+                    None
+                )
+            })
+    }
+
+    async fn get_user_lp_balances(&self, user: Principal) -> Result<Vec<(String, Nat)>, InternalError> {
+        let (result,) = sonic_canister_c2c_client::getUserLPBalances(
+            *SONIC_SWAP_CANISTER_ID,
+            (user,)
+        ).await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(1003, 4, 3), // 1003 04 03
+                    "SonicProvider::get_user_lp_balances".to_string(),
+                    format!("IC error calling 'sonic_canister_c2c_client::getUserLPBalances': {error:?}"),
+                    Some(HashMap::from([
+                        ("user".to_string(), user.to_text()),
+                    ]))
+                )
+            })?;
+
+        Ok(result)
+    }
+
+    async fn balance_of(&self, token: CanisterId, user: Principal) -> Result<Nat, InternalError> {
+        let (result,) = sonic_canister_c2c_client::balanceOf(
+            *SONIC_SWAP_CANISTER_ID,
+            (token.to_text(), user)
+        ).await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(1003, 4, 4), // 1003 04 04
+                    "SonicProvider::balance_of".to_string(),
+                    format!("IC error calling 'sonic_canister_c2c_client::balanceOf': {error:?}"),
+                    Some(HashMap::from([
+                        ("token".to_string(), token.to_text()),
+                        ("user".to_string(), user.to_text()),
+                    ]))
+                )
+            })?;
+
+        Ok(result)
+    }
+
+    async fn deposit(&self, token: CanisterId, amount: Nat) -> Result<Nat, InternalError> {
+        let (result,) = sonic_canister_c2c_client::deposit(
+            *SONIC_SWAP_CANISTER_ID,
+            (token, amount.clone())
+        ).await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(1003, 4, 5), // 1003 04 05
+                    "SonicProvider::deposit".to_string(),
+                    format!("IC error calling 'sonic_canister_c2c_client::deposit': {error:?}"),
+                    Some(HashMap::from([
+                        ("token".to_string(), token.to_text()),
+                        ("amount".to_string(), amount.to_string()),
+                    ]))
+                )
+            })?;
+
+        result.into_std().map_err(|error_message| {
+            InternalError::business_logic(
+                build_error_code(1003, 3, 6), // 1003 03 06
+                "SonicProvider::deposit".to_string(),
+                format!("Error calling 'sonic_canister_c2c_client::deposit': {error_message:?}"),
+                Some(HashMap::from([
+                    ("token".to_string(), token.to_text()),
+                    ("amount".to_string(), amount.to_string()),
+                ]))
+            )
+        })
+    }
+
+    async fn withdraw(&self, token: CanisterId, amount: Nat) -> Result<Nat, InternalError> {
+        let (result,) = sonic_canister_c2c_client::withdraw(
+            *SONIC_SWAP_CANISTER_ID,
+            (token, amount.clone())
+        ).await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(1003, 4, 7), // 1003 04 07
+                    "SonicProvider::withdraw".to_string(),
+                    format!("IC error calling 'sonic_canister_c2c_client::withdraw': {error:?}"),
+                    Some(HashMap::from([
+                        ("token".to_string(), token.to_text()),
+                        ("amount".to_string(), amount.to_string()),
+                    ]))
+                )
+            })?;
+
+        result.into_std().map_err(|error_message| {
+            InternalError::business_logic(
+                build_error_code(1003, 3, 8), // 1003 03 08
+                "SonicProvider::withdraw".to_string(),
+                format!("Error calling 'sonic_canister_c2c_client::withdraw': {error_message:?}"),
+                Some(HashMap::from([
+                    ("token".to_string(), token.to_text()),
+                    ("amount".to_string(), amount.to_string()),
+                ]))
+            )
+        })
+    }
+
+    async fn swap_exact_tokens_for_tokens(
+        &self,
+        amount_in: Nat,
+        amount_out_min: Nat,
+        token_in: CanisterId,
+        token_out: CanisterId,
+        to: Principal,
+    ) -> Result<Nat, InternalError> {
+        let (result,) = sonic_canister_c2c_client::swapExactTokensForTokens(
+            *SONIC_SWAP_CANISTER_ID,
+            (
+                amount_in.clone(),
+                amount_out_min.clone(),
+                vec![token_in.to_text(), token_out.to_text()],
+                to,
+                Self::deadline(),
+            )
+        ).await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(1003, 4, 9), // 1003 04 09
+                    "SonicProvider::swap_exact_tokens_for_tokens".to_string(),
+                    format!("IC error calling 'sonic_canister_c2c_client::swapExactTokensForTokens': {error:?}"),
+                    Some(HashMap::from([
+                        ("amount_in".to_string(), amount_in.to_string()),
+                        ("amount_out_min".to_string(), amount_out_min.to_string()),
+                        ("token_in".to_string(), token_in.to_text()),
+                        ("token_out".to_string(), token_out.to_text()),
+                    ]))
+                )
+            })?;
+
+        result.into_std().map_err(|error_message| {
+            InternalError::business_logic(
+                build_error_code(1003, 3, 10), // 1003 03 10
+                "SonicProvider::swap_exact_tokens_for_tokens".to_string(),
+                format!("Error calling 'sonic_canister_c2c_client::swapExactTokensForTokens': {error_message:?}"),
+                Some(HashMap::from([
+                    ("amount_in".to_string(), amount_in.to_string()),
+                    ("amount_out_min".to_string(), amount_out_min.to_string()),
+                    ("token_in".to_string(), token_in.to_text()),
+                    ("token_out".to_string(), token_out.to_text()),
+                ]))
+            )
+        })
+    }
+
+    async fn add_liquidity(
+        &self,
+        token0: CanisterId,
+        token1: CanisterId,
+        amount0_desired: Nat,
+        amount1_desired: Nat,
+        amount0_min: Nat,
+        amount1_min: Nat,
+    ) -> Result<Nat, InternalError> {
+        let error_details = HashMap::from([
+            ("token0".to_string(), token0.to_text()),
+            ("token1".to_string(), token1.to_text()),
+            ("amount0_desired".to_string(), amount0_desired.to_string()),
+            ("amount1_desired".to_string(), amount1_desired.to_string()),
+            ("amount0_min".to_string(), amount0_min.to_string()),
+            ("amount1_min".to_string(), amount1_min.to_string()),
+        ]);
+
+        let (result,) = sonic_canister_c2c_client::addLiquidity(
+            *SONIC_SWAP_CANISTER_ID,
+            (
+                token0,
+                token1,
+                amount0_desired,
+                amount1_desired,
+                amount0_min,
+                amount1_min,
+                Self::deadline(),
+            )
+        ).await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(1003, 4, 11), // 1003 04 11
+                    "SonicProvider::add_liquidity".to_string(),
+                    format!("IC error calling 'sonic_canister_c2c_client::addLiquidity': {error:?}"),
+                    Some(error_details.clone())
+                )
+            })?;
+
+        result.into_std().map_err(|error_message| {
+            InternalError::business_logic(
+                build_error_code(1003, 3, 12), // 1003 03 12
+                "SonicProvider::add_liquidity".to_string(),
+                format!("Error calling 'sonic_canister_c2c_client::addLiquidity': {error_message:?}"),
+                Some(error_details)
+            )
+        })
+    }
+
+    async fn remove_liquidity(
+        &self,
+        token0: CanisterId,
+        token1: CanisterId,
+        lp_amount: Nat,
+        amount0_min: Nat,
+        amount1_min: Nat,
+        to: Principal,
+    ) -> Result<Nat, InternalError> {
+        let error_details = HashMap::from([
+            ("token0".to_string(), token0.to_text()),
+            ("token1".to_string(), token1.to_text()),
+            ("lp_amount".to_string(), lp_amount.to_string()),
+            ("amount0_min".to_string(), amount0_min.to_string()),
+            ("amount1_min".to_string(), amount1_min.to_string()),
+        ]);
+
+        let (result,) = sonic_canister_c2c_client::removeLiquidity(
+            *SONIC_SWAP_CANISTER_ID,
+            (
+                token0,
+                token1,
+                lp_amount,
+                amount0_min,
+                amount1_min,
+                to,
+                Self::deadline(),
+            )
+        ).await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(1003, 4, 13), // 1003 04 13
+                    "SonicProvider::remove_liquidity".to_string(),
+                    format!("IC error calling 'sonic_canister_c2c_client::removeLiquidity': {error:?}"),
+                    Some(error_details.clone())
+                )
+            })?;
+
+        result.into_std().map_err(|error_message| {
+            InternalError::business_logic(
+                build_error_code(1003, 3, 14), // 1003 03 14
+                "SonicProvider::remove_liquidity".to_string(),
+                format!("Error calling 'sonic_canister_c2c_client::removeLiquidity': {error_message:?}"),
+                Some(error_details)
+            )
+        })
+    }
+}
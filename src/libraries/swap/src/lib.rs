@@ -1,2 +1,7 @@
 pub mod swap_service;
+pub mod swap_router;
+pub mod pool_graph;
+pub mod swap_limits;
+pub mod price_guard;
 pub mod token_swaps;
+pub mod swap_adapters;
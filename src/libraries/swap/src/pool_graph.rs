@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+
+use types::CanisterId;
+use types::exchange_id::ExchangeId;
+
+/// Maximum number of swaps in a route
+pub const MAX_HOPS: usize = 3;
+
+/// Graph of token pairs which have a pool on at least one exchange
+#[derive(Clone, Debug, Default)]
+pub struct PoolGraph {
+    edges: HashMap<CanisterId, HashMap<CanisterId, HashSet<ExchangeId>>>,
+}
+
+impl PoolGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_pool(&mut self, token0: CanisterId, token1: CanisterId, provider: ExchangeId) {
+        self.edges.entry(token0).or_default().entry(token1).or_default().insert(provider);
+        self.edges.entry(token1).or_default().entry(token0).or_default().insert(provider);
+    }
+
+    pub fn has_pool(&self, token0: &CanisterId, token1: &CanisterId) -> bool {
+        self.edges.get(token0).is_some_and(|neighbours| neighbours.contains_key(token1))
+    }
+
+    pub fn providers(&self, token0: &CanisterId, token1: &CanisterId) -> Vec<ExchangeId> {
+        self.edges
+            .get(token0)
+            .and_then(|neighbours| neighbours.get(token1))
+            .map(|providers| providers.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Finds token paths from `input_token` to `output_token` with at most `max_hops` swaps.
+    /// Intermediate tokens are limited to the hub tokens, so the number of candidates stays small.
+    /// Paths are sorted by the number of hops.
+    pub fn find_paths(
+        &self,
+        input_token: CanisterId,
+        output_token: CanisterId,
+        hubs: &[CanisterId],
+        max_hops: usize,
+    ) -> Vec<Vec<CanisterId>> {
+        let mut paths = Vec::new();
+        let mut path = vec![input_token];
+
+        self.collect_paths(&mut path, output_token, hubs, max_hops, &mut paths);
+
+        paths.sort_by_key(|path| path.len());
+        paths
+    }
+
+    fn collect_paths(
+        &self,
+        path: &mut Vec<CanisterId>,
+        output_token: CanisterId,
+        hubs: &[CanisterId],
+        max_hops: usize,
+        paths: &mut Vec<Vec<CanisterId>>,
+    ) {
+        let current_token = *path.last().unwrap();
+
+        if self.has_pool(&current_token, &output_token) {
+            let mut found_path = path.clone();
+            found_path.push(output_token);
+            paths.push(found_path);
+        }
+
+        // Leave room for the last hop to the output token
+        if path.len() >= max_hops {
+            return;
+        }
+
+        for hub in hubs {
+            if path.contains(hub) || *hub == output_token || !self.has_pool(&current_token, hub) {
+                continue;
+            }
+
+            path.push(*hub);
+            self.collect_paths(path, output_token, hubs, max_hops, paths);
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::token;
+
+    mod find_paths {
+        use super::*;
+
+        #[test]
+        fn finds_direct_and_hub_paths() {
+            let (panda, icp, ckusdt, ckbtc) = (token(1), token(2), token(3), token(4));
+            let mut graph = PoolGraph::new();
+            graph.add_pool(panda, icp, ExchangeId::KongSwap);
+            graph.add_pool(icp, ckbtc, ExchangeId::ICPSwap);
+            graph.add_pool(icp, ckusdt, ExchangeId::KongSwap);
+            graph.add_pool(ckusdt, ckbtc, ExchangeId::KongSwap);
+
+            let paths = graph.find_paths(panda, ckbtc, &[icp, ckusdt], MAX_HOPS);
+
+            assert_eq!(paths, vec![
+                vec![panda, icp, ckbtc],
+                vec![panda, icp, ckusdt, ckbtc],
+            ]);
+        }
+
+        #[test]
+        fn prefers_direct_path() {
+            let (panda, icp) = (token(1), token(2));
+            let mut graph = PoolGraph::new();
+            graph.add_pool(panda, icp, ExchangeId::KongSwap);
+
+            let paths = graph.find_paths(icp, panda, &[icp], MAX_HOPS);
+
+            assert_eq!(paths, vec![vec![icp, panda]]);
+        }
+
+        #[test]
+        fn respects_max_hops() {
+            let (panda, icp, ckusdt, ckbtc) = (token(1), token(2), token(3), token(4));
+            let mut graph = PoolGraph::new();
+            graph.add_pool(panda, icp, ExchangeId::KongSwap);
+            graph.add_pool(icp, ckusdt, ExchangeId::KongSwap);
+            graph.add_pool(ckusdt, ckbtc, ExchangeId::KongSwap);
+
+            assert!(graph.find_paths(panda, ckbtc, &[icp, ckusdt], 2).is_empty());
+            assert_eq!(graph.find_paths(panda, ckbtc, &[icp, ckusdt], 3).len(), 1);
+        }
+    }
+
+    mod providers {
+        use super::*;
+
+        #[test]
+        fn collects_providers_of_pair() {
+            let (icp, ckbtc) = (token(2), token(4));
+            let mut graph = PoolGraph::new();
+            graph.add_pool(icp, ckbtc, ExchangeId::KongSwap);
+            graph.add_pool(ckbtc, icp, ExchangeId::ICPSwap);
+
+            let mut providers = graph.providers(&icp, &ckbtc);
+            providers.sort_by_key(|provider| provider.to_string());
+
+            assert_eq!(providers, vec![ExchangeId::ICPSwap, ExchangeId::KongSwap]);
+        }
+    }
+}
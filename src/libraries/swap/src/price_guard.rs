@@ -0,0 +1,177 @@
+use candid::Nat;
+use std::collections::HashMap;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use providers::providers_factory::ProviderImpls;
+use types::CanisterId;
+use types::exchange_id::ExchangeId;
+use types::swap_tokens::{
+    PriceGuardCheck,
+    PriceGuardDecision,
+    PriceReference,
+    PriceReferenceSource,
+    BPS_DENOMINATOR,
+};
+
+use crate::swap_service;
+use crate::swap_adapters::swap_adapters;
+
+/// Price of the output token per input token, None for an empty quote
+pub fn quote_price(amount_in: u128, amount_out: u128) -> Option<f64> {
+    if amount_in == 0 || amount_out == 0 {
+        return None;
+    }
+
+    Some(amount_out as f64 / amount_in as f64)
+}
+
+/// Deviation of the quoted price from the reference price in basis points, in either direction
+pub fn price_deviation_bps(quoted_price: f64, reference_price: f64) -> u32 {
+    ((quoted_price - reference_price).abs() / reference_price * BPS_DENOMINATOR as f64).round() as u32
+}
+
+/// Compares the quoted price against the reference price.
+/// Without a reference the quote is let through, the decision records that it was not checked.
+pub fn evaluate(
+    quoted_price: f64,
+    reference: Option<PriceReference>,
+    max_deviation_bps: u32,
+) -> PriceGuardCheck {
+    let deviation_bps = reference.as_ref()
+        .map(|reference| price_deviation_bps(quoted_price, reference.price));
+
+    let decision = match deviation_bps {
+        None => PriceGuardDecision::NoReference,
+        Some(deviation_bps) if deviation_bps <= max_deviation_bps => PriceGuardDecision::Passed,
+        Some(_) => PriceGuardDecision::Rejected,
+    };
+
+    PriceGuardCheck {
+        quoted_price,
+        reference,
+        deviation_bps,
+        max_deviation_bps,
+        decision,
+    }
+}
+
+/// Quotes the same trade on the registered exchanges other than the execution exchange
+/// and returns the first successful quote as the reference price
+pub async fn quote_reference_price(
+    provider_impls: ProviderImpls,
+    token_in: CanisterId,
+    token_out: CanisterId,
+    amount_in: u128,
+    execution_provider: ExchangeId,
+) -> Option<PriceReference> {
+    let providers = swap_adapters()
+        .iter()
+        .map(|adapter| adapter.exchange_id())
+        .filter(|provider| *provider != execution_provider)
+        .collect::<Vec<_>>();
+
+    for provider in providers {
+        if let Ok(quote) = swap_service::quote_swap_icrc2(
+            provider_impls.clone(),
+            token_in,
+            token_out,
+            Nat::from(amount_in),
+            provider,
+        ).await {
+            if let Some(price) = quote_price(amount_in, quote.amount_out) {
+                return Some(PriceReference {
+                    source: PriceReferenceSource::Exchange(provider),
+                    price,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Converts a rejected check into an error carrying the quoted and reference prices
+pub fn ensure_passed(check: &PriceGuardCheck) -> Result<(), InternalError> {
+    if check.decision != PriceGuardDecision::Rejected {
+        return Ok(());
+    }
+
+    let reference = check.reference.as_ref().unwrap();
+
+    Err(InternalError::business_logic(
+        build_error_code(2005, 3, 1), // 2005 03 01
+        "price_guard::ensure_passed".to_string(),
+        "Quoted price deviates from the reference price beyond the allowed band".to_string(),
+        Some(HashMap::from([
+            ("quoted_price".to_string(), check.quoted_price.to_string()),
+            ("reference_price".to_string(), reference.price.to_string()),
+            ("reference_source".to_string(), format!("{:?}", reference.source)),
+            ("deviation_bps".to_string(), check.deviation_bps.unwrap_or_default().to_string()),
+            ("max_deviation_bps".to_string(), check.max_deviation_bps.to_string()),
+        ])),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange_reference(price: f64) -> Option<PriceReference> {
+        Some(PriceReference {
+            source: PriceReferenceSource::Exchange(ExchangeId::ICPSwap),
+            price,
+        })
+    }
+
+    mod price_deviation_bps {
+        use super::*;
+
+        #[test]
+        fn measures_deviation_in_both_directions() {
+            assert_eq!(price_deviation_bps(1.05, 1.0), 500);
+            assert_eq!(price_deviation_bps(0.95, 1.0), 500);
+            assert_eq!(price_deviation_bps(2.0, 2.0), 0);
+        }
+    }
+
+    mod evaluate {
+        use super::*;
+
+        #[test]
+        fn passes_within_band() {
+            let check = evaluate(1.02, exchange_reference(1.0), 300);
+
+            assert_eq!(check.decision, PriceGuardDecision::Passed);
+            assert_eq!(check.deviation_bps, Some(200));
+            assert!(ensure_passed(&check).is_ok());
+        }
+
+        #[test]
+        fn rejects_outside_band() {
+            let check = evaluate(1.10, exchange_reference(1.0), 300);
+
+            assert_eq!(check.decision, PriceGuardDecision::Rejected);
+            assert!(ensure_passed(&check).is_err());
+        }
+
+        #[test]
+        fn lets_through_without_reference() {
+            let check = evaluate(1.10, None, 300);
+
+            assert_eq!(check.decision, PriceGuardDecision::NoReference);
+            assert!(ensure_passed(&check).is_ok());
+        }
+    }
+
+    mod quote_price {
+        use super::*;
+
+        #[test]
+        fn is_none_for_empty_quote() {
+            assert_eq!(quote_price(100, 250), Some(2.5));
+            assert_eq!(quote_price(0, 250), None);
+            assert_eq!(quote_price(100, 0), None);
+        }
+    }
+}
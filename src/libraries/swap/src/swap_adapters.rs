@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use providers::providers_factory::ProviderImpls;
+use types::CanisterId;
+use types::exchange_id::ExchangeId;
+use types::swap_tokens::SwapLimits;
+
+use crate::token_swaps::icpswap::ICPSwapSwapAdapter;
+use crate::token_swaps::kongswap::KongSwapSwapAdapter;
+use crate::token_swaps::sonic::SonicSwapAdapter;
+use crate::token_swaps::swap_client::SwapClient;
+
+/// Swap side of an exchange integration, registered in `swap_adapters` under its exchange id
+#[async_trait]
+pub trait SwapAdapter: Send + Sync {
+    fn exchange_id(&self) -> ExchangeId;
+
+    /// Swap client of the exchange for the token pair, with the provider taken from `provider_impls`
+    async fn swap_client(
+        &self,
+        provider_impls: ProviderImpls,
+        token_in: CanisterId,
+        token_out: CanisterId,
+        limits: SwapLimits,
+    ) -> Result<Box<dyn SwapClient>, InternalError>;
+
+    /// Pools of the exchange between the tokens. Exchanges which list all their pools
+    /// may return pools of other tokens too.
+    async fn pools(
+        &self,
+        provider_impls: ProviderImpls,
+        tokens: &[CanisterId],
+    ) -> Vec<(CanisterId, CanisterId)>;
+}
+
+/// Adapters of all supported exchanges
+pub fn swap_adapters() -> Vec<Box<dyn SwapAdapter>> {
+    vec![
+        Box::new(KongSwapSwapAdapter),
+        Box::new(ICPSwapSwapAdapter),
+        Box::new(SonicSwapAdapter),
+    ]
+}
+
+pub fn get_swap_adapter(exchange_id: ExchangeId) -> Result<Box<dyn SwapAdapter>, InternalError> {
+    swap_adapters()
+        .into_iter()
+        .find(|adapter| adapter.exchange_id() == exchange_id)
+        .ok_or_else(|| InternalError::validation(
+            build_error_code(2007, 2, 1), // 2007 02 01
+            "swap_adapters::get_swap_adapter".to_string(),
+            "Exchange is not supported for swaps".to_string(),
+            Some(HashMap::from([
+                ("exchange_id".to_string(), exchange_id.to_string()),
+            ])),
+        ))
+}
+
+/// Swap client of the exchange registered under `exchange_id`
+pub async fn build_swap_client(
+    provider_impls: ProviderImpls,
+    token_in: CanisterId,
+    token_out: CanisterId,
+    exchange_id: ExchangeId,
+    limits: SwapLimits,
+) -> Result<Box<dyn SwapClient>, InternalError> {
+    get_swap_adapter(exchange_id)?
+        .swap_client(provider_impls, token_in, token_out, limits)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod swap_adapters {
+        use super::*;
+
+        #[test]
+        fn registers_every_exchange_once() {
+            let exchange_ids: Vec<ExchangeId> = swap_adapters().iter().map(|adapter| adapter.exchange_id()).collect();
+
+            for exchange_id in [ExchangeId::KongSwap, ExchangeId::ICPSwap, ExchangeId::Sonic] {
+                assert_eq!(exchange_ids.iter().filter(|id| **id == exchange_id).count(), 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use candid::Nat;
+use std::collections::HashMap;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use types::swap_tokens::{SwapLimits, BPS_DENOMINATOR};
+use utils::util::nat_to_u128;
+
+use crate::token_swaps::swap_client::SwapClient;
+
+/// Share of the swapped amount (in basis points) quoted as the reference price for price impact
+pub const REFERENCE_AMOUNT_BPS: u128 = 10;
+
+/// Small amount quoted to get the pool price before the trade moves it
+pub fn reference_amount(amount_in: u128) -> u128 {
+    (amount_in * REFERENCE_AMOUNT_BPS / BPS_DENOMINATOR as u128).max(1)
+}
+
+/// Price impact of the trade in basis points: how much worse the trade price is
+/// than the reference price. Returns 0 when the trade price is not worse.
+pub fn price_impact_bps(
+    reference_amount_in: u128,
+    reference_amount_out: u128,
+    amount_in: u128,
+    amount_out: u128,
+) -> u32 {
+    if reference_amount_in == 0 || reference_amount_out == 0 || amount_in == 0 {
+        return 0;
+    }
+
+    let reference_price = reference_amount_out as f64 / reference_amount_in as f64;
+    let trade_price = amount_out as f64 / amount_in as f64;
+
+    if trade_price >= reference_price {
+        return 0;
+    }
+
+    ((1.0 - trade_price / reference_price) * BPS_DENOMINATOR as f64).round() as u32
+}
+
+/// Rejects the trade if its price impact exceeds the limit
+pub fn check_price_impact(
+    limits: &SwapLimits,
+    price_impact_bps: u32,
+    amount_in: u128,
+    amount_out: u128,
+) -> Result<(), InternalError> {
+    if price_impact_bps <= limits.max_price_impact_bps {
+        return Ok(());
+    }
+
+    Err(InternalError::business_logic(
+        build_error_code(2004, 3, 1), // 2004 03 01
+        "swap_limits::check_price_impact".to_string(),
+        "Price impact exceeds the limit".to_string(),
+        Some(HashMap::from([
+            ("price_impact_bps".to_string(), price_impact_bps.to_string()),
+            ("max_price_impact_bps".to_string(), limits.max_price_impact_bps.to_string()),
+            ("amount_in".to_string(), amount_in.to_string()),
+            ("amount_out".to_string(), amount_out.to_string()),
+        ])),
+    ))
+}
+
+/// Part of `amount` proportional to `part` of `whole`, rounded down
+pub fn proportional_amount(amount: u128, part: u128, whole: u128) -> u128 {
+    if whole == 0 {
+        return 0;
+    }
+
+    nat_to_u128(&(Nat::from(amount) * Nat::from(part) / Nat::from(whole)))
+}
+
+/// Minimum output of a quoted hop executed with `amount_in`: the quoted output
+/// scaled to the amount actually swapped, minus the slippage tolerance
+pub fn hop_min_amount_out(
+    limits: &SwapLimits,
+    quoted_amount_in: u128,
+    quoted_amount_out: u128,
+    amount_in: u128,
+) -> u128 {
+    limits.min_amount_out(proportional_amount(quoted_amount_out, amount_in, quoted_amount_in))
+}
+
+/// Minimum output the exchange has to pay out: the quote minus the slippage tolerance,
+/// raised to `required_amount_out` of the caller. Rejects the trade before it is executed
+/// if the quote is already below `required_amount_out`.
+pub fn amount_out_minimum(
+    limits: &SwapLimits,
+    expected_amount_out: u128,
+    required_amount_out: Option<u128>,
+) -> Result<u128, InternalError> {
+    let required_amount_out = required_amount_out.unwrap_or(0);
+
+    if expected_amount_out < required_amount_out {
+        return Err(InternalError::business_logic(
+            build_error_code(2004, 3, 3), // 2004 03 03
+            "swap_limits::amount_out_minimum".to_string(),
+            "Quoted amount is below the required output".to_string(),
+            Some(HashMap::from([
+                ("expected_amount_out".to_string(), expected_amount_out.to_string()),
+                ("required_amount_out".to_string(), required_amount_out.to_string()),
+            ])),
+        ));
+    }
+
+    Ok(limits.min_amount_out(expected_amount_out).max(required_amount_out))
+}
+
+/// Quotes the reference amount and checks the price impact of the quoted trade
+pub async fn ensure_price_impact(
+    swap_client: &dyn SwapClient,
+    limits: &SwapLimits,
+    amount_in: u128,
+    amount_out: u128,
+) -> Result<(), InternalError> {
+    let reference_amount_in = reference_amount(amount_in);
+    let reference_amount_out = swap_client.quote(Nat::from(reference_amount_in)).await?.amount_out;
+
+    check_price_impact(
+        limits,
+        price_impact_bps(reference_amount_in, reference_amount_out, amount_in, amount_out),
+        amount_in,
+        amount_out,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod price_impact_bps {
+        use super::*;
+
+        #[test]
+        fn measures_worse_trade_price() {
+            assert_eq!(price_impact_bps(100, 200, 10_000, 19_000), 500);
+            assert_eq!(price_impact_bps(100, 200, 10_000, 20_000), 0);
+        }
+
+        #[test]
+        fn ignores_better_price_and_empty_quotes() {
+            assert_eq!(price_impact_bps(100, 200, 10_000, 21_000), 0);
+            assert_eq!(price_impact_bps(100, 0, 10_000, 19_000), 0);
+        }
+    }
+
+    mod check_price_impact {
+        use super::*;
+
+        #[test]
+        fn rejects_impact_above_limit() {
+            let limits = SwapLimits {
+                slippage_tolerance_bps: 100,
+                max_price_impact_bps: 300,
+                max_price_deviation_bps: 300,
+            };
+
+            assert!(check_price_impact(&limits, 300, 1_000, 970).is_ok());
+            assert!(check_price_impact(&limits, 301, 1_000, 969).is_err());
+        }
+    }
+
+    mod hop_min_amount_out {
+        use super::*;
+
+        #[test]
+        fn scales_quoted_output_to_swapped_amount() {
+            let limits = SwapLimits {
+                slippage_tolerance_bps: 100,
+                max_price_impact_bps: 300,
+                max_price_deviation_bps: 300,
+            };
+
+            assert_eq!(hop_min_amount_out(&limits, 1_000, 2_000, 1_000), 1_980);
+            assert_eq!(hop_min_amount_out(&limits, 1_000, 2_000, 990), 1_960);
+            assert_eq!(hop_min_amount_out(&limits, 0, 2_000, 990), 0);
+        }
+
+        #[test]
+        fn does_not_overflow_large_amounts() {
+            let limits = SwapLimits::default();
+            let amount = u128::MAX / 2;
+
+            assert_eq!(hop_min_amount_out(&limits, amount, amount, amount), limits.min_amount_out(amount));
+        }
+    }
+
+    mod amount_out_minimum {
+        use super::*;
+
+        #[test]
+        fn raises_tolerance_minimum_to_required_output() {
+            let limits = SwapLimits {
+                slippage_tolerance_bps: 100,
+                max_price_impact_bps: 300,
+                max_price_deviation_bps: 300,
+            };
+
+            assert_eq!(amount_out_minimum(&limits, 10_000, None).unwrap(), 9_900);
+            assert_eq!(amount_out_minimum(&limits, 10_000, Some(9_000)).unwrap(), 9_900);
+            assert_eq!(amount_out_minimum(&limits, 10_000, Some(9_950)).unwrap(), 9_950);
+        }
+
+        #[test]
+        fn rejects_quote_below_required_output() {
+            assert!(amount_out_minimum(&SwapLimits::default(), 10_000, Some(10_001)).is_err());
+        }
+    }
+
+    mod reference_amount {
+        use super::*;
+
+        #[test]
+        fn is_never_zero() {
+            assert_eq!(reference_amount(1_000_000), 1_000);
+            assert_eq!(reference_amount(10), 1);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use types::exchange_id::ExchangeId;
+use types::swap_tokens::{SwapRoute, SwapRouteLeg};
+
+/// Shares of the order (in percent) quoted on each exchange when looking for the best split
+pub const SPLIT_PERCENTAGES: [u8; 4] = [25, 50, 75, 100];
+
+/// Shares of the order routed to the first exchange of a split
+const SPLIT_CANDIDATES: [u8; 3] = [75, 25, 50];
+
+/// Successful quotes of one exchange by the input amount
+#[derive(Clone, Debug)]
+pub struct VenueQuotes {
+    pub provider: ExchangeId,
+    pub amounts_out: HashMap<u128, u128>,
+}
+
+/// Splits the amount into the part routed to the first exchange and the rest
+pub fn leg_amounts(amount: u128, percentage: u8) -> (u128, u128) {
+    let first = amount * percentage as u128 / 100;
+
+    (first, amount - first)
+}
+
+/// Input amounts every exchange is quoted for, so that each exchange can take
+/// either leg of every split
+pub fn quote_amounts(amount: u128) -> Vec<u128> {
+    let mut amounts: Vec<u128> = SPLIT_PERCENTAGES
+        .iter()
+        .flat_map(|percentage| [leg_amounts(amount, *percentage).0, leg_amounts(amount, 100 - percentage).1])
+        .filter(|amount| *amount > 0)
+        .collect();
+
+    amounts.sort_unstable();
+    amounts.dedup();
+    amounts
+}
+
+/// Selects the route with the highest total output, either a single exchange
+/// or a split between two of the exchanges. Single exchange routes go first,
+/// so a split is chosen only if it is strictly better.
+/// Routes which miss a quote are skipped, so a failed exchange only limits the candidates.
+pub fn select_route(amount: u128, venues: &[VenueQuotes]) -> Option<SwapRoute> {
+    let mut candidates: Vec<Vec<SwapRouteLeg>> = Vec::new();
+
+    for venue in venues {
+        candidates.push(leg(venue, amount).into_iter().collect());
+    }
+
+    for (index, first) in venues.iter().enumerate() {
+        for second in &venues[index + 1..] {
+            for percentage in SPLIT_CANDIDATES {
+                let (first_amount_in, second_amount_in) = leg_amounts(amount, percentage);
+
+                if let (Some(first_leg), Some(second_leg)) = (leg(first, first_amount_in), leg(second, second_amount_in)) {
+                    candidates.push(vec![first_leg, second_leg]);
+                }
+            }
+        }
+    }
+
+    let mut best_route: Option<SwapRoute> = None;
+
+    for legs in candidates.into_iter().filter(|legs| !legs.is_empty()) {
+        let route = SwapRoute { legs, unavailable_providers: Vec::new() };
+
+        if best_route.as_ref().map_or(true, |best_route| route.amount_out() > best_route.amount_out()) {
+            best_route = Some(route);
+        }
+    }
+
+    best_route
+}
+
+/// Leg of the exchange for the input amount, None if the exchange has no quote for it
+fn leg(venue: &VenueQuotes, amount_in: u128) -> Option<SwapRouteLeg> {
+    venue.amounts_out.get(&amount_in).map(|amount_out| SwapRouteLeg {
+        provider: venue.provider,
+        amount_in,
+        amount_out: *amount_out,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_quotes(provider: ExchangeId, amounts_out: &[(u128, u128)]) -> VenueQuotes {
+        VenueQuotes {
+            provider,
+            amounts_out: amounts_out.iter().cloned().collect(),
+        }
+    }
+
+    mod leg_amounts {
+        use super::*;
+
+        #[test]
+        fn legs_sum_up_to_amount() {
+            assert_eq!(leg_amounts(1_001, 50), (500, 501));
+            assert_eq!(leg_amounts(1_001, 25), (250, 751));
+        }
+    }
+
+    mod quote_amounts {
+        use super::*;
+
+        #[test]
+        fn covers_both_legs_of_every_split() {
+            assert_eq!(quote_amounts(1_000), vec![250, 500, 750, 1_000]);
+            assert_eq!(quote_amounts(1_001), vec![250, 251, 500, 501, 750, 751, 1_001]);
+        }
+    }
+
+    mod select_route {
+        use super::*;
+
+        #[test]
+        fn prefers_single_exchange_with_better_quote() {
+            let kongswap = build_quotes(ExchangeId::KongSwap, &[(250, 240), (500, 480), (750, 700), (1_000, 990)]);
+            let icpswap = build_quotes(ExchangeId::ICPSwap, &[(250, 230), (500, 450), (750, 650), (1_000, 900)]);
+
+            let route = select_route(1_000, &[kongswap, icpswap]).unwrap();
+
+            assert_eq!(route, SwapRoute::single(ExchangeId::KongSwap, 1_000, 990));
+        }
+
+        #[test]
+        fn splits_order_when_price_impact_is_high() {
+            let kongswap = build_quotes(ExchangeId::KongSwap, &[(250, 245), (500, 480), (750, 690), (1_000, 850)]);
+            let icpswap = build_quotes(ExchangeId::ICPSwap, &[(250, 240), (500, 470), (750, 680), (1_000, 840)]);
+
+            let route = select_route(1_000, &[kongswap, icpswap]).unwrap();
+
+            assert_eq!(route.amount_out(), 950);
+            assert_eq!(route.legs.len(), 2);
+            assert_eq!(route.legs[0].amount_in, 500);
+            assert_eq!(route.legs[1].amount_in, 500);
+        }
+
+        #[test]
+        fn splits_order_between_any_two_exchanges() {
+            let kongswap = build_quotes(ExchangeId::KongSwap, &[(250, 200), (500, 400), (750, 600), (1_000, 800)]);
+            let icpswap = build_quotes(ExchangeId::ICPSwap, &[(250, 245), (500, 480), (750, 690), (1_000, 850)]);
+            let sonic = build_quotes(ExchangeId::Sonic, &[(250, 240), (500, 470), (750, 680), (1_000, 840)]);
+
+            let route = select_route(1_000, &[kongswap, icpswap, sonic]).unwrap();
+
+            assert_eq!(route.amount_out(), 950);
+            assert_eq!(route.legs[0].provider, ExchangeId::ICPSwap);
+            assert_eq!(route.legs[1].provider, ExchangeId::Sonic);
+        }
+
+        #[test]
+        fn falls_back_to_available_exchange() {
+            let kongswap = build_quotes(ExchangeId::KongSwap, &[]);
+            let icpswap = build_quotes(ExchangeId::ICPSwap, &[(250, 240), (500, 470), (750, 680), (1_000, 900)]);
+
+            let route = select_route(1_000, &[kongswap, icpswap]).unwrap();
+
+            assert_eq!(route, SwapRoute::single(ExchangeId::ICPSwap, 1_000, 900));
+        }
+
+        #[test]
+        fn returns_none_without_quotes() {
+            let kongswap = build_quotes(ExchangeId::KongSwap, &[]);
+            let icpswap = build_quotes(ExchangeId::ICPSwap, &[]);
+
+            assert!(select_route(1_000, &[kongswap, icpswap]).is_none());
+        }
+    }
+}
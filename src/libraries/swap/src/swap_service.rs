@@ -1,238 +1,690 @@
 use candid::Nat;
 use std::collections::HashMap;
-use std::sync::Arc;
 
-use types::swap_tokens::{SwapResponse, QuoteResponse};
+use types::swap_tokens::{SwapResponse, QuoteResponse, SwapRoute, SwapRouteLeg, SwapHop, MultiHopRoute, SwapLimits};
 use types::exchange_id::ExchangeId;
-use utils::constants::KONGSWAP_CANISTER_ID;
+use utils::util::nat_to_u128;
 use types::CanisterId;
 use errors::internal_error::error::{InternalError, build_error_code};
 use icrc_ledger_client;
-use providers::kongswap::KongSwapProvider;
-use providers::icpswap::ICPSwapProvider;
+use token_registry::ledger_fees::{self, FeeDeduction, APPROVED_TRANSFER_FEE_COUNT};
+use token_registry::registry;
 use providers::providers_factory::ProviderImpls;
 
-use crate::token_swaps::kongswap::KongSwapSwapClient;
-use crate::token_swaps::icpswap::ICPSwapSwapClient;
-use crate::token_swaps::swap_client::SwapClient;
+use crate::token_swaps::swap_client::{SwapClient, QuoteExactOutputSuccess};
+use crate::swap_router::{self, VenueQuotes};
+use crate::pool_graph::{PoolGraph, MAX_HOPS};
+use crate::swap_limits;
+use crate::swap_adapters::{swap_adapters, build_swap_client};
 
+/// Quotes the swap on all exchanges and executes it by the best route,
+/// which can split the order between exchanges. The route pays out at least `min_amount_out` if it is given.
 pub async fn swap_icrc2_optimal(
     provider_impls: ProviderImpls,
     input_token: CanisterId,
     output_token: CanisterId,
     amount: Nat,
+    limits: SwapLimits,
+    min_amount_out: Option<u128>,
 ) -> Result<SwapResponse, InternalError> {
-    let provider = quote_swap_icrc2_optimal(
+    let route = quote_swap_icrc2_optimal(
         provider_impls.clone(),
         input_token.clone(),
         output_token.clone(),
         amount.clone()
-    ).await?.provider;
+    ).await?.route;
 
-    swap_icrc2(
+    swap_icrc2_route(
         provider_impls,
         input_token,
         output_token,
-        amount,
-        provider
+        route,
+        limits,
+        min_amount_out,
     ).await
 }
 
-pub async fn swap_icrc2(
+/// Executes every leg of the route. A leg which fails to be quoted or approved on its exchange,
+/// before any tokens move, is retried on the registered exchange with the best quote for the leg
+/// among those which have not failed yet. A leg which fails after that is not retried,
+/// the error is returned and the legs executed before stay swapped.
+/// The returned error reports the input amount still held and the output amount swapped.
+///
+/// `min_amount_out` of the route is split between the legs in proportion to their input.
+pub async fn swap_icrc2_route(
     provider_impls: ProviderImpls,
     input_token: CanisterId,
     output_token: CanisterId,
-    amount: Nat,
-    provider: ExchangeId,
-) -> Result<SwapResponse, InternalError>
-{
-    match provider {
-        ExchangeId::KongSwap => {
-            swap_icrc2_kongswap(provider_impls.kongswap, input_token, output_token, amount).await
-        }
-        ExchangeId::ICPSwap => {
-            swap_icrc2_icpswap(provider_impls.icpswap, input_token, output_token, amount).await
-        }
-        _ => Err(InternalError::business_logic(
-            build_error_code(2000, 3, 1),
-            "swap_service::swap_icrc2".to_string(),
-            "Invalid provider".to_string(),
+    route: SwapRoute,
+    limits: SwapLimits,
+    min_amount_out: Option<u128>,
+) -> Result<SwapResponse, InternalError> {
+    if route.legs.is_empty() {
+        return Err(InternalError::validation(
+            build_error_code(2000, 2, 1), // 2000 02 01
+            "swap_service::swap_icrc2_route".to_string(),
+            "Swap route has no legs".to_string(),
             Some(HashMap::from([
                 ("input_token".to_string(), input_token.to_text()),
                 ("output_token".to_string(), output_token.to_text()),
-                ("amount".to_string(), amount.to_string()),
-                ("provider".to_string(), provider.to_string()),
             ])),
-        )),
+        ));
     }
+
+    let mut executed_route = SwapRoute {
+        legs: Vec::new(),
+        unavailable_providers: route.unavailable_providers.clone(),
+    };
+
+    let mut ledger_fee = 0;
+    let route_amount_in = route.amount_in();
+
+    for leg in route.legs {
+        let leg_min_amount_out = min_amount_out
+            .map(|min_amount_out| swap_limits::proportional_amount(min_amount_out, leg.amount_in, route_amount_in));
+
+        let prepared_swap = match prepare_swap_icrc2(
+            provider_impls.clone(),
+            input_token,
+            output_token,
+            Nat::from(leg.amount_in),
+            leg.provider,
+            limits,
+        ).await {
+            Ok(prepared_swap) => prepared_swap,
+            Err(error) => {
+                executed_route.unavailable_providers.push(leg.provider);
+
+                let fallback_swap = match quote_best_provider(
+                    provider_impls.clone(),
+                    input_token,
+                    output_token,
+                    leg.amount_in,
+                    &executed_route.unavailable_providers,
+                ).await {
+                    Some(fallback_provider) => prepare_swap_icrc2(
+                        provider_impls.clone(),
+                        input_token,
+                        output_token,
+                        Nat::from(leg.amount_in),
+                        fallback_provider,
+                        limits,
+                    ).await,
+                    None => Err(error),
+                };
+
+                match fallback_swap {
+                    Ok(prepared_swap) => prepared_swap,
+                    Err(error) => {
+                        return Err(with_route_progress(
+                            error,
+                            input_token,
+                            route_amount_in - executed_route.amount_in(),
+                            output_token,
+                            executed_route.amount_out(),
+                        ));
+                    }
+                }
+            }
+        };
+
+        let swap_response = match prepared_swap.execute(leg_min_amount_out).await {
+            Ok(swap_response) => swap_response,
+            Err(error) => {
+                // The tokens of the failed leg may have moved to the exchange, so they are not held any more
+                return Err(with_route_progress(
+                    error,
+                    input_token,
+                    route_amount_in - executed_route.amount_in() - leg.amount_in,
+                    output_token,
+                    executed_route.amount_out(),
+                ));
+            }
+        };
+
+        ledger_fee += swap_response.ledger_fee;
+
+        executed_route.legs.push(SwapRouteLeg {
+            provider: swap_response.provider,
+            amount_in: leg.amount_in,
+            amount_out: swap_response.amount_out,
+        });
+    }
+
+    Ok(SwapResponse {
+        provider: executed_route.main_provider().unwrap(),
+        amount_out: executed_route.amount_out(),
+        route: executed_route,
+        ledger_fee,
+    })
 }
 
-pub async fn quote_swap_icrc2_optimal(
-    provider_impls: ProviderImpls,
+/// Adds to the error of a route the input amount still held and the output amount swapped by the executed legs
+fn with_route_progress(
+    error: InternalError,
     input_token: CanisterId,
+    remaining_amount: u128,
     output_token: CanisterId,
-    amount: Nat,
-) -> Result<QuoteResponse, InternalError>
-{
-    let kong_quote = quote_swap_kongswap(
-        provider_impls.kongswap,
-        input_token.clone(),
-        output_token.clone(),
-        amount
-    ).await;
-    // let icp_quote = quote_swap_icpswap(
-    //     icpswap_provider,
-    //     input_token.clone(),
-    //     output_token.clone(),
-    //     amount
-    // ).await;
-
-    //Return the quote with the highest amount_out
-    // std::cmp::max_by(
-    //     kong_quote.unwrap(),
-    //     icp_quote.unwrap(),
-    //     |a, b| a.amount_out.cmp(&b.amount_out)
-    // )
-
-    // TODO: remove this after testing and return the quote with the highest amount_out
-    Ok(kong_quote?)
+    swapped_amount: u128,
+) -> InternalError {
+    let mut extra = error.extra.clone().unwrap_or_default();
+    extra.insert("remaining_token".to_string(), input_token.to_text());
+    extra.insert("remaining_amount".to_string(), remaining_amount.to_string());
+    extra.insert("swapped_token".to_string(), output_token.to_text());
+    extra.insert("swapped_amount".to_string(), swapped_amount.to_string());
+
+    InternalError {
+        extra: Some(extra),
+        ..error
+    }
 }
 
-pub async fn quote_swap_icrc2(
+/// Quotes the amount on every registered exchange which is not excluded
+/// and returns the exchange with the highest output, None if none of them quotes the swap
+async fn quote_best_provider(
     provider_impls: ProviderImpls,
     input_token: CanisterId,
     output_token: CanisterId,
-    amount: Nat,
-    provider: ExchangeId,
-) -> Result<QuoteResponse, InternalError>
-{
-    match provider {
-        ExchangeId::KongSwap => {
-            quote_swap_kongswap(provider_impls.kongswap, input_token, output_token, amount).await
+    amount: u128,
+    excluded_providers: &[ExchangeId],
+) -> Option<ExchangeId> {
+    let mut best_quote: Option<(ExchangeId, u128)> = None;
+
+    for adapter in swap_adapters() {
+        let provider = adapter.exchange_id();
+        if excluded_providers.contains(&provider) {
+            continue;
         }
-        ExchangeId::ICPSwap => {
-            quote_swap_icpswap(provider_impls.icpswap, input_token, output_token, amount).await
+
+        let Ok(swap_client) = adapter.swap_client(
+            provider_impls.clone(),
+            input_token,
+            output_token,
+            SwapLimits::default()
+        ).await else { continue };
+
+        if let Ok(quote) = swap_client.quote(Nat::from(amount)).await {
+            if best_quote.map_or(true, |(_, amount_out)| quote.amount_out > amount_out) {
+                best_quote = Some((provider, quote.amount_out));
+            }
+        }
+    }
+
+    best_quote.map(|(provider, _)| provider)
+}
+
+/// Builds the graph of pools a route from `input_token` to `output_token` can use,
+/// from the pools of the registered exchanges. An exchange which fails to respond is left out of the graph.
+pub async fn build_pool_graph(
+    provider_impls: ProviderImpls,
+    input_token: CanisterId,
+    output_token: CanisterId,
+) -> PoolGraph {
+    let mut graph = PoolGraph::new();
+
+    let mut tokens = vec![input_token, output_token];
+    tokens.extend(registry::hub_tokens().into_iter().filter(|hub| *hub != input_token && *hub != output_token));
+
+    for adapter in swap_adapters() {
+        for (token0, token1) in adapter.pools(provider_impls.clone(), &tokens).await {
+            graph.add_pool(token0, token1, adapter.exchange_id());
         }
-        _ => Err(InternalError::business_logic(
-            build_error_code(2000, 3, 2),
-            "swap_service::quote_swap_icrc2".to_string(),
-            "Invalid provider".to_string(),
+    }
+
+    graph
+}
+
+/// Quotes every path from `input_token` to `output_token` found in the pool graph
+/// and returns the route with the highest output. Every hop is quoted with the optimal router.
+pub async fn quote_swap_icrc2_multi_hop(
+    provider_impls: ProviderImpls,
+    input_token: CanisterId,
+    output_token: CanisterId,
+    amount: Nat,
+) -> Result<MultiHopRoute, InternalError> {
+    let graph = build_pool_graph(provider_impls.clone(), input_token, output_token).await;
+    let paths = graph.find_paths(input_token, output_token, &registry::hub_tokens(), MAX_HOPS);
+
+    if paths.is_empty() {
+        return Err(InternalError::not_found(
+            build_error_code(2000, 1, 1), // 2000 01 01
+            "swap_service::quote_swap_icrc2_multi_hop".to_string(),
+            "No swap path found".to_string(),
             Some(HashMap::from([
                 ("input_token".to_string(), input_token.to_text()),
                 ("output_token".to_string(), output_token.to_text()),
-                ("amount".to_string(), amount.to_string()),
-                ("provider".to_string(), provider.to_string()),
             ])),
-        )),
+        ));
     }
+
+    let mut best_route: Option<MultiHopRoute> = None;
+
+    for path in paths {
+        // Path which fails to quote is skipped in favour of the other paths
+        if let Ok(route) = quote_path(provider_impls.clone(), path, amount.clone()).await {
+            if best_route.as_ref().map_or(true, |best_route| route.amount_out > best_route.amount_out) {
+                best_route = Some(route);
+            }
+        }
+    }
+
+    best_route.ok_or_else(|| InternalError::external_service(
+        build_error_code(2000, 4, 2), // 2000 04 02
+        "swap_service::quote_swap_icrc2_multi_hop".to_string(),
+        "No swap path could be quoted".to_string(),
+        Some(HashMap::from([
+            ("input_token".to_string(), input_token.to_text()),
+            ("output_token".to_string(), output_token.to_text()),
+            ("amount".to_string(), amount.to_string()),
+        ])),
+    ))
+}
+
+async fn quote_path(
+    provider_impls: ProviderImpls,
+    path: Vec<CanisterId>,
+    amount: Nat,
+) -> Result<MultiHopRoute, InternalError> {
+    let mut hops = Vec::new();
+    let mut hop_amount_in = nat_to_u128(&amount);
+
+    for tokens in path.windows(2) {
+        let quote = quote_swap_icrc2_optimal(
+            provider_impls.clone(),
+            tokens[0],
+            tokens[1],
+            Nat::from(hop_amount_in),
+        ).await?;
+
+        hops.push(SwapHop {
+            token_in: tokens[0],
+            token_out: tokens[1],
+            amount_in: hop_amount_in,
+            amount_out: quote.amount_out,
+            route: quote.route,
+            ledger_fee: 0,
+        });
+
+        hop_amount_in = quote.amount_out;
+    }
+
+    Ok(MultiHopRoute {
+        path,
+        hops,
+        amount_in: nat_to_u128(&amount),
+        amount_out: hop_amount_in,
+    })
 }
 
-// TODO: make private
-pub async fn swap_icrc2_kongswap(
-    provider_impl: Arc<dyn KongSwapProvider + Send + Sync>,
+/// Quotes the direct swap of the tokens on the routed exchanges. Only if the tokens
+/// have no direct pool, quotes the best route through intermediate tokens.
+pub async fn quote_swap_icrc2_direct_or_multi_hop(
+    provider_impls: ProviderImpls,
     input_token: CanisterId,
     output_token: CanisterId,
     amount: Nat,
-) -> Result<SwapResponse, InternalError>
-{
-    let swap_client = Box::new(
-        KongSwapSwapClient::new(
-            provider_impl,
-            *KONGSWAP_CANISTER_ID,
-            input_token.clone(),
-            output_token
-        )
-    );
+) -> Result<MultiHopRoute, InternalError> {
+    match quote_swap_icrc2_optimal(provider_impls.clone(), input_token, output_token, amount.clone()).await {
+        Ok(quote) => Ok(MultiHopRoute {
+            path: vec![input_token, output_token],
+            hops: vec![SwapHop {
+                token_in: input_token,
+                token_out: output_token,
+                amount_in: nat_to_u128(&amount),
+                amount_out: quote.amount_out,
+                route: quote.route,
+                ledger_fee: 0,
+            }],
+            amount_in: nat_to_u128(&amount),
+            amount_out: quote.amount_out,
+        }),
+        Err(_) => quote_swap_icrc2_multi_hop(provider_impls, input_token, output_token, amount).await,
+    }
+}
 
-    icrc_ledger_client::icrc2_approve(
-        swap_client.canister_id(),
-        input_token.clone(),
-        amount.clone()
-    ).await?;
+/// Executes the hops of the quoted route one by one, each hop with the optimal router.
+/// Every hop has to pay out its quoted output, scaled to the amount actually swapped,
+/// minus the slippage tolerance of the limits, so the route stops at the hop where the price moved.
+///
+/// If a hop after the first one fails, the tokens swapped by the previous hops are swapped back
+/// to the input token. The error of the failed hop reports the token and the amount left.
+pub async fn swap_icrc2_multi_hop(
+    provider_impls: ProviderImpls,
+    route: MultiHopRoute,
+    amount: Nat,
+    limits: SwapLimits,
+) -> Result<MultiHopRoute, InternalError> {
+    let amount_in = nat_to_u128(&amount);
+    let mut hops = Vec::new();
+    let mut hop_amount_in = amount_in;
 
-    let swap_result = swap_client.swap(amount.clone()).await?;
+    for quoted_hop in &route.hops {
+        let hop_min_amount_out = swap_limits::hop_min_amount_out(
+            &limits,
+            quoted_hop.amount_in,
+            quoted_hop.amount_out,
+            hop_amount_in,
+        );
 
-    Ok(SwapResponse {
-        provider: ExchangeId::KongSwap,
-        amount_out: swap_result.amount_out,
+        let swap_response = match swap_icrc2_optimal(
+            provider_impls.clone(),
+            quoted_hop.token_in,
+            quoted_hop.token_out,
+            Nat::from(hop_amount_in),
+            limits,
+            Some(hop_min_amount_out),
+        ).await {
+            Ok(swap_response) => swap_response,
+            Err(error) if hops.is_empty() => return Err(error),
+            Err(error) => {
+                return Err(swap_back(
+                    provider_impls,
+                    &route.path[..=hops.len()],
+                    hop_amount_in,
+                    limits,
+                    error,
+                ).await);
+            }
+        };
+
+        hops.push(SwapHop {
+            token_in: quoted_hop.token_in,
+            token_out: quoted_hop.token_out,
+            amount_in: hop_amount_in,
+            amount_out: swap_response.amount_out,
+            route: swap_response.route,
+            ledger_fee: swap_response.ledger_fee,
+        });
+
+        hop_amount_in = swap_response.amount_out;
+    }
+
+    Ok(MultiHopRoute {
+        path: route.path,
+        hops,
+        amount_in,
+        amount_out: hop_amount_in,
     })
 }
 
-// TODO: make private
-pub async fn swap_icrc2_icpswap(
-    provider_impl: Arc<dyn ICPSwapProvider + Send + Sync>,
+/// Swaps `amount` of the last token of the executed `path` back along the path to its first token.
+/// Returns the error of the failed hop with the token and the amount the swap back ended with,
+/// which is an intermediate token if the swap back fails too.
+async fn swap_back(
+    provider_impls: ProviderImpls,
+    path: &[CanisterId],
+    amount: u128,
+    limits: SwapLimits,
+    error: InternalError,
+) -> InternalError {
+    let mut token = *path.last().unwrap();
+    let mut amount = amount;
+
+    for token_out in path.iter().rev().skip(1) {
+        match swap_icrc2_optimal(
+            provider_impls.clone(),
+            token,
+            *token_out,
+            Nat::from(amount),
+            limits,
+            None,
+        ).await {
+            Ok(swap_response) => {
+                token = *token_out;
+                amount = swap_response.amount_out;
+            }
+            Err(_) => break,
+        }
+    }
+
+    let mut extra = error.extra.clone().unwrap_or_default();
+    extra.insert("remaining_token".to_string(), token.to_text());
+    extra.insert("remaining_amount".to_string(), amount.to_string());
+
+    InternalError {
+        extra: Some(extra),
+        ..error
+    }
+}
+
+/// Swaps on the exchange registered under `provider`
+pub async fn swap_icrc2(
+    provider_impls: ProviderImpls,
     input_token: CanisterId,
     output_token: CanisterId,
     amount: Nat,
+    provider: ExchangeId,
+    limits: SwapLimits,
+    min_amount_out: Option<u128>,
 ) -> Result<SwapResponse, InternalError>
 {
-    let swap_client = Box::new(
-        ICPSwapSwapClient::new(
-            provider_impl,
-            input_token.clone(),
-            output_token
-        ).with_pool().await?
-    );
+    prepare_swap_icrc2(
+        provider_impls,
+        input_token,
+        output_token,
+        amount,
+        provider,
+        limits
+    ).await?.execute(min_amount_out).await
+}
+
+/// Swap on an exchange which is quoted and approved, no tokens have moved yet
+struct PreparedSwap {
+    swap_client: Box<dyn SwapClient>,
+    provider: ExchangeId,
+    amount: Nat,
+    deduction: FeeDeduction,
+}
+
+impl PreparedSwap {
+    async fn execute(self, min_amount_out: Option<u128>) -> Result<SwapResponse, InternalError> {
+        let swap_result = self.swap_client.swap(self.deduction.amount, min_amount_out).await?;
+
+        Ok(SwapResponse {
+            provider: self.provider,
+            amount_out: swap_result.amount_out,
+            route: SwapRoute::single(self.provider, nat_to_u128(&self.amount), swap_result.amount_out),
+            ledger_fee: nat_to_u128(&self.deduction.fees_paid),
+        })
+    }
+}
+
+/// Quotes `amount` on the exchange registered under `provider` and approves it to the exchange
+async fn prepare_swap_icrc2(
+    provider_impls: ProviderImpls,
+    input_token: CanisterId,
+    output_token: CanisterId,
+    amount: Nat,
+    provider: ExchangeId,
+    limits: SwapLimits,
+) -> Result<PreparedSwap, InternalError>
+{
+    let swap_client = build_swap_client(
+        provider_impls,
+        input_token,
+        output_token,
+        provider,
+        limits
+    ).await?;
+
+    // The approval and the transfer to the exchange are paid out of `amount`
+    let deduction = ledger_fees::deduct_ledger_fees(
+        input_token,
+        &amount,
+        APPROVED_TRANSFER_FEE_COUNT,
+    ).await?;
+
+    swap_client.quote(deduction.amount.clone()).await?;
 
     icrc_ledger_client::icrc2_approve(
         swap_client.canister_id(),
-        input_token.clone(),
-        amount.clone()
+        input_token,
+        deduction.amount.clone(),
+        deduction.fee.clone()
     ).await?;
 
-    let swap_result = swap_client.swap(amount.clone()).await?;
-
-    Ok(SwapResponse {
-        provider: ExchangeId::ICPSwap,
-        amount_out: swap_result.amount_out,
+    Ok(PreparedSwap {
+        swap_client,
+        provider,
+        amount,
+        deduction,
     })
 }
 
-// TODO: make private
-pub async fn quote_swap_kongswap(
-    provider_impl: Arc<dyn KongSwapProvider + Send + Sync>,
+/// Quotes the swap on the registered exchanges for the whole amount and for its parts,
+/// and returns the route with the highest output. An exchange which fails to quote
+/// is reported in `unavailable_providers` and the route uses the remaining ones.
+pub async fn quote_swap_icrc2_optimal(
+    provider_impls: ProviderImpls,
     input_token: CanisterId,
     output_token: CanisterId,
     amount: Nat,
 ) -> Result<QuoteResponse, InternalError>
 {
-    let swap_client = Box::new(
-        KongSwapSwapClient::new(
-            provider_impl,
-            *KONGSWAP_CANISTER_ID,
-            input_token.clone(),
-            output_token.clone()
-        )
-    );
+    let amount_in = nat_to_u128(&amount);
 
-    let result = swap_client.quote(amount.clone()).await?;
+    let mut venue_quotes = Vec::new();
+
+    for adapter in swap_adapters() {
+        let provider = adapter.exchange_id();
+
+        let quotes = match adapter.swap_client(
+            provider_impls.clone(),
+            input_token,
+            output_token,
+            SwapLimits::default()
+        ).await {
+            Ok(swap_client) => quote_split_amounts(swap_client.as_ref(), provider, amount_in).await,
+            Err(_) => VenueQuotes { provider, amounts_out: HashMap::new() },
+        };
+
+        venue_quotes.push(quotes);
+    }
+
+    let unavailable_providers = venue_quotes
+        .iter()
+        .filter(|quotes| quotes.amounts_out.is_empty())
+        .map(|quotes| quotes.provider)
+        .collect();
+
+    let mut route = swap_router::select_route(amount_in, &venue_quotes)
+        .ok_or_else(|| InternalError::external_service(
+            build_error_code(2000, 4, 1), // 2000 04 01
+            "swap_service::quote_swap_icrc2_optimal".to_string(),
+            "No exchange is able to quote the swap".to_string(),
+            Some(HashMap::from([
+                ("input_token".to_string(), input_token.to_text()),
+                ("output_token".to_string(), output_token.to_text()),
+                ("amount".to_string(), amount.to_string()),
+            ])),
+        ))?;
+    route.unavailable_providers = unavailable_providers;
 
     Ok(QuoteResponse {
-        provider: ExchangeId::KongSwap,
-        amount_out: result.amount_out,
+        provider: route.main_provider().unwrap(),
+        amount_out: route.amount_out(),
+        route,
     })
 }
 
-// TODO: make private
-pub async fn quote_swap_icpswap(
-    provider_impl: Arc<dyn ICPSwapProvider + Send + Sync>,
+/// Quotes every leg amount of the splits on the exchange, skipping failed quotes
+async fn quote_split_amounts(
+    swap_client: &dyn SwapClient,
+    provider: ExchangeId,
+    amount: u128,
+) -> VenueQuotes {
+    let mut amounts_out = HashMap::new();
+
+    for amount_in in swap_router::quote_amounts(amount) {
+        if let Ok(quote) = swap_client.quote(Nat::from(amount_in)).await {
+            amounts_out.insert(amount_in, quote.amount_out);
+        }
+    }
+
+    VenueQuotes { provider, amounts_out }
+}
+
+/// Quotes the swap on the exchange registered under `provider`
+pub async fn quote_swap_icrc2(
+    provider_impls: ProviderImpls,
     input_token: CanisterId,
     output_token: CanisterId,
     amount: Nat,
+    provider: ExchangeId,
 ) -> Result<QuoteResponse, InternalError>
 {
-    let swap_client = Box::new(
-        ICPSwapSwapClient::new(
-            provider_impl,
-            input_token.clone(),
-            output_token.clone()
-        ).with_pool().await?
-    );
+    let swap_client = build_swap_client(
+        provider_impls,
+        input_token,
+        output_token,
+        provider,
+        SwapLimits::default()
+    ).await?;
 
     let result = swap_client.quote(amount.clone()).await?;
 
     Ok(QuoteResponse {
-        provider: ExchangeId::ICPSwap,
+        provider,
         amount_out: result.amount_out,
+        route: SwapRoute::single(provider, nat_to_u128(&amount), result.amount_out),
+    })
+}
+
+/// Swaps the input amount needed to receive `amount_out` on the provider.
+/// The ledger approval is limited to `max_amount_in`, the ledger fees included.
+/// The route of the response reports the input amount moved to the exchange.
+pub async fn swap_icrc2_exact_output(
+    provider_impls: ProviderImpls,
+    input_token: CanisterId,
+    output_token: CanisterId,
+    amount_out: Nat,
+    max_amount_in: Nat,
+    provider: ExchangeId,
+    limits: SwapLimits,
+) -> Result<SwapResponse, InternalError> {
+    let swap_client = build_swap_client(provider_impls, input_token, output_token, provider, limits).await?;
+
+    // The approval and the transfer to the exchange are paid out of `max_amount_in`
+    let deduction = ledger_fees::deduct_ledger_fees(
+        input_token,
+        &max_amount_in,
+        APPROVED_TRANSFER_FEE_COUNT,
+    ).await?;
+
+    icrc_ledger_client::icrc2_approve(
+        swap_client.canister_id(),
+        input_token.clone(),
+        deduction.amount.clone(),
+        deduction.fee
+    ).await?;
+
+    let swap_result = swap_client.swap_exact_output(amount_out, deduction.amount).await?;
+
+    Ok(SwapResponse {
+        provider,
+        amount_out: swap_result.amount_out,
+        route: SwapRoute::single(provider, swap_result.amount_in, swap_result.amount_out),
+        ledger_fee: nat_to_u128(&deduction.fees_paid),
     })
 }
+
+/// Quotes the input amount needed to receive `amount_out` on the provider with the limits of the caller
+pub async fn quote_swap_icrc2_exact_output(
+    provider_impls: ProviderImpls,
+    input_token: CanisterId,
+    output_token: CanisterId,
+    amount_out: Nat,
+    max_amount_in: Nat,
+    provider: ExchangeId,
+    limits: SwapLimits,
+) -> Result<QuoteExactOutputSuccess, InternalError> {
+    let swap_client = build_swap_client(
+        provider_impls,
+        input_token,
+        output_token,
+        provider,
+        limits
+    ).await?;
+
+    swap_client.quote_exact_output(amount_out, max_amount_in).await
+}
@@ -0,0 +1,111 @@
+use candid::Nat;
+use std::collections::HashMap;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+
+use super::swap_client::SwapClient;
+
+/// Number of quotes used to narrow down the input amount
+pub const EXACT_OUTPUT_MAX_ITERATIONS: usize = 8;
+/// Extra output targeted on top of the requested amount to absorb price movement, 5 points == 0.5%
+pub const EXACT_OUTPUT_BUFFER: u128 = 5;
+
+/// Requested output increased by the buffer
+pub fn buffered_amount_out(amount_out: u128) -> u128 {
+    amount_out + (amount_out * EXACT_OUTPUT_BUFFER).div_ceil(1000)
+}
+
+/// Next input amount to quote, proportional to the last quote and kept strictly
+/// between the largest insufficient input `low` and the smallest sufficient input `high`
+pub fn next_amount_in(target_out: u128, amount_in: u128, amount_out: u128, low: u128, high: u128) -> u128 {
+    let midpoint = low + (high - low) / 2;
+
+    if amount_out == 0 {
+        return midpoint;
+    }
+
+    let estimate = (amount_in * target_out).div_ceil(amount_out);
+
+    if estimate <= low || estimate >= high {
+        midpoint
+    } else {
+        estimate
+    }
+}
+
+/// Finds the smallest input amount, found within the iteration limit,
+/// which is quoted to return at least `amount_out` and does not exceed `max_amount_in`
+pub async fn find_amount_in(
+    swap_client: &dyn SwapClient,
+    amount_out: u128,
+    max_amount_in: u128,
+) -> Result<u128, InternalError> {
+    let max_amount_out = swap_client.quote(Nat::from(max_amount_in)).await?.amount_out;
+
+    if max_amount_out < amount_out {
+        return Err(InternalError::business_logic(
+            build_error_code(2003, 3, 1), // 2003 03 01
+            "exact_output::find_amount_in".to_string(),
+            "Maximum input amount is not enough for the requested output".to_string(),
+            Some(HashMap::from([
+                ("amount_out".to_string(), amount_out.to_string()),
+                ("max_amount_in".to_string(), max_amount_in.to_string()),
+                ("max_amount_out".to_string(), max_amount_out.to_string()),
+            ])),
+        ));
+    }
+
+    let (mut low, mut high) = (0u128, max_amount_in);
+    let mut amount_in = next_amount_in(amount_out, max_amount_in, max_amount_out, low, high);
+
+    for _ in 0..EXACT_OUTPUT_MAX_ITERATIONS {
+        if high - low <= 1 {
+            break;
+        }
+
+        let quoted_amount_out = swap_client.quote(Nat::from(amount_in)).await?.amount_out;
+
+        if quoted_amount_out >= amount_out {
+            high = amount_in;
+        } else {
+            low = amount_in;
+        }
+
+        amount_in = next_amount_in(amount_out, amount_in, quoted_amount_out, low, high);
+    }
+
+    Ok(high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod buffered_amount_out {
+        use super::*;
+
+        #[test]
+        fn adds_buffer_rounded_up() {
+            assert_eq!(buffered_amount_out(1_000), 1_005);
+            assert_eq!(buffered_amount_out(1), 2);
+            assert_eq!(buffered_amount_out(0), 0);
+        }
+    }
+
+    mod next_amount_in {
+        use super::*;
+
+        #[test]
+        fn estimates_proportionally_to_last_quote() {
+            assert_eq!(next_amount_in(500, 1_000, 2_000, 0, 1_000), 250);
+        }
+
+        #[test]
+        fn falls_back_to_midpoint_outside_of_bounds() {
+            assert_eq!(next_amount_in(500, 300, 1_000, 300, 400), 350);
+            assert_eq!(next_amount_in(500, 1_000, 0, 0, 1_000), 500);
+        }
+    }
+
+}
@@ -6,23 +6,30 @@ use std::sync::Arc;
 
 use types::CanisterId;
 use providers::icpswap::ICPSwapProvider;
+use providers::providers_factory::ProviderImpls;
+use types::exchange_id::ExchangeId;
 use icpswap_swap_factory_canister::ICPSwapPool;
 use icpswap_swap_pool_canister::getTokenMeta::TokenMeta;
 use types::liquidity::TokensFee;
 use utils::util::nat_to_u128;
 use errors::internal_error::error::InternalError;
 use errors::internal_error::error::build_error_code;
+use token_registry::ledger_fees;
 
-use crate::token_swaps::swap_client::{SwapClient, SwapSuccess, QuoteSuccess};
-
-pub const SLIPPAGE_TOLERANCE: u128 = 50; // 50 slippage tolerance points == 5%
+use crate::token_swaps::swap_client::{SwapClient, SwapSuccess, QuoteSuccess, QuoteExactOutputSuccess, SwapExactOutputSuccess};
+use crate::token_swaps::exact_output;
+use crate::swap_limits;
+use crate::swap_adapters::SwapAdapter;
+use types::swap_tokens::SwapLimits;
 
+/// Swap client of a resolved ICPSwap pool, it can only be built once the pool of the tokens is known
 pub struct ICPSwapSwapClient {
     provider_impl: Arc<dyn ICPSwapProvider + Send + Sync>,
-    canister_id: Option<CanisterId>,
+    canister_id: CanisterId,
     token0: CanisterId,
     token1: CanisterId,
-    pool: Option<ICPSwapPool>,
+    pool: ICPSwapPool,
+    limits: SwapLimits,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -31,30 +38,48 @@ pub struct DepositFromSuccess {
 }
 
 impl ICPSwapSwapClient {
-    pub fn new(provider_impl: Arc<dyn ICPSwapProvider + Send + Sync>, token0: CanisterId, token1: CanisterId) -> Self {
-        Self {
+    /// Looks up the ICPSwap pool of the tokens and builds the client for it
+    pub async fn resolve(
+        provider_impl: Arc<dyn ICPSwapProvider + Send + Sync>,
+        token0: CanisterId,
+        token1: CanisterId,
+    ) -> Result<Self, InternalError> {
+        let pool = provider_impl.get_pool(token0, token1).await?;
+
+        Self::from_pool(provider_impl, token0, token1, pool)
+    }
+
+    /// Builds the client for a known pool, fails if the pool does not hold the tokens
+    pub fn from_pool(
+        provider_impl: Arc<dyn ICPSwapProvider + Send + Sync>,
+        token0: CanisterId,
+        token1: CanisterId,
+        pool: ICPSwapPool,
+    ) -> Result<Self, InternalError> {
+        let client = Self {
             provider_impl,
-            canister_id: None,
+            canister_id: pool.canisterId,
             token0, // token0 may be token1 in the pool and vice versa
             token1, // token1 may be token0 in the pool and vice versa
-            pool: None,
-        }
-    }
+            pool,
+            limits: SwapLimits::default(),
+        };
 
-    pub async fn with_pool(mut self) -> Result<Self, InternalError> {
-        let pool = self.provider_impl.get_pool(self.token0.clone(), self.token1.clone()).await?;
+        client.is_zero_for_one_swap_direction()?;
 
-        self.pool = Some(pool.clone());
-        self.canister_id = Some(pool.canisterId);
+        Ok(client)
+    }
 
-        Ok(self)
+    pub fn with_limits(mut self, limits: SwapLimits) -> Self {
+        self.limits = limits;
+        self
     }
 
     fn is_zero_for_one_swap_direction(&self) -> Result<bool, InternalError> {
         let token0_str = self.token0.to_text();
         let token1_str = self.token1.to_text();
 
-        let pool = self.pool.as_ref().unwrap();
+        let pool = &self.pool;
 
         match (pool.token0.address.as_str(), pool.token1.address.as_str()) {
             (t0, t1) if t0 == token0_str && t1 == token1_str => Ok(true),
@@ -77,7 +102,7 @@ impl ICPSwapSwapClient {
         let token0_str = self.token0.to_text();
         let token1_str = self.token1.to_text();
 
-        let pool = self.pool.as_ref().unwrap();
+        let pool = &self.pool;
 
         match (pool.token0.address.as_str(), pool.token1.address.as_str()) {
             (t0, t1) if t0 == token0_str && t1 == token1_str => Ok(TokensFee {
@@ -107,13 +132,13 @@ impl ICPSwapSwapClient {
     }
 
     async fn get_token_meta(&self) -> Result<TokenMeta, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         self.provider_impl.get_token_meta(canister_id.clone()).await
     }
     
     async fn deposit_from(&self, amount: Nat, token_fee: Nat) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         self.provider_impl.deposit_from(
             canister_id.clone(),
@@ -124,7 +149,7 @@ impl ICPSwapSwapClient {
     }
 
     async fn withdraw(&self, amount: Nat, token_fee: Nat) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         self.provider_impl.withdraw(
             canister_id.clone(),
@@ -135,7 +160,7 @@ impl ICPSwapSwapClient {
     }
 
     async fn quote_internal(&self, amount: Nat) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
         let is_zero_for_one_swap_direction = self.is_zero_for_one_swap_direction()?;
 
         self.provider_impl.quote(
@@ -147,7 +172,7 @@ impl ICPSwapSwapClient {
     }
 
     async fn swap_internal(&self, amount_in: Nat, zero_for_one: bool, amount_out_minimum: Nat) -> Result<Nat, InternalError> {
-        let canister_id = self.canister_id.as_ref().unwrap();
+        let canister_id = &self.canister_id;
 
         self.provider_impl.swap(
             canister_id.clone(),
@@ -161,35 +186,43 @@ impl ICPSwapSwapClient {
 #[async_trait]
 impl SwapClient for ICPSwapSwapClient {
     fn canister_id(&self) -> CanisterId {
-        self.canister_id.as_ref().unwrap().clone()
+        self.canister_id
     }
 
-    async fn swap(&self, amount: Nat) -> Result<SwapSuccess, InternalError> {
+    async fn swap(&self, amount: Nat, min_amount_out: Option<u128>) -> Result<SwapSuccess, InternalError> {
         // Flow:
         // 1. Get token fees
-        // 2. Deposit from token0 to ICPSwap
-        // 3. Quote
+        // 2. Quote and check the price impact and the minimum output
+        // 3. Deposit from token0 to ICPSwap
         // 4. Swap
         // 5. Withdraw from ICPSwap to token1
 
         // 1. Get token fees
-        let token0_fee = icrc_ledger_client::icrc1_fee(self.token0.clone()).await?;
-        let token1_fee = icrc_ledger_client::icrc1_fee(self.token1.clone()).await?;
+        let token0_fee = token_registry::registry::fee(self.token0.clone()).await?;
+        let token1_fee = token_registry::registry::fee(self.token1.clone()).await?;
+
+        // 2. Quote
+        // Checked before the deposit, so that a rejected swap leaves no tokens in the pool
+        let expected_out = self.quote_internal(amount.clone()).await?;
+        let expected_out_u128 = nat_to_u128(&expected_out);
+
+        swap_limits::ensure_price_impact(
+            self,
+            &self.limits,
+            nat_to_u128(&amount),
+            expected_out_u128,
+        ).await?;
 
-        // 2. Deposit
+        // Сonsider slippage tolerance and the minimum output of the caller
+        let amount_out_minimum = Nat::from(swap_limits::amount_out_minimum(&self.limits, expected_out_u128, min_amount_out)?);
+
+        // 3. Deposit
         let deposited_amount = self.deposit_from(
             amount.clone(),
             token0_fee.clone()
         ).await?;
 
-        // 3. Quote
-        let expected_out = self.quote_internal(deposited_amount.clone()).await?;
-
         // 4. Swap
-        let expected_out_u128 = nat_to_u128(&expected_out);
-        // Сonsider slippage tolerance
-        let amount_out_minimum = Nat::from(expected_out_u128 * (1000 - SLIPPAGE_TOLERANCE) / 1000u128);
-
         let amount_out = self.swap_internal(
             deposited_amount.clone(),
             self.is_zero_for_one_swap_direction()?,
@@ -212,4 +245,186 @@ impl SwapClient for ICPSwapSwapClient {
             amount_out: nat_to_u128(&quote_amount),
         })
     }
+
+    async fn quote_exact_output(&self, amount_out: Nat, max_amount_in: Nat) -> Result<QuoteExactOutputSuccess, InternalError> {
+        // The withdrawal of the output pays the ledger fee out of the swapped amount
+        let token1_fee = token_registry::registry::fee(self.token1.clone()).await?;
+        let swapped_amount_out = amount_out + token1_fee.clone();
+
+        let amount_in = exact_output::find_amount_in(
+            self,
+            exact_output::buffered_amount_out(nat_to_u128(&swapped_amount_out)),
+            nat_to_u128(&max_amount_in),
+        ).await?;
+
+        let quote_amount = self.quote_internal(Nat::from(amount_in)).await?;
+
+        Ok(QuoteExactOutputSuccess {
+            amount_in,
+            amount_out: nat_to_u128(&ledger_fees::received_after_fee(&quote_amount, &token1_fee).amount),
+        })
+    }
+
+    async fn swap_exact_output(&self, amount_out: Nat, max_amount_in: Nat) -> Result<SwapExactOutputSuccess, InternalError> {
+        let quote = self.quote_exact_output(amount_out.clone(), max_amount_in).await?;
+
+        let token0_fee = token_registry::registry::fee(self.token0.clone()).await?;
+        let token1_fee = token_registry::registry::fee(self.token1.clone()).await?;
+
+        let deposited_amount = self.deposit_from(
+            Nat::from(quote.amount_in),
+            token0_fee.clone()
+        ).await?;
+
+        // The pool rejects the swap if the output is below the requested amount and the fee of its withdrawal
+        let swapped_amount = self.swap_internal(
+            deposited_amount,
+            self.is_zero_for_one_swap_direction()?,
+            amount_out + token1_fee.clone(),
+        ).await?;
+
+        let withdrawn_amount = self.withdraw(swapped_amount, token1_fee).await?;
+
+        Ok(SwapExactOutputSuccess {
+            amount_in: quote.amount_in,
+            amount_out: nat_to_u128(&withdrawn_amount),
+            withdrawal_success: Some(true),
+        })
+    }
+}
+
+pub struct ICPSwapSwapAdapter;
+
+#[async_trait]
+impl SwapAdapter for ICPSwapSwapAdapter {
+    fn exchange_id(&self) -> ExchangeId {
+        ExchangeId::ICPSwap
+    }
+
+    async fn swap_client(
+        &self,
+        provider_impls: ProviderImpls,
+        token_in: CanisterId,
+        token_out: CanisterId,
+        limits: SwapLimits,
+    ) -> Result<Box<dyn SwapClient>, InternalError> {
+        Ok(Box::new(
+            ICPSwapSwapClient::resolve(
+                provider_impls.get::<dyn ICPSwapProvider + Send + Sync>(self.exchange_id())?,
+                token_in,
+                token_out
+            ).await?.with_limits(limits)
+        ))
+    }
+
+    /// ICPSwap pools are looked up for every pair of the tokens
+    async fn pools(&self, provider_impls: ProviderImpls, tokens: &[CanisterId]) -> Vec<(CanisterId, CanisterId)> {
+        let Ok(provider) = provider_impls.get::<dyn ICPSwapProvider + Send + Sync>(self.exchange_id()) else {
+            return Vec::new();
+        };
+
+        let mut pools = Vec::new();
+
+        for (index, token0) in tokens.iter().enumerate() {
+            for token1 in tokens.iter().skip(index + 1) {
+                if provider.get_pool(*token0, *token1).await.is_ok() {
+                    pools.push((*token0, *token1));
+                }
+            }
+        }
+
+        pools
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::token;
+    use futures::executor::block_on;
+    use icpswap_swap_factory_canister::ICPSwapToken;
+    use providers::mock::icpswap::MockICPSwapProvider;
+    use providers::providers_factory;
+    use utils::environment::Environment;
+
+    fn pool(token0: CanisterId, token1: CanisterId) -> ICPSwapPool {
+        ICPSwapPool {
+            fee: Nat::from(3_000u64),
+            key: format!("{}_{}_3000", token0.to_text(), token1.to_text()),
+            tickSpacing: 60,
+            token0: ICPSwapToken { address: token0.to_text(), standard: "ICRC2".to_string() },
+            token1: ICPSwapToken { address: token1.to_text(), standard: "ICRC2".to_string() },
+            canisterId: token(9),
+        }
+    }
+
+    mod resolve {
+        use super::*;
+
+        #[test]
+        fn returns_error_when_pool_is_missing() {
+            let result = block_on(ICPSwapSwapClient::resolve(
+                Arc::new(MockICPSwapProvider::new()),
+                token(1),
+                token(2),
+            ));
+
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn resolves_pool_of_tokens() {
+            let mut provider = MockICPSwapProvider::new();
+            provider.mock_get_pool(token(1), token(2), Ok(pool(token(2), token(1))));
+
+            let client = block_on(ICPSwapSwapClient::resolve(Arc::new(provider), token(1), token(2))).unwrap();
+
+            assert_eq!(client.canister_id(), token(9));
+            assert!(!client.is_zero_for_one_swap_direction().unwrap());
+        }
+    }
+
+    mod from_pool {
+        use super::*;
+
+        #[test]
+        fn rejects_pool_of_other_tokens() {
+            let result = ICPSwapSwapClient::from_pool(
+                Arc::new(MockICPSwapProvider::new()),
+                token(1),
+                token(2),
+                pool(token(1), token(3)),
+            );
+
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn quote_returns_error_when_pool_does_not_respond() {
+            let client = ICPSwapSwapClient::from_pool(
+                Arc::new(MockICPSwapProvider::new()),
+                token(1),
+                token(2),
+                pool(token(1), token(2)),
+            ).unwrap();
+
+            assert!(block_on(client.quote(Nat::from(1_000u64))).is_err());
+        }
+    }
+
+    mod icpswap_swap_adapter {
+        use super::*;
+
+        #[test]
+        fn returns_error_when_pool_is_missing() {
+            let result = block_on(ICPSwapSwapAdapter.swap_client(
+                providers_factory::get_provider_impls(Environment::Test),
+                token(1),
+                token(2),
+                SwapLimits::default(),
+            ));
+
+            assert!(result.is_err());
+        }
+    }
 }
@@ -3,18 +3,24 @@ use types::CanisterId;
 use candid::Nat;
 use std::sync::Arc;
 
-use super::swap_client::{SwapClient, SwapSuccess, QuoteSuccess};
+use super::swap_client::{SwapClient, SwapSuccess, QuoteSuccess, QuoteExactOutputSuccess, SwapExactOutputSuccess};
+use super::exact_output;
+use crate::swap_limits;
+use crate::swap_adapters::SwapAdapter;
 use errors::internal_error::error::InternalError;
 use providers::kongswap::KongSwapProvider;
+use providers::providers_factory::ProviderImpls;
+use types::exchange_id::ExchangeId;
+use types::swap_tokens::SwapLimits;
 use utils::util::nat_to_u128;
-
-const SLIPPAGE_PERCENTAGE: f64 = 40.0; // TODO: Improve slippage settings
+use utils::constants::KONGSWAP_CANISTER_ID;
 
 pub struct KongSwapSwapClient {
     provider_impl: Arc<dyn KongSwapProvider + Send + Sync>,
     canister_id: CanisterId,
     token_in: CanisterId,
     token_out: CanisterId,
+    limits: SwapLimits,
 }
 
 impl KongSwapSwapClient {
@@ -29,8 +35,14 @@ impl KongSwapSwapClient {
             canister_id,
             token_in,
             token_out,
+            limits: SwapLimits::default(),
         }
     }
+
+    pub fn with_limits(mut self, limits: SwapLimits) -> Self {
+        self.limits = limits;
+        self
+    }
 }
 
 #[async_trait]
@@ -39,12 +51,31 @@ impl SwapClient for KongSwapSwapClient {
         self.canister_id
     }
 
-    async fn swap(&self, amount: Nat) -> Result<SwapSuccess, InternalError> {
+    async fn swap(&self, amount: Nat, min_amount_out: Option<u128>) -> Result<SwapSuccess, InternalError> {
+        let quote = self.provider_impl.swap_amounts(
+            self.token_in.clone(),
+            amount.clone(),
+            self.token_out.clone(),
+        ).await?;
+        let expected_amount_out = nat_to_u128(&quote.receive_amount);
+
+        // KongSwap reports the price impact of the quoted trade as slippage in percent
+        swap_limits::check_price_impact(
+            &self.limits,
+            (quote.slippage * 100.0).round() as u32,
+            nat_to_u128(&amount),
+            expected_amount_out,
+        )?;
+
+        let min_amount_out = swap_limits::amount_out_minimum(&self.limits, expected_amount_out, min_amount_out)?;
+
+        // KongSwap rejects the swap if the output is below the minimum output
         let result = self.provider_impl.swap(
             self.token_in.clone(),
             amount.clone(),
             self.token_out.clone(),
-            Some(SLIPPAGE_PERCENTAGE),
+            Some(Nat::from(min_amount_out)),
+            Some(self.limits.slippage_tolerance_percentage()),
         ).await?;
 
         Ok(SwapSuccess {
@@ -64,4 +95,78 @@ impl SwapClient for KongSwapSwapClient {
             amount_out: nat_to_u128(&result.receive_amount),
         })
     }
+
+    async fn quote_exact_output(&self, amount_out: Nat, max_amount_in: Nat) -> Result<QuoteExactOutputSuccess, InternalError> {
+        let amount_in = exact_output::find_amount_in(
+            self,
+            exact_output::buffered_amount_out(nat_to_u128(&amount_out)),
+            nat_to_u128(&max_amount_in),
+        ).await?;
+
+        let result = self.quote(Nat::from(amount_in)).await?;
+
+        Ok(QuoteExactOutputSuccess {
+            amount_in,
+            amount_out: result.amount_out,
+        })
+    }
+
+    async fn swap_exact_output(&self, amount_out: Nat, max_amount_in: Nat) -> Result<SwapExactOutputSuccess, InternalError> {
+        let quote = self.quote_exact_output(amount_out.clone(), max_amount_in).await?;
+
+        // The requested output is the minimum output of the swap
+        let result = self.swap(Nat::from(quote.amount_in), Some(nat_to_u128(&amount_out))).await?;
+
+        Ok(SwapExactOutputSuccess {
+            amount_in: quote.amount_in,
+            amount_out: result.amount_out,
+            withdrawal_success: result.withdrawal_success,
+        })
+    }
+}
+
+pub struct KongSwapSwapAdapter;
+
+#[async_trait]
+impl SwapAdapter for KongSwapSwapAdapter {
+    fn exchange_id(&self) -> ExchangeId {
+        ExchangeId::KongSwap
+    }
+
+    async fn swap_client(
+        &self,
+        provider_impls: ProviderImpls,
+        token_in: CanisterId,
+        token_out: CanisterId,
+        limits: SwapLimits,
+    ) -> Result<Box<dyn SwapClient>, InternalError> {
+        Ok(Box::new(
+            KongSwapSwapClient::new(
+                provider_impls.get::<dyn KongSwapProvider + Send + Sync>(self.exchange_id())?,
+                *KONGSWAP_CANISTER_ID,
+                token_in,
+                token_out
+            ).with_limits(limits)
+        ))
+    }
+
+    /// KongSwap lists all its pools
+    async fn pools(&self, provider_impls: ProviderImpls, _tokens: &[CanisterId]) -> Vec<(CanisterId, CanisterId)> {
+        let Ok(provider) = provider_impls.get::<dyn KongSwapProvider + Send + Sync>(self.exchange_id()) else {
+            return Vec::new();
+        };
+
+        provider.pools().await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|pool| !pool.is_removed)
+            .filter_map(|pool| match (
+                CanisterId::from_text(&pool.address_0),
+                CanisterId::from_text(&pool.address_1),
+            ) {
+                (Ok(token0), Ok(token1)) => Some((token0, token1)),
+                _ => None,
+            })
+            .collect()
+    }
 }
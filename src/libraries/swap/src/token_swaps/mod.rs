@@ -3,7 +3,9 @@ use candid::Nat;
 
 pub mod kongswap;
 pub mod icpswap;
+pub mod sonic;
 pub mod swap_client;
+pub mod exact_output;
 
 pub fn nat_to_u128(value: Nat) -> u128 {
     value.0.try_into().unwrap()
@@ -0,0 +1,333 @@
+use async_trait::async_trait;
+use candid::Nat;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use types::CanisterId;
+use sonic_canister::PairInfoExt;
+use providers::sonic::SonicProvider;
+use providers::providers_factory::ProviderImpls;
+use types::exchange_id::ExchangeId;
+use types::swap_tokens::SwapLimits;
+use utils::util::nat_to_u128;
+use utils::constants::SONIC_SWAP_CANISTER_ID;
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use token_registry::ledger_fees;
+
+use super::swap_client::{SwapClient, SwapSuccess, QuoteSuccess, QuoteExactOutputSuccess, SwapExactOutputSuccess};
+use super::exact_output;
+use crate::swap_limits;
+use crate::swap_adapters::SwapAdapter;
+
+/// Sonic pairs keep 0.3% of the input amount as the LP fee
+const SWAP_FEE_NUMERATOR: u32 = 997;
+const SWAP_FEE_DENOMINATOR: u32 = 1000;
+
+/// Output of a constant product swap after the LP fee, as computed by the Sonic pair
+pub fn get_amount_out(amount_in: &Nat, reserve_in: &Nat, reserve_out: &Nat) -> Nat {
+    if *amount_in == 0u8 || *reserve_in == 0u8 || *reserve_out == 0u8 {
+        return Nat::from(0u8);
+    }
+
+    let amount_in_with_fee = amount_in.clone() * Nat::from(SWAP_FEE_NUMERATOR);
+    let numerator = amount_in_with_fee.clone() * reserve_out.clone();
+    let denominator = reserve_in.clone() * Nat::from(SWAP_FEE_DENOMINATOR) + amount_in_with_fee;
+
+    numerator / denominator
+}
+
+/// Amount credited to a Sonic balance between two reads of it
+pub fn credited_amount(balance_before: &Nat, balance_after: &Nat) -> Nat {
+    if balance_after > balance_before {
+        balance_after.clone() - balance_before.clone()
+    } else {
+        Nat::from(0u8)
+    }
+}
+
+/// Reserves of the pair ordered as (token_a, token_b), None if the pair holds other tokens
+pub fn pair_reserves(pair: &PairInfoExt, token_a: CanisterId, token_b: CanisterId) -> Option<(Nat, Nat)> {
+    let token_a = token_a.to_text();
+    let token_b = token_b.to_text();
+
+    match (pair.token0.as_str(), pair.token1.as_str()) {
+        (t0, t1) if t0 == token_a && t1 == token_b => Some((pair.reserve0.clone(), pair.reserve1.clone())),
+        (t0, t1) if t0 == token_b && t1 == token_a => Some((pair.reserve1.clone(), pair.reserve0.clone())),
+        _ => None,
+    }
+}
+
+pub struct SonicSwapClient {
+    provider_impl: Arc<dyn SonicProvider + Send + Sync>,
+    canister_id: CanisterId,
+    token_in: CanisterId,
+    token_out: CanisterId,
+    limits: SwapLimits,
+}
+
+impl SonicSwapClient {
+    pub fn new(
+        provider_impl: Arc<dyn SonicProvider + Send + Sync>,
+        canister_id: CanisterId,
+        token_in: CanisterId,
+        token_out: CanisterId,
+    ) -> Self {
+        Self {
+            provider_impl,
+            canister_id,
+            token_in,
+            token_out,
+            limits: SwapLimits::default(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: SwapLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Current reserves of the pair ordered as (token_in, token_out)
+    async fn get_reserves(&self) -> Result<(Nat, Nat), InternalError> {
+        self.provider_impl.get_pair(self.token_in, self.token_out).await?
+            .and_then(|pair| pair_reserves(&pair, self.token_in, self.token_out))
+            .ok_or_else(|| InternalError::business_logic(
+                build_error_code(2006, 3, 1), // 2006 03 01
+                "SonicSwapClient::get_reserves".to_string(),
+                "No Sonic pair for the tokens".to_string(),
+                Some(HashMap::from([
+                    ("token_in".to_string(), self.token_in.to_text()),
+                    ("token_out".to_string(), self.token_out.to_text()),
+                ])),
+            ))
+    }
+
+    /// Deposits the approved amount, swaps it with the minimum output
+    /// and withdraws the output credited to the Sonic balance by the swap
+    async fn execute_swap(&self, amount_in: Nat, amount_out_min: Nat) -> Result<u128, InternalError> {
+        let canister_id = ic_cdk::id();
+
+        self.provider_impl.deposit(self.token_in, amount_in.clone()).await?;
+
+        let balance_before = self.provider_impl.balance_of(self.token_out, canister_id).await?;
+
+        self.provider_impl.swap_exact_tokens_for_tokens(
+            amount_in.clone(),
+            amount_out_min,
+            self.token_in,
+            self.token_out,
+            canister_id,
+        ).await?;
+
+        let balance_after = self.provider_impl.balance_of(self.token_out, canister_id).await?;
+        let swapped_amount = credited_amount(&balance_before, &balance_after);
+
+        // Sonic pays out the withdrawal with a ledger transfer, the fee is taken from the amount
+        let token_out_fee = token_registry::registry::fee(self.token_out).await?;
+        let received = ledger_fees::received_after_fee(&swapped_amount, &token_out_fee);
+
+        if received.amount == 0u8 {
+            return Err(InternalError::business_logic(
+                build_error_code(2006, 3, 2), // 2006 03 02
+                "SonicSwapClient::execute_swap".to_string(),
+                "Swapped amount does not cover the withdrawal fee".to_string(),
+                Some(HashMap::from([
+                    ("token_in".to_string(), self.token_in.to_text()),
+                    ("token_out".to_string(), self.token_out.to_text()),
+                    ("amount_in".to_string(), amount_in.to_string()),
+                    ("swapped_amount".to_string(), swapped_amount.to_string()),
+                    ("token_out_fee".to_string(), token_out_fee.to_string()),
+                ])),
+            ));
+        }
+
+        self.provider_impl.withdraw(self.token_out, swapped_amount).await?;
+
+        Ok(nat_to_u128(&received.amount))
+    }
+}
+
+#[async_trait]
+impl SwapClient for SonicSwapClient {
+    fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    async fn swap(&self, amount: Nat, min_amount_out: Option<u128>) -> Result<SwapSuccess, InternalError> {
+        let expected_amount_out = self.quote(amount.clone()).await?.amount_out;
+
+        swap_limits::ensure_price_impact(
+            self,
+            &self.limits,
+            nat_to_u128(&amount),
+            expected_amount_out,
+        ).await?;
+
+        // The pair rejects the swap if the output is below the slippage tolerance or the minimum output of the caller
+        let amount_out_min = Nat::from(swap_limits::amount_out_minimum(&self.limits, expected_amount_out, min_amount_out)?);
+        let amount_out = self.execute_swap(amount, amount_out_min).await?;
+
+        Ok(SwapSuccess {
+            amount_out,
+            withdrawal_success: Some(true),
+        })
+    }
+
+    async fn quote(&self, amount: Nat) -> Result<QuoteSuccess, InternalError> {
+        let (reserve_in, reserve_out) = self.get_reserves().await?;
+
+        Ok(QuoteSuccess {
+            amount_out: nat_to_u128(&get_amount_out(&amount, &reserve_in, &reserve_out)),
+        })
+    }
+
+    async fn quote_exact_output(&self, amount_out: Nat, max_amount_in: Nat) -> Result<QuoteExactOutputSuccess, InternalError> {
+        // The withdrawal of the output pays the ledger fee out of the swapped amount
+        let token_out_fee = token_registry::registry::fee(self.token_out).await?;
+        let swapped_amount_out = amount_out + token_out_fee.clone();
+
+        let amount_in = exact_output::find_amount_in(
+            self,
+            exact_output::buffered_amount_out(nat_to_u128(&swapped_amount_out)),
+            nat_to_u128(&max_amount_in),
+        ).await?;
+
+        let result = self.quote(Nat::from(amount_in)).await?;
+
+        Ok(QuoteExactOutputSuccess {
+            amount_in,
+            amount_out: nat_to_u128(&ledger_fees::received_after_fee(&Nat::from(result.amount_out), &token_out_fee).amount),
+        })
+    }
+
+    async fn swap_exact_output(&self, amount_out: Nat, max_amount_in: Nat) -> Result<SwapExactOutputSuccess, InternalError> {
+        let quote = self.quote_exact_output(amount_out.clone(), max_amount_in).await?;
+        let token_out_fee = token_registry::registry::fee(self.token_out).await?;
+
+        // The pair rejects the swap if the output is below the requested amount and the fee of its withdrawal
+        let received_amount_out = self.execute_swap(
+            Nat::from(quote.amount_in),
+            amount_out + token_out_fee,
+        ).await?;
+
+        Ok(SwapExactOutputSuccess {
+            amount_in: quote.amount_in,
+            amount_out: received_amount_out,
+            withdrawal_success: Some(true),
+        })
+    }
+}
+
+pub struct SonicSwapAdapter;
+
+#[async_trait]
+impl SwapAdapter for SonicSwapAdapter {
+    fn exchange_id(&self) -> ExchangeId {
+        ExchangeId::Sonic
+    }
+
+    async fn swap_client(
+        &self,
+        provider_impls: ProviderImpls,
+        token_in: CanisterId,
+        token_out: CanisterId,
+        limits: SwapLimits,
+    ) -> Result<Box<dyn SwapClient>, InternalError> {
+        Ok(Box::new(
+            SonicSwapClient::new(
+                provider_impls.get::<dyn SonicProvider + Send + Sync>(self.exchange_id())?,
+                *SONIC_SWAP_CANISTER_ID,
+                token_in,
+                token_out
+            ).with_limits(limits)
+        ))
+    }
+
+    /// Sonic lists all its pairs
+    async fn pools(&self, provider_impls: ProviderImpls, _tokens: &[CanisterId]) -> Vec<(CanisterId, CanisterId)> {
+        let Ok(provider) = provider_impls.get::<dyn SonicProvider + Send + Sync>(self.exchange_id()) else {
+            return Vec::new();
+        };
+
+        provider.get_all_pairs().await
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|pair| match (
+                CanisterId::from_text(&pair.token0),
+                CanisterId::from_text(&pair.token1),
+            ) {
+                (Ok(token0), Ok(token1)) => Some((token0, token1)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::token;
+    use candid::{Int, Principal};
+
+    fn pair(reserve0: u64, reserve1: u64) -> PairInfoExt {
+        PairInfoExt {
+            id: format!("{}:{}", token(1).to_text(), token(2).to_text()),
+            token0: token(1).to_text(),
+            token1: token(2).to_text(),
+            creator: Principal::anonymous(),
+            reserve0: Nat::from(reserve0),
+            reserve1: Nat::from(reserve1),
+            price0CumulativeLast: Nat::from(0u8),
+            price1CumulativeLast: Nat::from(0u8),
+            kLast: Nat::from(0u8),
+            blockTimestampLast: Int::from(0),
+            totalSupply: Nat::from(0u8),
+            lptoken: "LP".to_string(),
+        }
+    }
+
+    mod get_amount_out {
+        use super::*;
+
+        #[test]
+        fn applies_constant_product_and_fee() {
+            let amount_out = get_amount_out(&Nat::from(1_000u64), &Nat::from(100_000u64), &Nat::from(200_000u64));
+
+            // 1_000 * 997 * 200_000 / (100_000 * 1000 + 1_000 * 997)
+            assert_eq!(amount_out, Nat::from(1_974u64));
+        }
+
+        #[test]
+        fn is_zero_for_empty_reserves() {
+            assert_eq!(get_amount_out(&Nat::from(1_000u64), &Nat::from(0u8), &Nat::from(200_000u64)), Nat::from(0u8));
+        }
+    }
+
+    mod credited_amount {
+        use super::*;
+
+        #[test]
+        fn is_increase_of_balance() {
+            assert_eq!(credited_amount(&Nat::from(100u64), &Nat::from(350u64)), Nat::from(250u64));
+        }
+
+        #[test]
+        fn is_zero_if_balance_did_not_grow() {
+            assert_eq!(credited_amount(&Nat::from(100u64), &Nat::from(100u64)), Nat::from(0u8));
+            assert_eq!(credited_amount(&Nat::from(100u64), &Nat::from(40u64)), Nat::from(0u8));
+        }
+    }
+
+    mod pair_reserves {
+        use super::*;
+
+        #[test]
+        fn orders_reserves_by_tokens() {
+            let pair = pair(100, 200);
+
+            assert_eq!(pair_reserves(&pair, token(1), token(2)), Some((Nat::from(100u64), Nat::from(200u64))));
+            assert_eq!(pair_reserves(&pair, token(2), token(1)), Some((Nat::from(200u64), Nat::from(100u64))));
+            assert_eq!(pair_reserves(&pair, token(1), token(3)), None);
+        }
+    }
+}
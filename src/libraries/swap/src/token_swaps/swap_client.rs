@@ -8,8 +8,14 @@ use errors::internal_error::error::InternalError;
 #[async_trait]
 pub trait SwapClient: Send + Sync + 'static {
     fn canister_id(&self) -> CanisterId;
-    async fn swap(&self, amount: Nat) -> Result<SwapSuccess, InternalError>;
+    /// Swaps `amount`, the exchange pays out at least the quote minus the slippage tolerance
+    /// and at least `min_amount_out` if it is given
+    async fn swap(&self, amount: Nat, min_amount_out: Option<u128>) -> Result<SwapSuccess, InternalError>;
     async fn quote(&self, amount: Nat) -> Result<QuoteSuccess, InternalError>;
+    /// Quotes the input amount needed to receive `amount_out`, not exceeding `max_amount_in`
+    async fn quote_exact_output(&self, amount_out: Nat, max_amount_in: Nat) -> Result<QuoteExactOutputSuccess, InternalError>;
+    /// Swaps the input amount needed to receive at least `amount_out`, not exceeding `max_amount_in`
+    async fn swap_exact_output(&self, amount_out: Nat, max_amount_in: Nat) -> Result<SwapExactOutputSuccess, InternalError>;
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -22,3 +28,16 @@ pub struct SwapSuccess {
 pub struct QuoteSuccess {
     pub amount_out: u128,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QuoteExactOutputSuccess {
+    pub amount_in: u128,
+    pub amount_out: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SwapExactOutputSuccess {
+    pub amount_in: u128,
+    pub amount_out: u128,
+    pub withdrawal_success: Option<bool>,
+}
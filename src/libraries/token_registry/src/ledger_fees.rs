@@ -0,0 +1,266 @@
+use candid::Nat;
+use std::collections::HashMap;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use types::CanisterId;
+
+use crate::registry;
+
+/// Ledger fees paid to move tokens to an exchange with an ICRC-2 approval:
+/// one for the approval, one for the transfer made by the exchange
+/// and one for revoking the allowance if the exchange does not spend all of it
+pub const APPROVED_TRANSFER_FEE_COUNT: u32 = 3;
+
+/// Ledger fees paid for a direct ICRC-1 transfer
+pub const TRANSFER_FEE_COUNT: u32 = 1;
+
+/// Part of the available balance which can be moved and the ledger fees paid for moving it
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeDeduction {
+    pub amount: Nat,
+    pub fee: Nat,
+    pub fees_paid: Nat,
+}
+
+/// Splits the available balance into the amount to move and `fee_count` ledger fees,
+/// so the amount and the fees together never exceed the balance.
+/// None if the fees take the whole balance.
+pub fn deduct_fees(available: &Nat, fee: &Nat, fee_count: u32) -> Option<FeeDeduction> {
+    let fees_paid = fee.clone() * Nat::from(fee_count);
+
+    if *available <= fees_paid {
+        return None;
+    }
+
+    Some(FeeDeduction {
+        amount: available.clone() - fees_paid.clone(),
+        fee: fee.clone(),
+        fees_paid,
+    })
+}
+
+/// Amount actually received from a transfer whose fee is taken from the transferred amount.
+/// A transfer which does not cover the fee is not made, so nothing is received and no fee is paid.
+pub fn received_after_fee(amount: &Nat, fee: &Nat) -> FeeDeduction {
+    deduct_fees(amount, fee, TRANSFER_FEE_COUNT).unwrap_or(FeeDeduction {
+        amount: Nat::from(0u8),
+        fee: fee.clone(),
+        fees_paid: Nat::from(0u8),
+    })
+}
+
+/// Deducts `fee_count` fees of the ledger, taken from the registry, from the available balance
+pub async fn deduct_ledger_fees(
+    ledger_canister_id: CanisterId,
+    available: &Nat,
+    fee_count: u32,
+) -> Result<FeeDeduction, InternalError> {
+    let fee = registry::fee(ledger_canister_id).await?;
+
+    deduct_fees(available, &fee, fee_count).ok_or_else(|| {
+        InternalError::business_logic(
+            build_error_code(1300, 3, 2), // 1300 03 02
+            "ledger_fees::deduct_ledger_fees".to_string(),
+            "Amount does not cover the ledger fees".to_string(),
+            Some(HashMap::from([
+                ("ledger_canister_id".to_string(), ledger_canister_id.to_text()),
+                ("available".to_string(), available.to_string()),
+                ("fee".to_string(), fee.to_string()),
+                ("fee_count".to_string(), fee_count.to_string()),
+            ])),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod deduct_fees {
+        use super::*;
+
+        #[test]
+        fn deducts_every_fee() {
+            let deduction = deduct_fees(&Nat::from(1_000u64), &Nat::from(10u64), APPROVED_TRANSFER_FEE_COUNT).unwrap();
+
+            assert_eq!(deduction.amount, Nat::from(970u64));
+            assert_eq!(deduction.fees_paid, Nat::from(30u64));
+        }
+
+        #[test]
+        fn rejects_balance_not_covering_fees() {
+            assert_eq!(deduct_fees(&Nat::from(30u64), &Nat::from(10u64), APPROVED_TRANSFER_FEE_COUNT), None);
+            assert_eq!(deduct_fees(&Nat::from(5u64), &Nat::from(10u64), TRANSFER_FEE_COUNT), None);
+        }
+
+        #[test]
+        fn never_spends_more_than_available() {
+            for available in [1u64, 11, 21, 999, 10_000, 123_456_789] {
+                for fee in [0u64, 1, 10, 10_000] {
+                    for fee_count in [TRANSFER_FEE_COUNT, APPROVED_TRANSFER_FEE_COUNT] {
+                        let available = Nat::from(available);
+
+                        if let Some(deduction) = deduct_fees(&available, &Nat::from(fee), fee_count) {
+                            assert_eq!(deduction.amount.clone() + deduction.fees_paid, available);
+                            assert!(deduction.amount > 0u8);
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn receives_nothing_from_transfer_below_fee() {
+            assert_eq!(received_after_fee(&Nat::from(100u64), &Nat::from(10u64)).amount, Nat::from(90u64));
+            assert_eq!(received_after_fee(&Nat::from(10u64), &Nat::from(10u64)).amount, Nat::from(0u8));
+            assert_eq!(received_after_fee(&Nat::from(10u64), &Nat::from(10u64)).fees_paid, Nat::from(0u8));
+        }
+    }
+
+    mod deposit_flow {
+        use super::*;
+
+        /// Vault account on an ICRC-2 ledger, charging the fees the way the ledger does
+        struct Ledger {
+            balance: Nat,
+            allowance: Nat,
+            fee: Nat,
+            fees_paid: Nat,
+        }
+
+        impl Ledger {
+            fn new(balance: u64, fee: u64) -> Self {
+                Self {
+                    balance: Nat::from(balance),
+                    allowance: Nat::from(0u8),
+                    fee: Nat::from(fee),
+                    fees_paid: Nat::from(0u8),
+                }
+            }
+
+            fn charge(&mut self, amount: Nat) -> Result<(), String> {
+                if self.balance < amount {
+                    return Err(format!("InsufficientFunds: balance {}, charged {}", self.balance, amount));
+                }
+
+                self.balance -= amount;
+                Ok(())
+            }
+
+            fn pay_fee(&mut self) -> Result<(), String> {
+                self.charge(self.fee.clone())?;
+                self.fees_paid += self.fee.clone();
+                Ok(())
+            }
+
+            /// Adds `amount` plus the transfer fee to the allowance, as `icrc2_approve` does
+            fn approve(&mut self, amount: &Nat) -> Result<(), String> {
+                self.pay_fee()?;
+                self.allowance += amount.clone() + self.fee.clone();
+                Ok(())
+            }
+
+            /// Transfer made by the exchange, taken from the balance and the allowance
+            fn transfer_from(&mut self, amount: &Nat) -> Result<(), String> {
+                let spent = amount.clone() + self.fee.clone();
+                if self.allowance < spent {
+                    return Err("InsufficientAllowance".to_string());
+                }
+
+                self.charge(amount.clone())?;
+                self.pay_fee()?;
+                self.allowance -= spent;
+                Ok(())
+            }
+
+            /// Revokes the leftover allowance, as `icrc2_revoke` does
+            fn revoke(&mut self) -> Result<(), String> {
+                if self.allowance > 0u8 {
+                    self.pay_fee()?;
+                    self.allowance = Nat::from(0u8);
+                }
+                Ok(())
+            }
+        }
+
+        /// Moves every part of the deposit to the exchange with an approval and revokes
+        /// the leftover allowances afterwards. `spent_by_exchange` tells how much of each
+        /// deducted amount the exchange actually transfers.
+        fn deposit(ledger: &mut Ledger, parts: &[u64], mut spent_by_exchange: impl FnMut(&Nat) -> Nat) -> Result<Nat, String> {
+            let mut moved = Nat::from(0u8);
+            let mut reserved_fees = Nat::from(0u8);
+
+            for part in parts {
+                let deduction = deduct_fees(&Nat::from(*part), &ledger.fee, APPROVED_TRANSFER_FEE_COUNT)
+                    .ok_or("Part does not cover the fees")?;
+
+                ledger.approve(&deduction.amount)?;
+
+                let transferred = spent_by_exchange(&deduction.amount);
+                if transferred > 0u8 {
+                    ledger.transfer_from(&transferred)?;
+                }
+
+                moved += transferred;
+                reserved_fees += deduction.fees_paid;
+            }
+
+            ledger.revoke()?;
+
+            assert!(ledger.fees_paid <= reserved_fees);
+            Ok(moved)
+        }
+
+        #[test]
+        fn pays_approvals_and_transfers_within_deposited_amount() {
+            let mut ledger = Ledger::new(100_000, 10);
+
+            let moved = deposit(&mut ledger, &[50_000, 50_000], |amount| amount.clone()).unwrap();
+
+            assert_eq!(moved, Nat::from(99_940u64));
+            assert_eq!(ledger.fees_paid, Nat::from(40u64));
+            assert_eq!(moved + ledger.fees_paid.clone() + ledger.balance.clone(), Nat::from(100_000u64));
+        }
+
+        #[test]
+        fn pays_revocation_of_unspent_allowance_within_deposited_amount() {
+            let mut ledger = Ledger::new(100_000, 10);
+
+            // The exchange fails before pulling the pool part, its allowance has to be revoked
+            let mut calls = 0;
+            let moved = deposit(&mut ledger, &[50_000, 50_000], |amount| {
+                calls += 1;
+                if calls == 1 { amount.clone() } else { Nat::from(0u8) }
+            });
+
+            let moved = moved.unwrap();
+            assert_eq!(moved, Nat::from(49_970u64));
+            assert_eq!(ledger.fees_paid, Nat::from(40u64));
+            assert_eq!(moved + ledger.fees_paid.clone() + ledger.balance.clone(), Nat::from(100_000u64));
+        }
+
+        #[test]
+        fn never_overdraws_balance() {
+            for fee in [0u64, 1, 10, 10_000] {
+                for parts in [vec![100_000u64], vec![50_000, 50_000], vec![1, 99_999], vec![33_333, 33_333, 33_334]] {
+                    for exchange_spends_all in [true, false] {
+                        let mut ledger = Ledger::new(100_000, fee);
+
+                        // Parts which do not cover their fees are not moved at all
+                        let parts: Vec<u64> = parts.iter()
+                            .copied()
+                            .filter(|part| *part > fee * APPROVED_TRANSFER_FEE_COUNT as u64)
+                            .collect();
+
+                        let result = deposit(&mut ledger, &parts, |amount| {
+                            if exchange_spends_all { amount.clone() } else { amount.clone() / 2u8 }
+                        });
+
+                        assert!(result.is_ok(), "fee {}, parts {:?}: {:?}", fee, parts, result);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod token_metadata;
+pub mod registry;
+pub mod ledger_fees;
@@ -0,0 +1,90 @@
+use candid::Nat;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use errors::internal_error::error::InternalError;
+use types::CanisterId;
+use utils::util::current_timestamp;
+use utils::constants::{CKUSDT_TOKEN_CANISTER_ID, ICP_TOKEN_CANISTER_ID};
+
+use crate::token_metadata::TokenMetadata;
+
+/// Cached metadata is fetched again from the ledger after this time
+pub const METADATA_TTL: u64 = 86_400; // 1 day
+
+thread_local! {
+    static TOKENS: RefCell<HashMap<CanisterId, TokenMetadata>> = RefCell::new(HashMap::new());
+}
+
+/// Metadata of the token, fetched from the ledger if it is not cached or stale.
+/// If the ledger can not be reached, stale metadata is returned as is.
+pub async fn get_token(canister_id: CanisterId) -> Result<TokenMetadata, InternalError> {
+    let cached = get_cached_token(canister_id);
+
+    match cached {
+        Some(token) if !token.is_stale(current_timestamp(), METADATA_TTL) => Ok(token),
+        Some(token) => Ok(refresh_token(canister_id).await.unwrap_or(token)),
+        None => refresh_token(canister_id).await,
+    }
+}
+
+pub async fn decimals(canister_id: CanisterId) -> Result<u8, InternalError> {
+    Ok(get_token(canister_id).await?.decimals)
+}
+
+pub async fn fee(canister_id: CanisterId) -> Result<Nat, InternalError> {
+    Ok(get_token(canister_id).await?.fee)
+}
+
+/// Stablecoin the USD values of pools and positions are expressed in
+pub async fn usd_token() -> Result<TokenMetadata, InternalError> {
+    get_token(*CKUSDT_TOKEN_CANISTER_ID).await
+}
+
+/// Tokens routes through intermediate tokens may hop through
+pub fn hub_tokens() -> Vec<CanisterId> {
+    vec![*ICP_TOKEN_CANISTER_ID, *CKUSDT_TOKEN_CANISTER_ID]
+}
+
+/// Fetches the metadata from the ledger and stores it in the registry
+pub async fn add_token(canister_id: CanisterId) -> Result<TokenMetadata, InternalError> {
+    refresh_token(canister_id).await
+}
+
+/// Fetches the metadata of every registered token again.
+/// Tokens which fail to refresh keep their cached metadata.
+pub async fn refresh_tokens() -> Vec<TokenMetadata> {
+    let canister_ids: Vec<CanisterId> = TOKENS.with(|tokens| tokens.borrow().keys().copied().collect());
+
+    for canister_id in canister_ids {
+        let _ = refresh_token(canister_id).await;
+    }
+
+    get_tokens()
+}
+
+pub fn get_cached_token(canister_id: CanisterId) -> Option<TokenMetadata> {
+    TOKENS.with(|tokens| tokens.borrow().get(&canister_id).cloned())
+}
+
+/// Replaces the registry with the tokens restored from stable memory
+pub fn set_tokens(tokens: Vec<TokenMetadata>) {
+    TOKENS.with(|cell| {
+        cell.replace(tokens.into_iter().map(|token| (token.canister_id, token)).collect());
+    });
+}
+
+pub fn get_tokens() -> Vec<TokenMetadata> {
+    let mut tokens: Vec<TokenMetadata> = TOKENS.with(|tokens| tokens.borrow().values().cloned().collect());
+    tokens.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    tokens
+}
+
+async fn refresh_token(canister_id: CanisterId) -> Result<TokenMetadata, InternalError> {
+    let metadata = icrc_ledger_client::icrc1_metadata(canister_id).await?;
+    let token = TokenMetadata::from_icrc1_metadata(canister_id, metadata, current_timestamp())?;
+
+    TOKENS.with(|tokens| tokens.borrow_mut().insert(canister_id, token.clone()));
+
+    Ok(token)
+}
@@ -0,0 +1,146 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use types::CanisterId;
+
+const DECIMALS_KEY: &str = "icrc1:decimals";
+const FEE_KEY: &str = "icrc1:fee";
+const SYMBOL_KEY: &str = "icrc1:symbol";
+const NAME_KEY: &str = "icrc1:name";
+const LOGO_KEY: &str = "icrc1:logo";
+
+/// ICRC-1 metadata of a token ledger
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TokenMetadata {
+    pub canister_id: CanisterId,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    pub fee: Nat,
+    pub logo: Option<String>,
+    /// Time of the last fetch from the ledger, in seconds
+    pub updated_at: u64,
+}
+
+impl TokenMetadata {
+    /// Builds the metadata from the `icrc1_metadata` response of the ledger.
+    /// Decimals, fee and symbol are required, the name falls back to the symbol.
+    pub fn from_icrc1_metadata(
+        canister_id: CanisterId,
+        metadata: Vec<(String, MetadataValue)>,
+        updated_at: u64,
+    ) -> Result<Self, InternalError> {
+        let values: HashMap<String, MetadataValue> = metadata.into_iter().collect();
+
+        let missing_key_error = |key: &str| InternalError::business_logic(
+            build_error_code(1300, 3, 1), // 1300 03 01
+            "TokenMetadata::from_icrc1_metadata".to_string(),
+            "Token metadata is missing a required value".to_string(),
+            Some(HashMap::from([
+                ("canister_id".to_string(), canister_id.to_text()),
+                ("key".to_string(), key.to_string()),
+            ])),
+        );
+
+        let decimals = match values.get(DECIMALS_KEY) {
+            Some(MetadataValue::Nat(decimals)) => u8::try_from(decimals.0.clone())
+                .map_err(|_| missing_key_error(DECIMALS_KEY))?,
+            _ => return Err(missing_key_error(DECIMALS_KEY)),
+        };
+
+        let fee = match values.get(FEE_KEY) {
+            Some(MetadataValue::Nat(fee)) => fee.clone(),
+            _ => return Err(missing_key_error(FEE_KEY)),
+        };
+
+        let symbol = match values.get(SYMBOL_KEY) {
+            Some(MetadataValue::Text(symbol)) => symbol.clone(),
+            _ => return Err(missing_key_error(SYMBOL_KEY)),
+        };
+
+        let name = match values.get(NAME_KEY) {
+            Some(MetadataValue::Text(name)) => name.clone(),
+            _ => symbol.clone(),
+        };
+
+        let logo = match values.get(LOGO_KEY) {
+            Some(MetadataValue::Text(logo)) => Some(logo.clone()),
+            _ => None,
+        };
+
+        Ok(Self {
+            canister_id,
+            symbol,
+            name,
+            decimals,
+            fee,
+            logo,
+            updated_at,
+        })
+    }
+
+    pub fn is_stale(&self, now: u64, ttl: u64) -> bool {
+        now.saturating_sub(self.updated_at) > ttl
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::token;
+
+    fn metadata() -> Vec<(String, MetadataValue)> {
+        vec![
+            (DECIMALS_KEY.to_string(), MetadataValue::Nat(Nat::from(8u8))),
+            (FEE_KEY.to_string(), MetadataValue::Nat(Nat::from(10_000u64))),
+            (SYMBOL_KEY.to_string(), MetadataValue::Text("ICP".to_string())),
+            (NAME_KEY.to_string(), MetadataValue::Text("Internet Computer".to_string())),
+        ]
+    }
+
+    mod from_icrc1_metadata {
+        use super::*;
+
+        #[test]
+        fn parses_standard_values() {
+            let token_metadata = TokenMetadata::from_icrc1_metadata(token(1), metadata(), 100).unwrap();
+
+            assert_eq!(token_metadata.decimals, 8);
+            assert_eq!(token_metadata.fee, Nat::from(10_000u64));
+            assert_eq!(token_metadata.symbol, "ICP");
+            assert_eq!(token_metadata.name, "Internet Computer");
+            assert_eq!(token_metadata.logo, None);
+            assert_eq!(token_metadata.updated_at, 100);
+        }
+
+        #[test]
+        fn rejects_missing_decimals() {
+            let metadata = metadata().into_iter().filter(|(key, _)| key != DECIMALS_KEY).collect();
+
+            assert!(TokenMetadata::from_icrc1_metadata(token(1), metadata, 100).is_err());
+        }
+
+        #[test]
+        fn falls_back_to_symbol_for_name() {
+            let metadata = metadata().into_iter().filter(|(key, _)| key != NAME_KEY).collect();
+
+            assert_eq!(TokenMetadata::from_icrc1_metadata(token(1), metadata, 100).unwrap().name, "ICP");
+        }
+    }
+
+    mod is_stale {
+        use super::*;
+
+        #[test]
+        fn compares_age_with_ttl() {
+            let token_metadata = TokenMetadata::from_icrc1_metadata(token(1), metadata(), 100).unwrap();
+
+            assert!(!token_metadata.is_stale(150, 50));
+            assert!(token_metadata.is_stale(151, 50));
+        }
+    }
+}
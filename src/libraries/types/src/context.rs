@@ -1,29 +1,159 @@
+use std::cell::Cell;
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 use candid::Principal;
+use ic_cdk::api::call::CallResult;
+use ic_cdk::api::management_canister::main::raw_rand;
 
 pub type CorrelationId = String;
+pub type SpanId = String;
+
+thread_local! {
+    /// Random per install or upgrade, so that ids do not repeat when the counter restarts
+    static ID_SEED: Cell<Option<u64>> = const { Cell::new(None) };
+    static ID_COUNTER: Cell<u64> = const { Cell::new(0) };
+}
 
 #[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
 pub struct Context {
     pub correlation_id: CorrelationId,
     pub user: Option<Principal>,
+    /// Operation this context belongs to
+    pub span_id: SpanId,
+    /// Operation which started this one, none for an operation started by a user or a timer
+    pub parent_span_id: Option<SpanId>,
+}
+
+/// Part of a context passed along with a call to another canister,
+/// so that its operations share the correlation id of the calling operation
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TraceContext {
+    pub correlation_id: CorrelationId,
+    pub span_id: SpanId,
 }
 
 impl Context {
     pub fn new(correlation_id: CorrelationId, user: Option<Principal>) -> Self {
-        Self { correlation_id, user }
+        Self {
+            correlation_id,
+            user,
+            span_id: generate_id(),
+            parent_span_id: None,
+        }
     }
 
     pub fn generate(user: Option<Principal>) -> Self {
+        Self::new(generate_id(), user)
+    }
+
+    /// Context of an operation started by a call from another canister
+    pub fn from_trace(trace: TraceContext, user: Option<Principal>) -> Self {
         Self {
-            correlation_id: Self::generate_correlation_id(),
+            correlation_id: trace.correlation_id,
             user,
+            span_id: generate_id(),
+            parent_span_id: Some(trace.span_id),
+        }
+    }
+
+    /// Context of an operation started by this one
+    pub fn child(&self) -> Self {
+        Self {
+            correlation_id: self.correlation_id.clone(),
+            user: self.user,
+            span_id: generate_id(),
+            parent_span_id: Some(self.span_id.clone()),
+        }
+    }
+
+    /// Context to pass along with a call to another canister
+    pub fn trace(&self) -> TraceContext {
+        TraceContext {
+            correlation_id: self.correlation_id.clone(),
+            span_id: self.span_id.clone(),
+        }
+    }
+}
+
+/// Seeds correlation and span ids with randomness from the management canister.
+/// Call it after install and upgrade; ids generated before are seeded with the time of the first one.
+pub async fn seed_ids() -> CallResult<()> {
+    let (random_bytes,) = raw_rand().await?;
+
+    let mut seed = [0u8; 8];
+    seed.copy_from_slice(&random_bytes[..8]);
+    set_id_seed(u64::from_le_bytes(seed));
+
+    Ok(())
+}
+
+pub fn set_id_seed(seed: u64) {
+    ID_SEED.with(|cell| cell.set(Some(seed)));
+}
+
+/// Ids are unique within a seed, as the counter is increased on every id
+fn generate_id() -> String {
+    let seed = ID_SEED.with(|cell| {
+        cell.get().unwrap_or_else(|| {
+            let seed = ic_cdk::api::time();
+            cell.set(Some(seed));
+            seed
+        })
+    });
+    let counter = ID_COUNTER.with(|cell| cell.replace(cell.get() + 1));
+
+    format!("{:016x}-{:x}", seed, counter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod generate {
+        use super::*;
+
+        #[test]
+        fn generates_unique_correlation_ids() {
+            set_id_seed(42);
+
+            let first = Context::generate(None);
+            let second = Context::generate(None);
+
+            assert_ne!(first.correlation_id, second.correlation_id);
+            assert_ne!(first.span_id, second.span_id);
+            assert!(first.correlation_id.starts_with("000000000000002a-"));
+            assert_eq!(first.parent_span_id, None);
         }
     }
 
-    fn generate_correlation_id() -> String {
-        // TODO: replace with uuid or another library
-        ic_cdk::api::time().to_string()
+    mod child {
+        use super::*;
+
+        #[test]
+        fn shares_correlation_id_and_links_to_parent_span() {
+            set_id_seed(42);
+
+            let parent = Context::generate(None);
+            let child = parent.child();
+
+            assert_eq!(child.correlation_id, parent.correlation_id);
+            assert_eq!(child.parent_span_id, Some(parent.span_id.clone()));
+            assert_ne!(child.span_id, parent.span_id);
+        }
+    }
+
+    mod from_trace {
+        use super::*;
+
+        #[test]
+        fn continues_trace_of_calling_canister() {
+            set_id_seed(42);
+
+            let caller_context = Context::generate(None);
+            let context = Context::from_trace(caller_context.trace(), None);
+
+            assert_eq!(context.correlation_id, caller_context.correlation_id);
+            assert_eq!(context.parent_span_id, Some(caller_context.span_id));
+        }
     }
 }
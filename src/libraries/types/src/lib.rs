@@ -7,6 +7,7 @@ pub mod liquidity;
 pub mod pool;
 pub mod pool_stats;
 pub mod context;
+pub mod test_utils;
 
 use candid::{CandidType, Principal};
 use ic_ledger_types::Tokens;
@@ -1,10 +1,15 @@
 use candid::{CandidType, Nat};
 use serde::{Deserialize, Serialize};
 
+use crate::exchange_id::ExchangeId;
+use crate::CanisterId;
+
 #[derive(CandidType, Deserialize, Clone, Debug, Serialize)]
 pub struct WithdrawLiquidityResponse {
     pub token_0_amount: Nat,
     pub token_1_amount: Nat,
+    /// Ledger fees of the transfers from the exchange, already deducted from the amounts
+    pub ledger_fees: LiquidityFees,
 }
 
 #[derive(CandidType, Deserialize, Clone, Debug, Serialize)]
@@ -12,6 +17,8 @@ pub struct AddLiquidityResponse {
     pub token_0_amount: Nat,
     pub token_1_amount: Nat,
     pub position_id: u64,
+    /// Ledger fees of the approvals and transfers to the exchange, not included in the amounts
+    pub ledger_fees: LiquidityFees,
 }
 
 #[derive(CandidType, Deserialize, Clone, Serialize)]
@@ -33,3 +40,36 @@ pub struct GetPositionByIdResponse {
 pub struct GetPoolDataResponse {
     pub tvl: Nat,
 }
+
+#[derive(CandidType, Deserialize, Clone, Debug, Serialize, Default)]
+pub struct LiquidityFees {
+    pub token_0_fee: Nat,
+    pub token_1_fee: Nat,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Serialize)]
+pub struct QuoteAddLiquidityResponse {
+    pub token_0_for_swap: Nat,
+    pub token_0_for_pool: Nat,
+    pub token_1_for_pool: Nat,
+    pub swap_provider: ExchangeId,
+    pub fees: LiquidityFees,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Serialize)]
+pub struct QuoteWithdrawLiquidityResponse {
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+    pub fees: LiquidityFees,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Serialize)]
+pub struct MigrateLiquidityResponse {
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+    pub token_in: Option<CanisterId>,
+    pub swap_amount_in: Nat,
+    pub naive_swap_volume: Nat,
+    pub saved_swap_volume: Nat,
+    pub position_id: u64,
+}
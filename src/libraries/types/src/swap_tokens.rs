@@ -59,14 +59,159 @@ pub enum Response {
     InternalError(String),
 }
 
-#[derive(CandidType, Serialize, Deserialize, Debug)]
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub struct SwapResponse {
     pub provider: ExchangeId,
     pub amount_out: u128,
+    pub route: SwapRoute,
+    /// Ledger fees paid in the input token to move it to the exchanges
+    pub ledger_fee: u128,
 }
 
-#[derive(CandidType, Serialize, Deserialize, Debug)]
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub struct QuoteResponse {
     pub provider: ExchangeId,
     pub amount_out: u128,
+    pub route: SwapRoute,
+}
+
+/// Part of the order executed on a single exchange
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SwapRouteLeg {
+    pub provider: ExchangeId,
+    pub amount_in: u128,
+    pub amount_out: u128,
+}
+
+/// Split of the order between exchanges.
+/// `unavailable_providers` lists the exchanges skipped because their quote or swap failed.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SwapRoute {
+    pub legs: Vec<SwapRouteLeg>,
+    pub unavailable_providers: Vec<ExchangeId>,
+}
+
+impl SwapRoute {
+    pub fn single(provider: ExchangeId, amount_in: u128, amount_out: u128) -> Self {
+        Self {
+            legs: vec![SwapRouteLeg { provider, amount_in, amount_out }],
+            unavailable_providers: Vec::new(),
+        }
+    }
+
+    pub fn amount_in(&self) -> u128 {
+        self.legs.iter().map(|leg| leg.amount_in).sum()
+    }
+
+    pub fn amount_out(&self) -> u128 {
+        self.legs.iter().map(|leg| leg.amount_out).sum()
+    }
+
+    /// Provider executing the largest part of the order
+    pub fn main_provider(&self) -> Option<ExchangeId> {
+        self.legs.iter().max_by_key(|leg| leg.amount_in).map(|leg| leg.provider)
+    }
+}
+
+/// Single swap of a multi-hop route
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SwapHop {
+    pub token_in: CanisterId,
+    pub token_out: CanisterId,
+    pub amount_in: u128,
+    pub amount_out: u128,
+    pub route: SwapRoute,
+    /// Ledger fees paid in `token_in`, zero for a quoted hop
+    pub ledger_fee: u128,
+}
+
+/// Route through intermediate tokens. `path` starts with the input token and ends with the output token.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MultiHopRoute {
+    pub path: Vec<CanisterId>,
+    pub hops: Vec<SwapHop>,
+    pub amount_in: u128,
+    pub amount_out: u128,
+}
+
+impl MultiHopRoute {
+    /// Provider executing the largest part of the first hop
+    pub fn main_provider(&self) -> Option<ExchangeId> {
+        self.hops.first().and_then(|hop| hop.route.main_provider())
+    }
+}
+
+/// Default slippage tolerance, 500 basis points == 5%
+pub const DEFAULT_SLIPPAGE_TOLERANCE_BPS: u32 = 500;
+/// Default maximum price impact, 1000 basis points == 10%
+pub const DEFAULT_MAX_PRICE_IMPACT_BPS: u32 = 1_000;
+/// Default band around the reference price, 300 basis points == 3%
+pub const DEFAULT_MAX_PRICE_DEVIATION_BPS: u32 = 300;
+pub const BPS_DENOMINATOR: u32 = 10_000;
+
+/// Protection limits of a swap in basis points
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SwapLimits {
+    pub slippage_tolerance_bps: u32,
+    pub max_price_impact_bps: u32,
+    pub max_price_deviation_bps: u32,
+}
+
+impl Default for SwapLimits {
+    fn default() -> Self {
+        Self {
+            slippage_tolerance_bps: DEFAULT_SLIPPAGE_TOLERANCE_BPS,
+            max_price_impact_bps: DEFAULT_MAX_PRICE_IMPACT_BPS,
+            max_price_deviation_bps: DEFAULT_MAX_PRICE_DEVIATION_BPS,
+        }
+    }
+}
+
+impl SwapLimits {
+    /// Minimum output accepted for the expected output
+    pub fn min_amount_out(&self, expected_amount_out: u128) -> u128 {
+        let tolerance_bps = self.slippage_tolerance_bps.min(BPS_DENOMINATOR) as u128;
+        let denominator = BPS_DENOMINATOR as u128;
+
+        // Split to avoid overflow of large amounts
+        expected_amount_out / denominator * (denominator - tolerance_bps)
+            + expected_amount_out % denominator * (denominator - tolerance_bps) / denominator
+    }
+
+    /// Slippage tolerance as a percentage, as KongSwap expects it
+    pub fn slippage_tolerance_percentage(&self) -> f64 {
+        self.slippage_tolerance_bps as f64 / 100.0
+    }
+}
+
+/// Where the reference price of a price guard check comes from
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum PriceReferenceSource {
+    Exchange(ExchangeId),
+    PoolSnapshot { pool_id: String, timestamp: u64 },
+}
+
+/// Price of the output token per input token used as the reference of a quote
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PriceReference {
+    pub source: PriceReferenceSource,
+    pub price: f64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PriceGuardDecision {
+    Passed,
+    Rejected,
+    /// No reference price is available, the quote is not checked
+    NoReference,
+}
+
+/// Result of comparing the quote of the execution exchange against the reference price
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PriceGuardCheck {
+    pub quoted_price: f64,
+    pub reference: Option<PriceReference>,
+    pub deviation_bps: Option<u32>,
+    pub max_deviation_bps: u32,
+    pub decision: PriceGuardDecision,
 }
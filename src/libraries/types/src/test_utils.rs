@@ -0,0 +1,8 @@
+use candid::Principal;
+
+use crate::CanisterId;
+
+/// Canister id made of the repeated byte, for tokens and pools in tests
+pub fn token(byte: u8) -> CanisterId {
+    Principal::from_slice(&[byte; 29])
+}
@@ -25,6 +25,9 @@ pub const ICPSWAP_GLOBAL_INDEX_PRINCIPAL: &str = "gp26j-lyaaa-aaaag-qck6q-cai";
 // KONGSWAP PRINCIPALS
 pub const KONGSWAP_CANISTER_PRINCIPAL: &str = "2ipq2-uqaaa-aaaar-qailq-cai";
 
+// SONIC PRINCIPALS
+pub const SONIC_SWAP_PRINCIPAL: &str = "3xwpq-ziaaa-aaaah-qcn4a-cai";
+
 // ================= CANISTER IDS =================
 
 // POOL STATS CANISTER ID
@@ -59,3 +62,7 @@ pub static ICPSWAP_GLOBAL_INDEX_CANISTER_ID: Lazy<CanisterId> =
 // KONGSWAP CANISTER IDS
 pub static KONGSWAP_CANISTER_ID: Lazy<CanisterId> =
     Lazy::new(|| principal_to_canister_id(KONGSWAP_CANISTER_PRINCIPAL));
+
+// SONIC CANISTER IDS
+pub static SONIC_SWAP_CANISTER_ID: Lazy<CanisterId> =
+    Lazy::new(|| principal_to_canister_id(SONIC_SWAP_PRINCIPAL));
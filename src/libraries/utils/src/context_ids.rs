@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use types::context;
+
+/// Seeds correlation and span ids once init or upgrade is done, as the management canister
+/// can not be called from them. When seeding fails the ids stay seeded with the time
+/// and `on_error` is called with the error.
+pub fn schedule_context_ids_seeding(on_error: fn(InternalError)) {
+    ic_cdk_timers::set_timer(Duration::ZERO, move || ic_cdk::spawn(async move {
+        if let Err((code, message)) = context::seed_ids().await {
+            on_error(InternalError::external_service(
+                build_error_code(1200, 4, 3), // 1200 04 03
+                "context_ids::schedule_context_ids_seeding".to_string(),
+                format!("IC error calling 'raw_rand': {code:?} {message}"),
+                None,
+            ));
+        }
+    }));
+}
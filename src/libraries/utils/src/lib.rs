@@ -2,3 +2,4 @@ pub mod util;
 pub mod constants;
 pub mod token_transfer;
 pub mod environment;
+pub mod context_ids;
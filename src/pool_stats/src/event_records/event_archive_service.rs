@@ -0,0 +1,79 @@
+use std::time::Duration;
+use std::cell::RefCell;
+use candid::Principal;
+use ic_cdk_timers::TimerId;
+
+use errors::internal_error::error::InternalError;
+use event_records::archive::{locate_event_records, EventArchive, EventRecordsLocation, EventRetentionPolicy};
+use event_records::archiver;
+
+use crate::repository::event_archives_repo::{self, EVENT_ARCHIVES};
+use crate::repository::event_records_repo::EVENT_RECORDS;
+
+thread_local! {
+    static EVENT_ARCHIVE_TIMER_ID: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+/// Starts the timer which moves the records beyond the retention policy to archives
+pub fn start_event_archive_timer(interval: u64) {
+    let timer_id = ic_cdk_timers::set_timer_interval(Duration::from_secs(interval), || {
+        ic_cdk::spawn(async {
+            // Records stay in the canister until the next tick when archiving fails
+            let _ = archive_event_records().await;
+        });
+    });
+
+    EVENT_ARCHIVE_TIMER_ID.with(|cell| {
+        cell.replace(Some(timer_id));
+    });
+}
+
+pub fn stop_event_archive_timer() {
+    EVENT_ARCHIVE_TIMER_ID.with(|timer_id| {
+        if let Some(timer_id) = timer_id.borrow_mut().take() {
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+    });
+}
+
+/// Archives batches of records until the canister keeps no more than the retention policy allows.
+/// Returns the number of archived records.
+pub async fn archive_event_records() -> Result<u64, InternalError> {
+    let mut archived = 0;
+
+    loop {
+        let batch = archiver::archive_event_records(&EVENT_RECORDS, &EVENT_ARCHIVES).await?;
+        if batch == 0 {
+            return Ok(archived);
+        }
+        archived += batch;
+    }
+}
+
+pub fn get_event_archives() -> Vec<EventArchive> {
+    event_archives_repo::get_event_archives()
+}
+
+/// Canisters serving the records with ids in `start_id..end_id`
+pub fn get_event_records_location(start_id: u64, end_id: u64) -> Vec<EventRecordsLocation> {
+    let local_ids = EVENT_RECORDS.with(|events| events.borrow().id_range());
+
+    locate_event_records(&get_event_archives(), ic_cdk::id(), local_ids, start_id..end_id)
+}
+
+/// Makes `canister_id` the archive of the next archived records
+pub fn add_event_archive(canister_id: Principal) -> Result<EventArchive, InternalError> {
+    let start_id = EVENT_RECORDS.with(|events| events.borrow().id_range().start);
+
+    event_archives_repo::add_event_archive(canister_id, start_id)
+}
+
+pub fn set_event_retention_policy(policy: EventRetentionPolicy) -> Result<(), InternalError> {
+    policy.validate()?;
+    event_archives_repo::set_retention_policy(policy);
+    Ok(())
+}
+
+pub fn set_event_archive_wasm(wasm: Vec<u8>) {
+    event_archives_repo::set_archive_wasm(wasm);
+}
@@ -1,13 +1,41 @@
-use candid::{CandidType, Deserialize, Nat};
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Deserialize, Encode, Nat};
+use ic_stable_structures::storable::{Bound, Storable};
 use serde::Serialize;
 
 use errors::internal_error::error::InternalError;
 use event_records::generic_event_record::GenericEventRecord;
+use event_records::event_store::{IndexedEvent, StoredEventRecord};
 use event_records::events::pool_events::*;
+use event_records::events::context_events::*;
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct EventRecord(pub GenericEventRecord<Event>);
 
+impl Storable for EventRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl StoredEventRecord for EventRecord {
+    type Event = Event;
+
+    fn record(&self) -> &GenericEventRecord<Event> {
+        &self.0
+    }
+
+    fn record_mut(&mut self) -> &mut GenericEventRecord<Event> {
+        &mut self.0
+    }
+}
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub enum Event {
     AddLiquidityToPoolStarted(AddLiquidityToPoolStarted),
@@ -16,6 +44,8 @@ pub enum Event {
     WithdrawLiquidityFromPoolStarted(WithdrawLiquidityFromPoolStarted),
     WithdrawLiquidityFromPoolCompleted(WithdrawLiquidityFromPoolCompleted),
     WithdrawLiquidityFromPoolFailed(WithdrawLiquidityFromPoolFailed),
+    PoolsNotFound(PoolsNotFound),
+    ContextIdsSeedingFailed(ContextIdsSeedingFailed),
 }
 
 impl Event {
@@ -29,6 +59,10 @@ impl Event {
             Self::WithdrawLiquidityFromPoolStarted(_) => "WithdrawLiquidityFromPoolStarted",
             Self::WithdrawLiquidityFromPoolCompleted(_) => "WithdrawLiquidityFromPoolCompleted",
             Self::WithdrawLiquidityFromPoolFailed(_) => "WithdrawLiquidityFromPoolFailed",
+            // Pool stats requests
+            Self::PoolsNotFound(_) => "PoolsNotFound",
+            // Context ids
+            Self::ContextIdsSeedingFailed(_) => "ContextIdsSeedingFailed",
         }
     }
 
@@ -55,4 +89,31 @@ impl Event {
     pub fn withdraw_liquidity_from_pool_failed(pool_id: String, total_shares: Nat, shares: Nat, error: InternalError) -> Self {
         Self::WithdrawLiquidityFromPoolFailed(WithdrawLiquidityFromPoolFailed { pool_id, total_shares, shares, error })
     }
+
+    pub fn pools_not_found(pool_ids: Vec<String>) -> Self {
+        Self::PoolsNotFound(PoolsNotFound { pool_ids })
+    }
+
+    pub fn context_ids_seeding_failed(error: InternalError) -> Self {
+        Self::ContextIdsSeedingFailed(ContextIdsSeedingFailed { error })
+    }
+}
+
+impl IndexedEvent for Event {
+    fn type_str(&self) -> &str {
+        Event::type_str(self)
+    }
+
+    fn pool_ids(&self) -> Vec<&str> {
+        match self {
+            Self::AddLiquidityToPoolStarted(event) => vec![event.pool_id.as_str()],
+            Self::AddLiquidityToPoolCompleted(event) => vec![event.pool_id.as_str()],
+            Self::AddLiquidityToPoolFailed(event) => vec![event.pool_id.as_str()],
+            Self::WithdrawLiquidityFromPoolStarted(event) => vec![event.pool_id.as_str()],
+            Self::WithdrawLiquidityFromPoolCompleted(event) => vec![event.pool_id.as_str()],
+            Self::WithdrawLiquidityFromPoolFailed(event) => vec![event.pool_id.as_str()],
+            Self::PoolsNotFound(event) => event.pool_ids.iter().map(String::as_str).collect(),
+            Self::ContextIdsSeedingFailed(_) => vec![],
+        }
+    }
 }
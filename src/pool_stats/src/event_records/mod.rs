@@ -1,3 +1,4 @@
 pub mod event_record;
 pub mod event_record_impl;
 pub mod event_record_service;
+pub mod event_archive_service;
@@ -1,21 +1,27 @@
 use candid::{CandidType, Deserialize, Principal, Nat};
 use serde::Serialize;
 use std::cell::RefCell;
-use ic_cdk::{call, id, trap, update, caller};
+use ic_cdk::{call, id, trap, query, update, caller};
 use ic_cdk::api::call::CallResult;
 use candid::{candid_method, export_service};
 
 use ::types::exchange_id::ExchangeId;
-use ::types::context::Context;
+use ::types::context::{Context, TraceContext};
 use ::types::CanisterId;
 use ::types::pool::PoolTrait;
 use errors::response_error::error::ResponseError;
 use errors::internal_error::error::InternalError;
 use errors::internal_error::error::build_error_code;
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade};
+use ::utils::context_ids;
+use ::event_records::archive::{EventArchive, EventRecordsLocation, EventRetentionPolicy};
 
 use crate::pool_snapshots::pool_snapshot::PoolSnapshot;
 use crate::pool_snapshots::pool_snapshot_service;
+use crate::event_records::event_archive_service;
+use crate::event_records::event_record_service;
+use crate::repository::event_records_repo;
+use crate::pool_snapshots::snapshot_retention::SnapshotRetentionPolicy;
 use crate::pools::pool::Pool;
 use crate::repository::pools_repo;
 use crate::repository::stable_state;
@@ -31,6 +37,12 @@ use crate::types::types::{
     GetPoolMetricsResult,
     GetPoolsSnapshotsResult,
     GetEventRecordsResult,
+    SetSnapshotRetentionPolicyResult,
+    EventRecordsQuery,
+    SetEventRetentionPolicyResult,
+    AddEventArchiveResult,
+    SetEventArchiveWasmResult,
+    CertifiedEventRecords,
 };
 
 pub mod pools;
@@ -45,6 +57,7 @@ pub mod utils;
 
 // const SNAPSHOTS_FETCHING_INTERVAL: u64 = 3600; // 1 hour
 const SNAPSHOTS_FETCHING_INTERVAL: u64 = 604_800; // 1 week
+const EVENT_ARCHIVE_INTERVAL: u64 = 3600; // 1 hour
 
 #[derive(CandidType, Debug, Clone, Deserialize)]
 pub struct CanisterIdRequest {
@@ -85,7 +98,7 @@ pub struct PoolSnapshotArgs {
 #[update]
 pub fn test_add_pool_snapshot(args: PoolSnapshotArgs) {
     let snapshot = PoolSnapshot::new(
-        (pools_repo::get_pool_snapshots_count(args.pool_id.clone()) + 1).to_string(),
+        pools_repo::next_pool_snapshot_id(),
         args.pool_id,
         args.timestamp,
         args.position_data,
@@ -196,28 +209,46 @@ pub fn get_pool_by_id(id: String) -> GetPoolByIdResult {
 // ========================== Pool metrics ==========================
 
 #[update]
-pub fn get_pool_metrics(pool_ids: Vec<String>) -> GetPoolMetricsResult {
-    let result = service::get_pool_metrics(pool_ids);
+pub fn get_pool_metrics(pool_ids: Vec<String>, trace: Option<TraceContext>) -> GetPoolMetricsResult {
+    let context = generate_context(trace);
+
+    let result = service::get_pool_metrics(context, pool_ids);
 
     GetPoolMetricsResult(result)
 }
 
 #[update]
-pub fn get_pools_snapshots(pool_ids: Vec<String>) -> GetPoolsSnapshotsResult {
-    let result = service::get_pools_snapshots(pool_ids);
+pub fn get_pools_snapshots(pool_ids: Vec<String>, trace: Option<TraceContext>) -> GetPoolsSnapshotsResult {
+    let context = generate_context(trace);
+
+    let result = service::get_pools_snapshots(context, pool_ids);
 
     GetPoolsSnapshotsResult(result)
 }
 
+#[update]
+pub fn get_snapshot_retention_policy() -> SnapshotRetentionPolicy {
+    service::get_snapshot_retention_policy()
+}
+
+#[update]
+pub fn set_snapshot_retention_policy(policy: SnapshotRetentionPolicy) -> SetSnapshotRetentionPolicyResult {
+    let result = service::set_snapshot_retention_policy(policy)
+        .map_err(ResponseError::from_internal_error);
+
+    SetSnapshotRetentionPolicyResult(result)
+}
+
 // ========================== Liquidity management ==========================
 
 #[update]
 pub async fn add_liquidity_to_pool(
     ledger: CanisterId,
     pool_id: String,
-    amount: Nat
+    amount: Nat,
+    trace: Option<TraceContext>,
 ) -> AddLiquidityResult {
-    let context = generate_context();
+    let context = generate_context(trace);
 
     let result = service::add_liquidity_to_pool(
         context,
@@ -231,8 +262,11 @@ pub async fn add_liquidity_to_pool(
 }
 
 #[update]
-pub async fn withdraw_liquidity_from_pool(pool_id: String) -> WithdrawLiquidityResult {
-    let context = generate_context();
+pub async fn withdraw_liquidity_from_pool(
+    pool_id: String,
+    trace: Option<TraceContext>,
+) -> WithdrawLiquidityResult {
+    let context = generate_context(trace);
 
     let result = service::withdraw_liquidity_from_pool(
         context,
@@ -243,20 +277,66 @@ pub async fn withdraw_liquidity_from_pool(pool_id: String) -> WithdrawLiquidityR
     WithdrawLiquidityResult(result)
 }
 
-fn generate_context() -> Context {
-    Context::generate(Some(caller()))
+/// Continues the trace of the calling canister's operation when it is passed
+fn generate_context(trace: Option<TraceContext>) -> Context {
+    match trace {
+        Some(trace) => Context::from_trace(trace, Some(caller())),
+        None => Context::generate(Some(caller())),
+    }
 }
 
 // ========================== Event records ==========================
 
 #[update]
-pub fn get_event_records(offset: u64, limit: u64) -> GetEventRecordsResult {
-    let result = service::get_event_records(offset, limit)
+pub fn get_event_records(query: EventRecordsQuery) -> GetEventRecordsResult {
+    let result = service::get_event_records(query)
         .map_err(|error| ResponseError::from_internal_error(error));
 
     GetEventRecordsResult(result)
 }
 
+/// Records from `start_id` on up to the certified latest record, with its hash.
+/// The certificate is only returned to query calls.
+#[query]
+pub fn get_certified_event_records(start_id: u64, limit: u64) -> CertifiedEventRecords {
+    event_record_service::get_certified_event_records(start_id, limit)
+}
+
+#[update]
+pub fn get_event_archives() -> Vec<EventArchive> {
+    event_archive_service::get_event_archives()
+}
+
+/// Canisters serving the event records with ids in `start_id..end_id`, archives first
+#[update]
+pub fn get_event_records_location(start_id: u64, end_id: u64) -> Vec<EventRecordsLocation> {
+    event_archive_service::get_event_records_location(start_id, end_id)
+}
+
+#[update]
+pub fn set_event_retention_policy(policy: EventRetentionPolicy) -> SetEventRetentionPolicyResult {
+    let result = service::set_event_retention_policy(policy)
+        .map_err(ResponseError::from_internal_error);
+
+    SetEventRetentionPolicyResult(result)
+}
+
+#[update]
+pub fn add_event_archive(canister_id: CanisterId) -> AddEventArchiveResult {
+    let result = service::add_event_archive(canister_id)
+        .map_err(ResponseError::from_internal_error);
+
+    AddEventArchiveResult(result)
+}
+
+#[update]
+pub fn set_event_archive_wasm(wasm: Vec<u8>) -> SetEventArchiveWasmResult {
+    let result = service::set_event_archive_wasm(wasm)
+        .map_err(ResponseError::from_internal_error);
+
+    SetEventArchiveWasmResult(result)
+}
+
 // ========================== Vault management ==========================
 #[init]
 #[candid_method(init)]
@@ -266,18 +346,24 @@ async fn init(runtime_config: Option<RuntimeConfig>) {
 
     pool_service::init_pools();
     pool_snapshot_service::start_pool_snapshots_timer(SNAPSHOTS_FETCHING_INTERVAL);
+    event_archive_service::start_event_archive_timer(EVENT_ARCHIVE_INTERVAL);
+    context_ids::schedule_context_ids_seeding(event_record_service::record_context_ids_seeding_failed);
 }
 
 #[pre_upgrade]
 fn pre_upgrade() {
     stable_state::stable_save();
     pool_snapshot_service::stop_pool_snapshots_timer();
+    event_archive_service::stop_event_archive_timer();
 }
 
 #[post_upgrade]
 fn post_upgrade() {
     stable_state::stable_restore();
+    event_records_repo::certify_event_records();
     pool_snapshot_service::start_pool_snapshots_timer(SNAPSHOTS_FETCHING_INTERVAL);
+    event_archive_service::start_event_archive_timer(EVENT_ARCHIVE_INTERVAL);
+    context_ids::schedule_context_ids_seeding(event_record_service::record_context_ids_seeding_failed);
 }
 
 // Sets the operator principal.
@@ -26,7 +26,7 @@ pub async fn add_liquidity_to_pool(
         Some(user),
     );
 
-    let liquidity_client = liquidity_client(pool.clone()).await;
+    let liquidity_client = liquidity_client(pool.clone()).await?;
 
     let add_liquidity_response = liquidity_client.add_liquidity_to_pool(
         amount.clone()
@@ -65,7 +65,7 @@ pub async fn withdraw_liquidity_from_pool(context: Context, pool: Pool) -> Resul
         Some(user),
     );
 
-    let liquidity_client = liquidity_client(pool.clone()).await;
+    let liquidity_client = liquidity_client(pool.clone()).await?;
 
     let withdraw_liquidity_response = liquidity_client.withdraw_liquidity_from_pool(
         total_shares.clone(),
@@ -103,7 +103,7 @@ pub async fn withdraw_liquidity_from_pool(context: Context, pool: Pool) -> Resul
     Ok(withdraw_liquidity_response)
 }
 
-async fn liquidity_client(pool: Pool) -> Box<dyn LiquidityClient> {
+async fn liquidity_client(pool: Pool) -> Result<Box<dyn LiquidityClient>, InternalError> {
     get_liquidity_client(
         get_environment_provider_impls(),
         pool.token0.clone(),
@@ -1,4 +1,5 @@
 pub mod pool_snapshot;
 pub mod pool_snapshot_service;
+pub mod snapshot_retention;
 pub mod position_data;
 pub mod pool_data;
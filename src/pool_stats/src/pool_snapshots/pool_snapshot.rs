@@ -1,4 +1,6 @@
-use candid::{CandidType, Deserialize};
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Deserialize, Encode};
+use ic_stable_structures::storable::{Bound, Storable};
 use serde::Serialize;
 
 use errors::internal_error::error::InternalError;
@@ -17,6 +19,18 @@ pub struct PoolSnapshot {
     pub pool_data: Option<PoolData>,
 }
 
+impl Storable for PoolSnapshot {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
 impl PoolSnapshot {
     pub fn new(
         id: String,
@@ -35,7 +49,7 @@ impl PoolSnapshot {
     }
 
     pub fn build(pool_id: String, position_data: Option<PositionData>, pool_data: Option<PoolData>) -> Self {
-        let id = (pools_repo::get_pool_snapshots_count(pool_id.clone()) + 1).to_string();
+        let id = pools_repo::next_pool_snapshot_id();
         let timestamp = current_timestamp();
 
         Self::new(
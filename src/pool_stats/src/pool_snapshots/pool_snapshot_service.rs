@@ -8,16 +8,19 @@ use liquidity::liquidity_client::LiquidityClient;
 use types::context::Context;
 use errors::internal_error::error::InternalError;
 use errors::internal_error::error::build_error_code;
+use utils::util::current_timestamp;
 
 use crate::pools::pool::Pool;
 use crate::pool_snapshots::pool_snapshot::PoolSnapshot;
 use crate::pool_snapshots::position_data::position_data::PositionData;
 use crate::pool_snapshots::pool_data::pool_data::PoolData;
+use crate::pool_snapshots::snapshot_retention;
 use crate::repository::pools_repo;
+use crate::repository::snapshot_retention_repo;
 use crate::utils::provider_impls::get_environment_provider_impls;
 
 thread_local! {
-    static POOL_SNAPSHOT_TIMER_ID: RefCell<Option<TimerId>> = RefCell::new(None);
+    static POOL_SNAPSHOT_TIMER_ID: RefCell<Option<TimerId>> = const { RefCell::new(None) };
 }
 
 fn set_timer_interval(
@@ -58,6 +61,21 @@ pub async fn create_all_pool_snapshots() {
             // TODO: add event logging
         });
     }
+
+    prune_pool_snapshots(current_timestamp());
+}
+
+/// Downsamples the snapshots of all pools according to the retention policy
+pub fn prune_pool_snapshots(now: u64) {
+    let policy = snapshot_retention_repo::get_snapshot_retention_policy();
+
+    for pool in pools_repo::get_pools() {
+        let snapshots = pools_repo::get_pool_snapshots(pool.id.clone()).unwrap_or_default();
+
+        for snapshot_id in snapshot_retention::snapshots_to_prune(&snapshots, &policy, now) {
+            pools_repo::delete_pool_snapshot(pool.id.clone(), snapshot_id);
+        }
+    }
 }
 
 pub async fn create_pool_snapshot(context: Context, pool: &Pool) -> Result<PoolSnapshot, InternalError> {
@@ -81,7 +99,7 @@ pub async fn create_pool_snapshot(context: Context, pool: &Pool) -> Result<PoolS
 }
 
 async fn get_position_data(context: Context, pool: &Pool) -> Result<Option<PositionData>, InternalError> {
-    let liquidity_client = get_liquidity_client(pool).await;
+    let liquidity_client = get_liquidity_client(pool).await?;
 
     if let Some(position_id) = pool.position_id.as_ref().cloned() {
         let position_response = liquidity_client.get_position_by_id(position_id).await?;
@@ -101,7 +119,7 @@ async fn get_position_data(context: Context, pool: &Pool) -> Result<Option<Posit
 }
 
 async fn get_pool_data(context: Context, pool: &Pool) -> Result<Option<PoolData>, InternalError> {
-    let liquidity_client = get_liquidity_client(pool).await;
+    let liquidity_client = get_liquidity_client(pool).await?;
     let pool_data_response = liquidity_client.get_pool_data().await?;
 
     let pool_data = PoolData {
@@ -111,7 +129,7 @@ async fn get_pool_data(context: Context, pool: &Pool) -> Result<Option<PoolData>
     Ok(Some(pool_data))
 }
 
-async fn get_liquidity_client(pool: &Pool) -> Box<dyn LiquidityClient> {
+async fn get_liquidity_client(pool: &Pool) -> Result<Box<dyn LiquidityClient>, InternalError> {
     liquidity_router::get_liquidity_client(
         get_environment_provider_impls(),
         pool.token0.clone(),
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+
+use crate::pool_snapshots::pool_snapshot::PoolSnapshot;
+
+const HOUR: u64 = 3_600;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Downsampling of pool snapshots by age: one snapshot per hour for the
+/// first `hourly_retention_days`, one per day up to `daily_retention_days`
+/// and one per week beyond.
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug, PartialEq, Eq)]
+pub struct SnapshotRetentionPolicy {
+    pub hourly_retention_days: u64,
+    pub daily_retention_days: u64,
+}
+
+impl Default for SnapshotRetentionPolicy {
+    fn default() -> Self {
+        Self {
+            hourly_retention_days: 30,
+            daily_retention_days: 365,
+        }
+    }
+}
+
+impl SnapshotRetentionPolicy {
+    pub fn validate(&self) -> Result<(), InternalError> {
+        if self.hourly_retention_days > self.daily_retention_days {
+            return Err(InternalError::validation(
+                build_error_code(4101, 2, 1), // 4101 02 01
+                "SnapshotRetentionPolicy::validate".to_string(),
+                "Hourly retention can not be longer than daily retention".to_string(),
+                Some(HashMap::from([
+                    ("hourly_retention_days".to_string(), self.hourly_retention_days.to_string()),
+                    ("daily_retention_days".to_string(), self.daily_retention_days.to_string()),
+                ])),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Length of the bucket a snapshot of the given age is downsampled to
+    fn bucket_size(&self, age: u64) -> u64 {
+        if age < self.hourly_retention_days * DAY {
+            HOUR
+        } else if age < self.daily_retention_days * DAY {
+            DAY
+        } else {
+            WEEK
+        }
+    }
+}
+
+/// Ids of the snapshots dropped by the policy, the latest snapshot of each bucket is kept.
+/// Snapshots must be ordered by timestamp, as returned by the pools repository.
+pub fn snapshots_to_prune(
+    snapshots: &[PoolSnapshot],
+    policy: &SnapshotRetentionPolicy,
+    now: u64,
+) -> Vec<String> {
+    let bucket = |snapshot: &PoolSnapshot| {
+        let bucket_size = policy.bucket_size(now.saturating_sub(snapshot.timestamp));
+        (bucket_size, snapshot.timestamp / bucket_size)
+    };
+
+    // Snapshots of one bucket are adjacent, each one but the last is superseded by its successor
+    snapshots.windows(2)
+        .filter(|pair| bucket(&pair[0]) == bucket(&pair[1]))
+        .map(|pair| pair[0].id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_000 * WEEK;
+
+    fn snapshot_at(id: &str, timestamp: u64) -> PoolSnapshot {
+        PoolSnapshot {
+            id: id.to_string(),
+            pool_id: "pool".to_string(),
+            timestamp,
+            position_data: None,
+            pool_data: None,
+        }
+    }
+
+    fn policy() -> SnapshotRetentionPolicy {
+        SnapshotRetentionPolicy {
+            hourly_retention_days: 2,
+            daily_retention_days: 14,
+        }
+    }
+
+    mod snapshots_to_prune {
+        use super::*;
+
+        #[test]
+        fn keeps_latest_snapshot_per_hour_within_hourly_retention() {
+            let hour_start = NOW - 5 * HOUR;
+            let snapshots = vec![
+                snapshot_at("1", hour_start),
+                snapshot_at("2", hour_start + 10 * 60),
+                snapshot_at("3", hour_start + 50 * 60),
+                snapshot_at("4", hour_start + HOUR),
+            ];
+
+            assert_eq!(snapshots_to_prune(&snapshots, &policy(), NOW), vec!["1", "2"]);
+        }
+
+        #[test]
+        fn keeps_latest_snapshot_per_day_within_daily_retention() {
+            let day_start = NOW - 5 * DAY;
+            let snapshots = vec![
+                snapshot_at("1", day_start + HOUR),
+                snapshot_at("2", day_start + 2 * HOUR),
+                snapshot_at("3", day_start + 20 * HOUR),
+                snapshot_at("4", day_start + DAY),
+            ];
+
+            assert_eq!(snapshots_to_prune(&snapshots, &policy(), NOW), vec!["1", "2"]);
+        }
+
+        #[test]
+        fn keeps_latest_snapshot_per_week_beyond_daily_retention() {
+            let week_start = NOW - 10 * WEEK;
+            let snapshots = vec![
+                snapshot_at("1", week_start),
+                snapshot_at("2", week_start + 3 * DAY),
+                snapshot_at("3", week_start + 6 * DAY),
+                snapshot_at("4", week_start + WEEK),
+            ];
+
+            assert_eq!(snapshots_to_prune(&snapshots, &policy(), NOW), vec!["1", "2"]);
+        }
+
+        #[test]
+        fn keeps_sparse_snapshots() {
+            let snapshots = vec![
+                snapshot_at("1", NOW - 20 * WEEK),
+                snapshot_at("2", NOW - 10 * DAY),
+                snapshot_at("3", NOW - 3 * HOUR),
+                snapshot_at("4", NOW - HOUR),
+            ];
+
+            assert!(snapshots_to_prune(&snapshots, &policy(), NOW).is_empty());
+        }
+    }
+
+    mod validate {
+        use super::*;
+
+        #[test]
+        fn rejects_hourly_retention_longer_than_daily() {
+            let policy = SnapshotRetentionPolicy {
+                hourly_retention_days: 30,
+                daily_retention_days: 7,
+            };
+
+            assert!(policy.validate().is_err());
+            assert!(SnapshotRetentionPolicy::default().validate().is_ok());
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use candid::{CandidType, Deserialize, Nat};
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Deserialize, Encode, Nat};
+use ic_stable_structures::storable::{Bound, Storable};
 use serde::Serialize;
 
 use types::exchange_id::ExchangeId;
@@ -16,6 +18,18 @@ pub struct Pool {
     pub position_id: Option<u64>,
 }
 
+impl Storable for Pool {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
 impl PoolTrait for Pool {
     fn get_id(&self) -> String { self.id.clone() }
     fn get_token0(&self) -> CanisterId { self.token0 }
@@ -1,29 +1,54 @@
 use std::cell::RefCell;
 
+use event_records::event_chain;
+use event_records::event_store::EventStore;
+
 use crate::event_records::event_record::EventRecord;
+use crate::repository::memory::{
+    get_memory,
+    Memory,
+    EVENT_RECORDS_MEMORY_ID,
+    EVENT_RECORDS_LOOKUP_MEMORY_ID,
+    NEXT_EVENT_RECORD_ID_MEMORY_ID,
+};
+use crate::types::types::{CertifiedEventRecords, EventRecordsPage, EventRecordsQuery};
 
 thread_local! {
-    pub static EVENT_RECORDS: RefCell<Vec<EventRecord>> = RefCell::new(Default::default());
+    pub static EVENT_RECORDS: RefCell<EventStore<EventRecord, Memory>> = RefCell::new(
+        EventStore::init(
+            get_memory(EVENT_RECORDS_MEMORY_ID),
+            get_memory(EVENT_RECORDS_LOOKUP_MEMORY_ID),
+            get_memory(NEXT_EVENT_RECORD_ID_MEMORY_ID),
+        )
+    );
+}
+
+/// Links the record to the latest one and returns it
+pub fn save_event_record(event: EventRecord) -> EventRecord {
+    EVENT_RECORDS.with(|events| events.borrow_mut().append(event))
+}
+
+/// Publishes the hash of the latest record as certified data
+pub fn certify_event_records() {
+    EVENT_RECORDS.with(|events| event_chain::certify_event_chain_tip(&events.borrow()));
+}
+
+/// Certifies the latest record once the current message is done
+pub fn schedule_event_records_certification() {
+    event_chain::schedule_event_chain_certification(&EVENT_RECORDS);
 }
 
-pub fn save_event_record(event: EventRecord) {
-    EVENT_RECORDS.with(|events| events.borrow_mut().push(event));
+/// Ids keep increasing after the oldest records are archived
+pub fn next_event_record_id() -> u64 {
+    EVENT_RECORDS.with(|events| events.borrow().next_id())
 }
 
-pub fn get_event_records_count() -> u64 {
-    EVENT_RECORDS.with(|events| events.borrow().len() as u64)
+pub fn get_event_records(query: &EventRecordsQuery) -> EventRecordsPage {
+    EVENT_RECORDS.with(|events| events.borrow().query(query))
 }
 
-pub fn get_event_records(offset: usize, limit: usize) -> Vec<EventRecord> {
-    EVENT_RECORDS.with(|events| {
-        events
-            .borrow()
-            .iter()
-            .skip(offset)
-            .take(limit)
-            .cloned()
-            .collect()
-    })
+pub fn get_certified_event_records(start_id: u64, limit: u64) -> CertifiedEventRecords {
+    EVENT_RECORDS.with(|events| event_chain::get_certified_event_records(&events.borrow(), start_id, limit))
 }
 
 #[cfg(test)]
@@ -31,78 +56,78 @@ mod tests {
     use super::*;
     use candid::Nat;
 
+    use event_records::event_query::{EventRecordsFilter, SortOrder};
     use event_records::generic_event_record::GenericEventRecord;
 
     use crate::event_records::event_record::Event;
 
     fn create_test_event_record(
-        id: u64,
         correlation_id: String,
         event: Event,
         caller: Option<candid::Principal>,
     ) -> EventRecord {
+        let id = next_event_record_id();
+
         EventRecord(GenericEventRecord {
             id,
             event,
             timestamp: 1000 + id, // mock timestamp that increases with id
             correlation_id,
             user: caller,
+            prev_hash: None,
+            payload: Vec::new(),
         })
     }
 
+    fn query(filter: EventRecordsFilter, cursor: Option<u64>, limit: u64) -> EventRecordsQuery {
+        EventRecordsQuery {
+            filter,
+            cursor,
+            limit,
+            sort_order: SortOrder::Asc,
+        }
+    }
+
+    fn clear_event_records() {
+        EVENT_RECORDS.with(|events| events.borrow_mut().clear());
+    }
+
     mod save_event_record {
         use super::*;
 
         #[test]
         fn it_saves_event_record_correctly() {
-            EVENT_RECORDS.with(|events| events.borrow_mut().clear());
+            clear_event_records();
 
             let event = Event::add_liquidity_to_pool_started(
                 "pool-1".to_string(),
                 Some(Nat::from(10u64)),
                 Some(Nat::from(20u64)),
             );
-            let event_record = create_test_event_record(
-                0,
-                "corr-1".to_string(),
-                event,
-                None,
-            );
-
-            save_event_record(event_record.clone());
+            save_event_record(create_test_event_record("corr-1".to_string(), event, None));
 
-            EVENT_RECORDS.with(|events| {
-                let data = events.borrow();
-                assert_eq!(data.len(), 1);
-                assert_eq!(data[0].0.event.type_str(), "AddLiquidityToPoolStarted");
-            });
+            let page = get_event_records(&query(EventRecordsFilter::default(), None, 10));
+            assert_eq!(page.total, 1);
+            assert_eq!(page.items[0].0.event.type_str(), "AddLiquidityToPoolStarted");
         }
     }
 
-    mod get_event_records_count {
+    mod next_event_record_id {
         use super::*;
 
         #[test]
-        fn it_returns_correct_event_records_count() {
-            EVENT_RECORDS.with(|events| {
-                events.borrow_mut().clear();
-            });
-            assert_eq!(get_event_records_count(), 0);
+        fn it_follows_saved_records() {
+            clear_event_records();
+            assert_eq!(next_event_record_id(), 0);
 
             let event = Event::add_liquidity_to_pool_completed(
                 "pool-1".to_string(),
                 None,
                 None,
             );
-            let event_record = create_test_event_record(
-                0,
-                "corr-1".to_string(),
-                event,
-                None,
-            );
-            save_event_record(event_record);
+            save_event_record(create_test_event_record("corr-1".to_string(), event, None));
 
-            assert_eq!(get_event_records_count(), 1);
+            assert_eq!(next_event_record_id(), 1);
         }
     }
 
@@ -110,8 +135,8 @@ mod tests {
         use super::*;
 
         #[test]
-        fn it_returns_correct_event_records_slice() {
-            EVENT_RECORDS.with(|events| events.borrow_mut().clear());
+        fn it_returns_pages_of_event_records() {
+            clear_event_records();
 
             for i in 0..5 {
                 let event = Event::withdraw_liquidity_from_pool_started(
@@ -119,19 +144,31 @@ mod tests {
                     Nat::from(100u64),
                     Nat::from(i as u64),
                 );
-                let event_record = create_test_event_record(
-                    i,
-                    format!("corr-{}", i),
-                    event,
-                    None,
-                );
-                save_event_record(event_record);
+                save_event_record(create_test_event_record(format!("corr-{}", i), event, None));
             }
 
-            let event_records = get_event_records(1, 2);
-            assert_eq!(event_records.len(), 2);
-            assert_eq!(event_records[0].0.timestamp, 1001);
-            assert_eq!(event_records[1].0.timestamp, 1002);
+            let first_page = get_event_records(&query(EventRecordsFilter::default(), None, 2));
+            let second_page = get_event_records(&query(EventRecordsFilter::default(), first_page.next_cursor, 2));
+
+            assert_eq!(second_page.items.len(), 2);
+            assert_eq!(second_page.items[0].0.timestamp, 1002);
+            assert_eq!(second_page.items[1].0.timestamp, 1003);
+            assert_eq!(second_page.total, 5);
+        }
+
+        #[test]
+        fn it_filters_event_records_by_pool() {
+            clear_event_records();
+
+            for pool_id in vec!["pool-1", "pool-2", "pool-1"] {
+                let event = Event::add_liquidity_to_pool_completed(pool_id.to_string(), None, None);
+                save_event_record(create_test_event_record("corr".to_string(), event, None));
+            }
+
+            let filter = EventRecordsFilter { pool_id: Some("pool-1".to_string()), ..Default::default() };
+            let page = get_event_records(&query(filter, None, 10));
+
+            assert_eq!(page.items.iter().map(|record| record.0.id).collect::<Vec<_>>(), vec![0, 2]);
         }
     }
 }
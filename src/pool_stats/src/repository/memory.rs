@@ -0,0 +1,25 @@
+use std::cell::RefCell;
+use ic_stable_structures::DefaultMemoryImpl;
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+
+pub type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+/// Runtime config, snapshot retention policy and event archives, written in `pre_upgrade`
+pub const UPGRADES_MEMORY_ID: MemoryId = MemoryId::new(0);
+pub const POOLS_MEMORY_ID: MemoryId = MemoryId::new(1);
+pub const POOL_SNAPSHOTS_MEMORY_ID: MemoryId = MemoryId::new(2);
+pub const POOL_SNAPSHOT_IDS_MEMORY_ID: MemoryId = MemoryId::new(3);
+pub const LAST_POOL_SNAPSHOT_ID_MEMORY_ID: MemoryId = MemoryId::new(4);
+pub const EVENT_RECORDS_MEMORY_ID: MemoryId = MemoryId::new(5);
+/// Event record ids by user, pool, correlation id and event type
+pub const EVENT_RECORDS_LOOKUP_MEMORY_ID: MemoryId = MemoryId::new(6);
+pub const NEXT_EVENT_RECORD_ID_MEMORY_ID: MemoryId = MemoryId::new(7);
+
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+}
+
+pub fn get_memory(id: MemoryId) -> Memory {
+    MEMORY_MANAGER.with(|memory_manager| memory_manager.borrow().get(id))
+}
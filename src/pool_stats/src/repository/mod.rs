@@ -1,4 +1,7 @@
 pub mod stable_state;
+pub mod memory;
 pub mod pools_repo;
 pub mod event_records_repo;
+pub mod event_archives_repo;
 pub mod runtime_config_repo;
+pub mod snapshot_retention_repo;
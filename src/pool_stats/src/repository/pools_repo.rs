@@ -1,87 +1,210 @@
+use std::borrow::Cow;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use candid::{CandidType, Decode, Deserialize, Encode};
+use ic_stable_structures::{StableBTreeMap, StableCell};
+use ic_stable_structures::storable::{Bound, Storable};
 
 use crate::pools::pool::Pool;
 use crate::pool_snapshots::pool_snapshot::PoolSnapshot;
+use crate::repository::memory::{
+    get_memory,
+    Memory,
+    POOLS_MEMORY_ID,
+    POOL_SNAPSHOTS_MEMORY_ID,
+    POOL_SNAPSHOT_IDS_MEMORY_ID,
+    LAST_POOL_SNAPSHOT_ID_MEMORY_ID,
+};
+
+/// Pool ids are `<provider>_<token0>_<token1>`, well below the key bound
+const MAX_KEY_SIZE: u32 = 512;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct PoolKey(String);
+
+/// Orders the snapshots of a pool by time, the id tells apart snapshots taken at the same timestamp
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct PoolSnapshotKey {
+    pool_id: String,
+    timestamp: u64,
+    id: String,
+}
+
+/// Finds the timestamp of a snapshot by its id, to replace or delete it
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct PoolSnapshotIdKey {
+    pool_id: String,
+    id: String,
+}
+
+macro_rules! bounded_storable {
+    ($key:ty) => {
+        impl Storable for $key {
+            fn to_bytes(&self) -> Cow<'_, [u8]> {
+                Cow::Owned(Encode!(self).unwrap())
+            }
+
+            fn from_bytes(bytes: Cow<[u8]>) -> Self {
+                Decode!(bytes.as_ref(), Self).unwrap()
+            }
+
+            const BOUND: Bound = Bound::Bounded { max_size: MAX_KEY_SIZE, is_fixed_size: false };
+        }
+    };
+}
+
+bounded_storable!(PoolKey);
+bounded_storable!(PoolSnapshotKey);
+bounded_storable!(PoolSnapshotIdKey);
 
 thread_local! {
-    pub static POOLS: RefCell<HashMap<String, Pool>> = RefCell::new(HashMap::new());
-    pub static POOLS_SNAPSHOTS: RefCell<HashMap<String, Vec<PoolSnapshot>>> = RefCell::new(HashMap::new());
+    static POOLS: RefCell<StableBTreeMap<PoolKey, Pool, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(POOLS_MEMORY_ID))
+    );
+
+    static POOL_SNAPSHOTS: RefCell<StableBTreeMap<PoolSnapshotKey, PoolSnapshot, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(POOL_SNAPSHOTS_MEMORY_ID))
+    );
+
+    static POOL_SNAPSHOT_TIMESTAMPS: RefCell<StableBTreeMap<PoolSnapshotIdKey, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(POOL_SNAPSHOT_IDS_MEMORY_ID))
+    );
+
+    static LAST_POOL_SNAPSHOT_ID: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(get_memory(LAST_POOL_SNAPSHOT_ID_MEMORY_ID), 0)
+            .expect("failed to initialize the pool snapshot id counter")
+    );
 }
 
 // Pools
 
 // TODO: test method, remove after testing
 pub fn delete_all_pools_and_snapshots() {
-    POOLS.with(|pools| pools.borrow_mut().clear());
-    POOLS_SNAPSHOTS.with(|snapshots| snapshots.borrow_mut().clear());
+    POOLS.with(|pools| pools.borrow_mut().clear_new());
+    POOL_SNAPSHOTS.with(|snapshots| snapshots.borrow_mut().clear_new());
+    POOL_SNAPSHOT_TIMESTAMPS.with(|timestamps| timestamps.borrow_mut().clear_new());
 }
 
 pub fn save_pool(pool: Pool) {
-    POOLS.with(|pools| pools.borrow_mut().insert(pool.id.clone(), pool));
+    POOLS.with(|pools| pools.borrow_mut().insert(PoolKey(pool.id.clone()), pool));
 }
 
 pub fn delete_pool(pool_id: String) {
-    POOLS.with(|pools| pools.borrow_mut().remove(&pool_id));
+    POOLS.with(|pools| pools.borrow_mut().remove(&PoolKey(pool_id)));
 }
 
 pub fn get_pools() -> Vec<Pool> {
-    POOLS.with(|pools| pools.borrow().values().cloned().collect())
+    POOLS.with(|pools| pools.borrow().iter().map(|(_, pool)| pool).collect())
 }
 
 pub fn get_pool_by_id(pool_id: String) -> Option<Pool> {
-    POOLS.with(|pools| pools.borrow().get(&pool_id).cloned())
+    POOLS.with(|pools| pools.borrow().get(&PoolKey(pool_id)))
 }
 
 pub fn update_pool(pool_id: String, pool: Pool) {
-    POOLS.with(|pools| {
-        let mut pools = pools.borrow_mut();
-        pools.insert(pool_id.to_string(), pool);
-    });
+    POOLS.with(|pools| pools.borrow_mut().insert(PoolKey(pool_id), pool));
 }
 
 // Pool Snapshots
 
+/// Snapshots of the pool ordered by timestamp
 pub fn get_pool_snapshots(pool_id: String) -> Option<Vec<PoolSnapshot>> {
-    POOLS_SNAPSHOTS.with(|snapshots| snapshots.borrow().get(&pool_id).cloned())
+    let start = PoolSnapshotKey { pool_id: pool_id.clone(), timestamp: 0, id: String::new() };
+
+    let snapshots = POOL_SNAPSHOTS.with(|snapshots| {
+        snapshots.borrow()
+            .range(start..)
+            .take_while(|(key, _)| key.pool_id == pool_id)
+            .map(|(_, snapshot)| snapshot)
+            .collect::<Vec<_>>()
+    });
+
+    if snapshots.is_empty() {
+        None
+    } else {
+        Some(snapshots)
+    }
 }
 
 pub fn get_pool_snapshots_count(pool_id: String) -> u32 {
-    POOLS_SNAPSHOTS.with(|snapshots| {
-        snapshots.borrow().get(&pool_id).map(|snapshots| snapshots.len() as u32).unwrap_or(0)
+    get_pool_snapshot_id_keys(&pool_id).len() as u32
+}
+
+/// Ids are never reused, so a deleted snapshot can not be replaced by a new one
+pub fn next_pool_snapshot_id() -> String {
+    LAST_POOL_SNAPSHOT_ID.with(|last_id| {
+        let mut last_id = last_id.borrow_mut();
+        let id = *last_id.get() + 1;
+        last_id.set(id).expect("failed to update the pool snapshot id counter");
+        id.to_string()
     })
 }
 
+/// Moves the id counter past ids assigned before it existed
+pub fn reserve_pool_snapshot_ids(last_id: u64) {
+    LAST_POOL_SNAPSHOT_ID.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if last_id > *cell.get() {
+            cell.set(last_id).expect("failed to update the pool snapshot id counter");
+        }
+    });
+}
+
 pub fn save_pool_snapshot(snapshot: PoolSnapshot) {
-    POOLS_SNAPSHOTS.with(|snapshots| {
+    let id_key = PoolSnapshotIdKey { pool_id: snapshot.pool_id.clone(), id: snapshot.id.clone() };
+
+    let replaced_timestamp = POOL_SNAPSHOT_TIMESTAMPS.with(|timestamps| {
+        timestamps.borrow_mut().insert(id_key, snapshot.timestamp)
+    });
+
+    POOL_SNAPSHOTS.with(|snapshots| {
         let mut snapshots = snapshots.borrow_mut();
-        if let Some(entry) = snapshots.get_mut(&snapshot.pool_id) {
-            let index = entry.iter().position(|s| s.id == snapshot.id);
-            if let Some(index) = index {
-                entry[index] = snapshot;
-            } else {
-                entry.push(snapshot);
-            }
-        } else {
-            snapshots.insert(snapshot.pool_id.clone(), vec![snapshot]);
+
+        if let Some(timestamp) = replaced_timestamp {
+            snapshots.remove(&PoolSnapshotKey {
+                pool_id: snapshot.pool_id.clone(),
+                timestamp,
+                id: snapshot.id.clone(),
+            });
         }
+
+        let key = PoolSnapshotKey {
+            pool_id: snapshot.pool_id.clone(),
+            timestamp: snapshot.timestamp,
+            id: snapshot.id.clone(),
+        };
+        snapshots.insert(key, snapshot);
     });
 }
 
 // TODO: remove test method
 pub fn delete_pool_snapshots(pool_id: String) {
-    POOLS_SNAPSHOTS.with(|snapshots| {
-        snapshots.borrow_mut().remove(&pool_id);
-    });
+    for id_key in get_pool_snapshot_id_keys(&pool_id) {
+        delete_pool_snapshot(id_key.pool_id, id_key.id);
+    }
 }
 
-// TODO: remove test method
 pub fn delete_pool_snapshot(pool_id: String, snapshot_id: String) {
-    POOLS_SNAPSHOTS.with(|snapshots| {
-        let mut snapshots = snapshots.borrow_mut();
-        snapshots.get_mut(&pool_id)
-            .map(|snapshots| snapshots.retain(|snapshot| snapshot.id != snapshot_id));
-    });
+    let id_key = PoolSnapshotIdKey { pool_id: pool_id.clone(), id: snapshot_id.clone() };
+
+    let timestamp = POOL_SNAPSHOT_TIMESTAMPS.with(|timestamps| timestamps.borrow_mut().remove(&id_key));
+
+    if let Some(timestamp) = timestamp {
+        POOL_SNAPSHOTS.with(|snapshots| {
+            snapshots.borrow_mut().remove(&PoolSnapshotKey { pool_id, timestamp, id: snapshot_id })
+        });
+    }
+}
+
+fn get_pool_snapshot_id_keys(pool_id: &str) -> Vec<PoolSnapshotIdKey> {
+    let start = PoolSnapshotIdKey { pool_id: pool_id.to_string(), id: String::new() };
+
+    POOL_SNAPSHOT_TIMESTAMPS.with(|timestamps| {
+        timestamps.borrow()
+            .range(start..)
+            .take_while(|(key, _)| key.pool_id == pool_id)
+            .map(|(key, _)| key)
+            .collect()
+    })
 }
 
 #[cfg(test)]
@@ -104,10 +227,14 @@ mod tests {
     }
 
     fn dummy_snapshot(pool_id: &str, snapshot_id: &str) -> PoolSnapshot {
+        dummy_snapshot_at(pool_id, snapshot_id, 123)
+    }
+
+    fn dummy_snapshot_at(pool_id: &str, snapshot_id: &str, timestamp: u64) -> PoolSnapshot {
         PoolSnapshot {
             id: snapshot_id.to_string(),
             pool_id: pool_id.to_string(),
-            timestamp: 123,
+            timestamp,
             position_data: None,
             pool_data: None,
         }
@@ -228,6 +355,22 @@ mod tests {
             assert_eq!(snapshots.len(), 2);
         }
 
+        #[test]
+        fn returns_snapshots_ordered_by_timestamp() {
+            delete_pool_snapshots("ordered".to_string());
+
+            save_pool_snapshot(dummy_snapshot_at("ordered", "1", 300));
+            save_pool_snapshot(dummy_snapshot_at("ordered", "2", 100));
+            save_pool_snapshot(dummy_snapshot_at("ordered", "3", 200));
+            save_pool_snapshot(dummy_snapshot_at("ordered-other", "4", 150));
+
+            let timestamps = get_pool_snapshots("ordered".to_string()).unwrap()
+                .iter()
+                .map(|snapshot| snapshot.timestamp)
+                .collect::<Vec<_>>();
+            assert_eq!(timestamps, vec![100, 200, 300]);
+        }
+
         #[test]
         fn returns_none_if_no_snapshots() {
             delete_pool_snapshots("no-snap".to_string());
@@ -250,6 +393,30 @@ mod tests {
         }
     }
 
+    mod next_pool_snapshot_id {
+        use super::*;
+
+        #[test]
+        fn does_not_reuse_ids_of_deleted_snapshots() {
+            delete_pool_snapshots("monotonic".to_string());
+
+            let first_id = next_pool_snapshot_id();
+            save_pool_snapshot(dummy_snapshot("monotonic", &first_id));
+            delete_pool_snapshot("monotonic".to_string(), first_id.clone());
+
+            let second_id = next_pool_snapshot_id();
+            assert!(second_id.parse::<u64>().unwrap() > first_id.parse::<u64>().unwrap());
+        }
+
+        #[test]
+        fn continues_after_reserved_ids() {
+            let reserved = next_pool_snapshot_id().parse::<u64>().unwrap() + 10;
+            reserve_pool_snapshot_ids(reserved);
+
+            assert_eq!(next_pool_snapshot_id(), (reserved + 1).to_string());
+        }
+    }
+
     mod delete_pool_snapshots {
         use super::*;
 
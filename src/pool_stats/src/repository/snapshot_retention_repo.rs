@@ -0,0 +1,15 @@
+use std::cell::RefCell;
+
+use crate::pool_snapshots::snapshot_retention::SnapshotRetentionPolicy;
+
+thread_local! {
+    static SNAPSHOT_RETENTION_POLICY: RefCell<SnapshotRetentionPolicy> = RefCell::new(SnapshotRetentionPolicy::default());
+}
+
+pub fn get_snapshot_retention_policy() -> SnapshotRetentionPolicy {
+    SNAPSHOT_RETENTION_POLICY.with(|policy| policy.borrow().clone())
+}
+
+pub fn set_snapshot_retention_policy(policy: SnapshotRetentionPolicy) {
+    SNAPSHOT_RETENTION_POLICY.with(|cell| cell.replace(policy));
+}
@@ -1,19 +1,44 @@
-use candid::{CandidType, Deserialize};
+use candid::{CandidType, Decode, Deserialize, Encode};
 use serde::Serialize;
 use ic_cdk::storage;
+use ic_cdk::api::stable::{stable_read, stable_size};
+use ic_stable_structures::Memory as _;
+use ic_stable_structures::writer::Writer;
 use std::collections::HashMap;
+use std::io::Write;
+
+use event_records::archive::EventArchiveState;
 
 use crate::event_records::event_record::EventRecord;
 
 use crate::pools::pool::Pool;
 use crate::pool_snapshots::pool_snapshot::PoolSnapshot;
-use crate::repository::pools_repo::POOLS;
-use crate::repository::pools_repo::POOLS_SNAPSHOTS;
-use crate::repository::event_records_repo::EVENT_RECORDS;
+use crate::pool_snapshots::snapshot_retention::SnapshotRetentionPolicy;
+use crate::repository::pools_repo;
+use crate::repository::event_records_repo;
+use crate::repository::event_archives_repo;
+use crate::repository::memory::{get_memory, UPGRADES_MEMORY_ID};
 use crate::repository::runtime_config_repo::{self, RuntimeConfig};
+use crate::repository::snapshot_retention_repo;
+
+/// Stable memory written by the memory manager starts with its magic
+const MEMORY_MANAGER_MAGIC: &[u8; 3] = b"MGR";
 
+/// Heap state written to the upgrades memory in `pre_upgrade`.
+/// Pools, pool snapshots and event records are kept in stable structures and are not copied on upgrade.
 #[derive(Serialize, Deserialize, CandidType)]
 pub struct StableState {
+    pub runtime_config: RuntimeConfig,
+    pub snapshot_retention_policy: SnapshotRetentionPolicy,
+    /// Records kept on the heap before the event store, moved to it on upgrade and saved empty since
+    pub event_records: Vec<EventRecord>,
+    /// None in states saved before event records were archived
+    pub event_archives: Option<EventArchiveState>,
+}
+
+/// State serialized with `storage::stable_save` before pools were moved to stable structures
+#[derive(Serialize, Deserialize, CandidType)]
+pub struct LegacyStableState {
     pub runtime_config: RuntimeConfig,
     pub pools: HashMap<String, Pool>,
     pub pool_snapshots: HashMap<String, Vec<PoolSnapshot>>,
@@ -22,41 +47,84 @@ pub struct StableState {
 
 pub fn stable_save() {
     let runtime_config = runtime_config_repo::get_runtime_config();
+    let snapshot_retention_policy = snapshot_retention_repo::get_snapshot_retention_policy();
 
-    let pools = POOLS.with(|pools| {
-        pools.borrow().clone()
-    });
-
-    let pool_snapshots = POOLS_SNAPSHOTS.with(|snapshots| {
-        snapshots.borrow().clone()
-    });
+    let event_archives = Some(event_archives_repo::get_event_archive_state());
 
-    let event_records = EVENT_RECORDS.with(|records| {
-        records.borrow().clone()
-    });
+    let state = StableState {
+        runtime_config,
+        snapshot_retention_policy,
+        event_records: Vec::new(),
+        event_archives,
+    };
 
-    let state = StableState { runtime_config, pools, pool_snapshots, event_records };
+    let bytes = Encode!(&state).expect("failed to save stable state");
 
-    storage::stable_save((state,)).expect("failed to save stable state");
+    // The state is prefixed with its length
+    let mut memory = get_memory(UPGRADES_MEMORY_ID);
+    let mut writer = Writer::new(&mut memory, 0);
+    writer.write_all(&(bytes.len() as u64).to_le_bytes()).expect("failed to save stable state");
+    writer.write_all(&bytes).expect("failed to save stable state");
 }
 
 pub fn stable_restore() {
-    let (state,): (StableState,) = storage::stable_restore().expect("failed to restore stable state");
+    // Must run before the memory manager is initialized, which overwrites the legacy state
+    if is_legacy_layout() {
+        restore_legacy_state();
+        return;
+    }
+
+    let memory = get_memory(UPGRADES_MEMORY_ID);
+
+    let mut length_bytes = [0u8; 8];
+    memory.read(0, &mut length_bytes);
+
+    let mut bytes = vec![0u8; u64::from_le_bytes(length_bytes) as usize];
+    memory.read(8, &mut bytes);
 
-    runtime_config_repo::set_runtime_config(state.runtime_config.clone());
+    let state = Decode!(&bytes, StableState).expect("failed to restore stable state");
 
-    POOLS.with(|pools| {
-        pools.borrow_mut();
-        pools.replace(state.pools.clone())
-    });
+    snapshot_retention_repo::set_snapshot_retention_policy(state.snapshot_retention_policy);
+    event_archives_repo::set_event_archive_state(state.event_archives.unwrap_or_default());
+    restore_heap_state(state.runtime_config, state.event_records);
+}
+
+fn is_legacy_layout() -> bool {
+    if stable_size() == 0 {
+        return false;
+    }
+
+    let mut magic = [0u8; 3];
+    stable_read(0, &mut magic);
+
+    &magic != MEMORY_MANAGER_MAGIC
+}
+
+/// Moves the pools and snapshots written by `storage::stable_save` into the stable structures
+fn restore_legacy_state() {
+    let (state,): (LegacyStableState,) = storage::stable_restore().expect("failed to restore stable state");
+
+    restore_heap_state(state.runtime_config, state.event_records);
+
+    for pool in state.pools.into_values() {
+        pools_repo::save_pool(pool);
+    }
+
+    // Legacy ids were counted per pool, new ids continue after the highest of them
+    let mut last_snapshot_id = 0;
+    for snapshot in state.pool_snapshots.into_values().flatten() {
+        if let Ok(id) = snapshot.id.parse::<u64>() {
+            last_snapshot_id = last_snapshot_id.max(id);
+        }
+        pools_repo::save_pool_snapshot(snapshot);
+    }
+    pools_repo::reserve_pool_snapshot_ids(last_snapshot_id);
+}
 
-    POOLS_SNAPSHOTS.with(|snapshots| {
-        snapshots.borrow_mut();
-        snapshots.replace(state.pool_snapshots.clone())
-    });
+fn restore_heap_state(runtime_config: RuntimeConfig, event_records: Vec<EventRecord>) {
+    runtime_config_repo::set_runtime_config(runtime_config);
 
-    EVENT_RECORDS.with(|event_records| {
-        event_records.borrow_mut();
-        event_records.replace(state.event_records)
-    });
+    for event_record in event_records {
+        event_records_repo::save_event_record(event_record);
+    }
 }
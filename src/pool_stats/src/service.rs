@@ -10,20 +10,29 @@ use types::pool::PoolTrait;
 use errors::internal_error::error::InternalError;
 use errors::internal_error::error::build_error_code;
 use icrc_ledger_client;
+use liquidity::liquidity_adapters::get_liquidity_adapter;
+use event_records::archive::{EventArchive, EventRetentionPolicy};
 
 use crate::pool_snapshots::pool_snapshot_service;
 use crate::pool_snapshots::pool_snapshot::PoolSnapshot;
+use crate::pool_snapshots::snapshot_retention::SnapshotRetentionPolicy;
 use crate::pools::pool::Pool;
 use crate::pool_metrics::pool_metrics::PoolMetrics;
 use crate::pool_metrics::pool_metrics_service;
 use crate::repository::pools_repo;
 use crate::liquidity::liquidity_service;
-use crate::repository::event_records_repo;
-use crate::event_records::event_record::EventRecord;
+use crate::repository::snapshot_retention_repo;
+use crate::event_records::event_record_service;
+use crate::event_records::event_record::Event;
+use crate::event_records::event_archive_service;
+use crate::types::types::{EventRecordsPage, EventRecordsQuery};
 
 // ========================== Pools management ==========================
 
 pub fn add_pool(token0: CanisterId, token1: CanisterId, provider: ExchangeId) -> Result<String, InternalError> {
+    // Pools can only be added for exchanges with a registered liquidity adapter
+    get_liquidity_adapter(provider)?;
+
     let pool = Pool::build(token0, token1, provider);
     pool.save();
     Ok(pool.id)
@@ -63,24 +72,57 @@ pub fn get_pool_by_id(id: String) -> Result<Pool, InternalError> {
 
 // ========================== Pool metrics ==========================
 
-pub fn get_pool_metrics(pool_ids: Vec<String>) -> HashMap<String, PoolMetrics> {
-    pool_ids.into_iter()
-        .filter_map(|pool_id| {
-            pools_repo::get_pool_by_id(pool_id.clone())
-                .map(|pool| (pool_id, pool_metrics_service::create_pool_metrics(pool)))
-        })
+pub fn get_pool_metrics(context: Context, pool_ids: Vec<String>) -> HashMap<String, PoolMetrics> {
+    get_requested_pools(context, pool_ids)
+        .into_iter()
+        .map(|pool| (pool.id.clone(), pool_metrics_service::create_pool_metrics(pool)))
         .collect()
 }
 
-pub fn get_pools_snapshots(pool_ids: Vec<String>) -> HashMap<String, Vec<PoolSnapshot>> {
-    pool_ids.into_iter()
-        .filter_map(|pool_id| {
-            pools_repo::get_pool_by_id(pool_id.clone())
-                .map(|pool| (pool_id, pools_repo::get_pool_snapshots(pool.id).unwrap_or_default()))
-        })
+pub fn get_pools_snapshots(context: Context, pool_ids: Vec<String>) -> HashMap<String, Vec<PoolSnapshot>> {
+    get_requested_pools(context, pool_ids)
+        .into_iter()
+        .map(|pool| (pool.id.clone(), pools_repo::get_pool_snapshots(pool.id).unwrap_or_default()))
         .collect()
 }
 
+/// Registered pools with the requested ids. Ids of unregistered pools are recorded
+/// with the correlation id of the requesting operation, no stats are returned for them.
+fn get_requested_pools(context: Context, pool_ids: Vec<String>) -> Vec<Pool> {
+    let mut pools = Vec::new();
+    let mut missing_pool_ids = Vec::new();
+
+    for pool_id in pool_ids {
+        match pools_repo::get_pool_by_id(pool_id.clone()) {
+            Some(pool) => pools.push(pool),
+            None => missing_pool_ids.push(pool_id),
+        }
+    }
+
+    if !missing_pool_ids.is_empty() {
+        // Event: Pools not found
+        event_record_service::create_event_record(
+            Event::pools_not_found(missing_pool_ids),
+            context.correlation_id,
+            context.user,
+        );
+    }
+
+    pools
+}
+
+pub fn get_snapshot_retention_policy() -> SnapshotRetentionPolicy {
+    snapshot_retention_repo::get_snapshot_retention_policy()
+}
+
+pub fn set_snapshot_retention_policy(policy: SnapshotRetentionPolicy) -> Result<(), InternalError> {
+    check_controller("service::set_snapshot_retention_policy")?;
+
+    policy.validate()?;
+    snapshot_retention_repo::set_snapshot_retention_policy(policy);
+    Ok(())
+}
+
 // ========================== Liquidity management ==========================
 
 pub async fn add_liquidity_to_pool(
@@ -182,7 +224,51 @@ pub async fn withdraw_liquidity_from_pool(
 
 // ========================== Event records ==========================
 
-pub fn get_event_records(offset: u64, limit: u64) -> Result<Vec<EventRecord>, InternalError> {
-    let result = event_records_repo::get_event_records(offset as usize, limit as usize);
-    Ok(result)
+pub fn get_event_records(query: EventRecordsQuery) -> Result<EventRecordsPage, InternalError> {
+    if query.limit == 0 {
+        return Err(InternalError::validation(
+            build_error_code(4000, 2, 1), // 4000 02 01
+            "service::get_event_records".to_string(),
+            "Limit must be greater than zero".to_string(),
+            None,
+        ));
+    }
+
+    Ok(event_record_service::get_event_records(query))
+}
+
+pub fn set_event_retention_policy(policy: EventRetentionPolicy) -> Result<(), InternalError> {
+    check_controller("service::set_event_retention_policy")?;
+
+    event_archive_service::set_event_retention_policy(policy)
+}
+
+/// Makes an existing archive canister, owned by this canister, the archive of the next archived records
+pub fn add_event_archive(canister_id: CanisterId) -> Result<EventArchive, InternalError> {
+    check_controller("service::add_event_archive")?;
+
+    event_archive_service::add_event_archive(canister_id)
+}
+
+/// Uploads the wasm installed on archives spawned once the last archive is full
+pub fn set_event_archive_wasm(wasm: Vec<u8>) -> Result<(), InternalError> {
+    check_controller("service::set_event_archive_wasm")?;
+
+    event_archive_service::set_event_archive_wasm(wasm);
+    Ok(())
+}
+
+fn check_controller(error_context: &str) -> Result<(), InternalError> {
+    if ic_cdk::api::is_controller(&caller()) {
+        return Ok(());
+    }
+
+    Err(InternalError::access_denied(
+        build_error_code(4000, 5, 7), // 4000 05 07
+        error_context.to_string(),
+        "Caller is not a controller".to_string(),
+        Some(HashMap::from([
+            ("caller".to_string(), caller().to_text()),
+        ]))
+    ))
 }
@@ -4,12 +4,20 @@ use serde::Serialize;
 
 use ::types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse};
 use errors::response_error::error::ResponseError;
+use event_records::event_chain;
+use event_records::event_query;
+use event_records::archive::EventArchive;
 
 use crate::pools::pool::Pool;
 use crate::pool_metrics::pool_metrics::PoolMetrics;
 use crate::pool_snapshots::pool_snapshot::PoolSnapshot;
 use crate::event_records::event_record::EventRecord;
 
+pub use event_records::event_query::EventRecordsQuery;
+
+pub type EventRecordsPage = event_query::EventRecordsPage<EventRecord>;
+pub type CertifiedEventRecords = event_chain::CertifiedEventRecords<EventRecord>;
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct WithdrawLiquidityResult(pub Result<WithdrawLiquidityResponse, ResponseError>);
 
@@ -35,5 +43,17 @@ pub struct GetPoolMetricsResult(pub HashMap<String, PoolMetrics>);
 pub struct GetPoolsSnapshotsResult(pub HashMap<String, Vec<PoolSnapshot>>);
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct GetEventRecordsResult(pub Result<Vec<EventRecord>, ResponseError>);
+pub struct SetSnapshotRetentionPolicyResult(pub Result<(), ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetEventRecordsResult(pub Result<EventRecordsPage, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SetEventRetentionPolicyResult(pub Result<(), ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct AddEventArchiveResult(pub Result<EventArchive, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SetEventArchiveWasmResult(pub Result<(), ResponseError>);
 
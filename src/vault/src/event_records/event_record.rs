@@ -1,17 +1,51 @@
-use candid::{CandidType, Deserialize, Nat};
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Deserialize, Encode, Nat, Principal};
+use ic_stable_structures::storable::{Bound, Storable};
 use serde::Serialize;
 use types::CanisterId;
+use types::liquidity::MigrateLiquidityResponse;
+use types::swap_tokens::{SwapHop, PriceGuardCheck};
+use types::exchange_id::ExchangeId;
 
 use event_records::generic_event_record::GenericEventRecord;
+use event_records::event_store::{IndexedEvent, StoredEventRecord};
 use event_records::events::pool_events::*;
+use event_records::events::context_events::*;
 use errors::internal_error::error::InternalError;
 
 use crate::event_records::events::strategy_events::*;
 use crate::event_records::events::swap_events::*;
+use crate::event_records::events::ledger_events::*;
+use crate::strategies::strategy_state::StrategyState;
+use crate::strategies::strategy_lifecycle::StrategyLifecycle;
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct EventRecord(pub GenericEventRecord<Event>);
 
+impl Storable for EventRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl StoredEventRecord for EventRecord {
+    type Event = Event;
+
+    fn record(&self) -> &GenericEventRecord<Event> {
+        &self.0
+    }
+
+    fn record_mut(&mut self) -> &mut GenericEventRecord<Event> {
+        &mut self.0
+    }
+}
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub enum Event {
     // Strategy Deposit
@@ -26,6 +60,16 @@ pub enum Event {
     StrategyRebalanceStarted(StrategyRebalanceStarted),
     StrategyRebalanceCompleted(StrategyRebalanceCompleted),
     StrategyRebalanceFailed(StrategyRebalanceFailed),
+    StrategyRebalanceMigrated(StrategyRebalanceMigrated),
+    StrategyRebalanceStepCompleted(StrategyRebalanceStepCompleted),
+    // Strategy Allocation
+    StrategyAllocationDrifted(StrategyAllocationDrifted),
+    // Strategy State
+    StrategyStateChanged(StrategyStateChanged),
+    // Strategy Lifecycle
+    StrategyLifecycleChanged(StrategyLifecycleChanged),
+    StrategyUsersMigrated(StrategyUsersMigrated),
+    StrategyMigrationAccepted(StrategyMigrationAccepted),
     // Add liquidity to pool
     AddLiquidityToPoolStarted(AddLiquidityToPoolStarted),
     AddLiquidityToPoolCompleted(AddLiquidityToPoolCompleted),
@@ -37,7 +81,15 @@ pub enum Event {
     // Swap token
     SwapTokenStarted(SwapTokenStarted),
     SwapTokenCompleted(SwapTokenCompleted),
+    SwapTokenHopCompleted(SwapTokenHopCompleted),
     SwapTokenFailed(SwapTokenFailed),
+    SwapPriceChecked(SwapPriceChecked),
+    // Ledger fees
+    LedgerFeesPaid(LedgerFeesPaid),
+    // Allowances
+    AllowanceRevokeFailed(AllowanceRevokeFailed),
+    // Context ids
+    ContextIdsSeedingFailed(ContextIdsSeedingFailed),
 }
 
 impl Event {
@@ -55,6 +107,16 @@ impl Event {
             Self::StrategyRebalanceStarted(_) => "StrategyRebalanceStarted",
             Self::StrategyRebalanceCompleted(_) => "StrategyRebalanceCompleted",
             Self::StrategyRebalanceFailed(_) => "StrategyRebalanceFailed",
+            Self::StrategyRebalanceMigrated(_) => "StrategyRebalanceMigrated",
+            Self::StrategyRebalanceStepCompleted(_) => "StrategyRebalanceStepCompleted",
+            // Strategy Allocation
+            Self::StrategyAllocationDrifted(_) => "StrategyAllocationDrifted",
+            // Strategy State
+            Self::StrategyStateChanged(_) => "StrategyStateChanged",
+            // Strategy Lifecycle
+            Self::StrategyLifecycleChanged(_) => "StrategyLifecycleChanged",
+            Self::StrategyUsersMigrated(_) => "StrategyUsersMigrated",
+            Self::StrategyMigrationAccepted(_) => "StrategyMigrationAccepted",
             // Add liquidity to pool
             Self::AddLiquidityToPoolStarted(_) => "AddLiquidityToPoolStarted",
             Self::AddLiquidityToPoolCompleted(_) => "AddLiquidityToPoolCompleted",
@@ -66,7 +128,15 @@ impl Event {
             // Swap token
             Self::SwapTokenStarted(_) => "SwapTokenStarted",
             Self::SwapTokenCompleted(_) => "SwapTokenCompleted",
+            Self::SwapTokenHopCompleted(_) => "SwapTokenHopCompleted",
             Self::SwapTokenFailed(_) => "SwapTokenFailed",
+            Self::SwapPriceChecked(_) => "SwapPriceChecked",
+            // Ledger fees
+            Self::LedgerFeesPaid(_) => "LedgerFeesPaid",
+            // Allowances
+            Self::AllowanceRevokeFailed(_) => "AllowanceRevokeFailed",
+            // Context ids
+            Self::ContextIdsSeedingFailed(_) => "ContextIdsSeedingFailed",
         }
     }
 
@@ -74,20 +144,63 @@ impl Event {
         Self::StrategyDepositStarted(StrategyDepositStarted { strategy_id, pool_id, amount0 })
     }
 
-    pub fn strategy_deposit_completed(strategy_id: String, pool_id: Option<String>, amount0: Option<Nat>) -> Self {
-        Self::StrategyDepositCompleted(StrategyDepositCompleted { strategy_id, pool_id, amount0 })
+    pub fn strategy_deposit_completed(
+        strategy_id: String,
+        pool_id: Option<String>,
+        amount0: Option<Nat>,
+        shares: Nat,
+        share_price: Nat,
+        position_id: u64,
+    ) -> Self {
+        Self::StrategyDepositCompleted(StrategyDepositCompleted {
+            strategy_id,
+            pool_id,
+            amount0,
+            shares: Some(shares),
+            share_price: Some(share_price),
+            position_id: Some(position_id),
+        })
     }
 
     pub fn strategy_deposit_failed(strategy_id: String, pool_id: Option<String>, amount0: Option<Nat>, error: InternalError) -> Self {
-        Self::StrategyDepositFailed(StrategyDepositFailed { strategy_id, pool_id, amount0, error })
+        Self::StrategyDepositFailed(StrategyDepositFailed {
+            strategy_id,
+            pool_id,
+            amount0,
+            error,
+            deposited_amount: None,
+            shares: None,
+            position_id: None,
+        })
+    }
+
+    /// Deposit which failed after a part of it was added to pools and credited to the user
+    pub fn strategy_deposit_partially_failed(
+        strategy_id: String,
+        pool_id: Option<String>,
+        amount0: Option<Nat>,
+        deposited_amount: Nat,
+        shares: Nat,
+        position_id: u64,
+        error: InternalError,
+    ) -> Self {
+        Self::StrategyDepositFailed(StrategyDepositFailed {
+            strategy_id,
+            pool_id,
+            amount0,
+            error,
+            deposited_amount: Some(deposited_amount),
+            shares: Some(shares),
+            position_id: Some(position_id),
+        })
     }
     
     pub fn strategy_withdraw_started(strategy_id: String, pool_id: Option<String>, shares: Option<Nat>) -> Self {
         Self::StrategyWithdrawStarted(StrategyWithdrawStarted { strategy_id, pool_id, shares })
     }
 
-    pub fn strategy_withdraw_completed(strategy_id: String, pool_id: Option<String>, shares: Option<Nat>, amount0: Option<Nat>) -> Self {
-        Self::StrategyWithdrawCompleted(StrategyWithdrawCompleted { strategy_id, pool_id, shares, amount0 })
+    pub fn strategy_withdraw_completed(strategy_id: String, pool_id: Option<String>, shares: Option<Nat>, amount0: Option<Nat>, share_price: Nat) -> Self {
+        Self::StrategyWithdrawCompleted(StrategyWithdrawCompleted { strategy_id, pool_id, shares, amount0, share_price: Some(share_price) })
     }
 
     pub fn strategy_withdraw_failed(strategy_id: String, pool_id: Option<String>, shares: Option<Nat>, error: InternalError) -> Self {
@@ -98,14 +211,57 @@ impl Event {
         Self::StrategyRebalanceStarted(StrategyRebalanceStarted { strategy_id, previous_pool_id })
     }
 
-    pub fn strategy_rebalance_completed(strategy_id: String, previous_pool_id: Option<String>, new_pool_id: Option<String>) -> Self {
-        Self::StrategyRebalanceCompleted(StrategyRebalanceCompleted { strategy_id, previous_pool_id, new_pool_id })
+    pub fn strategy_rebalance_completed(strategy_id: String, previous_pool_id: Option<String>, new_pool_id: Option<String>, position_id: Option<u64>) -> Self {
+        Self::StrategyRebalanceCompleted(StrategyRebalanceCompleted { strategy_id, previous_pool_id, new_pool_id, position_id })
     }
 
     pub fn strategy_rebalance_failed(strategy_id: String, previous_pool_id: Option<String>, new_pool_id: Option<String>, error: InternalError) -> Self {
         Self::StrategyRebalanceFailed(StrategyRebalanceFailed { strategy_id, previous_pool_id, new_pool_id, error })
     }
+
+    pub fn strategy_rebalance_migrated(
+        strategy_id: String,
+        previous_pool_id: String,
+        new_pool_id: String,
+        migrate_response: &MigrateLiquidityResponse,
+    ) -> Self {
+        Self::StrategyRebalanceMigrated(StrategyRebalanceMigrated {
+            strategy_id,
+            previous_pool_id,
+            new_pool_id,
+            amount0: migrate_response.token_0_amount.clone(),
+            amount1: migrate_response.token_1_amount.clone(),
+            swap_token_in: migrate_response.token_in,
+            swap_amount_in: migrate_response.swap_amount_in.clone(),
+            naive_swap_volume: migrate_response.naive_swap_volume.clone(),
+            saved_swap_volume: migrate_response.saved_swap_volume.clone(),
+        })
+    }
     
+    pub fn strategy_rebalance_step_completed(strategy_id: String, previous_pool_id: String, new_pool_id: String, completed_steps: u32, total_steps: u32) -> Self {
+        Self::StrategyRebalanceStepCompleted(StrategyRebalanceStepCompleted { strategy_id, previous_pool_id, new_pool_id, completed_steps, total_steps })
+    }
+
+    pub fn strategy_allocation_drifted(strategy_id: String, max_drift: u32) -> Self {
+        Self::StrategyAllocationDrifted(StrategyAllocationDrifted { strategy_id, max_drift })
+    }
+
+    pub fn strategy_state_changed(strategy_id: String, previous_state: StrategyState, state: StrategyState) -> Self {
+        Self::StrategyStateChanged(StrategyStateChanged { strategy_id, previous_state, state })
+    }
+
+    pub fn strategy_lifecycle_changed(strategy_id: String, previous_lifecycle: StrategyLifecycle, lifecycle: StrategyLifecycle) -> Self {
+        Self::StrategyLifecycleChanged(StrategyLifecycleChanged { strategy_id, previous_lifecycle, lifecycle })
+    }
+
+    pub fn strategy_users_migrated(strategy_id: String, successor_strategy_id: String, amount: Nat, users_count: u32) -> Self {
+        Self::StrategyUsersMigrated(StrategyUsersMigrated { strategy_id, successor_strategy_id, amount, users_count })
+    }
+
+    pub fn strategy_migration_accepted(strategy_id: String, pool_id: String, position_id: u64, amount: Nat, users: Vec<MigratedUserShares>) -> Self {
+        Self::StrategyMigrationAccepted(StrategyMigrationAccepted { strategy_id, pool_id, position_id, amount, users })
+    }
+
     pub fn add_liquidity_to_pool_started(pool_id: String, amount0: Option<Nat>, amount1: Option<Nat>) -> Self {
         Self::AddLiquidityToPoolStarted(AddLiquidityToPoolStarted { pool_id, amount0, amount1 })
     }
@@ -138,7 +294,112 @@ impl Event {
         Self::SwapTokenCompleted(SwapTokenCompleted { pool_id, token_in, token_out, amount_in, amount_out })
     }
 
+    pub fn swap_token_hop_completed(pool_id: String, hop: u32, swap_hop: &SwapHop) -> Self {
+        Self::SwapTokenHopCompleted(SwapTokenHopCompleted {
+            pool_id,
+            hop,
+            token_in: swap_hop.token_in,
+            token_out: swap_hop.token_out,
+            provider: swap_hop.route.main_provider(),
+            amount_in: Nat::from(swap_hop.amount_in),
+            amount_out: Nat::from(swap_hop.amount_out),
+        })
+    }
+
     pub fn swap_token_failed(pool_id: String, token_in: CanisterId, token_out: CanisterId, amount_in: Option<Nat>, error: InternalError) -> Self {
         Self::SwapTokenFailed(SwapTokenFailed { pool_id, token_in, token_out, amount_in, error })
     }
+
+    pub fn swap_price_checked(
+        pool_id: String,
+        token_in: CanisterId,
+        token_out: CanisterId,
+        amount_in: Nat,
+        provider: ExchangeId,
+        check: PriceGuardCheck,
+    ) -> Self {
+        Self::SwapPriceChecked(SwapPriceChecked { pool_id, token_in, token_out, amount_in, provider, check })
+    }
+
+    pub fn ledger_fees_paid(pool_id: Option<String>, operation: LedgerFeeOperation, token: CanisterId, amount: Nat) -> Self {
+        Self::LedgerFeesPaid(LedgerFeesPaid { pool_id, operation, token, amount })
+    }
+
+    pub fn allowance_revoke_failed(spender: Principal, token: CanisterId, error: InternalError) -> Self {
+        Self::AllowanceRevokeFailed(AllowanceRevokeFailed { spender, token, error })
+    }
+
+    pub fn context_ids_seeding_failed(error: InternalError) -> Self {
+        Self::ContextIdsSeedingFailed(ContextIdsSeedingFailed { error })
+    }
+}
+
+impl IndexedEvent for Event {
+    fn type_str(&self) -> &str {
+        Event::type_str(self)
+    }
+
+    /// Strategy the event belongs to, used to look up event records by strategy
+    fn strategy_id(&self) -> Option<&str> {
+        match self {
+            Self::StrategyDepositStarted(event) => Some(&event.strategy_id),
+            Self::StrategyDepositCompleted(event) => Some(&event.strategy_id),
+            Self::StrategyDepositFailed(event) => Some(&event.strategy_id),
+            Self::StrategyWithdrawStarted(event) => Some(&event.strategy_id),
+            Self::StrategyWithdrawCompleted(event) => Some(&event.strategy_id),
+            Self::StrategyWithdrawFailed(event) => Some(&event.strategy_id),
+            Self::StrategyRebalanceStarted(event) => Some(&event.strategy_id),
+            Self::StrategyRebalanceCompleted(event) => Some(&event.strategy_id),
+            Self::StrategyRebalanceFailed(event) => Some(&event.strategy_id),
+            Self::StrategyRebalanceMigrated(event) => Some(&event.strategy_id),
+            Self::StrategyRebalanceStepCompleted(event) => Some(&event.strategy_id),
+            Self::StrategyAllocationDrifted(event) => Some(&event.strategy_id),
+            Self::StrategyStateChanged(event) => Some(&event.strategy_id),
+            Self::StrategyLifecycleChanged(event) => Some(&event.strategy_id),
+            Self::StrategyUsersMigrated(event) => Some(&event.strategy_id),
+            Self::StrategyMigrationAccepted(event) => Some(&event.strategy_id),
+            _ => None,
+        }
+    }
+
+    /// Pools the event refers to, used to look up event records by pool.
+    /// Rebalance events refer to both the previous and the new pool.
+    fn pool_ids(&self) -> Vec<&str> {
+        let pool_ids: Vec<Option<&String>> = match self {
+            Self::StrategyDepositStarted(event) => vec![event.pool_id.as_ref()],
+            Self::StrategyDepositCompleted(event) => vec![event.pool_id.as_ref()],
+            Self::StrategyDepositFailed(event) => vec![event.pool_id.as_ref()],
+            Self::StrategyWithdrawStarted(event) => vec![event.pool_id.as_ref()],
+            Self::StrategyWithdrawCompleted(event) => vec![event.pool_id.as_ref()],
+            Self::StrategyWithdrawFailed(event) => vec![event.pool_id.as_ref()],
+            Self::StrategyRebalanceStarted(event) => vec![event.previous_pool_id.as_ref()],
+            Self::StrategyRebalanceCompleted(event) => vec![event.previous_pool_id.as_ref(), event.new_pool_id.as_ref()],
+            Self::StrategyRebalanceFailed(event) => vec![event.previous_pool_id.as_ref(), event.new_pool_id.as_ref()],
+            Self::StrategyRebalanceMigrated(event) => vec![Some(&event.previous_pool_id), Some(&event.new_pool_id)],
+            Self::StrategyRebalanceStepCompleted(event) => vec![Some(&event.previous_pool_id), Some(&event.new_pool_id)],
+            Self::StrategyAllocationDrifted(_) |
+            Self::StrategyStateChanged(_) |
+            Self::StrategyLifecycleChanged(_) |
+            Self::StrategyUsersMigrated(_) => vec![],
+            Self::StrategyMigrationAccepted(event) => vec![Some(&event.pool_id)],
+            Self::AddLiquidityToPoolStarted(event) => vec![Some(&event.pool_id)],
+            Self::AddLiquidityToPoolCompleted(event) => vec![Some(&event.pool_id)],
+            Self::AddLiquidityToPoolFailed(event) => vec![Some(&event.pool_id)],
+            Self::WithdrawLiquidityFromPoolStarted(event) => vec![Some(&event.pool_id)],
+            Self::WithdrawLiquidityFromPoolCompleted(event) => vec![Some(&event.pool_id)],
+            Self::WithdrawLiquidityFromPoolFailed(event) => vec![Some(&event.pool_id)],
+            Self::SwapTokenStarted(event) => vec![Some(&event.pool_id)],
+            Self::SwapTokenCompleted(event) => vec![Some(&event.pool_id)],
+            Self::SwapTokenHopCompleted(event) => vec![Some(&event.pool_id)],
+            Self::SwapTokenFailed(event) => vec![Some(&event.pool_id)],
+            Self::SwapPriceChecked(event) => vec![Some(&event.pool_id)],
+            Self::LedgerFeesPaid(event) => vec![event.pool_id.as_ref()],
+            Self::AllowanceRevokeFailed(_) |
+            Self::ContextIdsSeedingFailed(_) => vec![],
+        };
+
+        let mut pool_ids: Vec<&str> = pool_ids.into_iter().flatten().map(String::as_str).collect();
+        pool_ids.dedup();
+        pool_ids
+    }
 }
@@ -21,6 +21,8 @@ impl EventRecord {
             timestamp,
             correlation_id,
             user,
+            prev_hash: None,
+            payload: Vec::new(),
         })
     }
 
@@ -39,7 +41,8 @@ impl EventRecord {
         )
     }
 
-    pub fn save(&self) {
-        event_records_repo::save_event_record(self.clone());
+    /// Returns the saved record, linked to the previous one
+    pub fn save(&self) -> Self {
+        event_records_repo::save_event_record(self.clone())
     }
 }
@@ -1,8 +1,11 @@
 use candid::Principal;
 
+use errors::internal_error::error::InternalError;
+use types::context::Context;
+
 use crate::event_records::event_record::{EventRecord, Event};
 use crate::repository::event_records_repo;
-use crate::types::types::ListItemsPaginationRequest;
+use crate::types::types::{CertifiedEventRecords, EventRecordsPage, EventRecordsQuery};
 
 pub fn create_event_record(
     event: Event,
@@ -14,15 +17,28 @@ pub fn create_event_record(
         correlation_id,
         event,
         user,
-    );
-    event_record.save();
+    ).save();
+    event_records_repo::schedule_event_records_certification();
     event_record
 }
 
-pub fn get_event_records(request: ListItemsPaginationRequest) -> Vec<EventRecord> {
-    event_records_repo::get_event_records(request)
+/// Records that correlation and span ids could not be seeded with randomness
+pub fn record_context_ids_seeding_failed(error: InternalError) {
+    create_event_record(
+        Event::context_ids_seeding_failed(error),
+        Context::generate(None).correlation_id,
+        None,
+    );
+}
+
+pub fn get_event_records(query: EventRecordsQuery) -> EventRecordsPage {
+    event_records_repo::get_event_records(&query)
+}
+
+pub fn get_certified_event_records(start_id: u64, limit: u64) -> CertifiedEventRecords {
+    event_records_repo::get_certified_event_records(start_id, limit)
 }
 
 fn next_id() -> u64 {
-    event_records_repo::get_event_records_count()
+    event_records_repo::next_event_record_id()
 }
@@ -0,0 +1,30 @@
+use candid::{CandidType, Deserialize, Nat, Principal};
+use serde::Serialize;
+
+use types::CanisterId;
+use errors::internal_error::error::InternalError;
+
+/// Token movement the ledger fees were paid for
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum LedgerFeeOperation {
+    AddLiquidity,
+    WithdrawLiquidity,
+    Swap,
+    Transfer,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct LedgerFeesPaid {
+    pub pool_id: Option<String>,
+    pub operation: LedgerFeeOperation,
+    pub token: CanisterId,
+    pub amount: Nat,
+}
+
+/// Allowance granted to an exchange which could not be revoked, it stays until it expires
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct AllowanceRevokeFailed {
+    pub spender: Principal,
+    pub token: CanisterId,
+    pub error: InternalError,
+}
@@ -1,5 +1,9 @@
-use candid::{CandidType, Deserialize, Nat};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use serde::Serialize;
+use types::CanisterId;
+
+use crate::strategies::strategy_state::StrategyState;
+use crate::strategies::strategy_lifecycle::StrategyLifecycle;
 use errors::internal_error::error::InternalError;
 
 // Strategy Deposit
@@ -15,6 +19,11 @@ pub struct StrategyDepositCompleted {
     pub strategy_id: String,
     pub pool_id: Option<String>,
     pub amount0: Option<Nat>,
+    /// Shares minted for the deposit, none in records made before shares were recorded
+    pub shares: Option<Nat>,
+    /// Balance per share the shares were minted at
+    pub share_price: Option<Nat>,
+    pub position_id: Option<u64>,
 }
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
@@ -23,6 +32,11 @@ pub struct StrategyDepositFailed {
     pub pool_id: Option<String>,
     pub amount0: Option<Nat>,
     pub error: InternalError,
+    /// Part of the deposit added to pools before the failure, credited to the user
+    pub deposited_amount: Option<Nat>,
+    /// Shares minted for the deposited part
+    pub shares: Option<Nat>,
+    pub position_id: Option<u64>,
 }
 
 // Strategy Withdraw
@@ -37,8 +51,11 @@ pub struct StrategyWithdrawStarted {
 pub struct StrategyWithdrawCompleted {
     pub strategy_id: String,
     pub pool_id: Option<String>,
+    /// Shares burned for the withdrawal
     pub shares: Option<Nat>,
     pub amount0: Option<Nat>,
+    /// Balance per share the shares were burned at
+    pub share_price: Option<Nat>,
 }
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
@@ -61,6 +78,8 @@ pub struct StrategyRebalanceCompleted {
     pub strategy_id: String,
     pub previous_pool_id: Option<String>,
     pub new_pool_id: Option<String>,
+    /// Position in the new pool
+    pub position_id: Option<u64>,
 }
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
@@ -70,3 +89,74 @@ pub struct StrategyRebalanceFailed {
     pub new_pool_id: Option<String>,
     pub error: InternalError,
 }
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyRebalanceStepCompleted {
+    pub strategy_id: String,
+    pub previous_pool_id: String,
+    pub new_pool_id: String,
+    pub completed_steps: u32,
+    pub total_steps: u32,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyRebalanceMigrated {
+    pub strategy_id: String,
+    pub previous_pool_id: String,
+    pub new_pool_id: String,
+    pub amount0: Nat,
+    pub amount1: Nat,
+    pub swap_token_in: Option<CanisterId>,
+    pub swap_amount_in: Nat,
+    pub naive_swap_volume: Nat,
+    pub saved_swap_volume: Nat,
+}
+
+// Strategy Allocation
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyAllocationDrifted {
+    pub strategy_id: String,
+    pub max_drift: u32,
+}
+
+// Strategy State
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyStateChanged {
+    pub strategy_id: String,
+    pub previous_state: StrategyState,
+    pub state: StrategyState,
+}
+
+// Strategy Lifecycle
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyLifecycleChanged {
+    pub strategy_id: String,
+    pub previous_lifecycle: StrategyLifecycle,
+    pub lifecycle: StrategyLifecycle,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct MigratedUserShares {
+    pub user: Principal,
+    /// Shares minted in the successor strategy
+    pub shares: Nat,
+    /// Initial deposit kept from the closing strategy
+    pub initial_deposit: Nat,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyMigrationAccepted {
+    pub strategy_id: String,
+    pub pool_id: String,
+    pub position_id: u64,
+    pub amount: Nat,
+    pub users: Vec<MigratedUserShares>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyUsersMigrated {
+    pub strategy_id: String,
+    pub successor_strategy_id: String,
+    pub amount: Nat,
+    pub users_count: u32,
+}
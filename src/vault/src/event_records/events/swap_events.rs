@@ -3,6 +3,8 @@ use serde::Serialize;
 use errors::internal_error::error::InternalError;
 
 use types::CanisterId;
+use types::exchange_id::ExchangeId;
+use types::swap_tokens::PriceGuardCheck;
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct SwapTokenStarted {
@@ -21,6 +23,27 @@ pub struct SwapTokenCompleted {
     pub amount_out: Option<Nat>,
 }
 
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SwapTokenHopCompleted {
+    pub pool_id: String,
+    pub hop: u32,
+    pub token_in: CanisterId,
+    pub token_out: CanisterId,
+    pub provider: Option<ExchangeId>,
+    pub amount_in: Nat,
+    pub amount_out: Nat,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SwapPriceChecked {
+    pub pool_id: String,
+    pub token_in: CanisterId,
+    pub token_out: CanisterId,
+    pub amount_in: Nat,
+    pub provider: ExchangeId,
+    pub check: PriceGuardCheck,
+}
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct SwapTokenFailed {
     pub pool_id: String,
@@ -2,3 +2,4 @@ pub mod event_record;
 pub mod event_record_impl;
 pub mod event_record_service;
 pub mod events;
+pub mod event_archive_service;
@@ -12,11 +12,17 @@ mod utils;
 
 use std::cell::RefCell;
 use candid::{candid_method, export_service, Nat, Principal};
-use ic_cdk::caller;
+use ic_cdk::{caller, trap};
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 
 use errors::response_error::error::ResponseError;
+use providers::icpswap::ICPSwapProvider;
+use token_registry::registry;
+use token_registry::token_metadata::TokenMetadata;
+use ::utils::context_ids;
+use ::event_records::archive::{EventArchive, EventRecordsLocation, EventRetentionPolicy};
 use ::types::CanisterId;
+use ::types::exchange_id::ExchangeId;
 use ::types::context::Context;
 
 use crate::repository::stable_state;
@@ -24,11 +30,17 @@ use crate::repository::strategies_repo;
 use crate::repository::runtime_config_repo::{self, RuntimeConfig};
 use crate::repository::config_repo::{self, Conf};
 use crate::strategies::strategy_service;
+use crate::strategies::rebalance_service;
 use crate::types::types::*;
 use crate::strategies::stats::strategy_stats_service;
+use crate::event_records::event_archive_service;
+use crate::event_records::event_record_service;
+use crate::repository::event_records_repo;
 use crate::utils::provider_impls::get_environment_provider_impls;
 
 const STRATEGY_STATS_FETCHING_INTERVAL: u64 = 3600; // 1 hour
+const REBALANCE_STEP_INTERVAL: u64 = 900; // 15 minutes
+const EVENT_ARCHIVE_INTERVAL: u64 = 3600; // 1 hour
 
 thread_local! {
     pub static HEARTBEAT: RefCell<u64> = RefCell::new(0);
@@ -65,7 +77,7 @@ fn heartbeat() {
 async fn test_icpswap_withdraw(token_out: CanisterId, amount: Nat, token_fee: Nat) -> Nat {
     let canister_id = Principal::from_text("5fq4w-lyaaa-aaaag-qjqta-cai").unwrap();
     let provider_impls = get_environment_provider_impls();
-    let icpswap_provider = provider_impls.icpswap.clone();
+    let icpswap_provider = provider_impls.get::<dyn ICPSwapProvider + Send + Sync>(ExchangeId::ICPSwap).unwrap();
 
     let icpswap_quote_result = icpswap_provider.withdraw(
         canister_id,
@@ -91,10 +103,13 @@ async fn test_update_strategy_stats() {
 }
 
 // TODO: Test function. Remove after testing.
+/// Starts a rebalance moving `step_percentage` of the position per step,
+/// or executes the next step of the rebalance in progress.
+/// Remaining steps are executed by the rebalance timer.
 #[update]
-async fn rebalance_strategy(strategy_id: u16) -> StrategyRebalanceResult {
+async fn rebalance_strategy(strategy_id: u16, step_percentage: Option<u8>) -> StrategyRebalanceResult {
     let mut strategy = strategies_repo::get_strategy_by_id(strategy_id).unwrap();
-    let result = strategy.rebalance().await
+    let result = strategy.rebalance(step_percentage).await
         .map_err(|error| ResponseError::from_internal_error(error));
 
     StrategyRebalanceResult(result)
@@ -102,15 +117,97 @@ async fn rebalance_strategy(strategy_id: u16) -> StrategyRebalanceResult {
 
 // =============== Events ===============
 
-#[update]
-async fn get_event_records(request: ListItemsPaginationRequest) -> GetEventRecordsResult {
-    let result = service::get_event_records(request)
-        .map_err(|error| ResponseError::from_internal_error(error))
-        .map(|response| EventRecordsPaginationResponse(response));
+#[query]
+fn get_event_records(query: EventRecordsQuery) -> GetEventRecordsResult {
+    let result = service::get_event_records(query)
+        .map_err(ResponseError::from_internal_error);
 
     GetEventRecordsResult(result)
 }
 
+/// Records from `start_id` on up to the certified latest record, with its hash.
+/// The certificate is only returned to query calls.
+#[query]
+fn get_certified_event_records(start_id: u64, limit: u64) -> CertifiedEventRecords {
+    event_record_service::get_certified_event_records(start_id, limit)
+}
+
+#[query]
+fn get_event_archives() -> Vec<EventArchive> {
+    event_archive_service::get_event_archives()
+}
+
+/// Canisters serving the event records with ids in `start_id..end_id`, archives first
+#[query]
+fn get_event_records_location(start_id: u64, end_id: u64) -> Vec<EventRecordsLocation> {
+    event_archive_service::get_event_records_location(start_id, end_id)
+}
+
+#[update]
+fn set_event_retention_policy(policy: EventRetentionPolicy) -> SetEventRetentionPolicyResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::set_event_retention_policy(context, policy)
+        .map_err(ResponseError::from_internal_error);
+
+    SetEventRetentionPolicyResult(result)
+}
+
+#[update]
+fn add_event_archive(canister_id: CanisterId) -> AddEventArchiveResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::add_event_archive(context, canister_id)
+        .map_err(ResponseError::from_internal_error);
+
+    AddEventArchiveResult(result)
+}
+
+#[update]
+fn set_event_archive_wasm(wasm: Vec<u8>) -> SetEventArchiveWasmResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::set_event_archive_wasm(context, wasm)
+        .map_err(ResponseError::from_internal_error);
+
+    SetEventArchiveWasmResult(result)
+}
+
+/// Upgrades every archive to the wasm uploaded with `set_event_archive_wasm`
+#[update]
+async fn upgrade_event_archives() -> UpgradeEventArchivesResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::upgrade_event_archives(context).await
+        .map_err(ResponseError::from_internal_error);
+
+    UpgradeEventArchivesResult(result)
+}
+
+/// Starts replaying the event log to compare the rebuilt strategies with their live state.
+/// Only controllers are allowed to call it.
+#[update]
+fn audit_strategies() -> AuditStrategiesResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::audit_strategies(context)
+        .map_err(ResponseError::from_internal_error);
+
+    AuditStrategiesResult(result)
+}
+
+/// Progress of the audit started with `audit_strategies`, the report once it is completed.
+/// Only controllers are allowed to call it.
+#[query]
+fn get_strategy_audit() -> GetStrategyAuditResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::get_strategy_audit(context)
+        .map_err(ResponseError::from_internal_error);
+
+    GetStrategyAuditResult(result)
+}
+
 // =============== Strategies ===============
 
 #[update]
@@ -133,6 +230,97 @@ async fn withdraw(args: StrategyWithdrawArgs) -> StrategyWithdrawResult {
     StrategyWithdrawResult(result)
 }
 
+/// Previews a deposit without moving any tokens.
+///
+/// Quotes are queried from the exchanges, so the call is a composite query.
+#[query(composite = true)]
+async fn preview_deposit(args: StrategyDepositArgs) -> StrategyPreviewDepositResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::preview_deposit(context, args).await
+        .map_err(ResponseError::from_internal_error);
+
+    StrategyPreviewDepositResult(result)
+}
+
+/// Previews a withdrawal of the caller's shares without moving any tokens.
+///
+/// Quotes are queried from the exchanges, so the call is a composite query.
+#[query(composite = true)]
+async fn preview_withdraw(args: StrategyWithdrawArgs) -> StrategyPreviewWithdrawResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::preview_withdraw(context, args).await
+        .map_err(ResponseError::from_internal_error);
+
+    StrategyPreviewWithdrawResult(result)
+}
+
+/// Moves a strategy to a new state. Only controllers are allowed to call it.
+///
+/// Moving to `Exited` withdraws the strategy liquidity to the base token,
+/// so the call is an update.
+#[update]
+async fn set_strategy_state(args: SetStrategyStateArgs) -> SetStrategyStateResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::set_strategy_state(context, args).await
+        .map_err(ResponseError::from_internal_error);
+
+    SetStrategyStateResult(result)
+}
+
+/// Moves a strategy to the next lifecycle stage. Only controllers are allowed to call it.
+#[update]
+async fn set_strategy_lifecycle(args: SetStrategyLifecycleArgs) -> SetStrategyLifecycleResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::set_strategy_lifecycle(context, args).await
+        .map_err(ResponseError::from_internal_error);
+
+    SetStrategyLifecycleResult(result)
+}
+
+/// Migrates all users of a closing strategy into a successor strategy
+/// without paying out to their wallets. Only controllers are allowed to call it.
+#[update]
+async fn migrate_strategy(args: MigrateStrategyArgs) -> MigrateStrategyResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::migrate_strategy(context, args).await
+        .map_err(ResponseError::from_internal_error);
+
+    MigrateStrategyResult(result)
+}
+
+/// Registers a token and caches its ledger metadata. Only controllers are allowed to call it.
+#[update]
+async fn add_token(canister_id: CanisterId) -> AddTokenResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::add_token(context, canister_id).await
+        .map_err(ResponseError::from_internal_error);
+
+    AddTokenResult(result)
+}
+
+/// Fetches the metadata of all registered tokens again. Only controllers are allowed to call it.
+#[update]
+async fn refresh_tokens() -> RefreshTokensResult {
+    let context = Context::generate(Some(caller()));
+
+    let result = service::refresh_tokens(context).await
+        .map_err(ResponseError::from_internal_error);
+
+    RefreshTokensResult(result)
+}
+
+/// Retrieves the cached metadata of the registered tokens.
+#[query]
+fn get_tokens() -> Vec<TokenMetadata> {
+    registry::get_tokens()
+}
+
 /// Retrieves the strategies for a specific user.
 ///
 /// # Arguments
@@ -255,18 +443,30 @@ fn init(conf: Option<Conf>, runtime_config: Option<RuntimeConfig>) {
 
     strategy_service::init_strategies();
     strategy_stats_service::start_strategy_stats_update_timer(STRATEGY_STATS_FETCHING_INTERVAL);
+    rebalance_service::start_rebalance_step_timer(REBALANCE_STEP_INTERVAL);
+    event_archive_service::start_event_archive_timer(EVENT_ARCHIVE_INTERVAL);
+    context_ids::schedule_context_ids_seeding(event_record_service::record_context_ids_seeding_failed);
 }
 
 #[pre_upgrade]
 fn pre_upgrade() {
     stable_state::stable_save();
     strategy_stats_service::stop_strategy_stats_update_timer();
+    rebalance_service::stop_rebalance_step_timer();
+    event_archive_service::stop_event_archive_timer();
 }
 
 #[post_upgrade]
 fn post_upgrade() {
-    stable_state::stable_restore();
+    if let Err(error) = stable_state::stable_restore() {
+        trap(&format!("Failed to restore stable state: {:?}", error));
+    }
+    strategy_service::migrate_strategies();
+    event_records_repo::certify_event_records();
     strategy_stats_service::start_strategy_stats_update_timer(STRATEGY_STATS_FETCHING_INTERVAL);
+    rebalance_service::start_rebalance_step_timer(REBALANCE_STEP_INTERVAL);
+    event_archive_service::start_event_archive_timer(EVENT_ARCHIVE_INTERVAL);
+    context_ids::schedule_context_ids_seeding(event_record_service::record_context_ids_seeding_failed);
 }
 
 export_service!();
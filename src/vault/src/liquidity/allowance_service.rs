@@ -0,0 +1,50 @@
+use std::cell::Cell;
+use std::future::Future;
+
+use types::context::Context;
+
+use crate::event_records::event_record_service;
+use crate::event_records::event_record::Event;
+
+thread_local! {
+    static OPERATIONS_IN_PROGRESS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Runs the liquidity operation and revokes the allowances left over on the exchanges
+/// once no other operation is in progress, so allowances of running operations stay in place
+pub async fn with_allowance_cleanup<T>(context: Context, operation: impl Future<Output = T>) -> T {
+    OPERATIONS_IN_PROGRESS.with(|count| count.set(count.get() + 1));
+
+    let result = operation.await;
+
+    let operations_in_progress = OPERATIONS_IN_PROGRESS.with(|count| {
+        count.set(count.get().saturating_sub(1));
+        count.get()
+    });
+
+    if operations_in_progress == 0 {
+        revoke_leftover_allowances(&context).await;
+    }
+
+    result
+}
+
+/// Revokes every allowance granted by the vault which is not fully spent.
+/// Failed revocations are recorded and retried by the next cleanup, the allowances expire in any case.
+pub async fn revoke_leftover_allowances(context: &Context) {
+    for (spender, ledger_canister_id) in icrc_ledger_client::granted_approvals() {
+        // An operation started while revoking, its allowances must stay in place
+        if OPERATIONS_IN_PROGRESS.with(|count| count.get()) > 0 {
+            return;
+        }
+
+        if let Err(error) = icrc_ledger_client::icrc2_revoke(spender, ledger_canister_id).await {
+            // Event: Allowance revoke failed
+            event_record_service::create_event_record(
+                Event::allowance_revoke_failed(spender, ledger_canister_id, error),
+                context.correlation_id.clone(),
+                context.user,
+            );
+        }
+    }
+}
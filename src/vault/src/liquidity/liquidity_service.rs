@@ -1,31 +1,50 @@
 use candid::Nat;
+use std::collections::HashMap;
 
+use types::CanisterId;
 use types::context::Context;
-use types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse};
-use liquidity::liquidity_router::get_liquidity_client;
-use errors::internal_error::error::InternalError;
+use types::liquidity::{
+    AddLiquidityResponse,
+    WithdrawLiquidityResponse,
+    QuoteAddLiquidityResponse,
+    QuoteWithdrawLiquidityResponse,
+    MigrateLiquidityResponse,
+    LiquidityFees,
+};
+use types::swap_tokens::{MultiHopRoute, SwapRouteLeg, BPS_DENOMINATOR};
+use types::exchange_id::ExchangeId;
+use liquidity::liquidity_router::{get_liquidity_client, get_liquidity_client_with_swap_limits};
+use liquidity::liquidity_calculator::LiquidityCalculator;
+use errors::internal_error::error::{InternalError, build_error_code};
+use utils::util::{nat_to_f64, nat_to_u128};
 use swap::swap_service;
+use token_registry::ledger_fees::{self, APPROVED_TRANSFER_FEE_COUNT};
 
 use crate::pools::pool_data::PoolData;
 use crate::pools::pool::Pool;
 use crate::pool_stats::pool_stats_service;
 use crate::event_records::event_record_service;
 use crate::event_records::event_record::Event;
+use crate::event_records::events::ledger_events::LedgerFeeOperation;
 use crate::utils::provider_impls::get_environment_provider_impls;
+use crate::strategies::swap_limits::StrategySwapLimits;
+use crate::liquidity::price_guard_service;
+use crate::liquidity::allowance_service;
 
-pub async fn get_pools_data(pools: Vec<Pool>) -> Vec<PoolData> {
+pub async fn get_pools_data(context: &Context, pools: Vec<Pool>) -> Vec<PoolData> {
     let pool_ids: Vec<String> = pools.iter().map(|pool| pool.id.clone()).collect();
-    let pool_metrics = pool_stats_service::get_pool_metrics(pool_ids).await;
+    let pool_metrics = pool_stats_service::get_pool_metrics(context, pool_ids).await;
 
+    // Pools without metrics keep their place with zero APY
     let pool_data: Vec<PoolData> = pools
         .into_iter()
-        .zip(pool_metrics.into_iter())
-        .map(|(pool, pool_metric)|
-            PoolData {
-                pool: pool.clone(),
-                apy: pool_metric.1.apy.tokens_apy,
-            }
-        )
+        .map(|pool| {
+            let apy = pool_metrics.get(&pool.id)
+                .map(|pool_metric| pool_metric.apy.tokens_apy)
+                .unwrap_or_default();
+
+            PoolData { pool, apy }
+        })
         .collect();
 
     pool_data
@@ -34,27 +53,57 @@ pub async fn get_pools_data(pools: Vec<Pool>) -> Vec<PoolData> {
 pub async fn add_liquidity_to_pool(
     context: Context,
     amount: Nat,
-    pool: Pool
+    pool: Pool,
+    swap_limits: &StrategySwapLimits,
+) -> Result<AddLiquidityResponse, InternalError> {
+    allowance_service::with_allowance_cleanup(
+        context.clone(),
+        execute_add_liquidity_to_pool(context, amount, pool, swap_limits)
+    ).await
+}
+
+async fn execute_add_liquidity_to_pool(
+    context: Context,
+    amount: Nat,
+    pool: Pool,
+    swap_limits: &StrategySwapLimits,
 ) -> Result<AddLiquidityResponse, InternalError> {
-    let user = context.user.clone().unwrap();
+    let user = context.user;
 
     // Event: Add liquidity to pool started
     event_record_service::create_event_record(
         Event::add_liquidity_to_pool_started(pool.id.clone(), Some(amount.clone()), None),
         context.correlation_id.clone(),
-        Some(user),
+        user,
     );
 
-    let liquidity_client = get_liquidity_client(
-        get_environment_provider_impls(),
+    let limits = swap_limits.for_pair(pool.token0, pool.token1);
+
+    // Half of the amount is swapped to token1 before adding liquidity,
+    // so the pool price is checked for that swap
+    let price_check = price_guard_service::check_pool_price(
+        &context,
+        &pool,
         pool.token0,
         pool.token1,
-        pool.provider
+        nat_to_u128(&amount) / 2,
+        &limits,
     ).await;
 
-    let add_liquidity_response = liquidity_client.add_liquidity_to_pool(
-        amount.clone()
-    ).await
+    let liquidity_client = get_liquidity_client_with_swap_limits(
+        get_environment_provider_impls(),
+        pool.token0,
+        pool.token1,
+        pool.provider,
+        limits,
+    ).await?;
+
+    let add_liquidity_result = match price_check {
+        Ok(()) => liquidity_client.add_liquidity_to_pool(amount.clone()).await,
+        Err(error) => Err(error),
+    };
+
+    let add_liquidity_response = add_liquidity_result
         .map_err(|error| {
             // Event: Add liquidity to pool failed
             event_record_service::create_event_record(
@@ -64,7 +113,7 @@ pub async fn add_liquidity_to_pool(
                     error.clone(),
                 ),
                 context.correlation_id.clone(),
-                Some(user),
+                user,
             );
             error
         })?;
@@ -77,9 +126,11 @@ pub async fn add_liquidity_to_pool(
             Some(add_liquidity_response.token_1_amount.clone()),
         ),
         context.correlation_id.clone(),
-        Some(user),
+        user,
     );
 
+    record_ledger_fees(&context, &pool, LedgerFeeOperation::AddLiquidity, &add_liquidity_response.ledger_fees);
+
     Ok(add_liquidity_response)
 }
 
@@ -89,7 +140,7 @@ pub async fn withdraw_liquidity_from_pool(
     shares: Nat,
     pool: Pool
 ) -> Result<WithdrawLiquidityResponse, InternalError> {
-    let user = context.user.clone().unwrap();
+    let user = context.user;
 
     // Event: Withdraw liquidity from pool started
     event_record_service::create_event_record(
@@ -99,7 +150,7 @@ pub async fn withdraw_liquidity_from_pool(
             shares.clone(),
         ),
         context.correlation_id.clone(),
-        Some(user),
+        user,
     );
 
     let liquidity_client = get_liquidity_client(
@@ -107,7 +158,7 @@ pub async fn withdraw_liquidity_from_pool(
         pool.token0,
         pool.token1,
         pool.provider
-    ).await;
+    ).await?;
 
     let withdraw_liquidity_response = liquidity_client.withdraw_liquidity_from_pool(
         total_shares.clone(),
@@ -123,7 +174,7 @@ pub async fn withdraw_liquidity_from_pool(
                     error.clone(),
                 ),
                 context.correlation_id.clone(),
-                Some(user),
+                user,
             );
             error
         })?;
@@ -138,19 +189,46 @@ pub async fn withdraw_liquidity_from_pool(
             withdraw_liquidity_response.token_1_amount.clone(),
         ),
         context.correlation_id.clone(),
-        Some(user),
+        user,
     );
 
+    record_ledger_fees(&context, &pool, LedgerFeeOperation::WithdrawLiquidity, &withdraw_liquidity_response.ledger_fees);
+
     Ok(withdraw_liquidity_response)
 }
 
+/// Withdraws liquidity from the pool and swaps the other token of the pair to `base_token`
 pub async fn withdraw_liquidity_from_pool_and_swap(
     context: Context,
     total_shares: Nat,
     shares: Nat,
-    pool: Pool
+    pool: Pool,
+    base_token: CanisterId,
+    swap_limits: &StrategySwapLimits,
+) -> Result<Nat, InternalError> {
+    allowance_service::with_allowance_cleanup(
+        context.clone(),
+        execute_withdraw_liquidity_from_pool_and_swap(context, total_shares, shares, pool, base_token, swap_limits)
+    ).await
+}
+
+async fn execute_withdraw_liquidity_from_pool_and_swap(
+    context: Context,
+    total_shares: Nat,
+    shares: Nat,
+    pool: Pool,
+    base_token: CanisterId,
+    swap_limits: &StrategySwapLimits,
 ) -> Result<Nat, InternalError> {
-    let user = context.user.clone().unwrap();
+    // Check the price of the swap before withdrawing, so a rejected swap leaves the liquidity in the pool
+    let swap_quote = check_withdraw_liquidity_and_swap_price(
+        &context,
+        total_shares.clone(),
+        shares.clone(),
+        &pool,
+        base_token,
+        swap_limits,
+    ).await?;
 
     let withdraw_response = withdraw_liquidity_from_pool(
         context.clone(),
@@ -159,57 +237,580 @@ pub async fn withdraw_liquidity_from_pool_and_swap(
         pool.clone(),
     ).await?;
 
+    // Withdrawn amounts follow the token order of the pool
+    let (token_in, amount_in, base_token_amount) = if pool.token0 == base_token {
+        (pool.token1, withdraw_response.token_1_amount, withdraw_response.token_0_amount)
+    } else {
+        (pool.token0, withdraw_response.token_0_amount, withdraw_response.token_1_amount)
+    };
+
+    let swapped_amount = swap_to_base_token(
+        &context,
+        &pool,
+        token_in,
+        amount_in,
+        base_token,
+        swap_quote,
+        swap_limits,
+    ).await?;
+
+    Ok(base_token_amount + swapped_amount)
+}
+
+/// Quotes the withdrawal of liquidity from the pool and the swap of the other token to `base_token`,
+/// and checks the price of the swap against the reference price of the pool. Returns the swap quote.
+pub async fn check_withdraw_liquidity_and_swap_price(
+    context: &Context,
+    total_shares: Nat,
+    shares: Nat,
+    pool: &Pool,
+    base_token: CanisterId,
+    swap_limits: &StrategySwapLimits,
+) -> Result<MultiHopRoute, InternalError> {
+    let (_, swap_quote) = quote_withdraw_liquidity_from_pool_and_swap(
+        total_shares,
+        shares,
+        pool.clone(),
+        base_token,
+    ).await?;
+
+    check_swap_quote_price(context, pool, base_token, &swap_quote, swap_limits).await?;
+
+    Ok(swap_quote)
+}
+
+/// Swaps `amount_in` of the other token of the pool, withdrawn from the pool before, to `base_token`.
+/// The swap is quoted and its price checked first. Returns the amount of base token received.
+pub async fn swap_withdrawn_token(
+    context: Context,
+    pool: Pool,
+    amount_in: Nat,
+    base_token: CanisterId,
+    swap_limits: &StrategySwapLimits,
+) -> Result<Nat, InternalError> {
+    allowance_service::with_allowance_cleanup(
+        context.clone(),
+        execute_swap_withdrawn_token(context, pool, amount_in, base_token, swap_limits)
+    ).await
+}
+
+async fn execute_swap_withdrawn_token(
+    context: Context,
+    pool: Pool,
+    amount_in: Nat,
+    base_token: CanisterId,
+    swap_limits: &StrategySwapLimits,
+) -> Result<Nat, InternalError> {
+    if amount_in == 0u64 {
+        return Ok(amount_in);
+    }
+
+    let token_in = if pool.token0 == base_token { pool.token1 } else { pool.token0 };
+
+    let swap_quote = swap_service::quote_swap_icrc2_direct_or_multi_hop(
+        get_environment_provider_impls(),
+        token_in,
+        base_token,
+        amount_in.clone(),
+    ).await?;
+
+    check_swap_quote_price(&context, &pool, base_token, &swap_quote, swap_limits).await?;
+
+    swap_to_base_token(
+        &context,
+        &pool,
+        token_in,
+        amount_in,
+        base_token,
+        swap_quote,
+        swap_limits,
+    ).await
+}
+
+/// Checks the price of the quoted swap to `base_token` against the reference price of the pool
+async fn check_swap_quote_price(
+    context: &Context,
+    pool: &Pool,
+    base_token: CanisterId,
+    swap_quote: &MultiHopRoute,
+    swap_limits: &StrategySwapLimits,
+) -> Result<(), InternalError> {
+    if let Some(quoted_token_in) = swap_quote.path.first().copied() {
+        price_guard_service::check_quoted_price(
+            context,
+            pool,
+            quoted_token_in,
+            base_token,
+            &SwapRouteLeg {
+                provider: swap_quote.main_provider().unwrap_or(pool.provider),
+                amount_in: swap_quote.amount_in,
+                amount_out: swap_quote.amount_out,
+            },
+            &swap_limits.for_pair(quoted_token_in, base_token),
+        ).await?;
+    }
+
+    Ok(())
+}
+
+/// Swaps `amount_in` of `token_in` to `base_token` by the quoted route and returns the amount received
+async fn swap_to_base_token(
+    context: &Context,
+    pool: &Pool,
+    token_in: CanisterId,
+    amount_in: Nat,
+    base_token: CanisterId,
+    swap_quote: MultiHopRoute,
+    swap_limits: &StrategySwapLimits,
+) -> Result<Nat, InternalError> {
+    let user = context.user;
+
     // Event: Swap token started
     event_record_service::create_event_record(
         Event::swap_token_started(
             pool.id.clone(),
-            pool.token1,
-            pool.token0,
-            Some(withdraw_response.token_1_amount.clone()),
+            token_in,
+            base_token,
+            Some(amount_in.clone()),
         ),
         context.correlation_id.clone(),
-        Some(user),
+        user,
     );
 
-
-    // Swap withdrawn token_1 to token_0 (base token)
-    let swap_response = swap_service::swap_icrc2_optimal(
+    // Swap withdrawn token to base token by the quoted route, through intermediate tokens if there is no direct pool.
+    // If a later hop fails, the error recorded with the failure reports the token and the amount left.
+    let swap_route = swap_service::swap_icrc2_multi_hop(
         get_environment_provider_impls(),
-        pool.token1,
-        pool.token0,
-        withdraw_response.token_1_amount.clone(),
+        swap_quote,
+        amount_in.clone(),
+        swap_limits.for_pair(token_in, base_token),
     ).await
         .map_err(|error| {
             // Event: Swap token failed
             event_record_service::create_event_record(
                 Event::swap_token_failed(
                     pool.id.clone(),
-                    pool.token1,
-                    pool.token0,
-                    Some(withdraw_response.token_1_amount.clone()),
+                    token_in,
+                    base_token,
+                    Some(amount_in.clone()),
                     error.clone()
                 ),
                 context.correlation_id.clone(),
-                Some(user),
+                user,
             );
 
             error
         })?;
 
+    for (hop, swap_hop) in swap_route.hops.iter().enumerate() {
+        // Event: Swap token hop completed
+        event_record_service::create_event_record(
+            Event::swap_token_hop_completed(pool.id.clone(), hop as u32, swap_hop),
+            context.correlation_id.clone(),
+            user,
+        );
+
+        record_ledger_fee(
+            context,
+            Some(pool.id.clone()),
+            LedgerFeeOperation::Swap,
+            swap_hop.token_in,
+            Nat::from(swap_hop.ledger_fee),
+        );
+    }
+
     // Event: Swap token completed
     event_record_service::create_event_record(
         Event::swap_token_completed(
             pool.id.clone(),
-            pool.token1,
-            pool.token0,
-            Some(withdraw_response.token_1_amount.clone()),
-            Some(Nat::from(swap_response.amount_out)),
+            token_in,
+            base_token,
+            Some(amount_in.clone()),
+            Some(Nat::from(swap_route.amount_out)),
+        ),
+        context.correlation_id.clone(),
+        user,
+    );
+
+    Ok(Nat::from(swap_route.amount_out))
+}
+
+/// Moves liquidity withdrawn from a pool to another pool of the same token pair, swapping only the
+/// amount needed to match the destination pool ratio. The withdrawn amounts follow the token order of
+/// the source pool. `on_swapped` gets the amounts held after the swap, in the same order,
+/// before they are added to the destination pool.
+pub async fn migrate_liquidity(
+    context: Context,
+    token_0_amount: Nat,
+    token_1_amount: Nat,
+    from_pool: Pool,
+    to_pool: Pool,
+    swap_limits: &StrategySwapLimits,
+    on_swapped: impl FnOnce(Nat, Nat),
+) -> Result<MigrateLiquidityResponse, InternalError> {
+    allowance_service::with_allowance_cleanup(
+        context.clone(),
+        execute_migrate_liquidity(context, token_0_amount, token_1_amount, from_pool, to_pool, swap_limits, on_swapped)
+    ).await
+}
+
+/// Output per input token of swapping `amount_in` and the exchange quoting the largest part of it.
+/// The ledger fees of the swap are paid out of `amount_in`, so they lower the price.
+async fn quote_migration_swap(
+    token_in: CanisterId,
+    token_out: CanisterId,
+    amount_in: f64,
+) -> Result<(f64, ExchangeId), InternalError> {
+    let amount_in = Nat::from(amount_in as u128);
+    let deduction = ledger_fees::deduct_ledger_fees(token_in, &amount_in, APPROVED_TRANSFER_FEE_COUNT).await?;
+
+    let quote = swap_service::quote_swap_icrc2_optimal(
+        get_environment_provider_impls(),
+        token_in,
+        token_out,
+        deduction.amount,
+    ).await?;
+
+    if quote.amount_out == 0 {
+        return Err(InternalError::business_logic(
+            build_error_code(3200, 3, 2), // 3200 03 02
+            "liquidity_service::quote_migration_swap".to_string(),
+            "Swap of the migrated liquidity is quoted with no output".to_string(),
+            Some(HashMap::from([
+                ("token_in".to_string(), token_in.to_text()),
+                ("token_out".to_string(), token_out.to_text()),
+                ("amount_in".to_string(), amount_in.to_string()),
+            ])),
+        ));
+    }
+
+    Ok((quote.amount_out as f64 / nat_to_f64(&amount_in), quote.provider))
+}
+
+async fn execute_migrate_liquidity(
+    context: Context,
+    token_0_amount: Nat,
+    token_1_amount: Nat,
+    from_pool: Pool,
+    to_pool: Pool,
+    swap_limits: &StrategySwapLimits,
+    on_swapped: impl FnOnce(Nat, Nat),
+) -> Result<MigrateLiquidityResponse, InternalError> {
+    let user = context.user;
+
+    // Withdrawn amounts follow the token order of the source pool
+    let is_reversed_token_order = from_pool.token0 == to_pool.token1 && from_pool.token1 == to_pool.token0;
+    let (amount_0, amount_1) = match (
+        from_pool.token0 == to_pool.token0 && from_pool.token1 == to_pool.token1,
+        is_reversed_token_order,
+    ) {
+        (true, _) => (token_0_amount, token_1_amount),
+        (_, true) => (token_1_amount, token_0_amount),
+        _ => {
+            return Err(InternalError::business_logic(
+                build_error_code(3200, 3, 1), // 3200 03 01
+                "liquidity_service::migrate_liquidity".to_string(),
+                "Pools have different token pairs".to_string(),
+                Some(HashMap::from([
+                    ("from_pool".to_string(), from_pool.id.clone()),
+                    ("to_pool".to_string(), to_pool.id.clone()),
+                ])),
+            ));
+        }
+    };
+
+    let liquidity_client = get_liquidity_client_with_swap_limits(
+        get_environment_provider_impls(),
+        to_pool.token0,
+        to_pool.token1,
+        to_pool.provider,
+        swap_limits.for_pair(to_pool.token0, to_pool.token1),
+    ).await?;
+
+    let pool_ratio = liquidity_client.get_pool_ratio().await?;
+
+    // Swap estimated at the pool price, to know which side and about how much of it is swapped
+    let estimated_swap = LiquidityCalculator::calculate_migration_swap(
+        nat_to_f64(&amount_0),
+        nat_to_f64(&amount_1),
+        pool_ratio,
+        pool_ratio,
+    );
+
+    // Swap price as token1 per token0, quoted on the estimated swap amount after its ledger fees
+    let (swap_price, swap_provider) = if estimated_swap.token_0_for_swap > 0.0 {
+        quote_migration_swap(
+            to_pool.token0,
+            to_pool.token1,
+            estimated_swap.token_0_for_swap,
+        ).await?
+    } else if estimated_swap.token_1_for_swap > 0.0 {
+        let (price, provider) = quote_migration_swap(
+            to_pool.token1,
+            to_pool.token0,
+            estimated_swap.token_1_for_swap,
+        ).await?;
+
+        (1.0 / price, provider)
+    } else {
+        (pool_ratio, to_pool.provider)
+    };
+
+    let migration_swap = LiquidityCalculator::calculate_migration_swap(
+        nat_to_f64(&amount_0),
+        nat_to_f64(&amount_1),
+        pool_ratio,
+        swap_price,
+    );
+
+    // The swap has to pay out exactly the amount of the other token the destination pool ratio needs
+    let (token_in, token_out, swap_amount_in, swap_amount_out, available_amount_in) = if migration_swap.token_0_for_swap > 0.0 {
+        (
+            Some(to_pool.token0),
+            Some(to_pool.token1),
+            Nat::from(migration_swap.token_0_for_swap as u128),
+            Nat::from((migration_swap.token_0_for_swap * swap_price) as u128),
+            amount_0.clone(),
+        )
+    } else if migration_swap.token_1_for_swap > 0.0 {
+        (
+            Some(to_pool.token1),
+            Some(to_pool.token0),
+            Nat::from(migration_swap.token_1_for_swap as u128),
+            Nat::from((migration_swap.token_1_for_swap / swap_price) as u128),
+            amount_1.clone(),
+        )
+    } else {
+        (None, None, Nat::from(0u64), Nat::from(0u64), Nat::from(0u64))
+    };
+
+    let (amount_0, amount_1, swap_amount_in) = match (token_in, token_out) {
+        (Some(token_in), Some(token_out)) => {
+            // Event: Swap token started
+            event_record_service::create_event_record(
+                Event::swap_token_started(
+                    to_pool.id.clone(),
+                    token_in,
+                    token_out,
+                    Some(swap_amount_in.clone()),
+                ),
+                context.correlation_id.clone(),
+                user,
+            );
+
+            let limits = swap_limits.for_pair(token_in, token_out);
+
+            // The swap may take more than the estimated input within the slippage tolerance,
+            // but never more than the withdrawn amount of the token
+            let max_amount_in = (swap_amount_in.clone()
+                * Nat::from(BPS_DENOMINATOR + limits.slippage_tolerance_bps)
+                / Nat::from(BPS_DENOMINATOR))
+                .min(available_amount_in);
+
+            // Swap only the excess of one token for the amount matching the destination pool ratio
+            let swap_response = swap_service::swap_icrc2_exact_output(
+                get_environment_provider_impls(),
+                token_in,
+                token_out,
+                swap_amount_out.clone(),
+                max_amount_in,
+                swap_provider,
+                limits,
+            ).await
+                .map_err(|error| {
+                    // Event: Swap token failed
+                    event_record_service::create_event_record(
+                        Event::swap_token_failed(
+                            to_pool.id.clone(),
+                            token_in,
+                            token_out,
+                            Some(swap_amount_in.clone()),
+                            error.clone()
+                        ),
+                        context.correlation_id.clone(),
+                        user,
+                    );
+
+                    error
+                })?;
+
+            // The input moved to the exchange and the ledger fees of moving it
+            let spent_amount_in = Nat::from(swap_response.route.amount_in() + swap_response.ledger_fee);
+
+            // Event: Swap token completed
+            event_record_service::create_event_record(
+                Event::swap_token_completed(
+                    to_pool.id.clone(),
+                    token_in,
+                    token_out,
+                    Some(spent_amount_in.clone()),
+                    Some(Nat::from(swap_response.amount_out)),
+                ),
+                context.correlation_id.clone(),
+                user,
+            );
+
+            record_ledger_fee(
+                &context,
+                Some(to_pool.id.clone()),
+                LedgerFeeOperation::Swap,
+                token_in,
+                Nat::from(swap_response.ledger_fee),
+            );
+
+            if token_in == to_pool.token0 {
+                (amount_0 - spent_amount_in.clone(), amount_1 + swap_response.amount_out, spent_amount_in)
+            } else {
+                (amount_0 + swap_response.amount_out, amount_1 - spent_amount_in.clone(), spent_amount_in)
+            }
+        }
+        _ => (amount_0, amount_1, swap_amount_in),
+    };
+
+    if is_reversed_token_order {
+        on_swapped(amount_1.clone(), amount_0.clone());
+    } else {
+        on_swapped(amount_0.clone(), amount_1.clone());
+    }
+
+    // Event: Add liquidity to pool started
+    event_record_service::create_event_record(
+        Event::add_liquidity_to_pool_started(
+            to_pool.id.clone(),
+            Some(amount_0.clone()),
+            Some(amount_1.clone()),
+        ),
+        context.correlation_id.clone(),
+        user,
+    );
+
+    let add_liquidity_response = liquidity_client.add_liquidity_to_pool_with_amounts(
+        amount_0.clone(),
+        amount_1.clone(),
+    ).await
+        .map_err(|error| {
+            // Event: Add liquidity to pool failed
+            event_record_service::create_event_record(
+                Event::add_liquidity_to_pool_failed(
+                    to_pool.id.clone(),
+                    Some(amount_0.clone()),
+                    error.clone(),
+                ),
+                context.correlation_id.clone(),
+                user,
+            );
+            error
+        })?;
+
+    // Event: Add liquidity to pool completed
+    event_record_service::create_event_record(
+        Event::add_liquidity_to_pool_completed(
+            to_pool.id.clone(),
+            Some(add_liquidity_response.token_0_amount.clone()),
+            Some(add_liquidity_response.token_1_amount.clone()),
         ),
         context.correlation_id.clone(),
-        Some(user),
+        user,
     );
 
-    let amount_0_to_withdraw = withdraw_response.token_0_amount + swap_response.amount_out;
+    record_ledger_fees(&context, &to_pool, LedgerFeeOperation::AddLiquidity, &add_liquidity_response.ledger_fees);
+
+    Ok(MigrateLiquidityResponse {
+        token_0_amount: add_liquidity_response.token_0_amount,
+        token_1_amount: add_liquidity_response.token_1_amount,
+        token_in,
+        swap_amount_in,
+        naive_swap_volume: Nat::from(migration_swap.naive_swap_volume as u128),
+        saved_swap_volume: Nat::from(migration_swap.saved_swap_volume as u128),
+        position_id: add_liquidity_response.position_id,
+    })
+}
+
+pub async fn quote_add_liquidity_to_pool(
+    amount: Nat,
+    pool: Pool
+) -> Result<QuoteAddLiquidityResponse, InternalError> {
+    let liquidity_client = get_liquidity_client(
+        get_environment_provider_impls(),
+        pool.token0,
+        pool.token1,
+        pool.provider
+    ).await?;
+
+    liquidity_client.quote_add_liquidity(amount).await
+}
+
+/// Quotes withdrawal of liquidity from the pool and swap of the other token to `base_token`.
+/// Amounts and fees of the withdraw quote are ordered as (base token, other token).
+pub async fn quote_withdraw_liquidity_from_pool_and_swap(
+    total_shares: Nat,
+    shares: Nat,
+    pool: Pool,
+    base_token: CanisterId,
+) -> Result<(QuoteWithdrawLiquidityResponse, MultiHopRoute), InternalError> {
+    let liquidity_client = get_liquidity_client(
+        get_environment_provider_impls(),
+        pool.token0,
+        pool.token1,
+        pool.provider
+    ).await?;
+
+    let withdraw_quote = liquidity_client.quote_withdraw_liquidity(
+        total_shares,
+        shares,
+    ).await?;
+
+    let (token_in, withdraw_quote) = if pool.token0 == base_token {
+        (pool.token1, withdraw_quote)
+    } else {
+        (pool.token0, QuoteWithdrawLiquidityResponse {
+            token_0_amount: withdraw_quote.token_1_amount,
+            token_1_amount: withdraw_quote.token_0_amount,
+            fees: LiquidityFees {
+                token_0_fee: withdraw_quote.fees.token_1_fee,
+                token_1_fee: withdraw_quote.fees.token_0_fee,
+            },
+        })
+    };
 
-    Ok(amount_0_to_withdraw)
+    // Quote swap of withdrawn token to base token, directly if the tokens have a pool
+    let swap_quote = swap_service::quote_swap_icrc2_direct_or_multi_hop(
+        get_environment_provider_impls(),
+        token_in,
+        base_token,
+        withdraw_quote.token_1_amount.clone(),
+    ).await?;
+
+    Ok((withdraw_quote, swap_quote))
+}
+
+/// Records the ledger fees paid for the tokens of the pool
+fn record_ledger_fees(
+    context: &Context,
+    pool: &Pool,
+    operation: LedgerFeeOperation,
+    ledger_fees: &LiquidityFees,
+) {
+    record_ledger_fee(context, Some(pool.id.clone()), operation.clone(), pool.token0, ledger_fees.token_0_fee.clone());
+    record_ledger_fee(context, Some(pool.id.clone()), operation, pool.token1, ledger_fees.token_1_fee.clone());
+}
+
+/// Records the ledger fees paid by the operation, nothing is recorded if no fee was paid
+pub fn record_ledger_fee(
+    context: &Context,
+    pool_id: Option<String>,
+    operation: LedgerFeeOperation,
+    token: CanisterId,
+    amount: Nat,
+) {
+    if amount == 0u8 {
+        return;
+    }
+
+    // Event: Ledger fees paid
+    event_record_service::create_event_record(
+        Event::ledger_fees_paid(pool_id, operation, token, amount),
+        context.correlation_id.clone(),
+        context.user,
+    );
 }
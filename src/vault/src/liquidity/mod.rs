@@ -1 +1,3 @@
 pub mod liquidity_service;
+pub mod price_guard_service;
+pub mod allowance_service;
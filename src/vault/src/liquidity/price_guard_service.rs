@@ -0,0 +1,150 @@
+use candid::Nat;
+use std::collections::HashMap;
+
+use types::CanisterId;
+use types::context::Context;
+use types::swap_tokens::{PriceReference, PriceReferenceSource, SwapLimits, SwapRouteLeg};
+use errors::internal_error::error::{InternalError, build_error_code};
+use swap::price_guard;
+use swap::swap_service;
+use utils::util::{current_timestamp, nat_to_u128};
+
+use crate::pools::pool::Pool;
+use crate::pool_stats::pool_stats_service;
+use crate::event_records::event_record_service;
+use crate::event_records::event_record::Event;
+use crate::utils::provider_impls::get_environment_provider_impls;
+
+/// Pool snapshots older than this are not used as a reference price
+const MAX_SNAPSHOT_AGE: u64 = 86_400; // 1 day
+
+/// Quotes the swap on the exchange of the pool and checks the quote against the reference price
+pub async fn check_pool_price(
+    context: &Context,
+    pool: &Pool,
+    token_in: CanisterId,
+    token_out: CanisterId,
+    amount_in: u128,
+    limits: &SwapLimits,
+) -> Result<(), InternalError> {
+    let quote = swap_service::quote_swap_icrc2(
+        get_environment_provider_impls(),
+        token_in,
+        token_out,
+        Nat::from(amount_in),
+        pool.provider,
+    ).await?;
+
+    let quoted_leg = SwapRouteLeg {
+        provider: pool.provider,
+        amount_in,
+        amount_out: quote.amount_out,
+    };
+
+    check_quoted_price(context, pool, token_in, token_out, &quoted_leg, limits).await
+}
+
+/// Checks the quote of the execution exchange against the price of the other exchange,
+/// or of the latest pool snapshot if the other exchange can not quote the swap.
+/// Every decision is recorded as an event, a quote outside of the band is rejected.
+pub async fn check_quoted_price(
+    context: &Context,
+    pool: &Pool,
+    token_in: CanisterId,
+    token_out: CanisterId,
+    quoted_leg: &SwapRouteLeg,
+    limits: &SwapLimits,
+) -> Result<(), InternalError> {
+    let reference = match price_guard::quote_reference_price(
+        get_environment_provider_impls(),
+        token_in,
+        token_out,
+        quoted_leg.amount_in,
+        quoted_leg.provider,
+    ).await {
+        Some(reference) => Some(reference),
+        None => snapshot_reference_price(context, pool, token_in, token_out).await,
+    };
+
+    let quoted_price = price_guard::quote_price(quoted_leg.amount_in, quoted_leg.amount_out)
+        .ok_or_else(|| InternalError::business_logic(
+            build_error_code(3500, 3, 1), // 3500 03 01
+            "price_guard_service::check_quoted_price".to_string(),
+            "Swap is quoted with no price".to_string(),
+            Some(HashMap::from([
+                ("pool_id".to_string(), pool.id.clone()),
+                ("token_in".to_string(), token_in.to_text()),
+                ("token_out".to_string(), token_out.to_text()),
+                ("amount_in".to_string(), quoted_leg.amount_in.to_string()),
+                ("amount_out".to_string(), quoted_leg.amount_out.to_string()),
+                ("provider".to_string(), quoted_leg.provider.to_string()),
+            ])),
+        ))?;
+
+    let check = price_guard::evaluate(
+        quoted_price,
+        reference,
+        limits.max_price_deviation_bps,
+    );
+
+    // Event: Swap price checked
+    event_record_service::create_event_record(
+        Event::swap_price_checked(
+            pool.id.clone(),
+            token_in,
+            token_out,
+            Nat::from(quoted_leg.amount_in),
+            quoted_leg.provider,
+            check.clone(),
+        ),
+        context.correlation_id.clone(),
+        context.user,
+    );
+
+    price_guard::ensure_passed(&check)
+}
+
+/// Price of `token_out` per `token_in` of the latest recent pool snapshot. A full range position holds
+/// the tokens in the ratio of the pool price, so the position amounts give the price.
+/// There is no snapshot price if the pool does not pair the two tokens.
+async fn snapshot_reference_price(
+    context: &Context,
+    pool: &Pool,
+    token_in: CanisterId,
+    token_out: CanisterId,
+) -> Option<PriceReference> {
+    let is_token0_in = if token_in == pool.token0 && token_out == pool.token1 {
+        true
+    } else if token_in == pool.token1 && token_out == pool.token0 {
+        false
+    } else {
+        return None;
+    };
+
+    let pools_snapshots = pool_stats_service::get_pools_snapshots(context, vec![pool.id.clone()]).await;
+
+    let snapshot = pools_snapshots.get(&pool.id)?
+        .iter()
+        .filter(|snapshot| snapshot.position_data.is_some())
+        .max_by_key(|snapshot| snapshot.timestamp)?;
+
+    if current_timestamp().saturating_sub(snapshot.timestamp) > MAX_SNAPSHOT_AGE {
+        return None;
+    }
+
+    let position_data = snapshot.position_data.as_ref()?;
+    let (amount_in, amount_out) = if is_token0_in {
+        (&position_data.amount0, &position_data.amount1)
+    } else {
+        (&position_data.amount1, &position_data.amount0)
+    };
+
+    price_guard::quote_price(nat_to_u128(amount_in), nat_to_u128(amount_out))
+        .map(|price| PriceReference {
+            source: PriceReferenceSource::PoolSnapshot {
+                pool_id: pool.id.clone(),
+                timestamp: snapshot.timestamp,
+            },
+            price,
+        })
+}
@@ -1,15 +1,34 @@
 use std::collections::HashMap;
+use ic_cdk::api::in_replicated_execution;
 use ic_cdk::call;
 
-use types::pool_stats::PoolMetrics;
+use types::context::Context;
+use types::pool_stats::{PoolMetrics, PoolSnapshot};
 use utils::constants::POOL_STATS_CANISTER_ID;
 
-pub async fn get_pool_metrics(pool_ids: Vec<String>) -> HashMap<String, PoolMetrics> {
+/// Pool metrics requested within the operation of `context`, the pool stats canister continues its trace.
+/// Empty within a composite query, which can not call the update method of the pool stats canister
+pub async fn get_pool_metrics(context: &Context, pool_ids: Vec<String>) -> HashMap<String, PoolMetrics> {
+    if !in_replicated_execution() {
+        return HashMap::new();
+    }
+
     let (pool_metrics,): (HashMap<String, PoolMetrics>,) = call(
         *POOL_STATS_CANISTER_ID,
         "get_pool_metrics",
-        (pool_ids,)
+        (pool_ids, Some(context.trace()))
     ).await.expect("Pool stats canister call failed");
 
     pool_metrics
 }
+
+/// Snapshots of the pools, empty if the pool stats canister is not reachable
+pub async fn get_pools_snapshots(context: &Context, pool_ids: Vec<String>) -> HashMap<String, Vec<PoolSnapshot>> {
+    call::<_, (HashMap<String, Vec<PoolSnapshot>>,)>(
+        *POOL_STATS_CANISTER_ID,
+        "get_pools_snapshots",
+        (pool_ids, Some(context.trace()))
+    ).await
+        .map(|(pools_snapshots,)| pools_snapshots)
+        .unwrap_or_default()
+}
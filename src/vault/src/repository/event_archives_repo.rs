@@ -0,0 +1,33 @@
+use std::cell::RefCell;
+use candid::Principal;
+
+use errors::internal_error::error::InternalError;
+use event_records::archive::{EventArchive, EventArchiveState, EventRetentionPolicy};
+
+thread_local! {
+    pub static EVENT_ARCHIVES: RefCell<EventArchiveState> = RefCell::new(EventArchiveState::default());
+}
+
+pub fn get_event_archive_state() -> EventArchiveState {
+    EVENT_ARCHIVES.with(|state| state.borrow().clone())
+}
+
+pub fn set_event_archive_state(state: EventArchiveState) {
+    EVENT_ARCHIVES.with(|current| current.replace(state));
+}
+
+pub fn get_event_archives() -> Vec<EventArchive> {
+    EVENT_ARCHIVES.with(|state| state.borrow().archives.clone())
+}
+
+pub fn add_event_archive(canister_id: Principal, start_id: u64) -> Result<EventArchive, InternalError> {
+    EVENT_ARCHIVES.with(|state| state.borrow_mut().add_archive(canister_id, start_id))
+}
+
+pub fn set_retention_policy(policy: EventRetentionPolicy) {
+    EVENT_ARCHIVES.with(|state| state.borrow_mut().retention_policy = policy);
+}
+
+pub fn set_archive_wasm(wasm: Vec<u8>) {
+    EVENT_ARCHIVES.with(|state| state.borrow_mut().archive_wasm = wasm);
+}
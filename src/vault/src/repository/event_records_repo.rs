@@ -1,71 +1,71 @@
 use std::cell::RefCell;
 
+use event_records::event_chain;
+use event_records::event_store::EventStore;
+
 use crate::event_records::event_record::EventRecord;
-use crate::types::types::{ListItemsPaginationRequest, SortOrder};
+use crate::types::types::{CertifiedEventRecords, EventRecordsPage, EventRecordsQuery};
+use crate::repository::memory::{
+    get_memory,
+    Memory,
+    EVENT_RECORDS_MEMORY_ID,
+    EVENT_RECORDS_LOOKUP_MEMORY_ID,
+    NEXT_EVENT_RECORD_ID_MEMORY_ID,
+};
 
 thread_local! {
-    pub static EVENT_RECORDS: RefCell<Vec<EventRecord>> = RefCell::new(Default::default());
+    pub static EVENT_RECORDS: RefCell<EventStore<EventRecord, Memory>> = RefCell::new(
+        EventStore::init(
+            get_memory(EVENT_RECORDS_MEMORY_ID),
+            get_memory(EVENT_RECORDS_LOOKUP_MEMORY_ID),
+            get_memory(NEXT_EVENT_RECORD_ID_MEMORY_ID),
+        )
+    );
 }
 
-pub fn save_event_record(event: EventRecord) {
-    EVENT_RECORDS.with(|events| events.borrow_mut().push(event));
+/// Links the record to the latest one and returns it
+pub fn save_event_record(event: EventRecord) -> EventRecord {
+    EVENT_RECORDS.with(|events| events.borrow_mut().append(event))
 }
 
-pub fn get_event_records_count() -> u64 {
-    EVENT_RECORDS.with(|events| events.borrow().len() as u64)
+/// Publishes the hash of the latest record as certified data
+pub fn certify_event_records() {
+    EVENT_RECORDS.with(|events| event_chain::certify_event_chain_tip(&events.borrow()));
 }
 
-pub fn get_event_records(request: ListItemsPaginationRequest) -> Vec<EventRecord> {
-    let skip = ((request.page - 1) * request.page_size) as usize;
-    let limit = request.page_size as usize;
-
-    EVENT_RECORDS.with(|event_records| {
-        let mut records: Vec<EventRecord> = event_records
-            .borrow()
-            .iter()
-            .filter(|record| {
-                if let Some(search) = &request.search {
-                    record.0.event.type_str().contains(search)
-                } else {
-                    true
-                }
-            })
-            .cloned()
-            .collect();
-
-        // Sort by timestamp
-        match request.sort_order {
-            SortOrder::Asc => records.sort_by(|a, b| {
-                a.0.timestamp.cmp(&b.0.timestamp)
-            }),
-            SortOrder::Desc => records.sort_by(|a, b| {
-                b.0.timestamp.cmp(&a.0.timestamp)
-            }),
-        }
+/// Certifies the latest record once the current message is done
+pub fn schedule_event_records_certification() {
+    event_chain::schedule_event_chain_certification(&EVENT_RECORDS);
+}
+
+/// Ids keep increasing after the oldest records are archived
+pub fn next_event_record_id() -> u64 {
+    EVENT_RECORDS.with(|events| events.borrow().next_id())
+}
+
+pub fn get_event_records(query: &EventRecordsQuery) -> EventRecordsPage {
+    EVENT_RECORDS.with(|events| events.borrow().query(query))
+}
 
-        records
-            .into_iter()
-            .skip(skip)
-            .take(limit)
-            .collect()
-    })
+pub fn get_certified_event_records(start_id: u64, limit: u64) -> CertifiedEventRecords {
+    EVENT_RECORDS.with(|events| event_chain::get_certified_event_records(&events.borrow(), start_id, limit))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use types::CanisterId;
-    use candid::Nat;
-    use std::time::{SystemTime, UNIX_EPOCH};
+    use candid::{Nat, Principal};
 
     use event_records::generic_event_record::GenericEventRecord;
+    use event_records::event_chain::{hash_event_record, verify_event_chain};
     use errors::internal_error::error::{InternalError, build_error_code};
 
-    use crate::types::types::{ListItemsPaginationRequest, SortOrder};
     use crate::event_records::event_record::{EventRecord, Event};
+    use event_records::event_query::{EventRecordsFilter, SortOrder};
 
-    fn mock_event_with_type(event_type: &str, timestamp: u64) -> EventRecord {
-        let event = match event_type {
+    fn mock_event(event_type: &str) -> Event {
+        match event_type {
             "AddLiquidityToPoolStarted" => Event::add_liquidity_to_pool_started(
                 "pool1".to_string(),
                 Some(Nat::from(1000_u64)),
@@ -76,10 +76,16 @@ mod tests {
                 Some("pool1".to_string()),
                 Some(Nat::from(100_u64)),
             ),
+            "StrategyRebalanceCompleted" => Event::strategy_rebalance_completed(
+                "strategy2".to_string(),
+                Some("pool1".to_string()),
+                Some("pool2".to_string()),
+                Some(1),
+            ),
             _ => Event::swap_token_failed(
                 "poolX".to_string(),
                 CanisterId::from_text("aaaaa-aa").unwrap(),
-                CanisterId::from_text("bbbbb-bb").unwrap(),
+                CanisterId::from_text("2vxsx-fae").unwrap(),
                 Some(Nat::from(1000_u64)),
                 InternalError::not_found(
                     build_error_code(0000, 00, 00),
@@ -88,19 +94,48 @@ mod tests {
                     None,
                 ),
             ),
-        };
+        }
+    }
+
+    fn mock_event_with_type(event_type: &str, timestamp: u64) -> EventRecord {
+        mock_event_record(mock_event(event_type), timestamp, "", None)
+    }
 
+    fn mock_event_record(event: Event, timestamp: u64, correlation_id: &str, user: Option<Principal>) -> EventRecord {
         EventRecord(GenericEventRecord {
-            id: 0,
+            id: next_event_record_id(),
             timestamp,
             event,
-            correlation_id: "".to_string(),
-            user: None,
+            correlation_id: correlation_id.to_string(),
+            user,
+            prev_hash: None,
+            payload: Vec::new(),
         })
     }
 
-    fn timestamp(offset_secs: u64) -> u64 {
-        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + offset_secs
+    fn query(filter: EventRecordsFilter, cursor: Option<u64>, limit: u64, sort_order: SortOrder) -> EventRecordsQuery {
+        EventRecordsQuery {
+            filter,
+            cursor,
+            limit,
+            sort_order,
+        }
+    }
+
+    fn all_records(sort_order: SortOrder) -> EventRecordsQuery {
+        query(EventRecordsFilter::default(), None, 10, sort_order)
+    }
+
+    fn timestamps(page: &EventRecordsPage) -> Vec<u64> {
+        page.items.iter().map(|record| record.0.timestamp).collect()
+    }
+
+    fn user(text: &str) -> Principal {
+        Principal::from_text(text).unwrap()
+    }
+
+    fn clear_event_records() {
+        EVENT_RECORDS.with(|events| events.borrow_mut().clear());
     }
 
     mod save_event_record {
@@ -108,7 +143,7 @@ mod tests {
 
         #[test]
         fn saves_event_correctly() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+            clear_event_records();
 
             let event = mock_event_with_type(
                 "StrategyDepositStarted",
@@ -116,36 +151,40 @@ mod tests {
             );
             save_event_record(event.clone());
 
-            let count = get_event_records_count();
-            assert_eq!(count, 1);
+            assert_eq!(next_event_record_id(), 1);
 
-            let all = get_event_records(ListItemsPaginationRequest {
-                page: 1,
-                page_size: 10,
-                search: None,
-                sort_order: SortOrder::Asc,
-            });
+            let all = get_event_records(&all_records(SortOrder::Asc));
 
-            assert_eq!(all.len(), 1);
-            assert_eq!(all[0].0.timestamp, 111);
-            assert_eq!(all[0].0.event.type_str(), "StrategyDepositStarted");
+            assert_eq!(all.items.len(), 1);
+            assert_eq!(all.total, 1);
+            assert_eq!(all.items[0].0.timestamp, 111);
+            assert_eq!(all.items[0].0.event.type_str(), "StrategyDepositStarted");
+        }
+
+        #[test]
+        fn links_record_to_previous_record() {
+            clear_event_records();
+
+            let first = save_event_record(mock_event_with_type("StrategyDepositStarted", 1));
+            let second = save_event_record(mock_event_with_type("StrategyDepositCompleted", 2));
+
+            assert_eq!(first.0.prev_hash, None);
+            assert_eq!(second.0.prev_hash, Some(hash_event_record(&first.0)));
+            assert_eq!(verify_event_chain(&[first.0, second.0]).unwrap(), EVENT_RECORDS.with(|events| events.borrow().tip_hash()));
         }
     }
 
-    mod get_event_records_count {
+    mod next_event_record_id {
         use super::*;
 
         #[test]
-        fn returns_correct_count() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
-
-            let event1 = mock_event_with_type("AddLiquidityToPoolStarted", 1);
-            let event2 = mock_event_with_type("AddLiquidityToPoolStarted", 2);
+        fn follows_saved_records() {
+            clear_event_records();
 
-            save_event_record(event1);
-            save_event_record(event2);
+            save_event_record(mock_event_with_type("AddLiquidityToPoolStarted", 1));
+            save_event_record(mock_event_with_type("AddLiquidityToPoolStarted", 2));
 
-            assert_eq!(get_event_records_count(), 2);
+            assert_eq!(next_event_record_id(), 2);
         }
     }
 
@@ -153,138 +192,153 @@ mod tests {
         use super::*;
 
         #[test]
-        fn returns_paginated_records_sorted_asc() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+        fn returns_records_in_recorded_order() {
+            clear_event_records();
 
-            let event1 = mock_event_with_type("AddLiquidityToPoolStarted", 20);
-            let event2 = mock_event_with_type("StrategyDepositStarted", 10);
+            save_event_record(mock_event_with_type("AddLiquidityToPoolStarted", 10));
+            save_event_record(mock_event_with_type("StrategyDepositStarted", 20));
+            save_event_record(mock_event_with_type("SwapTokenFailed", 30));
+
+            assert_eq!(timestamps(&get_event_records(&all_records(SortOrder::Asc))), vec![10, 20, 30]);
+            assert_eq!(timestamps(&get_event_records(&all_records(SortOrder::Desc))), vec![30, 20, 10]);
+        }
+
+        #[test]
+        fn filters_by_event_type() {
+            clear_event_records();
 
-            save_event_record(event1);
-            save_event_record(event2);
+            save_event_record(mock_event_with_type("StrategyDepositStarted", 1));
+            save_event_record(mock_event_with_type("AddLiquidityToPoolStarted", 2));
 
-            let result = get_event_records(ListItemsPaginationRequest {
-                page: 1,
-                page_size: 10,
-                search: None,
-                sort_order: SortOrder::Asc,
-            });
+            let filter = EventRecordsFilter {
+                event_type: Some("StrategyDepositStarted".to_string()),
+                ..Default::default()
+            };
+            let result = get_event_records(&query(filter, None, 10, SortOrder::Asc));
 
-            assert_eq!(result.len(), 2);
-            assert_eq!(result[0].0.timestamp, 10);
-            assert_eq!(result[1].0.timestamp, 20);
+            assert_eq!(result.total, 1);
+            assert_eq!(result.items[0].0.event.type_str(), "StrategyDepositStarted");
         }
 
         #[test]
-        fn returns_paginated_records_sorted_desc() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+        fn filters_by_user_and_correlation_id() {
+            clear_event_records();
 
-            let event1 = mock_event_with_type("StrategyDepositStarted", 5);
-            let event2 = mock_event_with_type("AddLiquidityToPoolStarted", 10);
+            let alice = user("aaaaa-aa");
+            let bob = user("2vxsx-fae");
 
-            save_event_record(event1);
-            save_event_record(event2);
+            save_event_record(mock_event_record(mock_event("StrategyDepositStarted"), 1, "1", Some(alice)));
+            save_event_record(mock_event_record(mock_event("SwapTokenFailed"), 2, "1", Some(alice)));
+            save_event_record(mock_event_record(mock_event("StrategyDepositStarted"), 3, "2", Some(bob)));
+            save_event_record(mock_event_record(mock_event("StrategyDepositStarted"), 4, "3", Some(alice)));
 
-            let result = get_event_records(ListItemsPaginationRequest {
-                page: 1,
-                page_size: 10,
-                search: None,
-                sort_order: SortOrder::Desc,
-            });
+            let by_user = EventRecordsFilter { user: Some(alice), ..Default::default() };
+            assert_eq!(timestamps(&get_event_records(&query(by_user, None, 10, SortOrder::Asc))), vec![1, 2, 4]);
 
-            assert_eq!(result.len(), 2);
-            assert_eq!(result[0].0.timestamp, 10);
-            assert_eq!(result[1].0.timestamp, 5);
+            let by_correlation_id = EventRecordsFilter { correlation_id: Some("1".to_string()), ..Default::default() };
+            assert_eq!(timestamps(&get_event_records(&query(by_correlation_id, None, 10, SortOrder::Asc))), vec![1, 2]);
         }
 
         #[test]
-        fn filters_by_event_type() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+        fn filters_by_strategy_and_pool() {
+            clear_event_records();
 
-            let event1 = mock_event_with_type("StrategyDepositStarted", 1);
-            let event2 = mock_event_with_type("AddLiquidityToPoolStarted", 2);
+            save_event_record(mock_event_with_type("StrategyDepositStarted", 1));
+            save_event_record(mock_event_with_type("StrategyRebalanceCompleted", 2));
+            save_event_record(mock_event_with_type("SwapTokenFailed", 3));
 
-            save_event_record(event1);
-            save_event_record(event2);
+            let by_strategy = EventRecordsFilter { strategy_id: Some("strategy2".to_string()), ..Default::default() };
+            assert_eq!(timestamps(&get_event_records(&query(by_strategy, None, 10, SortOrder::Asc))), vec![2]);
 
-            let result = get_event_records(ListItemsPaginationRequest {
-                page: 1,
-                page_size: 10,
-                search: Some("Deposit".to_string()),
-                sort_order: SortOrder::Asc,
-            });
+            // Rebalances are found by both the previous and the new pool
+            let by_pool = EventRecordsFilter { pool_id: Some("pool1".to_string()), ..Default::default() };
+            assert_eq!(timestamps(&get_event_records(&query(by_pool, None, 10, SortOrder::Asc))), vec![1, 2]);
 
-            assert_eq!(result.len(), 1);
-            assert_eq!(result[0].0.event.type_str(), "StrategyDepositStarted");
+            let by_new_pool = EventRecordsFilter { pool_id: Some("pool2".to_string()), ..Default::default() };
+            assert_eq!(timestamps(&get_event_records(&query(by_new_pool, None, 10, SortOrder::Asc))), vec![2]);
         }
 
         #[test]
-        fn handles_pagination_correctly() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+        fn filters_by_time_range() {
+            clear_event_records();
 
-            for i in 1..=15 {
-                let event = mock_event_with_type("AddLiquidityToPoolStarted", i);
-                save_event_record(event);
+            for timestamp in [10, 20, 30, 40, 50] {
+                save_event_record(mock_event_with_type("AddLiquidityToPoolStarted", timestamp));
             }
 
-            let page1 = get_event_records(ListItemsPaginationRequest {
-                page: 1,
-                page_size: 5,
-                search: None,
-                sort_order: SortOrder::Asc,
-            });
-
-            let page2 = get_event_records(ListItemsPaginationRequest {
-                page: 2,
-                page_size: 5,
-                search: None,
-                sort_order: SortOrder::Asc,
-            });
-
-            assert_eq!(page1.len(), 5);
-            assert_eq!(page2.len(), 5);
-            assert!(page1[0].0.timestamp < page2[0].0.timestamp);
+            let filter = EventRecordsFilter {
+                from_timestamp: Some(20),
+                to_timestamp: Some(40),
+                ..Default::default()
+            };
+            let result = get_event_records(&query(filter, None, 10, SortOrder::Asc));
+
+            assert_eq!(timestamps(&result), vec![20, 30, 40]);
+            assert_eq!(result.total, 3);
         }
 
         #[test]
-        fn sort_order_asc_and_desc() {
-            EVENT_RECORDS.with(|events| events.borrow_mut().clear());
-
-            let ts1 = timestamp(10);
-            let ts2 = timestamp(20);
-            let ts3 = timestamp(30);
-
-            let event1 = mock_event_with_type("AddLiquidityToPoolStarted", ts2);
-            let event2 = mock_event_with_type("AddLiquidityToPoolStarted", ts3);
-            let event3 = mock_event_with_type("AddLiquidityToPoolStarted", ts1);
-
-            save_event_record(event1);
-            save_event_record(event2);
-            save_event_record(event3);
-
-            let request_asc = ListItemsPaginationRequest {
-                page: 1,
-                page_size: 10,
-                sort_order: SortOrder::Asc,
-                search: None,
-            };
+        fn combines_filters() {
+            clear_event_records();
+
+            let alice = user("aaaaa-aa");
+
+            save_event_record(mock_event_record(mock_event("StrategyDepositStarted"), 10, "1", Some(alice)));
+            save_event_record(mock_event_record(mock_event("SwapTokenFailed"), 20, "1", Some(alice)));
+            save_event_record(mock_event_record(mock_event("StrategyDepositStarted"), 30, "2", None));
+            save_event_record(mock_event_record(mock_event("StrategyDepositStarted"), 40, "3", Some(alice)));
 
-            let request_desc = ListItemsPaginationRequest {
-                page: 1,
-                page_size: 10,
-                sort_order: SortOrder::Desc,
-                search: None,
+            let filter = EventRecordsFilter {
+                user: Some(alice),
+                event_type: Some("StrategyDepositStarted".to_string()),
+                from_timestamp: Some(20),
+                ..Default::default()
             };
+            let result = get_event_records(&query(filter, None, 10, SortOrder::Asc));
 
-            let asc = get_event_records(request_asc);
-            let desc = get_event_records(request_desc);
+            assert_eq!(timestamps(&result), vec![40]);
+            assert_eq!(result.total, 1);
+        }
+
+        #[test]
+        fn paginates_with_cursor() {
+            clear_event_records();
 
-            assert_eq!(asc[0].0.timestamp, ts1);
-            assert_eq!(asc[1].0.timestamp, ts2);
-            assert_eq!(asc[2].0.timestamp, ts3);
+            for timestamp in 1..=12 {
+                save_event_record(mock_event_with_type("AddLiquidityToPoolStarted", timestamp));
+            }
+
+            let page1 = get_event_records(&query(EventRecordsFilter::default(), None, 5, SortOrder::Asc));
+            let page2 = get_event_records(&query(EventRecordsFilter::default(), page1.next_cursor, 5, SortOrder::Asc));
+            let page3 = get_event_records(&query(EventRecordsFilter::default(), page2.next_cursor, 5, SortOrder::Asc));
+
+            assert_eq!(timestamps(&page1), vec![1, 2, 3, 4, 5]);
+            assert_eq!(timestamps(&page2), vec![6, 7, 8, 9, 10]);
+            assert_eq!(timestamps(&page3), vec![11, 12]);
+            assert_eq!(page3.next_cursor, None);
+            assert!([&page1, &page2, &page3].iter().all(|page| page.total == 12));
+        }
+
+        #[test]
+        fn paginates_filtered_records_in_descending_order() {
+            clear_event_records();
+
+            for timestamp in 1..=6 {
+                let event_type = if timestamp % 2 == 0 { "StrategyDepositStarted" } else { "SwapTokenFailed" };
+                save_event_record(mock_event_with_type(event_type, timestamp));
+            }
+
+            let filter = EventRecordsFilter {
+                event_type: Some("StrategyDepositStarted".to_string()),
+                ..Default::default()
+            };
+            let page1 = get_event_records(&query(filter.clone(), None, 2, SortOrder::Desc));
+            let page2 = get_event_records(&query(filter, page1.next_cursor, 2, SortOrder::Desc));
 
-            assert_eq!(desc[0].0.timestamp, ts3);
-            assert_eq!(desc[1].0.timestamp, ts2);
-            assert_eq!(desc[2].0.timestamp, ts1);
+            assert_eq!(timestamps(&page1), vec![6, 4]);
+            assert_eq!(timestamps(&page2), vec![2]);
+            assert_eq!(page2.next_cursor, None);
+            assert_eq!(page1.total, 3);
         }
     }
 }
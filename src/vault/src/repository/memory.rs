@@ -0,0 +1,23 @@
+use std::cell::RefCell;
+use ic_stable_structures::DefaultMemoryImpl;
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+
+pub type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+/// Config, runtime config, token registry and event archives, written in `pre_upgrade`
+pub const UPGRADES_MEMORY_ID: MemoryId = MemoryId::new(0);
+pub const STRATEGIES_MEMORY_ID: MemoryId = MemoryId::new(1);
+pub const USER_POSITIONS_MEMORY_ID: MemoryId = MemoryId::new(2);
+pub const EVENT_RECORDS_MEMORY_ID: MemoryId = MemoryId::new(3);
+/// Event record ids by user, strategy, pool, correlation id and event type
+pub const EVENT_RECORDS_LOOKUP_MEMORY_ID: MemoryId = MemoryId::new(4);
+pub const NEXT_EVENT_RECORD_ID_MEMORY_ID: MemoryId = MemoryId::new(5);
+
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+}
+
+pub fn get_memory(id: MemoryId) -> Memory {
+    MEMORY_MANAGER.with(|memory_manager| memory_manager.borrow().get(id))
+}
@@ -1,5 +1,8 @@
 pub mod stable_state;
 pub mod event_records_repo;
+pub mod event_archives_repo;
 pub mod strategies_repo;
 pub mod runtime_config_repo;
 pub mod config_repo;
+pub mod memory;
+pub mod state_migrations;
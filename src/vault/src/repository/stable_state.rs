@@ -1,68 +1,135 @@
-use candid::{CandidType, Deserialize};
+use candid::{CandidType, Deserialize, Encode};
 use ic_cdk::storage;
+use ic_cdk::api::stable::{stable_read, stable_size};
+use ic_stable_structures::Memory as _;
+use ic_stable_structures::writer::Writer;
 use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
 
-use crate::strategies::strategy::IStrategy;
-use crate::strategies::strategy_candid::{StrategyCandid, Candid as StrategyToCandid};
-use crate::repository::strategies_repo::STRATEGIES;
-use crate::repository::event_records_repo::EVENT_RECORDS;
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use token_registry::registry;
+use token_registry::token_metadata::TokenMetadata;
+use event_records::archive::EventArchiveState;
+
+use crate::repository::event_archives_repo;
+use crate::repository::memory::{get_memory, UPGRADES_MEMORY_ID};
 use crate::repository::runtime_config_repo::{self, RuntimeConfig};
 use crate::repository::config_repo::{self, Conf};
-use crate::event_records::event_record::EventRecord;
+use crate::repository::state_migrations::{self, StableStateV0};
+
+/// Stable memory written by the memory manager starts with its magic
+const MEMORY_MANAGER_MAGIC: &[u8; 3] = b"MGR";
+
+/// Version of `StableState` written by `stable_save`.
+/// Bump it on any change to the state and add a migration from the previous version.
+pub const STABLE_STATE_VERSION: u32 = 1;
 
+/// Heap state written to the upgrades memory in `pre_upgrade`.
+/// Strategies, user positions and event records are kept in stable structures
+/// and are not copied on upgrade.
 #[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
 pub struct StableState {
-    pub strategies: Vec<StrategyCandid>,
-    pub event_records: Vec<EventRecord>,
     pub config: Conf,
     pub runtime_config: RuntimeConfig,
+    pub tokens: Vec<TokenMetadata>,
+    pub event_archives: EventArchiveState,
 }
 
-pub fn stable_save() {
-    let conf = config_repo::get_config();
-    let runtime_config = runtime_config_repo::get_runtime_config();
+/// Encoded state tagged with its version, so that `post_upgrade` knows which migrations to run
+#[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
+pub struct StableStateEnvelope {
+    pub version: u32,
+    #[serde(with = "serde_bytes")]
+    pub state: Vec<u8>,
+}
 
-    let strategies: Vec<StrategyCandid> = STRATEGIES.with(|strategies| {
-        strategies.borrow().iter().map(|strategy| strategy.to_candid()).collect()
-    });
+impl StableStateEnvelope {
+    pub fn encode(state: &StableState) -> Vec<u8> {
+        let envelope = Self {
+            version: STABLE_STATE_VERSION,
+            state: Encode!(state).unwrap(),
+        };
 
-    let event_records: Vec<EventRecord> = EVENT_RECORDS.with(|events| {
-        events.borrow().clone()
-    });
+        Encode!(&envelope).unwrap()
+    }
+}
 
+pub fn stable_save() {
     let state = StableState {
-        strategies,
-        event_records,
-        config: conf,
-        runtime_config,
+        config: config_repo::get_config(),
+        runtime_config: runtime_config_repo::get_runtime_config(),
+        tokens: registry::get_tokens(),
+        event_archives: event_archives_repo::get_event_archive_state(),
+    };
+
+    let bytes = StableStateEnvelope::encode(&state);
+
+    // The envelope is prefixed with its length
+    let mut memory = get_memory(UPGRADES_MEMORY_ID);
+    let mut writer = Writer::new(&mut memory, 0);
+    writer.write_all(&(bytes.len() as u64).to_le_bytes()).unwrap();
+    writer.write_all(&bytes).unwrap();
+}
+
+pub fn stable_restore() -> Result<(), InternalError> {
+    // Must run before the memory manager is initialized, which overwrites the legacy state
+    let state = if is_legacy_layout() {
+        restore_legacy_state()?
+    } else {
+        let memory = get_memory(UPGRADES_MEMORY_ID);
+
+        let mut length_bytes = [0u8; 8];
+        memory.read(0, &mut length_bytes);
+
+        let mut bytes = vec![0u8; u64::from_le_bytes(length_bytes) as usize];
+        memory.read(8, &mut bytes);
+
+        state_migrations::restore_stable_state(&bytes)?
     };
 
-    storage::stable_save((state, )).unwrap();
+    restore_heap_state(state);
+    Ok(())
+}
+
+fn is_legacy_layout() -> bool {
+    if stable_size() == 0 {
+        return false;
+    }
+
+    let mut magic = [0u8; 3];
+    stable_read(0, &mut magic);
+
+    &magic != MEMORY_MANAGER_MAGIC
 }
 
-pub fn stable_restore() {
-    let (state, ): (StableState, ) = storage::stable_restore().unwrap();
+/// Reads the state written by `storage::stable_save`
+fn restore_legacy_state() -> Result<StableState, InternalError> {
+    let (state, ): (StableStateV0, ) = storage::stable_restore()
+        .map_err(|error| InternalError::validation(
+            build_error_code(3300, 2, 3), // 3300 02 03
+            "stable_state::restore_legacy_state".to_string(),
+            "Failed to decode stable state".to_string(),
+            Some(HashMap::from([
+                ("version".to_string(), "0".to_string()),
+                ("error".to_string(), error),
+            ])),
+        ))?;
+
+    Ok(state_migrations::migrate_v0_to_v1(state))
+}
 
+fn restore_heap_state(state: StableState) {
     // Conf
-    config_repo::set_config(state.config.clone());
+    config_repo::set_config(state.config);
 
     // Runtime Config
-    runtime_config_repo::set_runtime_config(state.runtime_config.clone());
-
-    // Strategies
-    let strategies: Vec<Box<dyn IStrategy>> = state.strategies.clone()
-        .into_iter()
-        .map(|x| x.to_strategy())
-        .collect();
-
-    STRATEGIES.with(|utrs| {
-        utrs.borrow_mut();
-        utrs.replace(strategies)
-    });
-
-    // EventRecords
-    EVENT_RECORDS.with(|event_records| {
-        event_records.borrow_mut();
-        event_records.replace(state.event_records)
-    });
+    runtime_config_repo::set_runtime_config(state.runtime_config);
+
+    // Token registry
+    registry::set_tokens(state.tokens);
+
+    // Event archives
+    event_archives_repo::set_event_archive_state(state.event_archives);
 }
@@ -0,0 +1,208 @@
+use candid::{CandidType, Decode, Deserialize};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use token_registry::token_metadata::TokenMetadata;
+use event_records::archive::EventArchiveState;
+
+use crate::strategies::strategy_candid::{StrategyCandid, Candid as StrategyToCandid};
+use crate::repository::strategies_repo;
+use crate::repository::event_records_repo;
+use crate::repository::runtime_config_repo::RuntimeConfig;
+use crate::repository::config_repo::Conf;
+use crate::repository::stable_state::{StableState, StableStateEnvelope, STABLE_STATE_VERSION};
+use crate::event_records::event_record::EventRecord;
+
+/// Version 0: state serialized with `storage::stable_save`,
+/// before strategies and event records were moved to stable structures
+#[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
+pub struct StableStateV0 {
+    pub strategies: Vec<StrategyCandid>,
+    pub event_records: Vec<EventRecord>,
+    pub config: Conf,
+    pub runtime_config: RuntimeConfig,
+    pub tokens: Option<Vec<TokenMetadata>>,
+}
+
+/// Moves strategies and event records into their stable structures.
+/// Tokens were optional for states saved before the token registry existed,
+/// and canisters without archives keep all their records until an archive is added or its wasm uploaded.
+pub fn migrate_v0_to_v1(state: StableStateV0) -> StableState {
+    for strategy in state.strategies {
+        strategies_repo::add_or_update_strategy(strategy.to_strategy());
+    }
+
+    for event_record in state.event_records {
+        event_records_repo::save_event_record(event_record);
+    }
+
+    StableState {
+        config: state.config,
+        runtime_config: state.runtime_config,
+        tokens: state.tokens.unwrap_or_default(),
+        event_archives: EventArchiveState::default(),
+    }
+}
+
+/// Decodes the envelope written to the upgrades memory by `stable_save` and migrates its state
+/// to the current version. State written by `storage::stable_save` is detected by the caller
+/// before the upgrades memory is read, anything else in the memory is an error.
+pub fn restore_stable_state(bytes: &[u8]) -> Result<StableState, InternalError> {
+    let envelope = Decode!(bytes, StableStateEnvelope).map_err(|error| InternalError::validation(
+        build_error_code(3300, 2, 4), // 3300 02 04
+        "state_migrations::restore_stable_state".to_string(),
+        "Upgrades memory does not hold a stable state envelope".to_string(),
+        Some(HashMap::from([
+            ("length".to_string(), bytes.len().to_string()),
+            ("error".to_string(), error.to_string()),
+        ])),
+    ))?;
+
+    migrate(envelope.version, &envelope.state)
+}
+
+fn migrate(version: u32, bytes: &[u8]) -> Result<StableState, InternalError> {
+    match version {
+        STABLE_STATE_VERSION => decode_state::<StableState>(version, bytes),
+        _ => Err(InternalError::validation(
+            build_error_code(3300, 2, 2), // 3300 02 02
+            "state_migrations::migrate".to_string(),
+            "Unsupported stable state version".to_string(),
+            Some(HashMap::from([
+                ("version".to_string(), version.to_string()),
+                ("current_version".to_string(), STABLE_STATE_VERSION.to_string()),
+            ])),
+        )),
+    }
+}
+
+fn decode_state<T: CandidType + DeserializeOwned>(version: u32, bytes: &[u8]) -> Result<T, InternalError> {
+    Decode!(bytes, T).map_err(|error| InternalError::validation(
+        build_error_code(3300, 2, 1), // 3300 02 01
+        "state_migrations::decode_state".to_string(),
+        "Failed to decode stable state".to_string(),
+        Some(HashMap::from([
+            ("version".to_string(), version.to_string()),
+            ("length".to_string(), bytes.len().to_string()),
+            ("error".to_string(), error.to_string()),
+        ])),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::{Encode, Nat, Principal};
+    use ::utils::environment::Environment;
+    use event_records::archive::{EventArchive, EventRetentionPolicy};
+
+    /// `StableStateEnvelope` bytes carrying a version 1 state
+    const STABLE_STATE_V1: &[u8] = include_bytes!("fixtures/stable_state_v1.bin");
+
+    fn controller() -> Principal {
+        Principal::from_text("aaaaa-aa").unwrap()
+    }
+
+    fn token() -> TokenMetadata {
+        TokenMetadata {
+            canister_id: Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap(),
+            symbol: "ICP".to_string(),
+            name: "Internet Computer".to_string(),
+            decimals: 8,
+            fee: Nat::from(10_000u64),
+            logo: None,
+            updated_at: 1_700_000_000,
+        }
+    }
+
+    fn archive() -> EventArchive {
+        EventArchive {
+            canister_id: controller(),
+            start_id: 0,
+            end_id: 1_000,
+        }
+    }
+
+    mod restore_stable_state {
+        use super::*;
+
+        #[test]
+        fn restores_v1_envelope() {
+            let state = restore_stable_state(STABLE_STATE_V1).unwrap();
+
+            assert_eq!(state.config, Conf { controllers: Some(vec![controller()]) });
+            assert_eq!(state.runtime_config.environment, Environment::Production);
+            assert_eq!(state.tokens, vec![token()]);
+            assert_eq!(state.event_archives.archives, vec![archive()]);
+        }
+
+        #[test]
+        fn restores_what_stable_save_encodes() {
+            let state = StableState {
+                config: Conf::default(),
+                runtime_config: RuntimeConfig::default(),
+                tokens: vec![token()],
+                event_archives: EventArchiveState {
+                    archives: vec![archive()],
+                    ..Default::default()
+                },
+            };
+
+            let restored = restore_stable_state(&StableStateEnvelope::encode(&state)).unwrap();
+
+            assert_eq!(restored.config, state.config);
+            assert_eq!(restored.tokens, state.tokens);
+            assert_eq!(restored.event_archives.archives, state.event_archives.archives);
+        }
+
+        #[test]
+        fn rejects_unsupported_version() {
+            let envelope = StableStateEnvelope { version: STABLE_STATE_VERSION + 1, state: vec![] };
+
+            let error = restore_stable_state(&Encode!(&envelope).unwrap()).unwrap_err();
+
+            assert_eq!(error.code, build_error_code(3300, 2, 2));
+        }
+
+        #[test]
+        fn rejects_memory_without_envelope() {
+            let error = restore_stable_state(b"not a stable state").unwrap_err();
+
+            assert_eq!(error.code, build_error_code(3300, 2, 4));
+        }
+
+        #[test]
+        fn reports_undecodable_state() {
+            let envelope = StableStateEnvelope { version: STABLE_STATE_VERSION, state: b"not a stable state".to_vec() };
+
+            let error = restore_stable_state(&Encode!(&envelope).unwrap()).unwrap_err();
+
+            assert_eq!(error.code, build_error_code(3300, 2, 1));
+            assert_eq!(error.extra.unwrap().get("version"), Some(&STABLE_STATE_VERSION.to_string()));
+        }
+    }
+
+    mod migrate_v0_to_v1 {
+        use super::*;
+
+        #[test]
+        fn defaults_missing_tokens_and_archives() {
+            let state = migrate_v0_to_v1(StableStateV0 {
+                strategies: vec![],
+                event_records: vec![],
+                config: Conf { controllers: Some(vec![controller()]) },
+                runtime_config: RuntimeConfig::default(),
+                tokens: None,
+            });
+
+            assert_eq!(state.config, Conf { controllers: Some(vec![controller()]) });
+            assert!(state.tokens.is_empty());
+            assert!(state.event_archives.archives.is_empty());
+            assert!(state.event_archives.archive_wasm.is_empty());
+            assert_eq!(state.event_archives.retention_policy, EventRetentionPolicy::default());
+        }
+    }
+}
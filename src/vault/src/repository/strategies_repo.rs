@@ -1,173 +1,178 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
 use candid::Principal;
+use ic_stable_structures::StableBTreeMap;
 
-use crate::strategies::strategy::{IStrategy, StrategyIterator};
+use crate::strategies::strategy::IStrategy;
+use crate::strategies::strategy_candid::{StrategyCandid, Candid};
+use crate::user::user_position::UserPosition;
+use crate::repository::memory::{get_memory, Memory, STRATEGIES_MEMORY_ID, USER_POSITIONS_MEMORY_ID};
 
 thread_local! {
-    pub static STRATEGIES: RefCell<Vec<Box<dyn IStrategy >>> = RefCell::new(Default::default());
+    /// Strategies by id, stored without the shares and initial deposits of their users
+    static STRATEGIES: RefCell<StableBTreeMap<u16, StrategyCandid, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(STRATEGIES_MEMORY_ID))
+    );
+
+    /// Positions of the users by (strategy id, user)
+    static USER_POSITIONS: RefCell<StableBTreeMap<(u16, Principal), UserPosition, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(USER_POSITIONS_MEMORY_ID))
+    );
 }
 
 pub fn get_all_strategies() -> Vec<Box<dyn IStrategy>> {
     STRATEGIES.with(|strategies| {
-        StrategyIterator::new(strategies.borrow_mut())
-            .into_iter()
+        strategies
+            .borrow()
+            .iter()
+            .map(|(_, strategy)| load_strategy(strategy))
             .collect()
     })
 }
 
 pub fn get_user_strategies(user: Principal) -> Vec<Box<dyn IStrategy>> {
     STRATEGIES.with(|strategies| {
-        StrategyIterator::new(strategies.borrow_mut())
-            .into_iter()
-            .filter(|s| s.get_user_shares().contains_key(&user))
+        strategies
+            .borrow()
+            .iter()
+            .filter(|(id, _)| has_user_shares(*id, user))
+            .map(|(_, strategy)| load_strategy(strategy))
             .collect()
     })
 }
 
 pub fn get_strategy_by_id(id: u16) -> Option<Box<dyn IStrategy>> {
-    STRATEGIES.with(|strategies| {
-        StrategyIterator::new(strategies.borrow_mut())
-            .into_iter()
-            .find(|s| s.get_id() == id)
-    })
+    STRATEGIES.with(|strategies| strategies.borrow().get(&id))
+        .map(load_strategy)
 }
 
 pub fn add_or_update_strategy(strategy: Box<dyn IStrategy>) {
-    STRATEGIES.with(|strategies| {
-        let mut strategies = strategies.borrow_mut();
-        let index = strategies.iter().position(|s| s.get_id() == strategy.get_id());
-        if let Some(index) = index {
-            strategies[index] = strategy;
-        } else {
-            strategies.push(strategy);
-        }
-    });
+    insert_strategy(strategy);
 }
 
 pub fn add_if_not_exists(strategy: Box<dyn IStrategy>) {
-    STRATEGIES.with(|strategies| {
-        let mut strategies = strategies.borrow_mut();
-        let index = strategies.iter().position(|s| s.get_id() == strategy.get_id());
-        if index.is_none() {
-            strategies.push(strategy);
-        }
-    });
+    if !strategy_exists(strategy.get_id()) {
+        insert_strategy(strategy);
+    }
 }
 
 pub fn save_strategy(strategy: Box<dyn IStrategy>) {
-    STRATEGIES.with(|strategies| {
-        let mut strategies = strategies.borrow_mut();
-        let index = strategies.iter().position(|s| s.get_id() == strategy.get_id());
-        if let Some(index) = index {
-            strategies[index] = strategy;
-        }
-    });
+    if strategy_exists(strategy.get_id()) {
+        insert_strategy(strategy);
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::HashMap;
-    use candid::{Nat, Principal};
+fn strategy_exists(id: u16) -> bool {
+    STRATEGIES.with(|strategies| strategies.borrow().contains_key(&id))
+}
 
-    use crate::strategies::basic_strategy::BasicStrategy;
-    use crate::pools::pool::Pool;
-    use crate::strategies::strategy_candid::StrategyCandid;
+fn has_user_shares(id: u16, user: Principal) -> bool {
+    USER_POSITIONS.with(|positions| {
+        positions
+            .borrow()
+            .get(&(id, user))
+            .is_some_and(|position| position.shares.is_some())
+    })
+}
 
-    #[derive(Clone)]
-    struct MockStrategy {
-        id: u16,
-        user_shares: HashMap<Principal, Nat>,
-    }
+/// Strategy with the shares and initial deposits of its users
+fn load_strategy(strategy: StrategyCandid) -> Box<dyn IStrategy> {
+    let mut strategy = strategy.to_strategy();
 
-    impl MockStrategy {
-        fn new(id: u16, user: Option<Principal>) -> Self {
-            let mut user_shares = HashMap::new();
-            if let Some(principal) = user.clone() {
-                user_shares.insert(principal, Nat::from(100u64));
-            }
-            Self { id, user_shares }
+    let mut user_shares = HashMap::new();
+    let mut initial_deposit = HashMap::new();
+
+    for (user, position) in get_user_positions(strategy.get_id()) {
+        if let Some(shares) = position.shares {
+            user_shares.insert(user, shares);
+        }
+        if let Some(deposit) = position.initial_deposit {
+            initial_deposit.insert(user, deposit);
         }
     }
 
-    impl BasicStrategy for MockStrategy {
-        fn get_id(&self) -> u16 {
-            self.id
-        }
+    strategy.set_user_shares(user_shares);
+    strategy.set_initial_deposit(initial_deposit);
+    strategy
+}
 
-        fn get_user_shares(&self) -> HashMap<Principal, Nat> {
-            self.user_shares.clone()
-        }
+/// Stores the strategy and the positions of its users apart,
+/// only positions which changed are written
+fn insert_strategy(mut strategy: Box<dyn IStrategy>) {
+    let id = strategy.get_id();
+    let user_shares = strategy.get_user_shares();
+    let initial_deposit = strategy.get_initial_deposit();
 
-        fn get_users_count(&self) -> u32 {
-            self.user_shares.len() as u32
-        }
+    let mut positions: HashMap<Principal, UserPosition> = HashMap::new();
+    for (user, shares) in user_shares {
+        positions.entry(user).or_insert_with(empty_position).shares = Some(shares);
+    }
+    for (user, deposit) in initial_deposit {
+        positions.entry(user).or_insert_with(empty_position).initial_deposit = Some(deposit);
+    }
 
-        fn get_position_id(&self) -> Option<u64> {
-            None
-        }
+    strategy.set_user_shares(HashMap::new());
+    strategy.set_initial_deposit(HashMap::new());
 
-        fn get_user_shares_by_principal(&self, user: Principal) -> Nat {
-            self.user_shares.get(&user).cloned().unwrap_or(Nat::from(0u64))
-        }
+    STRATEGIES.with(|strategies| strategies.borrow_mut().insert(id, strategy.to_candid()));
 
-        fn get_name(&self) -> String {
-            "mock-strategy".to_string()
-        }
+    let stored_positions = get_user_positions(id);
 
-        fn get_description(&self) -> String {
-            "mock-desc".to_string()
-        }
+    USER_POSITIONS.with(|user_positions| {
+        let mut user_positions = user_positions.borrow_mut();
 
-        fn get_pools(&self) -> Vec<Pool> {
-            vec![]
+        for (user, _) in stored_positions.iter().filter(|(user, _)| !positions.contains_key(user)) {
+            user_positions.remove(&(id, *user));
         }
 
-        fn get_current_pool(&self) -> Option<Pool> {
-            None
+        for (user, position) in positions {
+            if stored_positions.get(&user) != Some(&position) {
+                user_positions.insert((id, user), position);
+            }
         }
+    });
+}
 
-        fn get_total_shares(&self) -> Nat {
-            Nat::from(100u64)
-        }
+fn get_user_positions(id: u16) -> HashMap<Principal, UserPosition> {
+    USER_POSITIONS.with(|positions| {
+        positions
+            .borrow()
+            .range((id, Principal::management_canister())..)
+            .take_while(|((strategy_id, _), _)| *strategy_id == id)
+            .map(|((_, user), position)| (user, position))
+            .collect()
+    })
+}
 
-        fn get_total_balance(&self) -> Nat {
-            Nat::from(100u64)
-        }
+fn empty_position() -> UserPosition {
+    UserPosition {
+        shares: None,
+        initial_deposit: None,
+    }
+}
 
-        fn get_initial_deposit(&self) -> HashMap<Principal, Nat> {
-            HashMap::new()
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Nat;
 
-        fn get_current_liquidity(&self) -> Option<Nat> {
-            None
-        }
+    use crate::strategies::r#impl::ck_btc_strategy::ckBTCStrategy;
+    use crate::strategies::r#impl::icp_strategy::ICPStrategy;
 
-        fn get_current_liquidity_updated_at(&self) -> Option<u64> {
-            None
-        }
+    fn strategy_with_user(user: Principal, shares: u64) -> Box<dyn IStrategy> {
+        let mut strategy: Box<dyn IStrategy> = Box::new(ICPStrategy::new());
+        strategy.set_user_shares(HashMap::from([(user, Nat::from(shares))]));
+        strategy.set_initial_deposit(HashMap::from([(user, Nat::from(shares * 10))]));
+        strategy
+    }
 
-        fn set_user_shares(&mut self, _shares: HashMap<Principal, Nat>) {}
-        fn set_total_shares(&mut self, _shares: Nat) {}
-        fn set_total_balance(&mut self, _balance: Nat) {}
-        fn set_initial_deposit(&mut self, _map: HashMap<Principal, Nat>) {}
-        fn set_current_pool(&mut self, _pool: Option<Pool>) {}
-        fn set_position_id(&mut self, _id: Option<u64>) {}
-        fn set_current_liquidity(&mut self, _liq: Option<Nat>) {}
-        fn set_current_liquidity_updated_at(&mut self, _ts: Option<u64>) {}
+    fn user(byte: u8) -> Principal {
+        Principal::from_slice(&[byte; 29])
     }
 
-    #[async_trait::async_trait]
-    impl IStrategy for MockStrategy {
-        fn to_candid(&self) -> StrategyCandid {
-            unimplemented!()
-        }
-        fn clone_self(&self) -> Box<dyn IStrategy> {
-            Box::new(Self {
-                id: self.id,
-                user_shares: self.user_shares.clone(),
-            })
-        }
+    fn clear_strategies() {
+        STRATEGIES.with(|strategies| strategies.borrow_mut().clear_new());
+        USER_POSITIONS.with(|positions| positions.borrow_mut().clear_new());
     }
 
     mod get_all_strategies {
@@ -175,10 +180,10 @@ mod tests {
 
         #[test]
         fn returns_all_strategies() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            add_if_not_exists(Box::new(MockStrategy::new(1, None)));
-            add_if_not_exists(Box::new(MockStrategy::new(2, None)));
+            add_if_not_exists(Box::new(ckBTCStrategy::new()));
+            add_if_not_exists(Box::new(ICPStrategy::new()));
 
             let strategies = get_all_strategies();
             assert_eq!(strategies.len(), 2);
@@ -190,15 +195,16 @@ mod tests {
 
         #[test]
         fn filters_by_user() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
-            let user = Principal::anonymous();
+            clear_strategies();
 
-            add_if_not_exists(Box::new(MockStrategy::new(1, Some(user))));
-            add_if_not_exists(Box::new(MockStrategy::new(2, None)));
+            let user = user(1);
+
+            add_if_not_exists(Box::new(ckBTCStrategy::new()));
+            add_if_not_exists(strategy_with_user(user, 100));
 
             let strategies = get_user_strategies(user);
             assert_eq!(strategies.len(), 1);
-            assert_eq!(strategies[0].get_id(), 1);
+            assert_eq!(strategies[0].get_id(), 2);
         }
     }
 
@@ -207,13 +213,27 @@ mod tests {
 
         #[test]
         fn finds_correct_strategy() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            add_if_not_exists(Box::new(MockStrategy::new(42, None)));
+            add_if_not_exists(Box::new(ICPStrategy::new()));
 
-            let strategy = get_strategy_by_id(42);
+            let strategy = get_strategy_by_id(2);
             assert!(strategy.is_some());
-            assert_eq!(strategy.unwrap().get_id(), 42);
+            assert_eq!(strategy.unwrap().get_id(), 2);
+            assert!(get_strategy_by_id(42).is_none());
+        }
+
+        #[test]
+        fn restores_user_shares_and_initial_deposit() {
+            clear_strategies();
+
+            let user = user(1);
+
+            add_if_not_exists(strategy_with_user(user, 100));
+
+            let strategy = get_strategy_by_id(2).unwrap();
+            assert_eq!(strategy.get_user_shares().get(&user), Some(&Nat::from(100u64)));
+            assert_eq!(strategy.get_initial_deposit().get(&user), Some(&Nat::from(1_000u64)));
         }
     }
 
@@ -222,16 +242,16 @@ mod tests {
 
         #[test]
         fn replaces_existing_strategy() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            let s1 = Box::new(MockStrategy::new(7, None));
-            let s2 = Box::new(MockStrategy::new(7, None));
+            let user = user(1);
 
-            add_or_update_strategy(s1);
-            add_or_update_strategy(s2);
+            add_or_update_strategy(Box::new(ICPStrategy::new()));
+            add_or_update_strategy(strategy_with_user(user, 100));
 
             let strategies = get_all_strategies();
             assert_eq!(strategies.len(), 1);
+            assert_eq!(strategies[0].get_user_shares_by_principal(user), Nat::from(100u64));
         }
     }
 
@@ -240,14 +260,16 @@ mod tests {
 
         #[test]
         fn does_not_add_duplicate() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            let s = Box::new(MockStrategy::new(8, None));
-            add_if_not_exists(s.clone());
-            add_if_not_exists(s);
+            let user = user(1);
+
+            add_if_not_exists(Box::new(ICPStrategy::new()));
+            add_if_not_exists(strategy_with_user(user, 100));
 
             let strategies = get_all_strategies();
             assert_eq!(strategies.len(), 1);
+            assert!(strategies[0].get_user_shares().is_empty());
         }
     }
 
@@ -256,9 +278,9 @@ mod tests {
 
         #[test]
         fn updates_existing_strategy_only() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            let strategy = Box::new(MockStrategy::new(9, None));
+            let strategy: Box<dyn IStrategy> = Box::new(ICPStrategy::new());
             save_strategy(strategy.clone());
             assert_eq!(get_all_strategies().len(), 0);
 
@@ -266,5 +288,35 @@ mod tests {
             save_strategy(strategy);
             assert_eq!(get_all_strategies().len(), 1);
         }
+
+        #[test]
+        fn removes_positions_of_users_who_left() {
+            clear_strategies();
+
+            let first_user = user(1);
+            let second_user = user(2);
+
+            let mut strategy = strategy_with_user(first_user, 100);
+            add_or_update_strategy(strategy.clone());
+
+            strategy.set_user_shares(HashMap::from([(second_user, Nat::from(50u64))]));
+            strategy.set_initial_deposit(HashMap::from([(second_user, Nat::from(500u64))]));
+            save_strategy(strategy);
+
+            let strategy = get_strategy_by_id(2).unwrap();
+            assert_eq!(strategy.get_user_shares().len(), 1);
+            assert_eq!(strategy.get_user_shares_by_principal(second_user), Nat::from(50u64));
+            assert!(get_user_strategies(first_user).is_empty());
+        }
+
+        #[test]
+        fn stores_strategy_without_user_positions() {
+            clear_strategies();
+
+            add_or_update_strategy(strategy_with_user(user(1), 100));
+
+            let stored = STRATEGIES.with(|strategies| strategies.borrow().get(&2)).unwrap();
+            assert!(stored.to_strategy().get_user_shares().is_empty());
+        }
     }
 }
@@ -1,15 +1,24 @@
 use std::collections::HashMap;
+use candid::Principal;
 
+use ::types::CanisterId;
 use ::types::context::Context;
 use errors::internal_error::error::InternalError;
 use errors::internal_error::error::build_error_code;
+use token_registry::registry;
+use token_registry::token_metadata::TokenMetadata;
+use event_records::archive::{EventArchive, EventRetentionPolicy};
 
 use crate::repository::strategies_repo;
+use crate::repository::config_repo;
 use crate::user::user_service;
 use crate::strategies::strategy::IStrategy;
+use crate::strategies::strategy_state::StrategyState;
+use crate::strategies::strategy_lifecycle::StrategyLifecycle;
+use crate::strategies::strategy_audit::{self, StrategyAuditStatus};
 use crate::types::types::*;
-use crate::event_records::event_record::EventRecord;
 use crate::event_records::event_record_service;
+use crate::event_records::event_archive_service;
 
 
 /// Accepts an investment into a specified strategy.
@@ -74,17 +83,252 @@ pub async fn withdraw(context: Context, args: StrategyWithdrawArgs) -> Result<St
     strategy.withdraw(context.clone(), args.percentage.clone()).await
 }
 
+/// Previews a deposit into a specified strategy.
+///
+/// # Arguments
+///
+/// * `args` - An `StrategyDepositArgs` struct containing the ledger, amount, and strategy ID.
+///
+/// # Returns
+///
+/// A `Result` containing a `StrategyPreviewDepositResponse` struct (with the expected shares, token amounts and fees)
+/// or a `InternalError` if the strategy is not found or the quote fails.
+pub async fn preview_deposit(context: Context, args: StrategyDepositArgs) -> Result<StrategyPreviewDepositResponse, InternalError> {
+    let strategy = get_strategy_by_id(args.strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(3000, 1, 3), // 3000 01 03
+                "service::preview_deposit".to_string(),
+                "Strategy not found".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), args.strategy_id.to_string())
+                ]))
+            )
+        })?;
+
+    strategy.preview_deposit(context, args.amount.clone()).await
+}
+
+/// Previews a withdrawal from a specified strategy.
+///
+/// # Arguments
+///
+/// * `args` - A `StrategyWithdrawArgs` struct containing the ledger, percentage, and strategy ID.
+///
+/// # Returns
+///
+/// A `Result` containing a `StrategyPreviewWithdrawResponse` struct (with the shares, token amounts, swap output and fees)
+/// or a `InternalError` if the strategy is not found or the quote fails.
+pub async fn preview_withdraw(context: Context, args: StrategyWithdrawArgs) -> Result<StrategyPreviewWithdrawResponse, InternalError> {
+    let strategy = get_strategy_by_id(args.strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(3000, 1, 4), // 3000 01 04
+                "service::preview_withdraw".to_string(),
+                "Strategy not found".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), args.strategy_id.to_string()),
+                ]))
+            )
+        })?;
+
+    strategy.preview_withdraw(context.user.unwrap(), args.percentage.clone()).await
+}
+
+/// Moves a specified strategy to a new state.
+///
+/// # Arguments
+///
+/// * `args` - A `SetStrategyStateArgs` struct containing the strategy ID and the new state.
+///
+/// # Returns
+///
+/// A `Result` containing the new `StrategyState`
+/// or a `InternalError` if the caller is not a controller, the strategy is not found or the transition fails.
+pub async fn set_strategy_state(context: Context, args: SetStrategyStateArgs) -> Result<StrategyState, InternalError> {
+    check_controller(&context, "service::set_strategy_state")?;
+
+    let mut strategy = get_strategy_by_id(args.strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(3000, 1, 5), // 3000 01 05
+                "service::set_strategy_state".to_string(),
+                "Strategy not found".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), args.strategy_id.to_string()),
+                ]))
+            )
+        })?;
+
+    strategy.change_state(context, args.state).await
+}
+
+/// Changes the lifecycle stage of a strategy.
+///
+/// # Arguments
+///
+/// * `args` - A `SetStrategyLifecycleArgs` struct containing the strategy ID and the new lifecycle stage.
+///
+/// # Returns
+///
+/// A `Result` containing the new `StrategyLifecycle`
+/// or a `InternalError` if the caller is not a controller, the strategy is not found or the transition fails.
+pub async fn set_strategy_lifecycle(context: Context, args: SetStrategyLifecycleArgs) -> Result<StrategyLifecycle, InternalError> {
+    check_controller(&context, "service::set_strategy_lifecycle")?;
+
+    let mut strategy = get_strategy_by_id(args.strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(3000, 1, 6), // 3000 01 06
+                "service::set_strategy_lifecycle".to_string(),
+                "Strategy not found".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), args.strategy_id.to_string()),
+                ]))
+            )
+        })?;
+
+    strategy.change_lifecycle(context, args.lifecycle).await
+}
+
+/// Migrates all users of a closing strategy into a successor strategy.
+///
+/// # Arguments
+///
+/// * `args` - A `MigrateStrategyArgs` struct containing the closing and the successor strategy IDs.
+///
+/// # Returns
+///
+/// A `Result` containing a `MigrateStrategyResponse` struct
+/// or a `InternalError` if the caller is not a controller, a strategy is not found or the migration fails.
+pub async fn migrate_strategy(context: Context, args: MigrateStrategyArgs) -> Result<MigrateStrategyResponse, InternalError> {
+    check_controller(&context, "service::migrate_strategy")?;
+
+    let mut strategy = get_strategy_by_id(args.strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(3000, 1, 7), // 3000 01 07
+                "service::migrate_strategy".to_string(),
+                "Strategy not found".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), args.strategy_id.to_string()),
+                ]))
+            )
+        })?;
+
+    let mut successor = get_strategy_by_id(args.successor_strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(3000, 1, 8), // 3000 01 08
+                "service::migrate_strategy".to_string(),
+                "Successor strategy not found".to_string(),
+                Some(HashMap::from([
+                    ("successor_strategy_id".to_string(), args.successor_strategy_id.to_string()),
+                ]))
+            )
+        })?;
+
+    strategy.migrate_to(context, &mut successor).await
+}
+
+// ========================== Token registry ==========================
+
+/// Registers the token and caches its ledger metadata.
+///
+/// # Arguments
+///
+/// * `canister_id` - The ledger canister ID of the token.
+///
+/// # Returns
+///
+/// A `Result` containing the `TokenMetadata` of the token
+/// or a `InternalError` if the caller is not a controller or the ledger metadata can not be fetched.
+pub async fn add_token(context: Context, canister_id: CanisterId) -> Result<TokenMetadata, InternalError> {
+    check_controller(&context, "service::add_token")?;
+
+    registry::add_token(canister_id).await
+}
+
+/// Fetches the metadata of all registered tokens again.
+/// Tokens which fail to refresh keep their cached metadata.
+pub async fn refresh_tokens(context: Context) -> Result<Vec<TokenMetadata>, InternalError> {
+    check_controller(&context, "service::refresh_tokens")?;
+
+    Ok(registry::refresh_tokens().await)
+}
+
 // ========================== Event records ==========================
 
-pub fn get_event_records(request: ListItemsPaginationRequest) -> Result<ListItemsPaginationResponse<EventRecord>, InternalError> {
-    let event_records = event_record_service::get_event_records(request.clone());
+pub fn get_event_records(query: EventRecordsQuery) -> Result<EventRecordsPage, InternalError> {
+    if query.limit == 0 {
+        return Err(InternalError::validation(
+            build_error_code(3000, 2, 9), // 3000 02 09
+            "service::get_event_records".to_string(),
+            "Limit must be greater than zero".to_string(),
+            None,
+        ));
+    }
+
+    Ok(event_record_service::get_event_records(query))
+}
+
+/// Sets how many event records the canister keeps before older ones are archived.
+///
+/// # Arguments
+///
+/// * `policy` - The `EventRetentionPolicy` to apply from the next archiving run.
+///
+/// # Returns
+///
+/// A `Result` containing the applied `EventRetentionPolicy`
+/// or a `InternalError` if the caller is not a controller or a limit is 0.
+pub fn set_event_retention_policy(context: Context, policy: EventRetentionPolicy) -> Result<EventRetentionPolicy, InternalError> {
+    check_controller(&context, "service::set_event_retention_policy")?;
+
+    event_archive_service::set_event_retention_policy(policy)
+}
+
+/// Makes an existing archive canister, owned by this canister, the archive of the next archived records.
+pub fn add_event_archive(context: Context, canister_id: CanisterId) -> Result<EventArchive, InternalError> {
+    check_controller(&context, "service::add_event_archive")?;
 
-    Ok(ListItemsPaginationResponse {
-        items: event_records.clone(),
-        total: event_records.len() as u64,
-        page: request.page,
-        page_size: request.page_size,
-    })
+    event_archive_service::add_event_archive(canister_id)
+}
+
+/// Uploads the wasm installed on archives spawned once the last archive is full.
+pub fn set_event_archive_wasm(context: Context, wasm: Vec<u8>) -> Result<(), InternalError> {
+    check_controller(&context, "service::set_event_archive_wasm")?;
+
+    event_archive_service::set_event_archive_wasm(wasm);
+    Ok(())
+}
+
+/// Upgrades every archive to the uploaded archive wasm.
+///
+/// # Returns
+///
+/// A `Result` containing the number of upgraded archives
+/// or a `InternalError` if the caller is not a controller, no wasm is uploaded or an archive fails to upgrade.
+pub async fn upgrade_event_archives(context: Context) -> Result<u64, InternalError> {
+    check_controller(&context, "service::upgrade_event_archives")?;
+
+    event_archive_service::upgrade_event_archives().await
+}
+
+/// Starts rebuilding every strategy from the event log, archived records included,
+/// to report where the result differs from the live strategy state.
+/// The log is replayed a page per step, `get_strategy_audit` returns the progress and the report.
+pub fn audit_strategies(context: Context) -> Result<(), InternalError> {
+    check_controller(&context, "service::audit_strategies")?;
+
+    strategy_audit::start_strategy_audit()
+}
+
+/// Progress of the strategy audit, with the report once it is completed
+pub fn get_strategy_audit(context: Context) -> Result<StrategyAuditStatus, InternalError> {
+    check_controller(&context, "service::get_strategy_audit")?;
+
+    Ok(strategy_audit::get_strategy_audit_status())
 }
 
 /// Retrieves a strategy by its ID.
@@ -99,3 +343,23 @@ pub fn get_event_records(request: ListItemsPaginationRequest) -> Result<ListItem
 fn get_strategy_by_id(id: u16) -> Option<Box<dyn IStrategy>> {
     strategies_repo::get_strategy_by_id(id)
 }
+
+/// Checks that the caller is a controller of the canister or listed in the config controllers.
+fn check_controller(context: &Context, error_context: &str) -> Result<(), InternalError> {
+    let caller = context.user.unwrap_or(Principal::anonymous());
+    let is_config_controller = config_repo::get_controllers()
+        .is_some_and(|controllers| controllers.contains(&caller));
+
+    if is_config_controller || ic_cdk::api::is_controller(&caller) {
+        return Ok(());
+    }
+
+    Err(InternalError::access_denied(
+        build_error_code(3000, 5, 1), // 3000 05 01
+        error_context.to_string(),
+        "Caller is not a controller".to_string(),
+        Some(HashMap::from([
+            ("caller".to_string(), caller.to_text()),
+        ]))
+    ))
+}
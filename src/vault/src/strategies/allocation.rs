@@ -0,0 +1,266 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+
+use utils::util::nat_to_u64;
+
+use crate::pools::pool::Pool;
+use crate::pools::pool_data::PoolData;
+
+/// Weights are expressed in basis points
+pub const WEIGHT_DENOMINATOR: u32 = 10_000;
+/// Allocation is reported as drifted when any pool deviates from its target by more than 5%
+pub const DRIFT_THRESHOLD: u32 = 500;
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug, PartialEq)]
+pub struct PoolWeight {
+    pub pool_id: String,
+    pub weight: u32,
+}
+
+/// Defines how a multi-pool strategy splits capital between its pools
+#[derive(Clone, Debug)]
+pub enum AllocationPolicy {
+    /// Fixed weights per pool
+    Static(Vec<PoolWeight>),
+    /// Weights proportional to the pools APY
+    ApyProportional,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct PoolAllocation {
+    pub pool: Pool,
+    pub position_id: Option<u64>,
+    pub target_weight: u32,
+    pub current_liquidity: Option<Nat>,
+}
+
+/// Calculates weights proportional to the pools APY.
+/// Pools are weighted equally if no pool has a positive APY.
+pub fn calculate_apy_weights(pools_data: &[PoolData]) -> Vec<PoolWeight> {
+    if pools_data.is_empty() {
+        return Vec::new();
+    }
+
+    let total_apy: f64 = pools_data.iter().map(|pool_data| pool_data.apy.max(0.0)).sum();
+
+    let mut weights: Vec<PoolWeight> = pools_data
+        .iter()
+        .map(|pool_data| {
+            let weight = if total_apy > 0.0 {
+                (pool_data.apy.max(0.0) / total_apy * WEIGHT_DENOMINATOR as f64).floor() as u32
+            } else {
+                WEIGHT_DENOMINATOR / pools_data.len() as u32
+            };
+
+            PoolWeight { pool_id: pool_data.pool.id.clone(), weight }
+        })
+        .collect();
+
+    // Assign rounding remainder to the heaviest pool so that weights sum up to the denominator
+    let assigned: u32 = weights.iter().map(|pool_weight| pool_weight.weight).sum();
+    if let Some(heaviest) = weights.iter_mut().max_by_key(|pool_weight| pool_weight.weight) {
+        heaviest.weight += WEIGHT_DENOMINATOR - assigned;
+    }
+
+    weights
+}
+
+/// Splits the amount between the allocations according to their target weights.
+/// The rounding remainder goes to the allocation with the highest target weight.
+pub fn split_amount(amount: &Nat, allocations: &[PoolAllocation]) -> Vec<Nat> {
+    let mut amounts: Vec<Nat> = allocations
+        .iter()
+        .map(|allocation| amount.clone() * Nat::from(allocation.target_weight) / Nat::from(WEIGHT_DENOMINATOR))
+        .collect();
+
+    let assigned = amounts.iter().fold(Nat::from(0u64), |sum, part| sum + part.clone());
+
+    let heaviest_index = allocations
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, allocation)| allocation.target_weight)
+        .map(|(index, _)| index);
+
+    if let Some(index) = heaviest_index {
+        if *amount > assigned {
+            amounts[index] += amount.clone() - assigned;
+        }
+    }
+
+    amounts
+}
+
+/// Calculates the current weight of each allocation from its last known liquidity
+pub fn calculate_current_weights(allocations: &[PoolAllocation]) -> Vec<u32> {
+    let total_liquidity = allocations
+        .iter()
+        .fold(Nat::from(0u64), |sum, allocation| {
+            sum + allocation.current_liquidity.clone().unwrap_or(Nat::from(0u64))
+        });
+
+    if total_liquidity == Nat::from(0u64) {
+        return vec![0; allocations.len()];
+    }
+
+    allocations
+        .iter()
+        .map(|allocation| {
+            let liquidity = allocation.current_liquidity.clone().unwrap_or(Nat::from(0u64));
+            let weight = liquidity * Nat::from(WEIGHT_DENOMINATOR) / total_liquidity.clone();
+            nat_to_u64(&weight) as u32
+        })
+        .collect()
+}
+
+/// Calculates the absolute deviation of the current weight from the target weight for each allocation
+pub fn calculate_drift(allocations: &[PoolAllocation]) -> Vec<u32> {
+    calculate_current_weights(allocations)
+        .into_iter()
+        .zip(allocations.iter())
+        .map(|(current_weight, allocation)| current_weight.abs_diff(allocation.target_weight))
+        .collect()
+}
+
+/// Allocations of a strategy which held its liquidity in a single pool before it got an allocation policy.
+/// Every pool with a weight gets an allocation and the existing positions are kept in the allocations of their pools.
+pub fn allocations_from_positions(
+    pools: Vec<Pool>,
+    pool_weights: &[PoolWeight],
+    positions: &[(Pool, Option<u64>)],
+) -> Vec<PoolAllocation> {
+    pools
+        .into_iter()
+        .filter_map(|pool| {
+            let target_weight = pool_weights
+                .iter()
+                .find(|pool_weight| pool_weight.pool_id == pool.id)?
+                .weight;
+
+            let position_id = positions
+                .iter()
+                .find(|(position_pool, _)| position_pool.id == pool.id)
+                .and_then(|(_, position_id)| *position_id);
+
+            Some(PoolAllocation {
+                pool,
+                position_id,
+                target_weight,
+                current_liquidity: None,
+            })
+        })
+        .collect()
+}
+
+pub fn max_drift(allocations: &[PoolAllocation]) -> u32 {
+    calculate_drift(allocations).into_iter().max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::exchange_id::ExchangeId;
+    use types::pool::PoolTrait;
+    use types::test_utils::token;
+
+    fn build_pool(provider: ExchangeId) -> Pool {
+        Pool::build(token(1), token(2), provider)
+    }
+
+    fn build_allocation(provider: ExchangeId, target_weight: u32, current_liquidity: Option<u64>) -> PoolAllocation {
+        PoolAllocation {
+            pool: build_pool(provider),
+            position_id: None,
+            target_weight,
+            current_liquidity: current_liquidity.map(Nat::from),
+        }
+    }
+
+    mod calculate_apy_weights {
+        use super::*;
+
+        #[test]
+        fn weights_proportional_to_apy() {
+            let pools_data = vec![
+                PoolData { pool: build_pool(ExchangeId::KongSwap), apy: 30.0 },
+                PoolData { pool: build_pool(ExchangeId::ICPSwap), apy: 10.0 },
+            ];
+
+            let weights = calculate_apy_weights(&pools_data);
+
+            assert_eq!(weights[0].weight, 7_500);
+            assert_eq!(weights[1].weight, 2_500);
+        }
+
+        #[test]
+        fn equal_weights_without_apy() {
+            let pools_data = vec![
+                PoolData { pool: build_pool(ExchangeId::KongSwap), apy: 0.0 },
+                PoolData { pool: build_pool(ExchangeId::ICPSwap), apy: 0.0 },
+                PoolData { pool: build_pool(ExchangeId::Sonic), apy: 0.0 },
+            ];
+
+            let weights = calculate_apy_weights(&pools_data);
+            let total: u32 = weights.iter().map(|pool_weight| pool_weight.weight).sum();
+
+            assert_eq!(total, WEIGHT_DENOMINATOR);
+            assert_eq!(weights[1].weight, 3_333);
+        }
+    }
+
+    mod split_amount {
+        use super::*;
+
+        #[test]
+        fn splits_by_target_weights() {
+            let allocations = vec![
+                build_allocation(ExchangeId::KongSwap, 3_333, None),
+                build_allocation(ExchangeId::ICPSwap, 6_667, None),
+            ];
+
+            let amounts = split_amount(&Nat::from(1_000u64), &allocations);
+
+            assert_eq!(amounts, vec![Nat::from(333u64), Nat::from(667u64)]);
+        }
+    }
+
+    mod allocations_from_positions {
+        use super::*;
+
+        #[test]
+        fn keeps_position_of_single_pool() {
+            let kongswap_pool = build_pool(ExchangeId::KongSwap);
+            let icpswap_pool = build_pool(ExchangeId::ICPSwap);
+            let pool_weights = vec![
+                PoolWeight { pool_id: kongswap_pool.id.clone(), weight: 5_000 },
+                PoolWeight { pool_id: icpswap_pool.id.clone(), weight: 5_000 },
+            ];
+
+            let allocations = allocations_from_positions(
+                vec![kongswap_pool.clone(), icpswap_pool.clone()],
+                &pool_weights,
+                &[(icpswap_pool.clone(), Some(7))],
+            );
+
+            assert_eq!(allocations.len(), 2);
+            assert_eq!(allocations[0].position_id, None);
+            assert_eq!(allocations[1].pool.id, icpswap_pool.id);
+            assert_eq!(allocations[1].position_id, Some(7));
+            assert_eq!(allocations[1].target_weight, 5_000);
+        }
+    }
+
+    mod calculate_drift {
+        use super::*;
+
+        #[test]
+        fn returns_deviation_from_targets() {
+            let allocations = vec![
+                build_allocation(ExchangeId::KongSwap, 5_000, Some(700)),
+                build_allocation(ExchangeId::ICPSwap, 5_000, Some(300)),
+            ];
+
+            assert_eq!(calculate_drift(&allocations), vec![2_000, 2_000]);
+            assert_eq!(max_drift(&allocations), 2_000);
+        }
+    }
+}
@@ -2,6 +2,11 @@ use std::collections::HashMap;
 use candid::{Nat, Principal};
 use crate::types::types::StrategyId;
 use crate::pools::pool::Pool;
+use crate::strategies::rebalance_plan::RebalancePlan;
+use crate::strategies::allocation::{AllocationPolicy, PoolAllocation};
+use crate::strategies::strategy_state::StrategyState;
+use crate::strategies::strategy_lifecycle::StrategyLifecycle;
+use crate::strategies::swap_limits::StrategySwapLimits;
 
 pub trait BasicStrategy {
     fn get_name(&self) -> String;
@@ -26,6 +31,18 @@ pub trait BasicStrategy {
     fn set_current_liquidity(&mut self, current_liquidity: Option<Nat>);
     fn get_current_liquidity_updated_at(&self) -> Option<u64>;
     fn set_current_liquidity_updated_at(&mut self, current_liquidity_updated_at: Option<u64>);
+    fn get_rebalance_plan(&self) -> Option<RebalancePlan>;
+    fn set_rebalance_plan(&mut self, rebalance_plan: Option<RebalancePlan>);
+    fn get_allocation_policy(&self) -> Option<AllocationPolicy>;
+    fn get_swap_limits(&self) -> StrategySwapLimits;
+    fn get_pool_allocations(&self) -> Vec<PoolAllocation>;
+    fn set_pool_allocations(&mut self, pool_allocations: Vec<PoolAllocation>);
+    fn get_state(&self) -> StrategyState;
+    fn set_state(&mut self, state: StrategyState);
+    fn get_idle_balance(&self) -> Nat;
+    fn set_idle_balance(&mut self, idle_balance: Nat);
+    fn get_lifecycle(&self) -> StrategyLifecycle;
+    fn set_lifecycle(&mut self, lifecycle: StrategyLifecycle);
 }
 
 #[macro_export]
@@ -120,6 +137,54 @@ macro_rules! impl_strategy_methods {
             fn set_current_liquidity_updated_at(&mut self, current_liquidity_updated_at: Option<u64>) {
                 self.current_liquidity_updated_at = current_liquidity_updated_at;
             }
+
+            fn get_rebalance_plan(&self) -> Option<RebalancePlan> {
+                self.rebalance_plan.clone()
+            }
+
+            fn set_rebalance_plan(&mut self, rebalance_plan: Option<RebalancePlan>) {
+                self.rebalance_plan = rebalance_plan;
+            }
+
+            fn get_allocation_policy(&self) -> Option<AllocationPolicy> {
+                STRATEGY_MAP.get(&self.id).unwrap().allocation_policy.clone()
+            }
+
+            fn get_swap_limits(&self) -> StrategySwapLimits {
+                STRATEGY_MAP.get(&self.id).unwrap().swap_limits.clone()
+            }
+
+            fn get_pool_allocations(&self) -> Vec<PoolAllocation> {
+                self.pool_allocations.clone().unwrap_or_default()
+            }
+
+            fn set_pool_allocations(&mut self, pool_allocations: Vec<PoolAllocation>) {
+                self.pool_allocations = Some(pool_allocations);
+            }
+
+            fn get_state(&self) -> StrategyState {
+                self.state.unwrap_or_default()
+            }
+
+            fn set_state(&mut self, state: StrategyState) {
+                self.state = Some(state);
+            }
+
+            fn get_idle_balance(&self) -> Nat {
+                self.idle_balance.clone().unwrap_or(Nat::from(0u64))
+            }
+
+            fn set_idle_balance(&mut self, idle_balance: Nat) {
+                self.idle_balance = Some(idle_balance);
+            }
+
+            fn get_lifecycle(&self) -> StrategyLifecycle {
+                self.lifecycle.unwrap_or_default()
+            }
+
+            fn set_lifecycle(&mut self, lifecycle: StrategyLifecycle) {
+                self.lifecycle = Some(lifecycle);
+            }
         }
     };
 }
\ No newline at end of file
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 
 use types::exchange_id::ExchangeId;
 use types::pool::PoolTrait;
+use types::swap_tokens::SwapLimits;
 use utils::constants::{
     ICP_TOKEN_CANISTER_ID,
     CKUSDT_TOKEN_CANISTER_ID,
@@ -12,12 +13,16 @@ use utils::constants::{
 };
 
 use crate::pools::pool::Pool;
+use crate::strategies::allocation::{AllocationPolicy, PoolWeight};
+use crate::strategies::swap_limits::StrategySwapLimits;
 
 #[derive(Debug, Clone)]
 pub struct StrategyInfo {
     pub name: String,
     pub description: String,
     pub pools: Vec<Pool>,
+    pub allocation_policy: Option<AllocationPolicy>,
+    pub swap_limits: StrategySwapLimits,
 }
 
 //TODO init from file
@@ -39,6 +44,8 @@ lazy_static! {
                     ExchangeId::KongSwap,
                 ),
             ],
+            allocation_policy: None,
+            swap_limits: StrategySwapLimits::default(),
         });
         m.insert(1, StrategyInfo {
             name: "ckBTC Growth Strategy".to_string(),
@@ -55,6 +62,13 @@ lazy_static! {
                     ExchangeId::KongSwap,
                 ),
             ],
+            allocation_policy: None,
+            // ckBTC pools are deep, so swaps are held to tighter limits
+            swap_limits: StrategySwapLimits::new(SwapLimits {
+                slippage_tolerance_bps: 100,
+                max_price_impact_bps: 300,
+                max_price_deviation_bps: 200,
+            }),
         });
         m.insert(3, StrategyInfo {
             name: "ICP-ckBTC Dynamic Strategy".to_string(),
@@ -71,6 +85,12 @@ lazy_static! {
                     ExchangeId::KongSwap,
                 ),
             ],
+            allocation_policy: None,
+            swap_limits: StrategySwapLimits::new(SwapLimits {
+                slippage_tolerance_bps: 100,
+                max_price_impact_bps: 300,
+                max_price_deviation_bps: 200,
+            }),
         });
         m.insert(4, StrategyInfo {
             name: "Panda-ICP Balanced Strategy".to_string(),
@@ -87,22 +107,40 @@ lazy_static! {
                     ExchangeId::ICPSwap,
                 ),
             ],
+            allocation_policy: None,
+            // PANDA liquidity is thin, so its swaps move the price more
+            swap_limits: StrategySwapLimits::default().with_pair(
+                *PANDA_TOKEN_CANISTER_ID,
+                *ICP_TOKEN_CANISTER_ID,
+                SwapLimits {
+                    slippage_tolerance_bps: 1_000,
+                    max_price_impact_bps: 2_000,
+                    max_price_deviation_bps: 500,
+                },
+            ),
         });
+        let ics_icp_kongswap_pool = Pool::build(
+            *ICS_TOKEN_CANISTER_ID,
+            *ICP_TOKEN_CANISTER_ID,
+            ExchangeId::KongSwap,
+        );
+        let ics_icp_icpswap_pool = Pool::build(
+            *ICS_TOKEN_CANISTER_ID,
+            *ICP_TOKEN_CANISTER_ID,
+            ExchangeId::ICPSwap,
+        );
         m.insert(5, StrategyInfo {
             name: "ICS-ICP Balanced Strategy".to_string(),
             description: "Cheap test strategy".to_string(),
+            allocation_policy: Some(AllocationPolicy::Static(vec![
+                PoolWeight { pool_id: ics_icp_kongswap_pool.id.clone(), weight: 5_000 },
+                PoolWeight { pool_id: ics_icp_icpswap_pool.id.clone(), weight: 5_000 },
+            ])),
             pools: vec![
-                Pool::build(
-                    *ICS_TOKEN_CANISTER_ID,
-                    *ICP_TOKEN_CANISTER_ID,
-                    ExchangeId::KongSwap,
-                ),
-                Pool::build(
-                    *ICS_TOKEN_CANISTER_ID,
-                    *ICP_TOKEN_CANISTER_ID,
-                    ExchangeId::ICPSwap,
-                ),
+                ics_icp_kongswap_pool,
+                ics_icp_icpswap_pool,
             ],
+            swap_limits: StrategySwapLimits::default(),
         });
         m
     };
@@ -4,6 +4,11 @@ use crate::strategies::strategy::IStrategy;
 use crate::strategies::strategy_candid::StrategyCandid;
 use crate::types::types::StrategyId;
 use crate::pools::pool::Pool;
+use crate::strategies::rebalance_plan::RebalancePlan;
+use crate::strategies::allocation::{AllocationPolicy, PoolAllocation};
+use crate::strategies::swap_limits::StrategySwapLimits;
+use crate::strategies::strategy_state::StrategyState;
+use crate::strategies::strategy_lifecycle::StrategyLifecycle;
 use async_trait::async_trait;
 use candid::{CandidType, Deserialize, Nat, Principal};
 use serde::Serialize;
@@ -22,6 +27,11 @@ pub struct ICPStrategy {
     initial_deposit: HashMap<Principal, Nat>,
     current_liquidity: Option<Nat>,
     current_liquidity_updated_at: Option<u64>,
+    rebalance_plan: Option<RebalancePlan>,
+    pool_allocations: Option<Vec<PoolAllocation>>,
+    state: Option<StrategyState>,
+    idle_balance: Option<Nat>,
+    lifecycle: Option<StrategyLifecycle>,
 }
 
 impl ICPStrategy {
@@ -37,6 +47,11 @@ impl ICPStrategy {
             initial_deposit: HashMap::new(),
             current_liquidity: None,
             current_liquidity_updated_at: None,
+            rebalance_plan: None,
+            pool_allocations: None,
+            state: None,
+            idle_balance: None,
+            lifecycle: None,
         }
     }
 }
@@ -5,6 +5,11 @@ use crate::strategies::strategy::IStrategy;
 use crate::strategies::strategy_candid::StrategyCandid;
 use crate::types::types::StrategyId;
 use crate::pools::pool::Pool;
+use crate::strategies::rebalance_plan::RebalancePlan;
+use crate::strategies::allocation::{AllocationPolicy, PoolAllocation};
+use crate::strategies::swap_limits::StrategySwapLimits;
+use crate::strategies::strategy_state::StrategyState;
+use crate::strategies::strategy_lifecycle::StrategyLifecycle;
 use async_trait::async_trait;
 use candid::{CandidType, Deserialize, Nat, Principal};
 
@@ -26,6 +31,11 @@ pub struct IcpCkUSDTStrategy {
     initial_deposit: HashMap<Principal, Nat>,
     current_liquidity: Option<Nat>,
     current_liquidity_updated_at: Option<u64>,
+    rebalance_plan: Option<RebalancePlan>,
+    pool_allocations: Option<Vec<PoolAllocation>>,
+    state: Option<StrategyState>,
+    idle_balance: Option<Nat>,
+    lifecycle: Option<StrategyLifecycle>,
 }
 
 impl IcpCkUSDTStrategy {
@@ -41,6 +51,11 @@ impl IcpCkUSDTStrategy {
             initial_deposit: HashMap::new(),
             current_liquidity: None,
             current_liquidity_updated_at: None,
+            rebalance_plan: None,
+            pool_allocations: None,
+            state: None,
+            idle_balance: None,
+            lifecycle: None,
         }
     }
 }
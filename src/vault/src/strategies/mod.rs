@@ -1,7 +1,14 @@
 pub mod strategy;
-mod r#impl;
+pub(crate) mod r#impl;
 pub mod strategy_service;
 pub mod strategy_candid;
 pub mod basic_strategy;
+pub mod rebalance_plan;
+pub mod allocation;
+pub mod strategy_state;
+pub mod strategy_lifecycle;
+pub mod strategy_audit;
+pub mod swap_limits;
+pub mod rebalance_service;
 pub mod test;
 pub mod stats;
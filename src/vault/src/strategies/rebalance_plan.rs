@@ -0,0 +1,164 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+
+use crate::pools::pool::Pool;
+
+pub const FULL_REBALANCE_PERCENTAGE: u8 = 100;
+
+/// Gradual move of the strategy liquidity from one pool to another.
+/// Every step moves `step_percentage` of the initial position,
+/// so the strategy holds liquidity in both pools until the plan is completed.
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct RebalancePlan {
+    pub from_pool: Pool,
+    pub to_pool: Pool,
+    pub to_position_id: Option<u64>,
+    pub step_percentage: u8,
+    pub total_steps: u32,
+    pub completed_steps: u32,
+    pub started_at: u64,
+    pub updated_at: u64,
+    /// Tokens of the current step withdrawn from the source pool and not added to the destination pool yet,
+    /// so a failed step is retried from them instead of withdrawing again
+    pub step_amounts: Option<RebalanceStepAmounts>,
+}
+
+/// Token amounts held for a step of the plan, in the token order of the source pool
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug, PartialEq)]
+pub struct RebalanceStepAmounts {
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+}
+
+impl RebalancePlan {
+    pub fn new(from_pool: Pool, to_pool: Pool, step_percentage: u8, timestamp: u64) -> Self {
+        let step_percentage = step_percentage.clamp(1, FULL_REBALANCE_PERCENTAGE);
+        let total_steps = (FULL_REBALANCE_PERCENTAGE as u32).div_ceil(step_percentage as u32);
+
+        Self {
+            from_pool,
+            to_pool,
+            to_position_id: None,
+            step_percentage,
+            total_steps,
+            completed_steps: 0,
+            started_at: timestamp,
+            updated_at: timestamp,
+            step_amounts: None,
+        }
+    }
+
+    /// Percentage of the initial position still held in the source pool
+    pub fn remaining_percentage(&self) -> u32 {
+        (FULL_REBALANCE_PERCENTAGE as u32)
+            .saturating_sub(self.completed_steps * self.step_percentage as u32)
+    }
+
+    /// Returns `(total_shares, shares)` to withdraw from the source pool on the next step.
+    /// Liquidity clients withdraw `shares / total_shares` of the current position.
+    pub fn next_step_shares(&self) -> (Nat, Nat) {
+        let remaining_percentage = self.remaining_percentage();
+        let step_percentage = remaining_percentage.min(self.step_percentage as u32);
+
+        (Nat::from(remaining_percentage), Nat::from(step_percentage))
+    }
+
+    /// Records the tokens the current step holds, until they are added to the destination pool
+    pub fn hold_step_amounts(&mut self, token_0_amount: Nat, token_1_amount: Nat, timestamp: u64) {
+        self.step_amounts = Some(RebalanceStepAmounts { token_0_amount, token_1_amount });
+        self.updated_at = timestamp;
+    }
+
+    pub fn complete_step(&mut self, to_position_id: u64, timestamp: u64) {
+        self.to_position_id = Some(to_position_id);
+        self.completed_steps += 1;
+        self.step_amounts = None;
+        self.updated_at = timestamp;
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.completed_steps >= self.total_steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::exchange_id::ExchangeId;
+    use types::pool::PoolTrait;
+    use types::test_utils::token;
+
+    fn build_plan(step_percentage: u8) -> RebalancePlan {
+        RebalancePlan::new(
+            Pool::build(token(1), token(2), ExchangeId::KongSwap),
+            Pool::build(token(1), token(2), ExchangeId::ICPSwap),
+            step_percentage,
+            0,
+        )
+    }
+
+    mod new {
+        use super::*;
+
+        #[test]
+        fn calculates_total_steps() {
+            assert_eq!(build_plan(100).total_steps, 1);
+            assert_eq!(build_plan(25).total_steps, 4);
+            assert_eq!(build_plan(30).total_steps, 4);
+        }
+
+        #[test]
+        fn clamps_step_percentage() {
+            assert_eq!(build_plan(0).step_percentage, 1);
+            assert_eq!(build_plan(200).step_percentage, 100);
+        }
+    }
+
+    mod next_step_shares {
+        use super::*;
+
+        #[test]
+        fn withdraws_step_part_of_remaining_position() {
+            let mut plan = build_plan(30);
+
+            assert_eq!(plan.next_step_shares(), (Nat::from(100u32), Nat::from(30u32)));
+            plan.complete_step(1, 1);
+            assert_eq!(plan.next_step_shares(), (Nat::from(70u32), Nat::from(30u32)));
+            plan.complete_step(1, 2);
+            plan.complete_step(1, 3);
+            assert_eq!(plan.next_step_shares(), (Nat::from(10u32), Nat::from(10u32)));
+        }
+    }
+
+    mod complete_step {
+        use super::*;
+
+        #[test]
+        fn completes_plan_after_all_steps() {
+            let mut plan = build_plan(50);
+
+            plan.complete_step(7, 10);
+            assert!(!plan.is_completed());
+            assert_eq!(plan.to_position_id, Some(7));
+
+            plan.complete_step(7, 20);
+            assert!(plan.is_completed());
+            assert_eq!(plan.updated_at, 20);
+        }
+
+        #[test]
+        fn clears_held_step_amounts() {
+            let mut plan = build_plan(50);
+
+            plan.hold_step_amounts(Nat::from(10u32), Nat::from(20u32), 10);
+            assert_eq!(plan.step_amounts, Some(RebalanceStepAmounts {
+                token_0_amount: Nat::from(10u32),
+                token_1_amount: Nat::from(20u32),
+            }));
+
+            plan.complete_step(7, 20);
+            assert_eq!(plan.step_amounts, None);
+            assert_eq!(plan.completed_steps, 1);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+use std::cell::RefCell;
+use ic_cdk_timers::TimerId;
+
+use crate::repository::strategies_repo;
+
+thread_local! {
+    static REBALANCE_STEP_TIMER_ID: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+/// Starts the timer which executes the next step of every rebalance plan in progress
+pub fn start_rebalance_step_timer(interval: u64) {
+    let timer_id = ic_cdk_timers::set_timer_interval(Duration::from_secs(interval), || {
+        ic_cdk::spawn(async {
+            execute_rebalance_steps().await;
+        });
+    });
+
+    REBALANCE_STEP_TIMER_ID.with(|cell| {
+        cell.replace(Some(timer_id));
+    });
+}
+
+pub fn stop_rebalance_step_timer() {
+    REBALANCE_STEP_TIMER_ID.with(|timer_id| {
+        if let Some(timer_id) = timer_id.borrow_mut().take() {
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+    });
+}
+
+pub async fn execute_rebalance_steps() {
+    let strategies = strategies_repo::get_all_strategies()
+        .into_iter()
+        .filter(|strategy| {
+            strategy.get_rebalance_plan().is_some()
+                && strategy.get_allocation_policy().is_none()
+                && strategy.get_state().allows_rebalance()
+                && strategy.get_lifecycle().allows_rebalance()
+        })
+        .collect::<Vec<_>>();
+
+    for mut strategy in strategies {
+        // Failed step is recorded as event and retried on the next tick
+        let _ = strategy.rebalance(None).await;
+    }
+}
@@ -9,13 +9,19 @@ use types::exchange_id::ExchangeId;
 use liquidity::liquidity_router;
 use swap::swap_service;
 use utils::util::current_timestamp;
+use types::CanisterId;
+use types::context::Context;
 
 use crate::repository::strategies_repo;
 use crate::strategies::strategy::IStrategy;
+use crate::pools::pool::Pool;
+use crate::strategies::allocation;
+use crate::event_records::event_record::Event;
+use crate::event_records::event_record_service;
 use crate::utils::provider_impls::get_environment_provider_impls;
 
 thread_local! {
-    static STRATEGY_STATS_TIMER_ID: RefCell<Option<TimerId>> = RefCell::new(None);
+    static STRATEGY_STATS_TIMER_ID: RefCell<Option<TimerId>> = const { RefCell::new(None) };
 }
 
 fn set_timer_interval(
@@ -56,9 +62,35 @@ pub async fn update_all_strategy_liquidity() {
     }
 }
 
-pub async fn update_strategy_liquidity(mut strategy: Box<dyn IStrategy>) -> Result<(), InternalError> {
-    let liquidity_amount = get_strategy_current_liquidity(strategy.as_ref()).await?;
-    
+pub async fn update_strategy_liquidity(strategy: Box<dyn IStrategy>) -> Result<(), InternalError> {
+    let active_positions = get_active_position_ids(strategy.as_ref());
+
+    let (liquidity_amount, pool_allocations_liquidity) = if strategy.get_pool_allocations().is_empty() {
+        (get_strategy_current_liquidity(strategy.as_ref()).await?, None)
+    } else {
+        let pool_allocations_liquidity = get_pool_allocations_liquidity(strategy.as_ref()).await?;
+        let liquidity_amount = pool_allocations_liquidity
+            .values()
+            .fold(Nat::from(0u64), |sum, liquidity| sum + liquidity.clone());
+
+        (liquidity_amount, Some(pool_allocations_liquidity))
+    };
+
+    // The strategy can change during the awaits, so only the liquidity of its latest state is updated,
+    // provided it still holds the positions the liquidity was valued from
+    let mut strategy = match strategies_repo::get_strategy_by_id(strategy.get_id()) {
+        Some(strategy) => strategy,
+        None => return Ok(()),
+    };
+
+    if get_active_position_ids(strategy.as_ref()) != active_positions {
+        return Ok(());
+    }
+
+    if let Some(pool_allocations_liquidity) = pool_allocations_liquidity {
+        update_pool_allocations_liquidity(strategy.as_mut(), &pool_allocations_liquidity);
+    }
+
     strategy.set_current_liquidity(Some(liquidity_amount));
     strategy.set_current_liquidity_updated_at(Some(current_timestamp()));
 
@@ -73,6 +105,61 @@ pub fn spawn_update_strategy_liquidity(strategy: Box<dyn IStrategy>) -> () {
     });
 }
 
+/// Returns the pool ids and the position ids the strategy liquidity is held in
+fn get_active_position_ids(strategy: &dyn IStrategy) -> Vec<(String, Option<u64>)> {
+    strategy.get_active_positions()
+        .into_iter()
+        .map(|(pool, position_id)| (pool.id, position_id))
+        .collect()
+}
+
+/// Values the position of each pool allocation in the base token, by pool id
+async fn get_pool_allocations_liquidity(strategy: &dyn IStrategy) -> Result<HashMap<String, Nat>, InternalError> {
+    let pool_allocations = strategy.get_pool_allocations();
+    let base_token = pool_allocations[0].pool.token0;
+    let mut pool_allocations_liquidity = HashMap::new();
+
+    for pool_allocation in pool_allocations {
+        let liquidity = match pool_allocation.position_id {
+            Some(position_id) => get_position_value(pool_allocation.pool.clone(), position_id, base_token).await?,
+            None => Nat::from(0u64),
+        };
+
+        pool_allocations_liquidity.insert(pool_allocation.pool.id, liquidity);
+    }
+
+    Ok(pool_allocations_liquidity)
+}
+
+/// Updates the liquidity of each pool allocation and reports drift from the target weights
+fn update_pool_allocations_liquidity(strategy: &mut dyn IStrategy, pool_allocations_liquidity: &HashMap<String, Nat>) {
+    let mut pool_allocations = strategy.get_pool_allocations();
+    let mut total_liquidity = Nat::from(0u64);
+
+    for pool_allocation in pool_allocations.iter_mut() {
+        let liquidity = pool_allocations_liquidity
+            .get(&pool_allocation.pool.id)
+            .cloned()
+            .unwrap_or(Nat::from(0u64));
+
+        total_liquidity += liquidity.clone();
+        pool_allocation.current_liquidity = Some(liquidity);
+    }
+
+    let max_drift = allocation::max_drift(&pool_allocations);
+
+    if total_liquidity > Nat::from(0u64) && max_drift > allocation::DRIFT_THRESHOLD {
+        // Event: Strategy allocation drifted
+        event_record_service::create_event_record(
+            Event::strategy_allocation_drifted(strategy.get_id().to_string(), max_drift),
+            Context::generate(None).correlation_id,
+            None,
+        );
+    }
+
+    strategy.set_pool_allocations(pool_allocations);
+}
+
 pub async fn get_strategy_current_liquidity(strategy: &dyn IStrategy) -> Result<Nat, InternalError> {
     let strategy_id = strategy.get_id();
     let current_pool = strategy.get_current_pool();
@@ -88,14 +175,7 @@ pub async fn get_strategy_current_liquidity(strategy: &dyn IStrategy) -> Result<
         ));
     }
 
-    let pool = current_pool.unwrap();
-
-    let liquidity_client = liquidity_router::get_liquidity_client(
-        get_environment_provider_impls(),
-        pool.token0,
-        pool.token1,
-        pool.provider
-    ).await;
+    let current_pool = current_pool.unwrap();
 
     let position_id = strategy.get_position_id()
         .ok_or_else(|| {
@@ -109,17 +189,41 @@ pub async fn get_strategy_current_liquidity(strategy: &dyn IStrategy) -> Result<
             )
         })?;
 
+    let mut base_token_amount = get_position_value(current_pool.clone(), position_id, current_pool.token0).await?;
+
+    // During a gradual rebalance part of the liquidity is already in the new pool
+    if let Some(rebalance_plan) = strategy.get_rebalance_plan() {
+        if let Some(to_position_id) = rebalance_plan.to_position_id {
+            base_token_amount += get_position_value(rebalance_plan.to_pool, to_position_id, current_pool.token0).await?;
+        }
+    }
+
+    Ok(base_token_amount)
+}
+
+async fn get_position_value(pool: Pool, position_id: u64, base_token: CanisterId) -> Result<Nat, InternalError> {
+    let liquidity_client = liquidity_router::get_liquidity_client(
+        get_environment_provider_impls(),
+        pool.token0,
+        pool.token1,
+        pool.provider
+    ).await?;
+
     let position_response = liquidity_client.get_position_by_id(position_id).await?;
 
+    let (token_in, amount_in, base_token_amount) = if pool.token0 == base_token {
+        (pool.token1, position_response.token_1_amount, position_response.token_0_amount)
+    } else {
+        (pool.token0, position_response.token_0_amount, position_response.token_1_amount)
+    };
+
     let quote_response = swap_service::quote_swap_icrc2(
         get_environment_provider_impls(),
-        pool.token1,
-        pool.token0,
-        position_response.token_1_amount,
+        token_in,
+        base_token,
+        amount_in,
         ExchangeId::KongSwap
     ).await?;
 
-    let base_token_amount = Nat::from(quote_response.amount_out) + position_response.token_0_amount;
-
-    Ok(base_token_amount)
+    Ok(Nat::from(quote_response.amount_out) + base_token_amount)
 }
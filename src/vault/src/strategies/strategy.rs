@@ -1,27 +1,41 @@
 use async_trait::async_trait;
 use candid::{Nat, Principal};
-use std::cell::RefMut;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 
 use liquidity::liquidity_calculator::LiquidityCalculator;
 use types::exchange_id::ExchangeId;
+use types::liquidity::LiquidityFees;
 use types::pool::PoolTrait;
 use types::context::Context;
+use types::CanisterId;
 use errors::internal_error::error::InternalError;
 use errors::internal_error::error::build_error_code;
 use utils::token_transfer::icrc1_transfer_to_user;
+use token_registry::ledger_fees::{self, TRANSFER_FEE_COUNT};
+use utils::util::current_timestamp;
 
 use crate::event_records::event_record::Event;
 use crate::event_records::event_record_service;
+use crate::event_records::events::ledger_events::LedgerFeeOperation;
+use crate::event_records::events::strategy_events::MigratedUserShares;
 use crate::repository::strategies_repo;
 use crate::strategies::basic_strategy::BasicStrategy;
 use crate::strategies::strategy_candid::StrategyCandid;
 use crate::liquidity::liquidity_service;
 use crate::pools::pool::Pool;
+use crate::pools::pool_data::PoolData;
 use crate::strategies::stats::strategy_stats_service;
+use crate::strategies::rebalance_plan::{RebalancePlan, FULL_REBALANCE_PERCENTAGE};
+use crate::strategies::allocation::{self, AllocationPolicy, PoolAllocation};
+use crate::strategies::strategy_state::StrategyState;
+use crate::strategies::strategy_lifecycle::StrategyLifecycle;
 use crate::types::types::{
+    MigrateStrategyResponse,
+    PoolAllocationResponse,
     StrategyDepositResponse,
+    StrategyPreviewDepositResponse,
+    StrategyPreviewWithdrawResponse,
     StrategyRebalanceResponse,
     StrategyResponse,
     StrategyWithdrawResponse,
@@ -47,7 +61,8 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
     /// # Details
     ///
     /// This function:
-    /// 1. Retrieves the current pool from the strategy
+    /// 1. Retrieves the current pool from the strategy,
+    ///    or splits the deposit between pools if the strategy has an allocation policy
     /// 2. Calculates the new shares for the investor's deposit
     /// 3. Updates the total balance and total shares
     /// 4. Updates the user shares mapping
@@ -70,12 +85,38 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             Some(investor),
         );
 
+        if !self.get_state().allows_deposit() || !self.get_lifecycle().allows_deposit() {
+            let error = InternalError::business_logic(
+                build_error_code(3100, 3, 12), // 3100 03 12
+                "Strategy::deposit".to_string(),
+                "Deposits are not allowed in the current strategy state".to_string(),
+                Some(HashMap::from([
+                    ("state".to_string(), format!("{:?}", self.get_state())),
+                    ("lifecycle".to_string(), format!("{:?}", self.get_lifecycle())),
+                ]))
+            );
+
+            // Event: Strategy deposit failed
+            event_record_service::create_event_record(
+                Event::strategy_deposit_failed(strategy_id, None, Some(amount), error.clone()),
+                context.correlation_id,
+                Some(investor),
+            );
+
+            return Err(error);
+        }
+
+        // Multi-pool strategies split the deposit between pools by target weights
+        if let Some(allocation_policy) = self.get_allocation_policy() {
+            return self.deposit_with_allocation(context, investor, amount, allocation_policy).await;
+        }
+
         let mut current_pool = self.get_current_pool();
 
         // Set current pool to the best APY pool if not set
         if current_pool.is_none() {
             // Find the best APY pool
-            let best_apy_pool = self.get_best_apy_pool().await;
+            let best_apy_pool = self.get_best_apy_pool(&context).await;
 
             if best_apy_pool.is_none() {
                 let error = InternalError::not_found(
@@ -104,10 +145,12 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
         let add_liquidity_response = liquidity_service::add_liquidity_to_pool(
             context.clone(),
             amount.clone(),
-            current_pool.clone()
+            current_pool.clone(),
+            &self.get_swap_limits(),
         ).await?;
 
-        self.update_strategy_state_after_deposit(
+        let share_price = self.get_share_price();
+        let minted_shares = self.update_strategy_state_after_deposit(
             investor,
             amount.clone(),
             current_pool.clone(),
@@ -116,7 +159,14 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
 
         // Event: Strategy deposit completed
         event_record_service::create_event_record(
-            Event::strategy_deposit_completed(strategy_id, Some(current_pool.get_id()), Some(amount.clone())),
+            Event::strategy_deposit_completed(
+                strategy_id,
+                Some(current_pool.get_id()),
+                Some(amount.clone()),
+                minted_shares,
+                share_price,
+                add_liquidity_response.position_id,
+            ),
             context.correlation_id,
             Some(investor),
         );
@@ -129,6 +179,210 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
         })
     }
 
+    /// Deposits an amount of tokens split between the strategy pools according to the target weights
+    ///
+    /// # Details
+    ///
+    /// 1. Resolves target weights from the allocation policy
+    /// 2. Adds liquidity to each pool with its part of the amount
+    /// 3. If adding liquidity fails, credits the deposited part and returns the rest to the investor
+    /// 4. Updates the strategy state with the per-pool positions
+    ///
+    async fn deposit_with_allocation(
+        &mut self,
+        context: Context,
+        investor: Principal,
+        amount: Nat,
+        allocation_policy: AllocationPolicy,
+    ) -> Result<StrategyDepositResponse, InternalError> {
+        let strategy_id = self.get_id().to_string();
+        let mut pool_allocations = self.resolve_pool_allocations(&context, allocation_policy).await;
+        let pool_amounts = allocation::split_amount(&amount, &pool_allocations);
+
+        let mut deposited_amount = Nat::from(0u64);
+        let mut deposit_error = None;
+
+        for (pool_allocation, pool_amount) in pool_allocations.iter_mut().zip(pool_amounts) {
+            if pool_amount == Nat::from(0u64) {
+                continue;
+            }
+
+            match liquidity_service::add_liquidity_to_pool(
+                context.clone(),
+                pool_amount.clone(),
+                pool_allocation.pool.clone(),
+                &self.get_swap_limits(),
+            ).await {
+                Ok(add_liquidity_response) => {
+                    pool_allocation.position_id = Some(add_liquidity_response.position_id);
+                    deposited_amount += pool_amount;
+                }
+                Err(error) => {
+                    deposit_error = Some(error);
+                    break;
+                }
+            }
+        }
+
+        let main_allocation = pool_allocations
+            .iter()
+            .filter(|pool_allocation| pool_allocation.position_id.is_some())
+            .max_by_key(|pool_allocation| pool_allocation.target_weight)
+            .cloned();
+
+        self.set_pool_allocations(pool_allocations.clone());
+
+        let share_price = self.get_share_price();
+        let minted_shares = main_allocation.clone().map(|main_allocation| {
+            self.update_strategy_state_after_deposit(
+                investor,
+                deposited_amount.clone(),
+                main_allocation.pool,
+                main_allocation.position_id.unwrap(),
+            )
+        });
+
+        if let Some(error) = deposit_error {
+            // Return the part of the deposit which was not added to any pool
+            let base_token = pool_allocations[0].pool.token0;
+            let not_deposited_amount = amount.clone() - deposited_amount.clone();
+
+            // The transfer fee is paid out of the refunded amount
+            let refund_result = match ledger_fees::deduct_ledger_fees(base_token, &not_deposited_amount, TRANSFER_FEE_COUNT).await {
+                Ok(refund) => icrc1_transfer_to_user(investor, base_token, refund.amount.clone()).await.map(|_| refund),
+                Err(error) => Err(error),
+            };
+
+            if let Ok(refund) = &refund_result {
+                liquidity_service::record_ledger_fee(
+                    &context,
+                    None,
+                    LedgerFeeOperation::Transfer,
+                    base_token,
+                    refund.fees_paid.clone(),
+                );
+            }
+
+            // Event: Strategy deposit failed, with the part credited before the failure
+            let event = match (main_allocation, minted_shares) {
+                (Some(main_allocation), Some(minted_shares)) => Event::strategy_deposit_partially_failed(
+                    strategy_id,
+                    Some(main_allocation.pool.get_id()),
+                    Some(amount),
+                    deposited_amount,
+                    minted_shares,
+                    main_allocation.position_id.unwrap(),
+                    error.clone(),
+                ),
+                _ => Event::strategy_deposit_failed(strategy_id, None, Some(amount), error.clone()),
+            };
+            event_record_service::create_event_record(
+                event,
+                context.correlation_id,
+                Some(investor),
+            );
+
+            refund_result?;
+            return Err(error);
+        }
+
+        let main_allocation = main_allocation.unwrap();
+
+        // Event: Strategy deposit completed
+        event_record_service::create_event_record(
+            Event::strategy_deposit_completed(
+                strategy_id,
+                Some(main_allocation.pool.get_id()),
+                Some(amount.clone()),
+                minted_shares.unwrap(),
+                share_price,
+                main_allocation.position_id.unwrap(),
+            ),
+            context.correlation_id,
+            Some(investor),
+        );
+
+        Ok(StrategyDepositResponse {
+            amount,
+            shares: self.get_user_shares().get(&investor).unwrap().clone(),
+            tx_id: 0,
+            position_id: main_allocation.position_id.unwrap(),
+        })
+    }
+
+    /// Builds pool allocations with target weights from the allocation policy,
+    /// keeping positions of the existing allocations
+    async fn resolve_pool_allocations(&self, context: &Context, allocation_policy: AllocationPolicy) -> Vec<PoolAllocation> {
+        let pool_weights = match allocation_policy {
+            AllocationPolicy::Static(pool_weights) => pool_weights,
+            AllocationPolicy::ApyProportional => {
+                let pools_data = liquidity_service::get_pools_data(context, self.get_pools()).await;
+                allocation::calculate_apy_weights(&pools_data)
+            }
+        };
+
+        let existing_allocations = self.get_pool_allocations();
+
+        self.get_pools()
+            .into_iter()
+            .filter_map(|pool| {
+                let target_weight = pool_weights
+                    .iter()
+                    .find(|pool_weight| pool_weight.pool_id == pool.id)?
+                    .weight;
+
+                let existing_allocation = existing_allocations
+                    .iter()
+                    .find(|pool_allocation| pool_allocation.pool.id == pool.id);
+
+                Some(PoolAllocation {
+                    pool,
+                    position_id: existing_allocation.and_then(|pool_allocation| pool_allocation.position_id),
+                    target_weight,
+                    current_liquidity: existing_allocation.and_then(|pool_allocation| pool_allocation.current_liquidity.clone()),
+                })
+            })
+            .collect()
+    }
+
+    /// Moves the positions of a strategy which held its liquidity in a single pool before
+    /// it got an allocation policy into its pool allocations, so that its deposits and withdrawals
+    /// keep using them. A rebalance in progress is dropped, as allocation strategies are not rebalanced.
+    /// Target weights of APY proportional policies are resolved again on the next deposit.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the strategy was migrated
+    ///
+    fn migrate_to_pool_allocations(&mut self) -> bool {
+        let allocation_policy = match self.get_allocation_policy() {
+            Some(allocation_policy) => allocation_policy,
+            None => return false,
+        };
+
+        let positions = self.get_active_positions();
+
+        if !self.get_pool_allocations().is_empty() || positions.is_empty() {
+            return false;
+        }
+
+        let pool_weights = match allocation_policy {
+            AllocationPolicy::Static(pool_weights) => pool_weights,
+            AllocationPolicy::ApyProportional => {
+                let pools_data: Vec<PoolData> = self.get_pools()
+                    .into_iter()
+                    .map(|pool| PoolData { pool, apy: 0.0 })
+                    .collect();
+                allocation::calculate_apy_weights(&pools_data)
+            }
+        };
+
+        self.set_pool_allocations(allocation::allocations_from_positions(self.get_pools(), &pool_weights, &positions));
+        self.set_rebalance_plan(None);
+
+        true
+    }
+
     /// Withdraws shares from the strategy and returns the corresponding tokens to the investor
     ///
     /// # Arguments
@@ -144,12 +398,16 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
     /// This function:
     /// 1. Verifies the caller has sufficient shares
     /// 2. Gets the current pool and token information
-    /// 3. Removes liquidity from the pool proportional to shares
+    /// 3. Removes liquidity proportional to shares from every pool holding the strategy liquidity,
+    ///    or takes pro-rata share of the idle balance if the strategy is exited
     /// 4. Swaps secondary token to base token
     /// 5. Transfers total tokens to caller
     /// 6. Updates total shares, user shares and initial deposit
     /// 7. Saves updated strategy state
     ///
+    /// If withdrawing from a pool fails after other pools were withdrawn, the withdrawn amount is paid out,
+    /// the shares for the withdrawn part of the liquidity are burned and the error is returned.
+    ///
     /// TODO: Rename `shares` to `percentage`
     async fn withdraw(&mut self, context: Context, percentage: Nat) -> Result<StrategyWithdrawResponse, InternalError> {
         let strategy_id = self.get_id().to_string();
@@ -167,6 +425,32 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
         let current_pool = self.get_current_pool().clone();
         let current_pool_id = current_pool.clone().unwrap().get_id();
 
+        if !self.get_state().allows_withdraw() || !self.get_lifecycle().allows_withdraw() {
+            let error = InternalError::business_logic(
+                build_error_code(3100, 3, 13), // 3100 03 13
+                "Strategy::withdraw".to_string(),
+                "Withdrawals are not allowed in the current strategy state".to_string(),
+                Some(HashMap::from([
+                    ("state".to_string(), format!("{:?}", self.get_state())),
+                    ("lifecycle".to_string(), format!("{:?}", self.get_lifecycle())),
+                ]))
+            );
+
+            // Event: Strategy withdraw failed
+            event_record_service::create_event_record(
+                Event::strategy_withdraw_failed(
+                    strategy_id,
+                    Some(current_pool_id),
+                    Some(shares.clone()),
+                    error.clone(),
+                ),
+                context.correlation_id,
+                Some(investor),
+            );
+
+            return Err(error);
+        }
+
         if user_shares == Nat::from(0u8) {
             let error = InternalError::business_logic(
                 build_error_code(3100, 3, 3), // 3100 03 03
@@ -246,21 +530,86 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
         }
 
         let current_pool = current_pool.unwrap();
+        let is_exited = self.get_state() == StrategyState::Exited;
+        let mut amount_0_to_withdraw = Nat::from(0u64);
+        let mut shares = shares;
+        let mut withdraw_error = None;
+
+        if is_exited {
+            // Liquidity is already withdrawn, so pay out pro-rata share of the idle balance
+            amount_0_to_withdraw = self.get_idle_balance() * shares.clone() / self.get_total_shares();
+        } else {
+            // Withdraw liquidity from every pool holding the strategy liquidity
+            // and swap received tokens to token_0 (base token)
+            let positions = self.get_active_positions();
+            let weights = self.get_active_position_weights();
+            let total_weight: u32 = weights.iter().sum();
+            let mut withdrawn_weight = 0u32;
+            let mut withdrawn_pools_count = 0;
+
+            for ((pool, _), weight) in positions.into_iter().zip(weights) {
+                match liquidity_service::withdraw_liquidity_from_pool_and_swap(
+                    context.clone(),
+                    self.get_total_shares(),
+                    shares.clone(),
+                    pool,
+                    current_pool.token0,
+                    &self.get_swap_limits(),
+                ).await {
+                    Ok(amount) => {
+                        amount_0_to_withdraw += amount;
+                        withdrawn_weight += weight;
+                        withdrawn_pools_count += 1;
+                    }
+                    Err(error) => {
+                        withdraw_error = Some(error);
+                        break;
+                    }
+                }
+            }
 
-        // Withdraw liquidity from pool and swap token_1 to token_0 (base token)
-        let amount_0_to_withdraw = liquidity_service::withdraw_liquidity_from_pool_and_swap(
-            context.clone(),
-            self.get_total_shares(),
-            shares.clone(),
-            current_pool.clone(),
-        ).await?;
+            if let Some(error) = withdraw_error.clone() {
+                if withdrawn_pools_count == 0 {
+                    // Event: Strategy withdraw failed
+                    event_record_service::create_event_record(
+                        Event::strategy_withdraw_failed(
+                            strategy_id,
+                            Some(current_pool_id),
+                            Some(shares),
+                            error.clone(),
+                        ),
+                        context.correlation_id,
+                        Some(investor),
+                    );
+
+                    return Err(error);
+                }
+
+                // The pools withdrawn before the failed one are paid out below
+                // and only the shares for their part of the liquidity are burned
+                shares = if total_weight == 0 {
+                    Nat::from(0u64)
+                } else {
+                    shares * Nat::from(withdrawn_weight) / Nat::from(total_weight)
+                };
+            }
+        }
 
-        // Transfer amount of token_0 (base token) to user
-        icrc1_transfer_to_user(
-            investor,
+        // Transfer amount of token_0 (base token) to user, the transfer fee is paid out of the amount
+        let transfer_result = match ledger_fees::deduct_ledger_fees(
             current_pool.token0,
-            amount_0_to_withdraw.clone(),
-        ).await
+            &amount_0_to_withdraw,
+            TRANSFER_FEE_COUNT,
+        ).await {
+            Ok(transfer) => icrc1_transfer_to_user(
+                investor,
+                current_pool.token0,
+                transfer.amount.clone(),
+            ).await.map(|_| transfer),
+            Err(error) => Err(error),
+        };
+
+        let transfer = transfer_result
             .map_err(|error| {
                 // Event: Strategy withdraw failed
                 event_record_service::create_event_record(
@@ -277,58 +626,304 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
                 error
             })?;
 
+        liquidity_service::record_ledger_fee(
+            &context,
+            Some(current_pool_id.clone()),
+            LedgerFeeOperation::Transfer,
+            current_pool.token0,
+            transfer.fees_paid.clone(),
+        );
+
+        if is_exited {
+            self.set_idle_balance(self.get_idle_balance() - amount_0_to_withdraw.clone());
+        }
+
+        let share_price = self.get_share_price();
         let new_user_shares = self.update_strategy_state_after_withdraw(
             investor,
             shares.clone(),
         );
 
+        if let Some(error) = withdraw_error {
+            let mut extra = error.extra.clone().unwrap_or_default();
+            extra.insert("withdrawn_amount".to_string(), transfer.amount.to_string());
+            extra.insert("burned_shares".to_string(), shares.to_string());
+            let error = InternalError { extra: Some(extra), ..error };
+
+            // Event: Strategy withdraw failed
+            event_record_service::create_event_record(
+                Event::strategy_withdraw_failed(
+                    strategy_id,
+                    Some(current_pool_id),
+                    Some(shares),
+                    error.clone(),
+                ),
+                context.correlation_id,
+                Some(investor),
+            );
+
+            return Err(error);
+        }
+
         // Event: Strategy withdraw completed
         event_record_service::create_event_record(
             Event::strategy_withdraw_completed(
                 strategy_id,
                 Some(current_pool_id),
                 Some(shares.clone()),
-                Some(amount_0_to_withdraw.clone()),
+                Some(transfer.amount.clone()),
+                share_price,
             ),
             context.correlation_id,
             Some(investor),
         );
 
         Ok(StrategyWithdrawResponse {
-            amount: amount_0_to_withdraw,
+            amount: transfer.amount,
             current_shares: new_user_shares.clone(),
         })
     }
 
+    /// Previews a deposit into the strategy without moving any tokens
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The amount of tokens to deposit
+    ///
+    /// # Returns
+    ///
+    /// * `StrategyPreviewDepositResponse` - Contains the pool the deposit would go to,
+    ///   the expected shares, the token amounts for the swap and for the pool, and the fees
+    ///
+    async fn preview_deposit(&self, context: Context, amount: Nat) -> Result<StrategyPreviewDepositResponse, InternalError> {
+        if !self.get_state().allows_deposit() || !self.get_lifecycle().allows_deposit() {
+            return Err(InternalError::business_logic(
+                build_error_code(3100, 3, 14), // 3100 03 14
+                "Strategy::preview_deposit".to_string(),
+                "Deposits are not allowed in the current strategy state".to_string(),
+                Some(HashMap::from([
+                    ("state".to_string(), format!("{:?}", self.get_state())),
+                    ("lifecycle".to_string(), format!("{:?}", self.get_lifecycle())),
+                ]))
+            ));
+        }
+
+        // Deposit goes to the best APY pool if current pool is not set
+        let pool = match self.get_current_pool() {
+            Some(pool) => Some(pool),
+            None => self.get_best_apy_pool(&context).await,
+        };
+
+        let pool = pool.ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(3100, 1, 7), // 3100 01 07
+                "Strategy::preview_deposit".to_string(),
+                "No pool found to deposit".to_string(),
+                None,
+            )
+        })?;
+
+        let quote = liquidity_service::quote_add_liquidity_to_pool(
+            amount.clone(),
+            pool.clone(),
+        ).await?;
+
+        let shares = LiquidityCalculator::calculate_shares_for_deposit(
+            amount.clone(),
+            self.get_total_balance(),
+            self.get_total_shares(),
+        );
+
+        Ok(StrategyPreviewDepositResponse {
+            pool,
+            amount,
+            shares,
+            token_0_for_swap: quote.token_0_for_swap,
+            token_0_for_pool: quote.token_0_for_pool,
+            token_1_for_pool: quote.token_1_for_pool,
+            swap_provider: quote.swap_provider,
+            fees: quote.fees,
+        })
+    }
+
+    /// Previews a withdrawal from the strategy without moving any tokens
+    ///
+    /// # Arguments
+    ///
+    /// * `investor` - The Principal ID of the investor who is withdrawing tokens
+    /// * `percentage` - The percentage of the investor's shares to withdraw
+    ///
+    /// # Returns
+    ///
+    /// * `StrategyPreviewWithdrawResponse` - Contains the shares to burn, the token amounts
+    ///   withdrawn from the pool, the swap output and the total amount of base token
+    ///
+    async fn preview_withdraw(&self, investor: Principal, percentage: Nat) -> Result<StrategyPreviewWithdrawResponse, InternalError> {
+        let user_shares = self.get_user_shares().get(&investor).cloned().unwrap_or(Nat::from(0u64));
+        let shares = user_shares.clone() * percentage.clone() / Nat::from(100u64);
+
+        if !self.get_state().allows_withdraw() || !self.get_lifecycle().allows_withdraw() {
+            return Err(InternalError::business_logic(
+                build_error_code(3100, 3, 15), // 3100 03 15
+                "Strategy::preview_withdraw".to_string(),
+                "Withdrawals are not allowed in the current strategy state".to_string(),
+                Some(HashMap::from([
+                    ("state".to_string(), format!("{:?}", self.get_state())),
+                    ("lifecycle".to_string(), format!("{:?}", self.get_lifecycle())),
+                ]))
+            ));
+        }
+
+        if user_shares == Nat::from(0u8) {
+            return Err(InternalError::business_logic(
+                build_error_code(3100, 3, 9), // 3100 03 09
+                "Strategy::preview_withdraw".to_string(),
+                "No shares found for user".to_string(),
+                Some(HashMap::from([
+                    ("percentage".to_string(), percentage.to_string()),
+                    ("user_shares".to_string(), user_shares.to_string()),
+                ]))
+            ));
+        }
+
+        if shares > user_shares {
+            return Err(InternalError::business_logic(
+                build_error_code(3100, 3, 10), // 3100 03 10
+                "Strategy::preview_withdraw".to_string(),
+                "Not sufficient shares for user".to_string(),
+                Some(HashMap::from([
+                    ("percentage".to_string(), percentage.to_string()),
+                    ("user_shares".to_string(), user_shares.to_string()),
+                    ("shares".to_string(), shares.to_string()),
+                ]))
+            ));
+        }
+
+        let pool = self.get_current_pool().ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(3100, 1, 10), // 3100 01 10
+                "Strategy::preview_withdraw".to_string(),
+                "No current pool found in strategy".to_string(),
+                None,
+            )
+        })?;
+
+        let mut token_0_amount = Nat::from(0u64);
+        let mut token_1_amount = Nat::from(0u64);
+        let mut swap_amount_out = Nat::from(0u64);
+        let mut swap_provider = None;
+        let mut fees = LiquidityFees {
+            token_0_fee: Nat::from(0u64),
+            token_1_fee: Nat::from(0u64),
+        };
+
+        // Exited strategy pays out pro-rata share of the idle balance without swaps
+        let active_positions = if self.get_state() == StrategyState::Exited {
+            token_0_amount = self.get_idle_balance() * shares.clone() / self.get_total_shares();
+            Vec::new()
+        } else {
+            self.get_active_positions()
+        };
+
+        // Quote every pool holding the strategy liquidity
+        for (position_pool, _) in active_positions {
+            let (withdraw_quote, swap_quote) = liquidity_service::quote_withdraw_liquidity_from_pool_and_swap(
+                self.get_total_shares(),
+                shares.clone(),
+                position_pool,
+                pool.token0,
+            ).await?;
+
+            token_0_amount += withdraw_quote.token_0_amount;
+            token_1_amount += withdraw_quote.token_1_amount;
+            swap_amount_out += Nat::from(swap_quote.amount_out);
+            if let Some(provider) = swap_quote.main_provider() {
+                swap_provider.get_or_insert(provider);
+            }
+            fees.token_0_fee += withdraw_quote.fees.token_0_fee;
+            fees.token_1_fee += withdraw_quote.fees.token_1_fee;
+        }
+
+        let amount = token_0_amount.clone() + swap_amount_out.clone();
+
+        Ok(StrategyPreviewWithdrawResponse {
+            pool,
+            shares,
+            amount,
+            token_0_amount,
+            token_1_amount,
+            swap_provider: swap_provider.unwrap_or(ExchangeId::KongSwap),
+            swap_amount_out,
+            fees,
+        })
+    }
+
     /// Rebalances the strategy by finding and moving to the pool with the highest APY
     ///
+    /// # Arguments
+    ///
+    /// * `step_percentage` - The percentage of the position moved per step, 100% if not set
+    ///
     /// # Details
     ///
-    /// 1. Gets data for all available pools
-    /// 2. Finds the pool with highest APY
+    /// 1. If a rebalance plan is in progress, executes its next step
+    /// 2. Otherwise gets data for all available pools and finds the pool with highest APY
     /// 3. If current pool is different from highest APY pool:
-    ///    - Withdraws liquidity from current pool
-    ///    - Swaps token_1 to token_0 (base token)
-    ///    - Adds liquidity to new pool
-    ///    - Updates current pool
+    ///    - Creates a rebalance plan moving `step_percentage` of the position per step
+    ///    - Executes the first step of the plan
     ///
     /// # Returns
     ///
     /// * `StrategyRebalanceResponse` - Contains:
-    ///   * `pool` - The pool being used after rebalancing
+    ///   * `previous_pool` - The pool used before rebalancing
+    ///   * `current_pool` - The pool being used after rebalancing
+    ///   * `rebalance_plan` - The plan if the rebalance is still in progress
     ///
-    async fn rebalance(&mut self) -> Result<StrategyRebalanceResponse, InternalError> {
+    async fn rebalance(&mut self, step_percentage: Option<u8>) -> Result<StrategyRebalanceResponse, InternalError> {
         let context = Context::generate(None);
         let strategy_id = self.get_id().to_string();
 
-        // Event: Strategy rebalance started
-        event_record_service::create_event_record(
-            Event::strategy_rebalance_started(strategy_id.clone(), None),
-            context.correlation_id.clone(),
-            None,
-        );
+        if !self.get_state().allows_rebalance() || !self.get_lifecycle().allows_rebalance() {
+            let error = InternalError::business_logic(
+                build_error_code(3100, 3, 16), // 3100 03 16
+                "Strategy::rebalance".to_string(),
+                "Rebalance is not allowed in the current strategy state".to_string(),
+                Some(HashMap::from([
+                    ("state".to_string(), format!("{:?}", self.get_state())),
+                    ("lifecycle".to_string(), format!("{:?}", self.get_lifecycle())),
+                ]))
+            );
+
+            // Event: Strategy rebalance failed
+            event_record_service::create_event_record(
+                Event::strategy_rebalance_failed(strategy_id, None, None, error.clone()),
+                context.correlation_id,
+                None,
+            );
+
+            return Err(error);
+        }
+
+        // Multi-pool strategies keep liquidity in all pools by target weights, there is nothing to move
+        if self.get_allocation_policy().is_some() {
+            let current_pool = self.get_current_pool()
+                .or_else(|| self.get_pools().into_iter().next())
+                .unwrap();
+
+            return Ok(StrategyRebalanceResponse {
+                previous_pool: current_pool.clone(),
+                current_pool,
+                is_rebalanced: false,
+                rebalance_plan: None,
+            });
+        }
 
-        let pools_data = liquidity_service::get_pools_data(self.get_pools()).await;
+        // Continue rebalance which is already in progress
+        if let Some(rebalance_plan) = self.get_rebalance_plan() {
+            return self.execute_rebalance_step(context, rebalance_plan).await;
+        }
+
+        let pools_data = liquidity_service::get_pools_data(&context, self.get_pools()).await;
         let mut max_apy = 0.0;
         let mut max_apy_pool = None;
 
@@ -347,6 +942,7 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
                 previous_pool: current_pool.clone().unwrap(),
                 current_pool: current_pool.clone().unwrap(),
                 is_rebalanced: false,
+                rebalance_plan: None,
             });
         }
 
@@ -364,6 +960,7 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
                         strategy_id,
                         Some(current_pool.get_id()),
                         Some(max_apy_pool.get_id()),
+                        self.get_position_id(),
                     ),
                     context.correlation_id,
                     None,
@@ -373,46 +970,25 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
                     previous_pool: current_pool.clone(),
                     current_pool: current_pool.clone(),
                     is_rebalanced: false,
+                    rebalance_plan: None,
                 });
             }
 
-            // Withdraw liquidity from current pool and swap token_1 to token_0 (base token)
-            let token_0_to_pool_amount = liquidity_service::withdraw_liquidity_from_pool_and_swap(
-                context.clone(),
-                self.get_total_shares(),
-                self.get_total_shares(),
-                current_pool.clone(),
-            ).await?;
+            // Event: Strategy rebalance started
+            event_record_service::create_event_record(
+                Event::strategy_rebalance_started(strategy_id, Some(current_pool.get_id())),
+                context.correlation_id.clone(),
+                None,
+            );
 
-            // Add liquidity to new pool
-            let add_liquidity_response = liquidity_service::add_liquidity_to_pool(
-                context.clone(),
-                token_0_to_pool_amount.clone(),
-                max_apy_pool.clone(),
-            ).await?;
+            let rebalance_plan = RebalancePlan::new(
+                current_pool.clone(),
+                max_apy_pool,
+                step_percentage.unwrap_or(FULL_REBALANCE_PERCENTAGE),
+                current_timestamp(),
+            );
 
-            // Event: Strategy rebalance completed
-            event_record_service::create_event_record(
-                Event::strategy_rebalance_completed(
-                    strategy_id,
-                    Some(current_pool.get_id()),
-                    Some(max_apy_pool.get_id()),
-                ),
-                context.correlation_id,
-                None,
-            );
-
-            // Update current pool
-            self.set_current_pool(Some(max_apy_pool));
-
-            // Update position id
-            self.set_position_id(Some(add_liquidity_response.position_id));
-
-            Ok(StrategyRebalanceResponse {
-                previous_pool: current_pool.clone(),
-                current_pool: self.get_current_pool().unwrap(),
-                is_rebalanced: true,
-            })
+            self.execute_rebalance_step(context, rebalance_plan).await
         } else {
             let error = InternalError::not_found(
                 build_error_code(3100, 1, 6), // 3100 01 06
@@ -432,6 +1008,719 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
         }
     }
 
+    /// Moves the next part of the position from the source pool to the destination pool of the plan
+    ///
+    /// # Details
+    ///
+    /// 1. Withdraws the step part of the liquidity remaining in the source pool
+    /// 2. If both pools hold the same token pair, swaps only the amount needed
+    ///    to match the new pool ratio and adds both tokens to the new pool
+    /// 3. Otherwise swaps token_1 to token_0 (base token) and adds liquidity to new pool
+    /// 4. Updates the plan, or switches the current pool when the plan is completed
+    ///
+    /// The tokens held by the step are saved on the plan after the withdrawal and after the swap.
+    /// If the step fails, it is retried on the next run from the saved amounts.
+    ///
+    async fn execute_rebalance_step(
+        &mut self,
+        context: Context,
+        mut rebalance_plan: RebalancePlan,
+    ) -> Result<StrategyRebalanceResponse, InternalError> {
+        let strategy_id = self.get_id().to_string();
+        let from_pool = rebalance_plan.from_pool.clone();
+        let to_pool = rebalance_plan.to_pool.clone();
+
+        let is_same_token_pair = (from_pool.token0 == to_pool.token0 && from_pool.token1 == to_pool.token1)
+            || (from_pool.token0 == to_pool.token1 && from_pool.token1 == to_pool.token0);
+
+        let step_result = self.execute_rebalance_step_transfer(
+            &context,
+            &mut rebalance_plan,
+            is_same_token_pair,
+        ).await;
+
+        let position_id = step_result.map_err(|error| {
+            // Event: Strategy rebalance failed
+            event_record_service::create_event_record(
+                Event::strategy_rebalance_failed(
+                    strategy_id.clone(),
+                    Some(from_pool.get_id()),
+                    Some(to_pool.get_id()),
+                    error.clone(),
+                ),
+                context.correlation_id.clone(),
+                None,
+            );
+
+            error
+        })?;
+
+        rebalance_plan.complete_step(position_id, current_timestamp());
+
+        // Deposits and withdrawals can save the strategy while the step is awaiting,
+        // so the rebalance changes are applied to the strategy as it is stored now
+        let mut strategy = strategies_repo::get_strategy_by_id(self.get_id())
+            .unwrap_or_else(|| self.clone_self());
+
+        let rebalance_plan = if rebalance_plan.is_completed() {
+            // Switch to the new pool
+            strategy.set_current_pool(Some(to_pool.clone()));
+            strategy.set_position_id(Some(position_id));
+            strategy.set_rebalance_plan(None);
+
+            // Event: Strategy rebalance completed
+            event_record_service::create_event_record(
+                Event::strategy_rebalance_completed(
+                    strategy_id,
+                    Some(from_pool.get_id()),
+                    Some(to_pool.get_id()),
+                    Some(position_id),
+                ),
+                context.correlation_id,
+                None,
+            );
+
+            None
+        } else {
+            strategy.set_rebalance_plan(Some(rebalance_plan.clone()));
+
+            // Event: Strategy rebalance step completed
+            event_record_service::create_event_record(
+                Event::strategy_rebalance_step_completed(
+                    strategy_id,
+                    from_pool.get_id(),
+                    to_pool.get_id(),
+                    rebalance_plan.completed_steps,
+                    rebalance_plan.total_steps,
+                ),
+                context.correlation_id,
+                None,
+            );
+
+            Some(rebalance_plan)
+        };
+
+        strategies_repo::save_strategy(strategy.clone_self());
+
+        self.set_current_pool(strategy.get_current_pool());
+        self.set_position_id(strategy.get_position_id());
+        self.set_rebalance_plan(strategy.get_rebalance_plan());
+
+        // Update strategy current liquidity
+        strategy_stats_service::spawn_update_strategy_liquidity(strategy.clone_self());
+
+        Ok(StrategyRebalanceResponse {
+            previous_pool: from_pool,
+            current_pool: strategy.get_current_pool().unwrap(),
+            is_rebalanced: true,
+            rebalance_plan,
+        })
+    }
+
+    /// Moves the strategy to a new state
+    ///
+    /// # Details
+    ///
+    /// Moving to `Exited` pauses the strategy first and then withdraws the liquidity
+    /// from all pools to the base token. If the exit fails the strategy stays paused,
+    /// so the exit can be retried.
+    ///
+    /// # Returns
+    ///
+    /// * `StrategyState` - The new state of the strategy
+    ///
+    async fn change_state(&mut self, context: Context, state: StrategyState) -> Result<StrategyState, InternalError> {
+        let strategy_id = self.get_id().to_string();
+        let previous_state = self.get_state();
+
+        if !previous_state.can_transition_to(state) {
+            return Err(InternalError::business_logic(
+                build_error_code(3100, 3, 17), // 3100 03 17
+                "Strategy::change_state".to_string(),
+                "Strategy state transition is not allowed".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), strategy_id),
+                    ("previous_state".to_string(), format!("{:?}", previous_state)),
+                    ("state".to_string(), format!("{:?}", state)),
+                ]))
+            ));
+        }
+
+        if state == StrategyState::Exited {
+            // Stop all operations while liquidity is being withdrawn
+            self.set_state(StrategyState::Paused);
+            strategies_repo::save_strategy(self.clone_self());
+
+            self.emergency_exit(context.clone()).await?;
+        }
+
+        self.set_state(state);
+        strategies_repo::save_strategy(self.clone_self());
+
+        // Event: Strategy state changed
+        event_record_service::create_event_record(
+            Event::strategy_state_changed(strategy_id, previous_state, state),
+            context.correlation_id,
+            context.user,
+        );
+
+        Ok(state)
+    }
+
+    /// Moves the tokens of the current step of the plan to the destination pool and returns the new position id.
+    /// The step starts from the amounts saved on the plan, if a previous attempt has withdrawn them.
+    async fn execute_rebalance_step_transfer(
+        &mut self,
+        context: &Context,
+        rebalance_plan: &mut RebalancePlan,
+        is_same_token_pair: bool,
+    ) -> Result<u64, InternalError> {
+        let strategy_id = self.get_id().to_string();
+        let from_pool = rebalance_plan.from_pool.clone();
+        let to_pool = rebalance_plan.to_pool.clone();
+        let swap_limits = self.get_swap_limits();
+
+        let step_amounts = match rebalance_plan.step_amounts.clone() {
+            Some(step_amounts) => step_amounts,
+            None => {
+                let (total_shares, shares) = rebalance_plan.next_step_shares();
+
+                if !is_same_token_pair {
+                    // Check the price of the swap before withdrawing, so a rejected swap leaves the liquidity in the pool
+                    liquidity_service::check_withdraw_liquidity_and_swap_price(
+                        context,
+                        total_shares.clone(),
+                        shares.clone(),
+                        &from_pool,
+                        from_pool.token0,
+                        &swap_limits,
+                    ).await?;
+                }
+
+                let withdraw_response = liquidity_service::withdraw_liquidity_from_pool(
+                    context.clone(),
+                    total_shares,
+                    shares,
+                    from_pool.clone(),
+                ).await?;
+
+                rebalance_plan.hold_step_amounts(
+                    withdraw_response.token_0_amount,
+                    withdraw_response.token_1_amount,
+                    current_timestamp(),
+                );
+                self.save_rebalance_plan(rebalance_plan);
+
+                rebalance_plan.step_amounts.clone().unwrap()
+            }
+        };
+
+        if is_same_token_pair {
+            // Move both tokens to the new pool and swap only the ratio difference
+            let mut swapped_plan = rebalance_plan.clone();
+
+            let migrate_response = liquidity_service::migrate_liquidity(
+                context.clone(),
+                step_amounts.token_0_amount,
+                step_amounts.token_1_amount,
+                from_pool.clone(),
+                to_pool.clone(),
+                &swap_limits,
+                |token_0_amount, token_1_amount| {
+                    swapped_plan.hold_step_amounts(token_0_amount, token_1_amount, current_timestamp());
+                    self.save_rebalance_plan(&swapped_plan);
+                },
+            ).await?;
+
+            // Event: Strategy rebalance migrated
+            event_record_service::create_event_record(
+                Event::strategy_rebalance_migrated(
+                    strategy_id,
+                    from_pool.get_id(),
+                    to_pool.get_id(),
+                    &migrate_response,
+                ),
+                context.correlation_id.clone(),
+                None,
+            );
+
+            Ok(migrate_response.position_id)
+        } else {
+            // Swap token_1 to token_0 (base token)
+            let swapped_amount = liquidity_service::swap_withdrawn_token(
+                context.clone(),
+                from_pool.clone(),
+                step_amounts.token_1_amount,
+                from_pool.token0,
+                &swap_limits,
+            ).await?;
+
+            let token_0_to_pool_amount = step_amounts.token_0_amount + swapped_amount;
+
+            rebalance_plan.hold_step_amounts(token_0_to_pool_amount.clone(), Nat::from(0u64), current_timestamp());
+            self.save_rebalance_plan(rebalance_plan);
+
+            // Add liquidity to new pool
+            liquidity_service::add_liquidity_to_pool(
+                context.clone(),
+                token_0_to_pool_amount,
+                to_pool,
+                &swap_limits,
+            ).await.map(|response| response.position_id)
+        }
+    }
+
+    /// Saves the plan to the strategy as it is stored now, deposits and withdrawals
+    /// can save the strategy while a rebalance step is awaiting
+    fn save_rebalance_plan(&mut self, rebalance_plan: &RebalancePlan) {
+        let mut strategy = strategies_repo::get_strategy_by_id(self.get_id())
+            .unwrap_or_else(|| self.clone_self());
+
+        strategy.set_rebalance_plan(Some(rebalance_plan.clone()));
+        strategies_repo::save_strategy(strategy);
+
+        self.set_rebalance_plan(Some(rebalance_plan.clone()));
+    }
+
+    /// Withdraws liquidity from all pools, swaps it to the base token
+    /// and keeps it as idle balance of the strategy
+    ///
+    /// # Returns
+    ///
+    /// * `Nat` - The idle balance of the strategy after the exit
+    ///
+    async fn emergency_exit(&mut self, context: Context) -> Result<Nat, InternalError> {
+        let current_pool = self.get_current_pool();
+
+        if let Some(current_pool) = current_pool {
+            let total_shares = self.get_total_shares();
+
+            if total_shares > 0u64 {
+                for (pool, _) in self.get_active_positions() {
+                    let amount = liquidity_service::withdraw_liquidity_from_pool_and_swap(
+                        context.clone(),
+                        total_shares.clone(),
+                        total_shares.clone(),
+                        pool.clone(),
+                        current_pool.token0,
+                        &self.get_swap_limits(),
+                    ).await?;
+
+                    // Save progress after each pool, so a retry does not withdraw it twice
+                    self.set_idle_balance(self.get_idle_balance() + amount);
+                    self.clear_position(&pool);
+                    strategies_repo::save_strategy(self.clone_self());
+                }
+            }
+        }
+
+        self.set_position_id(None);
+        self.set_rebalance_plan(None);
+        self.set_pool_allocations(Vec::new());
+        self.set_current_liquidity(Some(self.get_idle_balance()));
+        self.set_current_liquidity_updated_at(Some(current_timestamp()));
+
+        strategies_repo::save_strategy(self.clone_self());
+
+        Ok(self.get_idle_balance())
+    }
+
+    /// Changes the lifecycle stage of the strategy
+    ///
+    /// Lifecycle only moves forward. Strategy can be closed only when all users
+    /// have withdrawn or have been migrated to a successor strategy.
+    ///
+    /// # Returns
+    ///
+    /// * `StrategyLifecycle` - The new lifecycle stage of the strategy
+    ///
+    async fn change_lifecycle(&mut self, context: Context, lifecycle: StrategyLifecycle) -> Result<StrategyLifecycle, InternalError> {
+        let strategy_id = self.get_id().to_string();
+        let previous_lifecycle = self.get_lifecycle();
+
+        if !previous_lifecycle.can_transition_to(lifecycle) {
+            return Err(InternalError::business_logic(
+                build_error_code(3100, 3, 18), // 3100 03 18
+                "Strategy::change_lifecycle".to_string(),
+                "Strategy lifecycle transition is not allowed".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), strategy_id),
+                    ("previous_lifecycle".to_string(), format!("{:?}", previous_lifecycle)),
+                    ("lifecycle".to_string(), format!("{:?}", lifecycle)),
+                ]))
+            ));
+        }
+
+        if lifecycle == StrategyLifecycle::Closed && self.get_total_shares() > Nat::from(0u64) {
+            return Err(InternalError::business_logic(
+                build_error_code(3100, 3, 19), // 3100 03 19
+                "Strategy::change_lifecycle".to_string(),
+                "Strategy with users can not be closed".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), strategy_id),
+                    ("users_count".to_string(), self.get_users_count().to_string()),
+                ]))
+            ));
+        }
+
+        self.set_lifecycle(lifecycle);
+        strategies_repo::save_strategy(self.clone_self());
+
+        // Event: Strategy lifecycle changed
+        event_record_service::create_event_record(
+            Event::strategy_lifecycle_changed(strategy_id, previous_lifecycle, lifecycle),
+            context.correlation_id,
+            context.user,
+        );
+
+        Ok(lifecycle)
+    }
+
+    /// Moves all users of the closing strategy into the successor strategy
+    ///
+    /// # Details
+    ///
+    /// 1. Withdraws the liquidity from all pools to the base token (see `emergency_exit`)
+    /// 2. Adds the withdrawn amount to the successor strategy as a single deposit,
+    ///    the strategy is paused meanwhile, so its users can not withdraw the amount
+    /// 3. Credits every user with successor shares for their pro-rata part of the amount,
+    ///    keeping their initial deposit as cost basis
+    /// 4. Clears the users of this strategy and closes it
+    ///
+    /// If adding liquidity to the successor fails, the strategy stays `Exited`
+    /// with the idle balance, so users can withdraw or the migration can be retried.
+    ///
+    async fn migrate_to(
+        &mut self,
+        context: Context,
+        successor: &mut Box<dyn IStrategy>,
+    ) -> Result<MigrateStrategyResponse, InternalError> {
+        let strategy_id = self.get_id().to_string();
+        let successor_strategy_id = successor.get_id().to_string();
+
+        if self.get_lifecycle() != StrategyLifecycle::Closing {
+            return Err(InternalError::business_logic(
+                build_error_code(3100, 3, 20), // 3100 03 20
+                "Strategy::migrate_to".to_string(),
+                "Only closing strategy can be migrated".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), strategy_id),
+                    ("lifecycle".to_string(), format!("{:?}", self.get_lifecycle())),
+                ]))
+            ));
+        }
+
+        if successor.get_id() == self.get_id()
+            || !successor.get_lifecycle().allows_deposit()
+            || !successor.get_state().allows_deposit()
+            || successor.get_allocation_policy().is_some()
+            || successor.get_base_token() != self.get_base_token()
+        {
+            return Err(InternalError::business_logic(
+                build_error_code(3100, 3, 21), // 3100 03 21
+                "Strategy::migrate_to".to_string(),
+                "Successor strategy can not accept the migration".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), strategy_id),
+                    ("successor_strategy_id".to_string(), successor_strategy_id),
+                    ("successor_state".to_string(), format!("{:?}", successor.get_state())),
+                    ("successor_lifecycle".to_string(), format!("{:?}", successor.get_lifecycle())),
+                ]))
+            ));
+        }
+
+        let mut position_id = None;
+
+        if self.get_total_shares() > Nat::from(0u64) {
+            if self.get_state() != StrategyState::Exited {
+                // Stop all operations while liquidity is being withdrawn
+                self.set_state(StrategyState::Paused);
+                strategies_repo::save_strategy(self.clone_self());
+
+                self.emergency_exit(context.clone()).await?;
+            }
+
+            // Stop withdrawals of the idle balance while it is credited to the users in the successor
+            self.set_state(StrategyState::Paused);
+            strategies_repo::save_strategy(self.clone_self());
+
+            let accept_result = successor.accept_migration(
+                context.clone(),
+                self.get_idle_balance(),
+                self.get_total_shares(),
+                self.get_user_shares(),
+                self.get_initial_deposit(),
+            ).await;
+
+            if let Err(error) = accept_result {
+                // Users can withdraw the idle balance again, the migration can be retried
+                let mut strategy = strategies_repo::get_strategy_by_id(self.get_id())
+                    .unwrap_or_else(|| self.clone_self());
+
+                strategy.set_state(StrategyState::Exited);
+                strategies_repo::save_strategy(strategy);
+                self.set_state(StrategyState::Exited);
+
+                return Err(error);
+            }
+
+            position_id = accept_result.ok();
+        }
+
+        // The strategy can be saved while the migration is awaiting,
+        // so the users are cleared on the strategy as it is stored now
+        let mut strategy = strategies_repo::get_strategy_by_id(self.get_id())
+            .unwrap_or_else(|| self.clone_self());
+
+        let users_count = strategy.get_users_count();
+        let amount = strategy.get_idle_balance();
+
+        strategy.set_user_shares(HashMap::new());
+        strategy.set_initial_deposit(HashMap::new());
+        strategy.set_total_shares(Nat::from(0u64));
+        strategy.set_total_balance(Nat::from(0u64));
+        strategy.set_idle_balance(Nat::from(0u64));
+        strategy.set_current_liquidity(None);
+        strategy.set_lifecycle(StrategyLifecycle::Closed);
+
+        if position_id.is_some() {
+            strategy.set_state(StrategyState::Exited);
+        }
+
+        strategies_repo::save_strategy(strategy.clone_self());
+
+        self.set_user_shares(strategy.get_user_shares());
+        self.set_initial_deposit(strategy.get_initial_deposit());
+        self.set_total_shares(strategy.get_total_shares());
+        self.set_total_balance(strategy.get_total_balance());
+        self.set_idle_balance(strategy.get_idle_balance());
+        self.set_current_liquidity(strategy.get_current_liquidity());
+        self.set_lifecycle(strategy.get_lifecycle());
+        self.set_state(strategy.get_state());
+
+        // Event: Strategy users migrated
+        event_record_service::create_event_record(
+            Event::strategy_users_migrated(strategy_id.clone(), successor_strategy_id, amount.clone(), users_count),
+            context.correlation_id.clone(),
+            context.user,
+        );
+
+        // Event: Strategy lifecycle changed
+        event_record_service::create_event_record(
+            Event::strategy_lifecycle_changed(strategy_id, StrategyLifecycle::Closing, StrategyLifecycle::Closed),
+            context.correlation_id,
+            context.user,
+        );
+
+        Ok(MigrateStrategyResponse {
+            successor_strategy_id: successor.get_id(),
+            amount,
+            users_count,
+            position_id,
+        })
+    }
+
+    /// Adds the amount migrated from a closing strategy and credits its users
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The amount of the base token migrated
+    /// * `total_shares` - Total shares of the closing strategy
+    /// * `user_shares` - Shares of each user in the closing strategy
+    /// * `initial_deposit` - Initial deposit of each user in the closing strategy
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The position id in the pool
+    ///
+    async fn accept_migration(
+        &mut self,
+        context: Context,
+        amount: Nat,
+        total_shares: Nat,
+        user_shares: HashMap<Principal, Nat>,
+        initial_deposit: HashMap<Principal, Nat>,
+    ) -> Result<u64, InternalError> {
+        let current_pool = match self.get_current_pool() {
+            Some(current_pool) => Some(current_pool),
+            None => self.get_best_apy_pool(&context).await,
+        };
+
+        let current_pool = current_pool.ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(3100, 1, 3), // 3100 01 03
+                "Strategy::accept_migration".to_string(),
+                "No pool found to deposit".to_string(),
+                Some(HashMap::from([
+                    ("strategy_id".to_string(), self.get_id().to_string()),
+                ]))
+            )
+        })?;
+
+        let add_liquidity_response = liquidity_service::add_liquidity_to_pool(
+            context.clone(),
+            amount.clone(),
+            current_pool.clone(),
+            &self.get_swap_limits(),
+        ).await?;
+
+        // Deposits can save the strategy while liquidity is being added,
+        // so the migrated users are credited on the strategy as it is stored now
+        let mut strategy = strategies_repo::get_strategy_by_id(self.get_id())
+            .unwrap_or_else(|| self.clone_self());
+
+        let mut migrated_users = Vec::new();
+
+        for (user, shares) in user_shares {
+            let user_amount = amount.clone() * shares / total_shares.clone();
+            let user_initial_deposit = initial_deposit.get(&user).cloned().unwrap_or(Nat::from(0u64));
+
+            let new_user_shares = LiquidityCalculator::calculate_shares_for_deposit(
+                user_amount,
+                strategy.get_total_balance(),
+                strategy.get_total_shares(),
+            );
+
+            // Keep the cost basis of the user from the closing strategy
+            strategy.increase_total_shares(new_user_shares.clone());
+            strategy.increase_user_shares(user, new_user_shares.clone());
+            strategy.increase_initial_deposit(user, user_initial_deposit.clone());
+            strategy.increase_total_balance(user_initial_deposit.clone());
+
+            migrated_users.push(MigratedUserShares {
+                user,
+                shares: new_user_shares,
+                initial_deposit: user_initial_deposit,
+            });
+        }
+
+        strategy.set_current_pool(Some(current_pool.clone()));
+        strategy.set_position_id(Some(add_liquidity_response.position_id));
+
+        strategies_repo::save_strategy(strategy.clone_self());
+
+        self.set_total_shares(strategy.get_total_shares());
+        self.set_user_shares(strategy.get_user_shares());
+        self.set_initial_deposit(strategy.get_initial_deposit());
+        self.set_total_balance(strategy.get_total_balance());
+        self.set_current_pool(strategy.get_current_pool());
+        self.set_position_id(strategy.get_position_id());
+
+        // Event: Strategy migration accepted
+        event_record_service::create_event_record(
+            Event::strategy_migration_accepted(
+                self.get_id().to_string(),
+                current_pool.get_id(),
+                add_liquidity_response.position_id,
+                amount,
+                migrated_users,
+            ),
+            context.correlation_id,
+            context.user,
+        );
+
+        // Update strategy current liquidity
+        strategy_stats_service::spawn_update_strategy_liquidity(strategy);
+
+        Ok(add_liquidity_response.position_id)
+    }
+
+    /// Returns the balance per share new shares are minted and burned at
+    fn get_share_price(&self) -> Nat {
+        LiquidityCalculator::calculate_share_price(self.get_total_balance(), self.get_total_shares())
+    }
+
+    /// Returns the token the strategy accepts deposits in
+    fn get_base_token(&self) -> Option<CanisterId> {
+        self.get_current_pool()
+            .or_else(|| self.get_pools().into_iter().next())
+            .map(|pool| pool.token0)
+    }
+
+    /// Forgets the position in the pool after its liquidity was fully withdrawn
+    fn clear_position(&mut self, pool: &Pool) {
+        let mut pool_allocations = self.get_pool_allocations();
+        for pool_allocation in pool_allocations.iter_mut().filter(|pool_allocation| pool_allocation.pool.id == pool.id) {
+            pool_allocation.position_id = None;
+        }
+        self.set_pool_allocations(pool_allocations);
+
+        if let Some(mut rebalance_plan) = self.get_rebalance_plan() {
+            if rebalance_plan.to_pool.id == pool.id {
+                rebalance_plan.to_position_id = None;
+                self.set_rebalance_plan(Some(rebalance_plan));
+            }
+        }
+
+        if self.get_current_pool().is_some_and(|current_pool| current_pool.id == pool.id) {
+            self.set_position_id(None);
+        }
+    }
+
+    /// Returns the pools holding the strategy liquidity with their position ids.
+    /// Multi-pool strategies hold liquidity in every allocated pool. During a gradual
+    /// rebalance the liquidity is split between the current pool and the destination pool.
+    fn get_active_positions(&self) -> Vec<(Pool, Option<u64>)> {
+        let pool_allocations = self.get_pool_allocations();
+
+        // Multi-pool strategies hold a position in every allocated pool
+        if !pool_allocations.is_empty() {
+            return pool_allocations
+                .into_iter()
+                .filter(|pool_allocation| pool_allocation.position_id.is_some())
+                .map(|pool_allocation| (pool_allocation.pool, pool_allocation.position_id))
+                .collect();
+        }
+
+        let mut positions = Vec::new();
+
+        if let (Some(current_pool), Some(position_id)) = (self.get_current_pool(), self.get_position_id()) {
+            positions.push((current_pool, Some(position_id)));
+        }
+
+        if let Some(rebalance_plan) = self.get_rebalance_plan() {
+            if rebalance_plan.to_position_id.is_some() {
+                positions.push((rebalance_plan.to_pool, rebalance_plan.to_position_id));
+            }
+        }
+
+        positions
+    }
+
+    /// Returns the weights of the positions returned by `get_active_positions`, in the same order.
+    /// Multi-pool positions are weighted by their last known liquidity and the positions of a gradual
+    /// rebalance by the part of the liquidity moved. Positions are weighted equally if their liquidity is unknown.
+    fn get_active_position_weights(&self) -> Vec<u32> {
+        let pool_allocations: Vec<PoolAllocation> = self.get_pool_allocations()
+            .into_iter()
+            .filter(|pool_allocation| pool_allocation.position_id.is_some())
+            .collect();
+
+        let weights = if !pool_allocations.is_empty() {
+            allocation::calculate_current_weights(&pool_allocations)
+        } else {
+            let remaining_percentage = self.get_rebalance_plan()
+                .map_or(FULL_REBALANCE_PERCENTAGE as u32, |rebalance_plan| rebalance_plan.remaining_percentage());
+            let mut weights = Vec::new();
+
+            if self.get_current_pool().is_some() && self.get_position_id().is_some() {
+                weights.push(remaining_percentage);
+            }
+
+            if self.get_rebalance_plan().is_some_and(|rebalance_plan| rebalance_plan.to_position_id.is_some()) {
+                weights.push(FULL_REBALANCE_PERCENTAGE as u32 - remaining_percentage);
+            }
+
+            weights
+        };
+
+        if weights.iter().all(|weight| *weight == 0) {
+            return vec![1; weights.len()];
+        }
+
+        weights
+    }
+
     fn update_user_shares(&mut self, user: Principal, shares: Nat) {
         let mut user_shares_map = self.get_user_shares();
         if shares == Nat::from(0u64) {
@@ -509,13 +1798,17 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
 
         self.set_current_liquidity(None);
         self.set_current_liquidity_updated_at(None);
+        self.set_rebalance_plan(None);
+        self.set_pool_allocations(Vec::new());
+        self.set_state(StrategyState::Active);
+        self.set_idle_balance(Nat::from(0u64));
 
         strategies_repo::save_strategy(self.clone_self());
     }
 
-    async fn get_best_apy_pool(&self) -> Option<Pool> {
+    async fn get_best_apy_pool(&self, context: &Context) -> Option<Pool> {
         let strategy_pools = self.get_pools();
-        let pools_data = liquidity_service::get_pools_data(strategy_pools).await; // TODO: handle error
+        let pools_data = liquidity_service::get_pools_data(context, strategy_pools).await; // TODO: handle error
 
         pools_data
             .iter()
@@ -598,6 +1891,8 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
         if self.get_total_shares() == Nat::from(0u64) {
             self.set_current_liquidity(None);
             self.set_position_id(None);
+            self.set_rebalance_plan(None);
+            self.set_pool_allocations(Vec::new());
         }
 
         strategies_repo::save_strategy(self.clone_self());
@@ -637,9 +1932,32 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             users_count: self.get_user_shares().len() as u32,
             current_liquidity: self.get_current_liquidity(),
             current_liquidity_updated_at: self.get_current_liquidity_updated_at(),
+            rebalance_plan: self.get_rebalance_plan(),
+            pool_allocations: self.get_pool_allocations_response(),
+            state: self.get_state(),
+            idle_balance: self.get_idle_balance(),
+            lifecycle: self.get_lifecycle(),
         }
     }
 
+    /// Returns the per-pool allocation with current weights calculated from the last known liquidity
+    fn get_pool_allocations_response(&self) -> Vec<PoolAllocationResponse> {
+        let pool_allocations = self.get_pool_allocations();
+        let current_weights = allocation::calculate_current_weights(&pool_allocations);
+
+        pool_allocations
+            .into_iter()
+            .zip(current_weights)
+            .map(|(pool_allocation, current_weight)| PoolAllocationResponse {
+                pool: pool_allocation.pool,
+                position_id: pool_allocation.position_id,
+                target_weight: pool_allocation.target_weight,
+                current_weight,
+                current_liquidity: pool_allocation.current_liquidity,
+            })
+            .collect()
+    }
+
     fn clone_self(&self) -> Box<dyn IStrategy>;
 }
 
@@ -649,35 +1967,6 @@ impl Clone for Box<dyn IStrategy> {
     }
 }
 
-pub struct StrategyIterator<'a> {
-    inner: RefMut<'a, Vec<Box<dyn IStrategy>>>,
-    index: usize,
-}
-
-impl<'a> StrategyIterator<'a> {
-    pub fn new(trs: RefMut<'a, Vec<Box<dyn IStrategy>>>) -> Self {
-        StrategyIterator {
-            inner: trs,
-            index: 0,
-        }
-    }
-}
-
-impl<'a> Iterator for StrategyIterator<'a> {
-    type Item = Box<dyn IStrategy>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.inner.len() {
-            // Clone the item at the current index
-            let cloned_item = self.inner[self.index].clone();
-            self.index += 1;
-            Some(cloned_item)
-        } else {
-            None
-        }
-    }
-}
-
 impl Eq for dyn IStrategy {}
 
 impl PartialEq for dyn IStrategy {
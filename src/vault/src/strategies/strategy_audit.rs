@@ -0,0 +1,657 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+use ic_cdk_timers::TimerId;
+use candid::{CandidType, Deserialize, Nat, Principal};
+use serde::Serialize;
+
+use errors::internal_error::error::InternalError;
+use errors::internal_error::error::build_error_code;
+use event_records::archive::ArchivedEventRecord;
+use event_records::archive_client;
+use event_records::event_query::{EventRecordsQuery, SortOrder, MAX_EVENT_RECORDS_PAGE_SIZE};
+use event_records::generic_event_record::GenericEventRecord;
+use liquidity::liquidity_calculator::LiquidityCalculator;
+use types::pool::PoolTrait;
+
+use crate::event_records::event_record::Event;
+use crate::repository::event_archives_repo;
+use crate::repository::event_records_repo::EVENT_RECORDS;
+use crate::repository::strategies_repo;
+use crate::strategies::strategy::IStrategy;
+use crate::strategies::strategy_state::StrategyState;
+
+/// Strategy state the event log records
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ReplayedStrategyState {
+    pub total_shares: Nat,
+    pub total_balance: Nat,
+    pub user_shares: HashMap<Principal, Nat>,
+    pub initial_deposit: HashMap<Principal, Nat>,
+    pub current_pool_id: Option<String>,
+    pub position_id: Option<u64>,
+}
+
+impl ReplayedStrategyState {
+    /// Live state of a strategy, in the form replayed from the event log
+    pub fn from_strategy(strategy: &dyn IStrategy) -> Self {
+        Self {
+            total_shares: strategy.get_total_shares(),
+            total_balance: strategy.get_total_balance(),
+            user_shares: strategy.get_user_shares(),
+            initial_deposit: strategy.get_initial_deposit(),
+            current_pool_id: strategy.get_current_pool().map(|pool| pool.get_id()),
+            position_id: strategy.get_position_id(),
+        }
+    }
+
+    /// Mirrors `update_strategy_state_after_deposit`.
+    /// Records made before minted shares were recorded get them calculated as the strategy did.
+    fn deposit(&mut self, user: Principal, amount: Nat, shares: Option<Nat>, pool_id: Option<String>, position_id: Option<u64>) {
+        let shares = shares.unwrap_or_else(|| LiquidityCalculator::calculate_shares_for_deposit(
+            amount.clone(),
+            self.total_balance.clone(),
+            self.total_shares.clone(),
+        ));
+
+        self.mint(user, shares, amount);
+
+        if pool_id.is_some() {
+            self.current_pool_id = pool_id;
+        }
+        if position_id.is_some() {
+            self.position_id = position_id;
+        }
+    }
+
+    fn mint(&mut self, user: Principal, shares: Nat, initial_deposit: Nat) {
+        self.total_shares += shares.clone();
+        self.total_balance += initial_deposit.clone();
+
+        let user_shares = self.user_shares.get(&user).cloned().unwrap_or_default() + shares;
+        set_or_remove(&mut self.user_shares, user, user_shares);
+
+        let user_initial_deposit = self.initial_deposit.get(&user).cloned().unwrap_or_default() + initial_deposit;
+        set_or_remove(&mut self.initial_deposit, user, user_initial_deposit);
+    }
+
+    /// Mirrors `update_strategy_state_after_withdraw`
+    fn withdraw(&mut self, user: Principal, shares: Nat) {
+        self.total_shares = saturating_sub(&self.total_shares, &shares);
+
+        let previous_user_shares = self.user_shares.get(&user).cloned().unwrap_or_default();
+        let user_shares = saturating_sub(&previous_user_shares, &shares);
+        set_or_remove(&mut self.user_shares, user, user_shares.clone());
+
+        let user_initial_deposit = self.initial_deposit.get(&user).cloned().unwrap_or_default();
+        let new_user_initial_deposit = if previous_user_shares == 0u64 {
+            Nat::from(0u64)
+        } else {
+            user_initial_deposit.clone() * user_shares / previous_user_shares
+        };
+        set_or_remove(&mut self.initial_deposit, user, new_user_initial_deposit.clone());
+
+        self.total_balance = saturating_sub(&self.total_balance, &user_initial_deposit) + new_user_initial_deposit;
+
+        if self.total_shares == 0u64 {
+            self.position_id = None;
+        }
+    }
+
+    /// Users and their shares moved to the successor strategy
+    fn clear_users(&mut self) {
+        self.total_shares = Nat::from(0u64);
+        self.total_balance = Nat::from(0u64);
+        self.user_shares.clear();
+        self.initial_deposit.clear();
+        self.position_id = None;
+    }
+}
+
+/// Rebuilds strategy state by applying event records in id order
+#[derive(Default)]
+pub struct StrategyReplay {
+    pub strategies: HashMap<String, ReplayedStrategyState>,
+    pub records_count: u64,
+}
+
+impl StrategyReplay {
+    pub fn apply(&mut self, record: &GenericEventRecord<Event>) {
+        self.records_count += 1;
+
+        let user = record.user;
+
+        match &record.event {
+            Event::StrategyDepositCompleted(event) => {
+                if let (Some(user), Some(amount)) = (user, event.amount0.clone()) {
+                    self.strategy(&event.strategy_id)
+                        .deposit(user, amount, event.shares.clone(), event.pool_id.clone(), event.position_id);
+                }
+            }
+            Event::StrategyDepositFailed(event) => {
+                // Only the part credited before the failure changes the state
+                if let (Some(user), Some(amount)) = (user, event.deposited_amount.clone()) {
+                    self.strategy(&event.strategy_id)
+                        .deposit(user, amount, event.shares.clone(), event.pool_id.clone(), event.position_id);
+                }
+            }
+            Event::StrategyWithdrawCompleted(event) => {
+                if let (Some(user), Some(shares)) = (user, event.shares.clone()) {
+                    self.strategy(&event.strategy_id).withdraw(user, shares);
+                }
+            }
+            Event::StrategyRebalanceCompleted(event) => {
+                let state = self.strategy(&event.strategy_id);
+                if event.new_pool_id.is_some() {
+                    state.current_pool_id = event.new_pool_id.clone();
+                }
+                if event.position_id.is_some() {
+                    state.position_id = event.position_id;
+                }
+            }
+            // Emergency exit withdraws the liquidity of all positions
+            Event::StrategyStateChanged(event) if event.state == StrategyState::Exited => {
+                self.strategy(&event.strategy_id).position_id = None;
+            }
+            Event::StrategyUsersMigrated(event) => {
+                self.strategy(&event.strategy_id).clear_users();
+            }
+            Event::StrategyMigrationAccepted(event) => {
+                let state = self.strategy(&event.strategy_id);
+                for migrated_user in &event.users {
+                    state.mint(migrated_user.user, migrated_user.shares.clone(), migrated_user.initial_deposit.clone());
+                }
+                state.current_pool_id = Some(event.pool_id.clone());
+                state.position_id = Some(event.position_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn strategy(&mut self, strategy_id: &str) -> &mut ReplayedStrategyState {
+        self.strategies.entry(strategy_id.to_string()).or_default()
+    }
+}
+
+/// Field whose replayed value differs from the live one
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct StrategyStateMismatch {
+    pub strategy_id: String,
+    pub field: String,
+    pub replayed: String,
+    pub live: String,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyAuditReport {
+    pub records_replayed: u64,
+    pub strategies_audited: u32,
+    pub mismatches: Vec<StrategyStateMismatch>,
+}
+
+/// Compares the replayed state of a strategy with its live state, field by field and user by user
+pub fn diff_strategy_state(
+    strategy_id: &str,
+    replayed: &ReplayedStrategyState,
+    live: &ReplayedStrategyState,
+) -> Vec<StrategyStateMismatch> {
+    let mut mismatches = Vec::new();
+    let mut compare = |field: String, replayed: String, live: String| {
+        if replayed != live {
+            mismatches.push(StrategyStateMismatch { strategy_id: strategy_id.to_string(), field, replayed, live });
+        }
+    };
+
+    compare("total_shares".to_string(), replayed.total_shares.to_string(), live.total_shares.to_string());
+    compare("total_balance".to_string(), replayed.total_balance.to_string(), live.total_balance.to_string());
+    compare("current_pool_id".to_string(), format!("{:?}", replayed.current_pool_id), format!("{:?}", live.current_pool_id));
+    compare("position_id".to_string(), format!("{:?}", replayed.position_id), format!("{:?}", live.position_id));
+
+    for &(field, replayed_map, live_map) in [
+        ("user_shares", &replayed.user_shares, &live.user_shares),
+        ("initial_deposit", &replayed.initial_deposit, &live.initial_deposit),
+    ].iter() {
+        let mut users: Vec<&Principal> = replayed_map.keys().chain(live_map.keys()).collect();
+        users.sort();
+        users.dedup();
+
+        for user in users {
+            compare(
+                format!("{}.{}", field, user.to_text()),
+                format!("{:?}", replayed_map.get(user).map(|value| value.to_string())),
+                format!("{:?}", live_map.get(user).map(|value| value.to_string())),
+            );
+        }
+    }
+
+    mismatches
+}
+
+/// Progress of the strategy audit, kept in heap memory: an upgrade drops an audit in progress
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub enum StrategyAuditStatus {
+    NotStarted,
+    InProgress { records_replayed: u64 },
+    Completed(StrategyAuditReport),
+    Failed(InternalError),
+}
+
+/// Audit replaying a page of records per step, so that no single message replays the whole log.
+/// Records are replayed by id, so the records archived while the audit runs are read from their archive.
+struct StrategyAudit {
+    replay: StrategyReplay,
+    next_id: u64,
+}
+
+/// Delay after which an audit step, which trapped after reading its page, is run again
+const AUDIT_STEP_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+thread_local! {
+    static STRATEGY_AUDIT: RefCell<Option<StrategyAudit>> = const { RefCell::new(None) };
+    static STRATEGY_AUDIT_STATUS: RefCell<StrategyAuditStatus> = const { RefCell::new(StrategyAuditStatus::NotStarted) };
+}
+
+impl StrategyAudit {
+    fn new() -> Self {
+        Self {
+            replay: StrategyReplay::default(),
+            next_id: 0,
+        }
+    }
+
+    /// Applies the page of records read from `next_id`, returns whether the whole log is replayed
+    fn apply_page(&mut self, records: &[GenericEventRecord<Event>]) -> bool {
+        let Some(last) = records.last() else { return true };
+
+        for record in records {
+            self.replay.apply(record);
+        }
+
+        self.next_id = last.id + 1;
+
+        false
+    }
+
+    /// Compares the replayed state with the live state of every strategy
+    fn report(&self) -> StrategyAuditReport {
+        let strategies = strategies_repo::get_all_strategies();
+        let mismatches = strategies.iter()
+            .flat_map(|strategy| {
+                let strategy_id = strategy.get_id().to_string();
+                let replayed = self.replay.strategies.get(&strategy_id).cloned().unwrap_or_default();
+
+                diff_strategy_state(&strategy_id, &replayed, &ReplayedStrategyState::from_strategy(strategy.as_ref()))
+            })
+            .collect();
+
+        StrategyAuditReport {
+            records_replayed: self.replay.records_count,
+            strategies_audited: strategies.len() as u32,
+            mismatches,
+        }
+    }
+}
+
+/// Starts replaying the event records of the archives and of the canister, oldest first.
+/// A page is replayed per timer step, the report is compared with the live state once all are replayed.
+pub fn start_strategy_audit() -> Result<(), InternalError> {
+    if matches!(get_strategy_audit_status(), StrategyAuditStatus::InProgress { .. }) {
+        return Err(InternalError::business_logic(
+            build_error_code(3400, 3, 2), // 3400 03 02
+            "strategy_audit::start_strategy_audit".to_string(),
+            "Strategy audit is already in progress".to_string(),
+            None,
+        ));
+    }
+
+    STRATEGY_AUDIT.with(|audit| *audit.borrow_mut() = Some(StrategyAudit::new()));
+    set_status(StrategyAuditStatus::InProgress { records_replayed: 0 });
+    schedule_audit_step(Duration::ZERO);
+
+    Ok(())
+}
+
+pub fn get_strategy_audit_status() -> StrategyAuditStatus {
+    STRATEGY_AUDIT_STATUS.with(|status| status.borrow().clone())
+}
+
+fn schedule_audit_step(delay: Duration) -> TimerId {
+    ic_cdk_timers::set_timer(delay, || ic_cdk::spawn(run_audit_step()))
+}
+
+/// Reads the next page while the audit stays in place and applies it, unless another step has applied it meanwhile.
+/// The retry step scheduled before the read runs the step again if it traps after the read.
+async fn run_audit_step() {
+    let Some(next_id) = STRATEGY_AUDIT.with(|audit| audit.borrow().as_ref().map(|audit| audit.next_id)) else { return };
+
+    let retry_step = schedule_audit_step(AUDIT_STEP_RETRY_DELAY);
+    let page = read_page(next_id).await;
+    ic_cdk_timers::clear_timer(retry_step);
+
+    let result = STRATEGY_AUDIT.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let audit = cell.as_mut().filter(|audit| audit.next_id == next_id)?;
+
+        Some(page.map(|records| (audit.apply_page(&records), audit.replay.records_count)))
+    });
+
+    match result {
+        None => {}
+        Some(Ok((false, records_replayed))) => {
+            set_status(StrategyAuditStatus::InProgress { records_replayed });
+            schedule_audit_step(Duration::ZERO);
+        }
+        Some(Ok((true, _))) => {
+            let audit = STRATEGY_AUDIT.with(|cell| cell.borrow_mut().take());
+            if let Some(audit) = audit {
+                set_status(StrategyAuditStatus::Completed(audit.report()));
+            }
+        }
+        Some(Err(error)) => {
+            STRATEGY_AUDIT.with(|cell| *cell.borrow_mut() = None);
+            set_status(StrategyAuditStatus::Failed(error));
+        }
+    }
+}
+
+/// Reads the page of records from `next_id`, from the canister or from the archive the records were moved to
+async fn read_page(next_id: u64) -> Result<Vec<GenericEventRecord<Event>>, InternalError> {
+    let local_start_id = EVENT_RECORDS.with(|events| events.borrow().id_range().start);
+
+    if next_id >= local_start_id {
+        let records = EVENT_RECORDS.with(|events| events.borrow().range(next_id, MAX_EVENT_RECORDS_PAGE_SIZE));
+        return Ok(records.into_iter().map(|record| record.0).collect());
+    }
+
+    let not_found_error = || InternalError::not_found(
+        build_error_code(3400, 1, 1), // 3400 01 01
+        "strategy_audit::read_page".to_string(),
+        "Event record is not found in the archives".to_string(),
+        Some(HashMap::from([
+            ("id".to_string(), next_id.to_string()),
+        ])),
+    );
+
+    let archive = event_archives_repo::get_event_archives()
+        .into_iter()
+        .find(|archive| (archive.start_id..archive.end_id).contains(&next_id))
+        .ok_or_else(not_found_error)?;
+
+    let page = archive_client::get_archived_event_records(archive.canister_id, EventRecordsQuery {
+        filter: Default::default(),
+        cursor: next_id.checked_sub(1),
+        limit: MAX_EVENT_RECORDS_PAGE_SIZE,
+        sort_order: SortOrder::Asc,
+    }).await?;
+
+    if page.items.is_empty() {
+        return Err(not_found_error());
+    }
+
+    page.items.iter().map(decode_archived_record).collect()
+}
+
+fn set_status(status: StrategyAuditStatus) {
+    STRATEGY_AUDIT_STATUS.with(|cell| *cell.borrow_mut() = status);
+}
+
+fn decode_archived_record(record: &ArchivedEventRecord) -> Result<GenericEventRecord<Event>, InternalError> {
+    let event = record.decode_payload::<Event>().map_err(|error| {
+        InternalError::business_logic(
+            build_error_code(3400, 3, 1), // 3400 03 01
+            "strategy_audit::decode_archived_record".to_string(),
+            format!("Archived event can not be decoded: {}", error),
+            Some(HashMap::from([
+                ("id".to_string(), record.id.to_string()),
+                ("event_type".to_string(), record.event.event_type.clone()),
+            ])),
+        )
+    })?;
+
+    Ok(GenericEventRecord {
+        id: record.id,
+        timestamp: record.timestamp,
+        event,
+        correlation_id: record.correlation_id.clone(),
+        user: record.user,
+        prev_hash: record.prev_hash.clone(),
+        payload: record.payload.clone(),
+    })
+}
+
+fn set_or_remove(map: &mut HashMap<Principal, Nat>, user: Principal, value: Nat) {
+    if value == 0u64 {
+        map.remove(&user);
+    } else {
+        map.insert(user, value);
+    }
+}
+
+fn saturating_sub(value: &Nat, subtrahend: &Nat) -> Nat {
+    if subtrahend > value {
+        Nat::from(0u64)
+    } else {
+        value.clone() - subtrahend.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_records::events::strategy_events::{MigratedUserShares, StrategyDepositCompleted};
+
+    fn alice() -> Principal {
+        Principal::from_text("2vxsx-fae").unwrap()
+    }
+
+    fn bob() -> Principal {
+        Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap()
+    }
+
+    fn nat(value: u64) -> Nat {
+        Nat::from(value)
+    }
+
+    fn replay(events: Vec<(Event, Option<Principal>)>) -> StrategyReplay {
+        let mut replay = StrategyReplay::default();
+
+        for (id, (event, user)) in events.into_iter().enumerate() {
+            replay.apply(&GenericEventRecord {
+                id: id as u64,
+                timestamp: id as u64,
+                event,
+                correlation_id: id.to_string(),
+                user,
+                prev_hash: None,
+                payload: Vec::new(),
+            });
+        }
+
+        replay
+    }
+
+    fn deposit(amount: u64, shares: u64, position_id: u64) -> Event {
+        Event::strategy_deposit_completed("1".to_string(), Some("pool1".to_string()), Some(nat(amount)), nat(shares), nat(1), position_id)
+    }
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn replays_deposits_and_withdrawals() {
+            let replay = replay(vec![
+                (deposit(1000, 1000, 7), Some(alice())),
+                (deposit(500, 500, 7), Some(bob())),
+                (Event::strategy_withdraw_completed("1".to_string(), Some("pool1".to_string()), Some(nat(400)), Some(nat(400)), nat(1)), Some(alice())),
+            ]);
+
+            let state = &replay.strategies["1"];
+
+            assert_eq!(state.total_shares, nat(1100));
+            assert_eq!(state.user_shares, HashMap::from([(alice(), nat(600)), (bob(), nat(500))]));
+            assert_eq!(state.initial_deposit, HashMap::from([(alice(), nat(600)), (bob(), nat(500))]));
+            assert_eq!(state.total_balance, nat(1100));
+            assert_eq!(state.current_pool_id, Some("pool1".to_string()));
+            assert_eq!(state.position_id, Some(7));
+            assert_eq!(replay.records_count, 3);
+        }
+
+        #[test]
+        fn clears_position_when_last_shares_are_withdrawn() {
+            let replay = replay(vec![
+                (deposit(1000, 1000, 7), Some(alice())),
+                (Event::strategy_withdraw_completed("1".to_string(), Some("pool1".to_string()), Some(nat(1000)), Some(nat(1000)), nat(1)), Some(alice())),
+            ]);
+
+            let state = &replay.strategies["1"];
+
+            assert_eq!(state.total_shares, nat(0));
+            assert!(state.user_shares.is_empty());
+            assert_eq!(state.position_id, None);
+        }
+
+        #[test]
+        fn calculates_shares_of_records_without_minted_shares() {
+            let legacy_deposit = Event::StrategyDepositCompleted(StrategyDepositCompleted {
+                strategy_id: "1".to_string(),
+                pool_id: Some("pool1".to_string()),
+                amount0: Some(nat(1000)),
+                shares: None,
+                share_price: None,
+                position_id: None,
+            });
+
+            let replay = replay(vec![(legacy_deposit, Some(alice()))]);
+
+            assert_eq!(replay.strategies["1"].user_shares[&alice()], nat(1000));
+            assert_eq!(replay.strategies["1"].position_id, None);
+        }
+
+        #[test]
+        fn credits_deposited_part_of_failed_deposit() {
+            let error = InternalError::business_logic(0, String::new(), String::new(), None);
+            let replay = replay(vec![
+                (Event::strategy_deposit_failed("1".to_string(), None, Some(nat(1000)), error.clone()), Some(alice())),
+                (Event::strategy_deposit_partially_failed("1".to_string(), Some("pool1".to_string()), Some(nat(1000)), nat(600), nat(600), 3, error), Some(bob())),
+            ]);
+
+            let state = &replay.strategies["1"];
+
+            assert_eq!(state.user_shares, HashMap::from([(bob(), nat(600))]));
+            assert_eq!(state.total_balance, nat(600));
+            assert_eq!(state.position_id, Some(3));
+        }
+
+        #[test]
+        fn moves_pool_and_position_on_rebalance_and_exit() {
+            let mut replay = replay(vec![
+                (deposit(1000, 1000, 7), Some(alice())),
+                (Event::strategy_rebalance_completed("1".to_string(), Some("pool1".to_string()), Some("pool2".to_string()), Some(9)), None),
+            ]);
+
+            assert_eq!(replay.strategies["1"].current_pool_id, Some("pool2".to_string()));
+            assert_eq!(replay.strategies["1"].position_id, Some(9));
+
+            replay.apply(&GenericEventRecord {
+                id: 2,
+                timestamp: 2,
+                event: Event::strategy_state_changed("1".to_string(), StrategyState::Active, StrategyState::Exited),
+                correlation_id: "2".to_string(),
+                user: None,
+                prev_hash: None,
+                payload: Vec::new(),
+            });
+
+            assert_eq!(replay.strategies["1"].position_id, None);
+            assert_eq!(replay.strategies["1"].total_shares, nat(1000));
+        }
+
+        #[test]
+        fn moves_users_to_successor_strategy_on_migration() {
+            let replay = replay(vec![
+                (deposit(1000, 1000, 7), Some(alice())),
+                (Event::strategy_users_migrated("1".to_string(), "2".to_string(), nat(1000), 1), None),
+                (Event::strategy_migration_accepted("2".to_string(), "pool3".to_string(), 11, nat(1000), vec![
+                    MigratedUserShares { user: alice(), shares: nat(900), initial_deposit: nat(1000) },
+                ]), None),
+            ]);
+
+            assert_eq!(replay.strategies["1"], ReplayedStrategyState {
+                current_pool_id: Some("pool1".to_string()),
+                ..Default::default()
+            });
+            assert_eq!(replay.strategies["2"], ReplayedStrategyState {
+                total_shares: nat(900),
+                total_balance: nat(1000),
+                user_shares: HashMap::from([(alice(), nat(900))]),
+                initial_deposit: HashMap::from([(alice(), nat(1000))]),
+                current_pool_id: Some("pool3".to_string()),
+                position_id: Some(11),
+            });
+        }
+    }
+
+    mod diff_strategy_state {
+        use super::*;
+
+        #[test]
+        fn returns_no_mismatches_for_equal_state() {
+            let state = replay(vec![(deposit(1000, 1000, 7), Some(alice()))]).strategies["1"].clone();
+
+            assert!(diff_strategy_state("1", &state, &state).is_empty());
+        }
+
+        #[test]
+        fn reports_mismatched_fields_and_users() {
+            let replayed = replay(vec![(deposit(1000, 1000, 7), Some(alice()))]).strategies["1"].clone();
+            let live = ReplayedStrategyState {
+                user_shares: HashMap::from([(alice(), nat(1000)), (bob(), nat(5))]),
+                position_id: Some(8),
+                ..replayed.clone()
+            };
+
+            let mismatches = diff_strategy_state("1", &replayed, &live);
+
+            assert_eq!(mismatches, vec![
+                StrategyStateMismatch {
+                    strategy_id: "1".to_string(),
+                    field: "position_id".to_string(),
+                    replayed: "Some(7)".to_string(),
+                    live: "Some(8)".to_string(),
+                },
+                StrategyStateMismatch {
+                    strategy_id: "1".to_string(),
+                    field: format!("user_shares.{}", bob().to_text()),
+                    replayed: "None".to_string(),
+                    live: "Some(\"5\")".to_string(),
+                },
+            ]);
+        }
+    }
+
+    mod apply_page {
+        use super::*;
+        use crate::event_records::event_record::EventRecord;
+        use crate::repository::event_records_repo;
+
+        #[test]
+        fn replays_a_page_per_step_until_all_records_are_replayed() {
+            for _ in 0..2 {
+                let id = event_records_repo::next_event_record_id();
+                event_records_repo::save_event_record(EventRecord::new(id, id.to_string(), deposit(1000, 1000, 7), id, Some(alice())));
+            }
+
+            let mut audit = StrategyAudit::new();
+            let records = EVENT_RECORDS.with(|events| events.borrow().range(audit.next_id, MAX_EVENT_RECORDS_PAGE_SIZE));
+            let records: Vec<GenericEventRecord<Event>> = records.into_iter().map(|record| record.0).collect();
+
+            assert!(!audit.apply_page(&records));
+            assert_eq!(audit.next_id, 2);
+            assert_eq!(audit.replay.records_count, 2);
+            assert_eq!(audit.replay.strategies["1"].total_shares, nat(2000));
+
+            assert!(audit.apply_page(&[]));
+            assert_eq!(audit.replay.records_count, 2);
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use candid::{CandidType, Deserialize};
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Deserialize, Encode};
+use ic_stable_structures::storable::{Bound, Storable};
 use serde::Serialize;
 
 use crate::strategies::r#impl::ck_btc_strategy::ckBTCStrategy;
@@ -35,4 +37,16 @@ impl Candid for StrategyCandid {
             StrategyCandid::IcsStrategyV(strategy) => Box::new(strategy.clone()),
         }
     }
-}
\ No newline at end of file
+}
+
+impl Storable for StrategyCandid {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -0,0 +1,88 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+/// Lifecycle stage of a strategy
+///
+/// * `Draft` - Strategy is configured but not open for deposits and not listed
+/// * `Active` - Strategy is open for deposits
+/// * `Closing` - Strategy is winding down, deposits are rejected and users
+///   can withdraw or be migrated to a successor strategy
+/// * `Closed` - Strategy has no users left and is retired
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum StrategyLifecycle {
+    Draft,
+    #[default]
+    Active,
+    Closing,
+    Closed,
+}
+
+impl StrategyLifecycle {
+    pub fn allows_deposit(&self) -> bool {
+        matches!(self, Self::Active)
+    }
+
+    pub fn allows_withdraw(&self) -> bool {
+        matches!(self, Self::Active | Self::Closing)
+    }
+
+    pub fn allows_rebalance(&self) -> bool {
+        matches!(self, Self::Active | Self::Closing)
+    }
+
+    pub fn is_listed(&self) -> bool {
+        matches!(self, Self::Active | Self::Closing)
+    }
+
+    /// Lifecycle only moves forward: Draft -> Active -> Closing -> Closed
+    pub fn can_transition_to(&self, lifecycle: StrategyLifecycle) -> bool {
+        matches!(
+            (self, lifecycle),
+            (Self::Draft, Self::Active) | (Self::Active, Self::Closing) | (Self::Closing, Self::Closed)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod can_transition_to {
+        use super::*;
+
+        #[test]
+        fn allows_forward_transitions() {
+            assert!(StrategyLifecycle::Draft.can_transition_to(StrategyLifecycle::Active));
+            assert!(StrategyLifecycle::Active.can_transition_to(StrategyLifecycle::Closing));
+            assert!(StrategyLifecycle::Closing.can_transition_to(StrategyLifecycle::Closed));
+        }
+
+        #[test]
+        fn rejects_skipping_and_backward_transitions() {
+            assert!(!StrategyLifecycle::Draft.can_transition_to(StrategyLifecycle::Closing));
+            assert!(!StrategyLifecycle::Active.can_transition_to(StrategyLifecycle::Closed));
+            assert!(!StrategyLifecycle::Closing.can_transition_to(StrategyLifecycle::Active));
+            assert!(!StrategyLifecycle::Closed.can_transition_to(StrategyLifecycle::Draft));
+        }
+    }
+
+    mod allows_operations {
+        use super::*;
+
+        #[test]
+        fn closing_allows_withdraw_but_not_deposit() {
+            let lifecycle = StrategyLifecycle::Closing;
+
+            assert!(!lifecycle.allows_deposit());
+            assert!(lifecycle.allows_withdraw());
+            assert!(lifecycle.is_listed());
+        }
+
+        #[test]
+        fn draft_and_closed_are_not_listed() {
+            assert!(!StrategyLifecycle::Draft.is_listed());
+            assert!(!StrategyLifecycle::Closed.is_listed());
+            assert!(!StrategyLifecycle::Closed.allows_withdraw());
+        }
+    }
+}
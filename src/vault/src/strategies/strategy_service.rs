@@ -1,4 +1,4 @@
-use crate::repository::strategies_repo::{add_if_not_exists, add_or_update_strategy, STRATEGIES};
+use crate::repository::strategies_repo::{add_if_not_exists, get_all_strategies, save_strategy};
 use crate::strategies::r#impl::ck_btc_strategy::ckBTCStrategy;
 use crate::strategies::r#impl::panda_icp_stategy::PandaTestStrategy;
 use crate::strategies::r#impl::icp_strategy::ICPStrategy;
@@ -6,23 +6,32 @@ use crate::strategies::r#impl::icp_usdt_kong_icpswap_strategy::IcpCkUSDTStrategy
 use crate::strategies::r#impl::ics_icp_strategy::IcsStrategy;
 use crate::types::types::StrategyResponse;
 
+/// Adds the strategies which are not registered yet.
+/// Registered strategies keep their state and lifecycle, so closed strategies are never re-opened.
+/// Test strategies start as `Draft` and are not listed until a controller activates them.
 pub fn init_strategies() {
     let ck_btc = Box::new(ckBTCStrategy::new());
     let icp = Box::new(ICPStrategy::new());
     add_if_not_exists(ck_btc);
     add_if_not_exists(icp);
-    add_or_update_strategy(Box::new(IcpCkUSDTStrategy::new()));
-    add_or_update_strategy(Box::new(PandaTestStrategy::new()));
-    add_or_update_strategy(Box::new(IcsStrategy::new()));
+    add_if_not_exists(Box::new(IcpCkUSDTStrategy::new()));
+    add_if_not_exists(Box::new(PandaTestStrategy::new()));
+    add_if_not_exists(Box::new(IcsStrategy::new()));
+}
+
+/// Moves the single pool positions of strategies which got an allocation policy into their pool allocations
+pub fn migrate_strategies() {
+    for mut strategy in get_all_strategies() {
+        if strategy.migrate_to_pool_allocations() {
+            save_strategy(strategy);
+        }
+    }
 }
 
-// TODO: move to repo
 pub fn get_actual_strategies() -> Vec<StrategyResponse> {
-    let strategies: Vec<StrategyResponse> = STRATEGIES.with(|trss| {
-        let a = trss.borrow();
-        a.iter()
-            .map(|a| a.to_response())
-            .collect()
-    });
-    strategies
+    get_all_strategies()
+        .iter()
+        .filter(|strategy| strategy.get_lifecycle().is_listed())
+        .map(|strategy| strategy.to_response())
+        .collect()
 }
\ No newline at end of file
@@ -0,0 +1,99 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+/// Operational state of a strategy
+///
+/// * `Active` - All operations are allowed
+/// * `DepositsPaused` - New deposits are rejected, withdrawals and rebalances are allowed
+/// * `Paused` - All operations are rejected while liquidity stays in the pools
+/// * `Exited` - Liquidity is withdrawn to the base token and held idle in the vault,
+///   users can only withdraw their pro-rata share of the idle balance
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum StrategyState {
+    #[default]
+    Active,
+    DepositsPaused,
+    Paused,
+    Exited,
+}
+
+impl StrategyState {
+    pub fn allows_deposit(&self) -> bool {
+        matches!(self, Self::Active)
+    }
+
+    pub fn allows_withdraw(&self) -> bool {
+        matches!(self, Self::Active | Self::DepositsPaused | Self::Exited)
+    }
+
+    pub fn allows_rebalance(&self) -> bool {
+        matches!(self, Self::Active | Self::DepositsPaused)
+    }
+
+    /// Exited strategy holds no pool positions, so it can not be moved back to other states
+    pub fn can_transition_to(&self, state: StrategyState) -> bool {
+        *self != state && *self != Self::Exited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod allows_operations {
+        use super::*;
+
+        #[test]
+        fn active_allows_everything() {
+            let state = StrategyState::Active;
+
+            assert!(state.allows_deposit());
+            assert!(state.allows_withdraw());
+            assert!(state.allows_rebalance());
+        }
+
+        #[test]
+        fn deposits_paused_rejects_deposits_only() {
+            let state = StrategyState::DepositsPaused;
+
+            assert!(!state.allows_deposit());
+            assert!(state.allows_withdraw());
+            assert!(state.allows_rebalance());
+        }
+
+        #[test]
+        fn paused_rejects_everything() {
+            let state = StrategyState::Paused;
+
+            assert!(!state.allows_deposit());
+            assert!(!state.allows_withdraw());
+            assert!(!state.allows_rebalance());
+        }
+
+        #[test]
+        fn exited_allows_withdraw_only() {
+            let state = StrategyState::Exited;
+
+            assert!(!state.allows_deposit());
+            assert!(state.allows_withdraw());
+            assert!(!state.allows_rebalance());
+        }
+    }
+
+    mod can_transition_to {
+        use super::*;
+
+        #[test]
+        fn allows_transitions_between_states() {
+            assert!(StrategyState::Active.can_transition_to(StrategyState::Paused));
+            assert!(StrategyState::Paused.can_transition_to(StrategyState::Exited));
+            assert!(StrategyState::DepositsPaused.can_transition_to(StrategyState::Active));
+        }
+
+        #[test]
+        fn rejects_same_state_and_leaving_exited() {
+            assert!(!StrategyState::Active.can_transition_to(StrategyState::Active));
+            assert!(!StrategyState::Exited.can_transition_to(StrategyState::Active));
+        }
+    }
+}
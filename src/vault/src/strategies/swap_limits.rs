@@ -0,0 +1,79 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+
+use types::CanisterId;
+use types::swap_tokens::SwapLimits;
+
+/// Swap limits overriding the strategy defaults for one token pair, in both directions
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PairSwapLimits {
+    pub token0: CanisterId,
+    pub token1: CanisterId,
+    pub limits: SwapLimits,
+}
+
+/// Slippage tolerance and maximum price impact of the swaps a strategy makes
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct StrategySwapLimits {
+    pub default: SwapLimits,
+    pub pairs: Vec<PairSwapLimits>,
+}
+
+impl StrategySwapLimits {
+    pub fn new(default: SwapLimits) -> Self {
+        Self { default, pairs: Vec::new() }
+    }
+
+    pub fn with_pair(mut self, token0: CanisterId, token1: CanisterId, limits: SwapLimits) -> Self {
+        self.pairs.push(PairSwapLimits { token0, token1, limits });
+        self
+    }
+
+    /// Limits of the pair, or the strategy defaults if the pair has no override
+    pub fn for_pair(&self, token_in: CanisterId, token_out: CanisterId) -> SwapLimits {
+        self.pairs
+            .iter()
+            .find(|pair| {
+                (pair.token0 == token_in && pair.token1 == token_out)
+                    || (pair.token0 == token_out && pair.token1 == token_in)
+            })
+            .map(|pair| pair.limits)
+            .unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::token;
+
+    mod for_pair {
+        use super::*;
+
+        #[test]
+        fn uses_pair_override_in_both_directions() {
+            let pair_limits = SwapLimits {
+                slippage_tolerance_bps: 1_000,
+                max_price_impact_bps: 2_000,
+                max_price_deviation_bps: 500,
+            };
+            let limits = StrategySwapLimits::default().with_pair(token(1), token(2), pair_limits);
+
+            assert_eq!(limits.for_pair(token(1), token(2)), pair_limits);
+            assert_eq!(limits.for_pair(token(2), token(1)), pair_limits);
+        }
+
+        #[test]
+        fn falls_back_to_default() {
+            let default = SwapLimits {
+                slippage_tolerance_bps: 100,
+                max_price_impact_bps: 300,
+                max_price_deviation_bps: 300,
+            };
+            let limits = StrategySwapLimits::new(default)
+                .with_pair(token(1), token(2), SwapLimits::default());
+
+            assert_eq!(limits.for_pair(token(1), token(3)), default);
+        }
+    }
+}
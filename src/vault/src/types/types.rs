@@ -3,11 +3,23 @@ use candid::{CandidType, Deserialize, Nat, Principal};
 use serde::Serialize;
 
 use types::CanisterId;
+use types::exchange_id::ExchangeId;
+use types::liquidity::LiquidityFees;
 use errors::response_error::error::ResponseError;
+use token_registry::token_metadata::TokenMetadata;
+use event_records::event_chain;
+use event_records::event_query;
+use event_records::archive::{EventArchive, EventRetentionPolicy};
 
 use crate::pools::pool::Pool;
+use crate::strategies::rebalance_plan::RebalancePlan;
+use crate::strategies::strategy_state::StrategyState;
+use crate::strategies::strategy_lifecycle::StrategyLifecycle;
+use crate::strategies::strategy_audit::StrategyAuditStatus;
 use crate::event_records::event_record::EventRecord;
 
+pub use event_records::event_query::EventRecordsQuery;
+
 #[derive(CandidType, Deserialize, Clone, Serialize)]
 pub struct StrategyDepositArgs {
     pub ledger: CanisterId,
@@ -22,6 +34,24 @@ pub struct StrategyWithdrawArgs {
     pub strategy_id: StrategyId,
 }
 
+#[derive(CandidType, Deserialize, Clone, Serialize)]
+pub struct SetStrategyStateArgs {
+    pub strategy_id: StrategyId,
+    pub state: StrategyState,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize)]
+pub struct SetStrategyLifecycleArgs {
+    pub strategy_id: StrategyId,
+    pub lifecycle: StrategyLifecycle,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize)]
+pub struct MigrateStrategyArgs {
+    pub strategy_id: StrategyId,
+    pub successor_strategy_id: StrategyId,
+}
+
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
 pub struct StrategyDepositResponse {
     pub amount: Nat,
@@ -36,11 +66,44 @@ pub struct StrategyWithdrawResponse {
     pub current_shares: Nat,
 }
 
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyPreviewDepositResponse {
+    pub pool: Pool,
+    pub amount: Nat,
+    pub shares: Nat,
+    pub token_0_for_swap: Nat,
+    pub token_0_for_pool: Nat,
+    pub token_1_for_pool: Nat,
+    pub swap_provider: ExchangeId,
+    pub fees: LiquidityFees,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyPreviewWithdrawResponse {
+    pub pool: Pool,
+    pub shares: Nat,
+    pub amount: Nat,
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+    pub swap_provider: ExchangeId,
+    pub swap_amount_out: Nat,
+    pub fees: LiquidityFees,
+}
+
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
 pub struct StrategyRebalanceResponse {
     pub previous_pool: Pool,
     pub current_pool: Pool,
     pub is_rebalanced: bool,
+    pub rebalance_plan: Option<RebalancePlan>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct MigrateStrategyResponse {
+    pub successor_strategy_id: StrategyId,
+    pub amount: Nat,
+    pub users_count: u32,
+    pub position_id: Option<u64>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
@@ -57,6 +120,20 @@ pub struct StrategyResponse {
     pub users_count: u32,
     pub current_liquidity: Option<Nat>,
     pub current_liquidity_updated_at: Option<u64>,
+    pub rebalance_plan: Option<RebalancePlan>,
+    pub pool_allocations: Vec<PoolAllocationResponse>,
+    pub state: StrategyState,
+    pub idle_balance: Nat,
+    pub lifecycle: StrategyLifecycle,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct PoolAllocationResponse {
+    pub pool: Pool,
+    pub position_id: Option<u64>,
+    pub target_weight: u32,
+    pub current_weight: u32,
+    pub current_liquidity: Option<Nat>,
 }
 
 // TODO: rename to UserPositionResponse
@@ -91,32 +168,49 @@ pub struct StrategyDepositResult(pub Result<StrategyDepositResponse, ResponseErr
 pub struct StrategyWithdrawResult(pub Result<StrategyWithdrawResponse, ResponseError>);
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct GetEventRecordsResult(pub Result<EventRecordsPaginationResponse, ResponseError>);
+pub struct StrategyPreviewDepositResult(pub Result<StrategyPreviewDepositResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyPreviewWithdrawResult(pub Result<StrategyPreviewWithdrawResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetEventRecordsResult(pub Result<EventRecordsPage, ResponseError>);
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct StrategyRebalanceResult(pub Result<StrategyRebalanceResponse, ResponseError>);
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct EventRecordsPaginationResponse(pub ListItemsPaginationResponse<EventRecord>);
+pub struct SetStrategyStateResult(pub Result<StrategyState, ResponseError>);
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub enum SortOrder {
-    Asc,
-    Desc,
-}
+pub struct SetStrategyLifecycleResult(pub Result<StrategyLifecycle, ResponseError>);
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct ListItemsPaginationRequest {
-    pub page: u64,
-    pub page_size: u64,
-    pub sort_order: SortOrder,
-    pub search: Option<String>,
-}
+pub struct MigrateStrategyResult(pub Result<MigrateStrategyResponse, ResponseError>);
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct ListItemsPaginationResponse<T> {
-    pub items: Vec<T>,
-    pub total: u64,
-    pub page: u64,
-    pub page_size: u64,
-}
+pub struct AddTokenResult(pub Result<TokenMetadata, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RefreshTokensResult(pub Result<Vec<TokenMetadata>, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SetEventRetentionPolicyResult(pub Result<EventRetentionPolicy, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct AddEventArchiveResult(pub Result<EventArchive, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SetEventArchiveWasmResult(pub Result<(), ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct UpgradeEventArchivesResult(pub Result<u64, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct AuditStrategiesResult(pub Result<(), ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetStrategyAuditResult(pub Result<StrategyAuditStatus, ResponseError>);
+
+pub type EventRecordsPage = event_query::EventRecordsPage<EventRecord>;
+pub type CertifiedEventRecords = event_chain::CertifiedEventRecords<EventRecord>;
@@ -0,0 +1,24 @@
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Deserialize, Encode, Nat};
+use ic_stable_structures::storable::{Bound, Storable};
+use serde::Serialize;
+
+/// Shares and initial deposit of a user in a strategy, stored apart from the strategy
+/// so that saving a strategy does not rewrite the data of all its users
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct UserPosition {
+    pub shares: Option<Nat>,
+    pub initial_deposit: Option<Nat>,
+}
+
+impl Storable for UserPosition {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}